@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+/// Smallest pause the delay grows from when it's currently zero.
+const MIN_STEP: Duration = Duration::from_millis(100);
+
+/// `ADAPTIVE_DELAY_MIN_MS`/`ADAPTIVE_DELAY_MAX_MS`: the range the pause
+/// between deletions may move within.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DelayBounds {
+    pub min: Duration,
+    pub max: Duration,
+}
+
+/// Pause between deletions that backs off while the server is slow to
+/// respond and eases off again once it recovers.
+///
+/// Each response's latency is compared with a running average: one more than
+/// twice the average doubles the pause, one at or under the average shortens
+/// it by a quarter. The pause always stays within the bounds.
+#[derive(Debug, Clone)]
+pub struct AdaptiveDelay {
+    bounds: DelayBounds,
+    current: Duration,
+    /// Exponentially weighted average latency, `None` until the first response.
+    baseline: Option<Duration>,
+}
+
+impl AdaptiveDelay {
+    /// Start from the platform's usual `delay`, pulled into `bounds`.
+    pub fn new(delay: Duration, bounds: DelayBounds) -> Self {
+        Self {
+            bounds,
+            current: delay.clamp(bounds.min, bounds.max),
+            baseline: None,
+        }
+    }
+
+    pub fn current(&self) -> Duration {
+        self.current
+    }
+
+    /// Adjust the pause for one response that took `latency`.
+    pub fn observe(&mut self, latency: Duration) {
+        let Some(baseline) = self.baseline else {
+            self.baseline = Some(latency);
+            return;
+        };
+        if latency > baseline * 2 {
+            self.current = (self.current * 2).max(MIN_STEP);
+        } else if latency <= baseline {
+            self.current = self.current * 3 / 4;
+        }
+        self.current = self.current.clamp(self.bounds.min, self.bounds.max);
+        // Weight new responses at a fifth, so one spike doesn't become normal
+        self.baseline = Some((baseline * 4 + latency) / 5);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    fn bounds() -> DelayBounds {
+        DelayBounds {
+            min: ms(200),
+            max: ms(5_000),
+        }
+    }
+
+    #[test]
+    fn starting_delay_is_pulled_into_bounds() {
+        assert_eq!(AdaptiveDelay::new(ms(50), bounds()).current(), ms(200));
+        assert_eq!(
+            AdaptiveDelay::new(ms(60_000), bounds()).current(),
+            ms(5_000)
+        );
+        assert_eq!(AdaptiveDelay::new(ms(1_000), bounds()).current(), ms(1_000));
+    }
+
+    #[test]
+    fn slow_responses_back_off_up_to_the_max() {
+        let mut delay = AdaptiveDelay::new(ms(1_000), bounds());
+        delay.observe(ms(100));
+        delay.observe(ms(500));
+        assert_eq!(delay.current(), ms(2_000));
+
+        // The average climbs with each spike, so keep getting slower
+        for latency in [2_000, 8_000, 30_000, 120_000] {
+            delay.observe(ms(latency));
+        }
+        assert_eq!(delay.current(), ms(5_000));
+    }
+
+    #[test]
+    fn fast_responses_recover_down_to_the_min() {
+        let mut delay = AdaptiveDelay::new(ms(4_000), bounds());
+        delay.observe(ms(100));
+        delay.observe(ms(80));
+        assert_eq!(delay.current(), ms(3_000));
+
+        for _ in 0..50 {
+            delay.observe(ms(80));
+        }
+        assert_eq!(delay.current(), ms(200));
+    }
+
+    #[test]
+    fn latency_between_average_and_double_leaves_the_delay_alone() {
+        let mut delay = AdaptiveDelay::new(ms(1_000), bounds());
+        delay.observe(ms(100));
+        delay.observe(ms(150));
+        assert_eq!(delay.current(), ms(1_000));
+    }
+
+    #[test]
+    fn zero_delay_grows_from_a_minimum_step() {
+        let mut delay = AdaptiveDelay::new(
+            Duration::ZERO,
+            DelayBounds {
+                min: Duration::ZERO,
+                max: ms(1_000),
+            },
+        );
+        delay.observe(ms(100));
+        delay.observe(ms(300));
+        assert_eq!(delay.current(), MIN_STEP);
+    }
+}
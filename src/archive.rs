@@ -0,0 +1,227 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A deleted record as written to `ARCHIVE_DIR`, enough to put it back.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ArchivedRecord {
+    pub uri: String,
+    pub collection: String,
+    pub rkey: String,
+    /// The record exactly as returned by `listRecords`.
+    pub value: serde_json::Value,
+}
+
+/// `<dir>/bluesky/<collection>/<rkey>.json`
+fn record_path(dir: &Path, collection: &str, rkey: &str) -> PathBuf {
+    dir.join("bluesky")
+        .join(collection)
+        .join(format!("{rkey}.json"))
+}
+
+/// Save `record` under `dir` before it's deleted.
+pub fn save(dir: &Path, record: &ArchivedRecord) -> Result<()> {
+    let path = record_path(dir, &record.collection, &record.rkey);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create archive dir {}", parent.display()))?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(record)?)
+        .with_context(|| format!("Failed to write archive {}", path.display()))
+}
+
+/// Every Bluesky record archived under `dir`, ordered by collection and rkey.
+pub fn load_all(dir: &Path) -> Result<Vec<ArchivedRecord>> {
+    let root = dir.join("bluesky");
+    let mut paths = Vec::new();
+    for collection in fs::read_dir(&root)
+        .with_context(|| format!("Failed to read archive dir {}", root.display()))?
+    {
+        let collection = collection?.path();
+        if !collection.is_dir() {
+            continue;
+        }
+        for entry in fs::read_dir(&collection)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| {
+            let contents = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read archive {}", path.display()))?;
+            serde_json::from_str(&contents)
+                .with_context(|| format!("Failed to parse archive {}", path.display()))
+        })
+        .collect()
+}
+
+impl ArchivedRecord {
+    fn created_at(&self) -> Option<DateTime<Utc>> {
+        let created_at = self.value.get("createdAt")?.as_str()?;
+        crate::parse_timestamp(created_at)
+            .ok()
+            .map(|t| t.with_timezone(&Utc))
+    }
+
+    /// The post's text, or for a like or repost the URI of its subject.
+    fn summary(&self) -> &str {
+        self.value
+            .get("text")
+            .or_else(|| self.value.get("subject").and_then(|s| s.get("uri")))
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+    }
+
+    /// The last segment of the collection NSID, e.g. `post` or `like`.
+    fn kind(&self) -> &str {
+        self.collection
+            .rsplit('.')
+            .next()
+            .unwrap_or(&self.collection)
+    }
+}
+
+/// Escape `&`, `<`, `>` and quotes for HTML and XML text and attributes.
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Write `index.html` and an Atom `feed.xml` to `dir` listing every record
+/// archived there, newest first. Records without a usable `createdAt` come
+/// last. Returns how many were listed.
+pub fn write_index(dir: &Path, now: DateTime<Utc>) -> Result<usize> {
+    let mut records: Vec<(Option<DateTime<Utc>>, ArchivedRecord)> = load_all(dir)?
+        .into_iter()
+        .map(|record| (record.created_at(), record))
+        .collect();
+    records.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Skyscraper archive</title>\n\
+         <link rel=\"alternate\" type=\"application/atom+xml\" href=\"feed.xml\">\n\
+         </head>\n<body>\n<h1>Skyscraper archive</h1>\n<ol>\n",
+    );
+    let updated = records.first().and_then(|(t, _)| *t).unwrap_or(now);
+    let mut feed = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <feed xmlns=\"http://www.w3.org/2005/Atom\">\n\
+         <title>Skyscraper archive</title>\n\
+         <id>urn:skyscraper:archive</id>\n\
+         <updated>{}</updated>\n",
+        updated.to_rfc3339()
+    );
+    for (created_at, record) in &records {
+        let date = created_at.map_or("unknown date".to_string(), |t| t.to_rfc3339());
+        let (kind, uri, summary) = (
+            escape(record.kind()),
+            escape(&record.uri),
+            escape(record.summary()),
+        );
+        html.push_str(&format!(
+            "<li id=\"{uri}\"><time>{date}</time> <b>{kind}</b> {summary}</li>\n"
+        ));
+        feed.push_str(&format!(
+            "<entry>\n<id>{uri}</id>\n<title>{kind} {date}</title>\n\
+             <updated>{}</updated>\n<content type=\"text\">{summary}</content>\n</entry>\n",
+            created_at.unwrap_or(now).to_rfc3339()
+        ));
+    }
+    html.push_str("</ol>\n</body>\n</html>\n");
+    feed.push_str("</feed>\n");
+
+    for (name, contents) in [("index.html", html), ("feed.xml", feed)] {
+        let path = dir.join(name);
+        fs::write(&path, contents)
+            .with_context(|| format!("Failed to write archive index {}", path.display()))?;
+    }
+    Ok(records.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(collection: &str, rkey: &str) -> ArchivedRecord {
+        ArchivedRecord {
+            uri: format!("at://did:plc:test/{collection}/{rkey}"),
+            collection: collection.to_string(),
+            rkey: rkey.to_string(),
+            value: serde_json::json!({"$type": collection, "createdAt": "2020-01-01T00:00:00Z"}),
+        }
+    }
+
+    #[test]
+    fn save_then_load_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        save(dir.path(), &record("app.bsky.feed.post", "b")).unwrap();
+        save(dir.path(), &record("app.bsky.feed.post", "a")).unwrap();
+        save(dir.path(), &record("app.bsky.feed.like", "c")).unwrap();
+
+        let loaded = load_all(dir.path()).unwrap();
+        assert_eq!(
+            loaded,
+            vec![
+                record("app.bsky.feed.like", "c"),
+                record("app.bsky.feed.post", "a"),
+                record("app.bsky.feed.post", "b"),
+            ]
+        );
+        assert!(dir
+            .path()
+            .join("bluesky/app.bsky.feed.post/a.json")
+            .exists());
+    }
+
+    #[test]
+    fn index_lists_records_newest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        for (rkey, created_at, text) in [
+            ("a", "2021-03-01T00:00:00Z", "middle"),
+            ("b", "2023-07-15T00:00:00Z", "newest <3 & more"),
+            ("c", "2019-11-30T00:00:00Z", "oldest"),
+        ] {
+            let mut archived = record("app.bsky.feed.post", rkey);
+            archived.value = serde_json::json!({"text": text, "createdAt": created_at});
+            save(dir.path(), &archived).unwrap();
+        }
+
+        let listed = write_index(dir.path(), chrono::Utc::now()).unwrap();
+        assert_eq!(listed, 3);
+        for name in ["index.html", "feed.xml"] {
+            let index = fs::read_to_string(dir.path().join(name)).unwrap();
+            let newest = index.find("newest &lt;3 &amp; more").unwrap();
+            let middle = index.find("middle").unwrap();
+            let oldest = index.find("oldest").unwrap();
+            assert!(newest < middle && middle < oldest, "{name}: {index}");
+        }
+        let feed = fs::read_to_string(dir.path().join("feed.xml")).unwrap();
+        assert!(feed.contains("<updated>2023-07-15T00:00:00+00:00</updated>"));
+    }
+
+    #[test]
+    fn load_missing_dir_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load_all(dir.path()).is_err());
+    }
+
+    #[test]
+    fn load_corrupt_file_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let posts = dir.path().join("bluesky/app.bsky.feed.post");
+        fs::create_dir_all(&posts).unwrap();
+        fs::write(posts.join("x.json"), "not json").unwrap();
+        assert!(load_all(dir.path()).is_err());
+    }
+}
@@ -0,0 +1,71 @@
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+use std::path::Path;
+
+/// Terms from `DELETE_BLOCKLIST_FILE`: posts containing any of them are
+/// deleted whatever their age.
+///
+/// One term or phrase per line; blank lines and `#` comments are skipped.
+/// Matching ignores case and respects word boundaries, so `cat` matches
+/// "Cat!" but not "concatenate".
+#[derive(Debug, Clone)]
+pub struct Blocklist {
+    pattern: Regex,
+}
+
+impl Blocklist {
+    /// Read the blocklist at `path`. Unlike the keep file, a blocklist that
+    /// was asked for but can't be read fails the run.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read DELETE_BLOCKLIST_FILE {}", path.display()))?;
+        Self::parse(&contents)
+            .with_context(|| format!("Invalid DELETE_BLOCKLIST_FILE {}", path.display()))
+    }
+
+    pub fn parse(contents: &str) -> Result<Self> {
+        let terms: Vec<String> = contents
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(|term| {
+                // \b only anchors between a word and non-word character, so
+                // a term like "c++" gets a boundary on its word side only
+                let word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+                let start = if word(term.chars().next()) { r"\b" } else { "" };
+                let end = if word(term.chars().last()) { r"\b" } else { "" };
+                format!("{start}{}{end}", regex::escape(term))
+            })
+            .collect();
+        anyhow::ensure!(!terms.is_empty(), "Blocklist has no terms");
+        let pattern = RegexBuilder::new(&terms.join("|"))
+            .case_insensitive(true)
+            .build()?;
+        Ok(Self { pattern })
+    }
+
+    pub fn matches(&self, text: &str) -> bool {
+        self.pattern.is_match(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_whole_words_ignoring_case() {
+        let blocklist = Blocklist::parse("# slurs and phrases\ncat\n\nbad take\nc++\n").unwrap();
+        assert!(blocklist.matches("My CAT!"));
+        assert!(blocklist.matches("what a Bad take"));
+        assert!(blocklist.matches("writing C++ today"));
+        assert!(!blocklist.matches("concatenate"));
+        assert!(!blocklist.matches("cats"));
+        assert!(!blocklist.matches("bad takes"));
+    }
+
+    #[test]
+    fn empty_blocklist_is_an_error() {
+        assert!(Blocklist::parse("# nothing yet\n").is_err());
+    }
+}
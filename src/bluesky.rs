@@ -1,14 +1,138 @@
-use crate::{is_protected, Config};
+use crate::adaptive::AdaptiveDelay;
+use crate::archive::{self, ArchivedRecord};
+use crate::error_limit::ErrorTracker;
+use crate::full_plan::PlanEntry;
+use crate::keep::{KeepList, MonthlyKeep};
+use crate::rate_limit_log::RateLimitEvent;
+use crate::report;
+use crate::stats::PlatformStats;
+use crate::watermark::Watermarks;
+use crate::{
+    Config, Decision, DeleteOutcome, GroupedPlan, KeptBy, MediaFilter, PlatformSummary,
+    RateLimitMode, Remaining, SkipCounts, SkipReason,
+};
 use anyhow::{Context, Result};
+use chrono::{DateTime, TimeDelta, Utc};
 use log::{info, warn};
 use serde::Deserialize;
-use std::collections::HashSet;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
 
-#[derive(Deserialize)]
+/// Default pause between deletions when no `delay_ms` is configured.
+const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+
+/// Text `REDACT` puts in place of a post's own.
+const REDACTED_TEXT: &str = "[deleted by skyscraper]";
+
+/// How long `wait` mode pauses after a 429 that has no `ratelimit-reset`.
+const DEFAULT_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// Most URIs `app.bsky.feed.getPosts` accepts in one call.
+const GET_POSTS_BATCH: usize = 25;
+
+#[derive(Deserialize, Clone)]
 pub(crate) struct Session {
     did: String,
     #[serde(rename = "accessJwt")]
     access_jwt: String,
+    #[serde(rename = "refreshJwt", default)]
+    refresh_jwt: String,
+}
+
+/// The access token expired mid-run. Refresh the session and retry.
+#[derive(Debug)]
+pub(crate) struct ExpiredToken;
+
+impl std::fmt::Display for ExpiredToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Bluesky session expired")
+    }
+}
+
+impl std::error::Error for ExpiredToken {}
+
+/// Bluesky answered 429. `reset` is when the rate limit window resets, from
+/// the `ratelimit-reset` header, if it was sent.
+#[derive(Debug)]
+pub(crate) struct RateLimited {
+    pub reset: Option<DateTime<Utc>>,
+    /// The XRPC method that was limited, e.g. `com.atproto.repo.deleteRecord`.
+    pub endpoint: String,
+    /// `ratelimit-remaining`, if it was sent.
+    pub remaining: Option<u64>,
+}
+
+impl std::fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.reset {
+            Some(reset) => write!(f, "Bluesky rate limit reached, resets at {reset}"),
+            None => write!(f, "Bluesky rate limit reached"),
+        }
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+impl RateLimited {
+    /// How long until the limit resets, or a default pause if Bluesky
+    /// didn't say.
+    fn wait(&self) -> Duration {
+        self.reset
+            .map(|reset| (reset - Utc::now()).to_std().unwrap_or_default())
+            .unwrap_or(DEFAULT_RATE_LIMIT_WAIT)
+    }
+
+    /// Add this to `RATE_LIMIT_LOG`, if it's set.
+    fn log(&self, config: &Config) {
+        if let Some(log) = &config.rate_limit_log {
+            log.record(&RateLimitEvent {
+                at: Utc::now(),
+                platform: "bluesky",
+                endpoint: &self.endpoint,
+                remaining: self.remaining,
+                reset: self.reset,
+            });
+        }
+    }
+}
+
+/// A 429, or a failure with `ratelimit-remaining: 0`, as [`RateLimited`].
+/// `ratelimit-reset` is in Unix seconds.
+fn rate_limited(resp: &reqwest::Response) -> Option<RateLimited> {
+    let header = |name: &str| resp.headers().get(name).and_then(|v| v.to_str().ok());
+    let exhausted = !resp.status().is_success() && header("ratelimit-remaining") == Some("0");
+    if resp.status() != reqwest::StatusCode::TOO_MANY_REQUESTS && !exhausted {
+        return None;
+    }
+    let reset = header("ratelimit-reset")
+        .and_then(|s| s.parse().ok())
+        .and_then(|secs| DateTime::from_timestamp(secs, 0));
+    let path = resp.url().path();
+    Some(RateLimited {
+        reset,
+        endpoint: path.strip_prefix("/xrpc/").unwrap_or(path).to_string(),
+        remaining: header("ratelimit-remaining").and_then(|s| s.parse().ok()),
+    })
+}
+
+/// A 400 or 401 whose body names `ExpiredToken` as [`ExpiredToken`], any
+/// other as an error carrying the body. Other responses are passed through.
+async fn expired_token(
+    resp: reqwest::Response,
+    failed: impl FnOnce() -> String,
+) -> Result<reqwest::Response> {
+    if !matches!(resp.status().as_u16(), 400 | 401) {
+        return Ok(resp);
+    }
+    let status = resp.status();
+    let body: serde_json::Value = resp.json().await.unwrap_or_default();
+    if body["error"] == "ExpiredToken" {
+        return Err(ExpiredToken.into());
+    }
+    anyhow::bail!("{}: {status} {body}", failed());
 }
 
 #[derive(Deserialize)]
@@ -29,61 +153,355 @@ pub(crate) struct ListRecordsResponse {
 }
 
 #[derive(Deserialize, Clone)]
-struct Record {
+pub(crate) struct ListBlobsResponse {
+    cids: Vec<String>,
+    cursor: Option<String>,
+}
+
+/// `com.atproto.sync.getRepoStatus`, of which only the revision is used.
+#[derive(Deserialize)]
+struct RepoStatusResponse {
+    rev: String,
+}
+
+/// `com.atproto.repo.describeRepo`, of which only the collections are used.
+#[derive(Deserialize)]
+struct DescribeRepoResponse {
+    collections: BTreeSet<String>,
+}
+
+/// A repo's revision and the collections it holds records in. Bluesky
+/// doesn't report a repo's size, so comparing these before and after a
+/// run is how to tell the repo changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RepoStatus {
+    rev: String,
+    collections: BTreeSet<String>,
+}
+
+impl RepoStatus {
+    /// e.g. "rev 3kabc -> 3kdef, collections removed: app.bsky.feed.like"
+    fn delta(&self, after: &RepoStatus) -> String {
+        if self == after {
+            return format!("unchanged at rev {}", self.rev);
+        }
+        let mut parts = vec![format!("rev {} -> {}", self.rev, after.rev)];
+        let removed: Vec<&str> = self
+            .collections
+            .difference(&after.collections)
+            .map(String::as_str)
+            .collect();
+        if !removed.is_empty() {
+            parts.push(format!("collections removed: {}", removed.join(", ")));
+        }
+        let added: Vec<&str> = after
+            .collections
+            .difference(&self.collections)
+            .map(String::as_str)
+            .collect();
+        if !added.is_empty() {
+            parts.push(format!("collections added: {}", added.join(", ")));
+        }
+        parts.join(", ")
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(try_from = "RawRecord")]
+pub(crate) struct Record {
     uri: String,
     value: RecordValue,
+    /// The untouched record value, for archiving.
+    raw: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct RawRecord {
+    uri: String,
+    value: serde_json::Value,
+}
+
+impl TryFrom<RawRecord> for Record {
+    type Error = serde_json::Error;
+
+    fn try_from(raw: RawRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            value: serde_json::from_value(raw.value.clone())?,
+            uri: raw.uri,
+            raw: raw.value,
+        })
+    }
 }
 
 #[derive(Deserialize, Clone)]
 struct RecordValue {
     #[serde(rename = "createdAt")]
     created_at: Option<String>,
+    /// Post text. Unset on likes and reposts.
+    text: Option<String>,
+    embed: Option<Embed>,
+    /// Rich text annotations on post text: mentions, links and tags.
+    #[serde(default)]
+    facets: Vec<Facet>,
+    /// The liked or reposted post, on `app.bsky.feed.like`/`repost` records.
+    /// Other collections use `subject` differently (a DID on list items).
+    #[serde(default, deserialize_with = "strong_ref")]
+    subject: Option<Subject>,
+    /// The thread a reply belongs to. Unset on top-level posts.
+    #[serde(default, deserialize_with = "strong_ref")]
+    reply: Option<ReplyRef>,
+}
+
+fn strong_ref<'de, D, T>(d: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::de::DeserializeOwned,
+{
+    let value = serde_json::Value::deserialize(d)?;
+    Ok(serde_json::from_value(value).ok())
+}
+
+#[derive(Deserialize, Clone)]
+struct Subject {
+    uri: String,
+}
+
+#[derive(Deserialize, Clone)]
+struct ReplyRef {
+    root: Subject,
+}
+
+#[derive(Deserialize, Clone)]
+struct Facet {
+    #[serde(default)]
+    features: Vec<FacetFeature>,
+}
+
+#[derive(Deserialize, Clone)]
+struct FacetFeature {
+    #[serde(rename = "$type")]
+    kind: String,
+}
+
+#[derive(Deserialize, Clone)]
+struct Embed {
+    #[serde(rename = "$type")]
+    kind: String,
+    /// Only set on `app.bsky.embed.recordWithMedia`, wrapping the actual media embed.
+    media: Option<Box<Embed>>,
+    /// Everything else, searched for blob refs (images, video, link thumbnails).
+    #[serde(flatten)]
+    rest: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MediaKind {
+    Text,
+    Images,
+    Video,
+    /// Link cards, quote posts, and anything else without media of its own.
+    Other,
+}
+
+impl RecordValue {
+    fn media_kind(&self) -> MediaKind {
+        self.embed
+            .as_ref()
+            .map_or(MediaKind::Text, Embed::media_kind)
+    }
+
+    /// No embed of any kind (media, link card or quote) and no link facets.
+    fn is_plain_text(&self) -> bool {
+        self.embed.is_none()
+            && !self
+                .facets
+                .iter()
+                .flat_map(|facet| &facet.features)
+                .any(|feature| feature.kind == "app.bsky.richtext.facet#link")
+    }
+
+    /// Total declared size of the blobs this record references. With `cids`,
+    /// only blobs actually stored in the repo count.
+    fn blob_bytes(&self, cids: Option<&HashSet<String>>) -> u64 {
+        let mut blobs = Vec::new();
+        if let Some(embed) = &self.embed {
+            embed.collect_blobs(&mut blobs);
+        }
+        blobs
+            .into_iter()
+            .filter(|(cid, _)| cids.is_none_or(|cids| cids.contains(*cid)))
+            .map(|(_, size)| size)
+            .sum()
+    }
+}
+
+impl Embed {
+    fn media_kind(&self) -> MediaKind {
+        match self.kind.as_str() {
+            "app.bsky.embed.video" => MediaKind::Video,
+            "app.bsky.embed.images" => MediaKind::Images,
+            "app.bsky.embed.recordWithMedia" => self
+                .media
+                .as_deref()
+                .map_or(MediaKind::Other, Embed::media_kind),
+            _ => MediaKind::Other,
+        }
+    }
+
+    fn collect_blobs<'a>(&'a self, out: &mut Vec<(&'a str, u64)>) {
+        for value in self.rest.values() {
+            collect_blob_refs(value, out);
+        }
+        if let Some(media) = &self.media {
+            media.collect_blobs(out);
+        }
+    }
+}
+
+/// Find every `{"$type": "blob", "ref": {"$link": cid}, "size": n}` in `value`.
+fn collect_blob_refs<'a>(value: &'a serde_json::Value, out: &mut Vec<(&'a str, u64)>) {
+    match value {
+        serde_json::Value::Object(map)
+            if map.get("$type").and_then(|t| t.as_str()) == Some("blob") =>
+        {
+            if let Some(cid) = value.pointer("/ref/$link").and_then(|c| c.as_str()) {
+                out.push((cid, map.get("size").and_then(|s| s.as_u64()).unwrap_or(0)));
+            }
+        }
+        serde_json::Value::Object(map) => map.values().for_each(|v| collect_blob_refs(v, out)),
+        serde_json::Value::Array(items) => items.iter().for_each(|v| collect_blob_refs(v, out)),
+        _ => {}
+    }
 }
 
 struct DeleteResult {
     deleted: u64,
-    skipped_pinned: u64,
-    skipped_kept: u64,
+    deleted_videos: u64,
+    skipped: SkipCounts,
+    already_gone: u64,
+    bytes_reclaimed: u64,
+    remaining: Remaining,
+    planned: GroupedPlan,
 }
 
 pub(crate) trait BlueskyClient {
     async fn create_session(&self, identifier: &str, password: &str) -> Result<Session>;
+    /// Swap the current session for a fresh one via its refresh token.
+    async fn refresh_session(&self) -> Result<()>;
     async fn get_pinned_post_uri(&self, did: &str) -> Option<String>;
+    /// Resolve a handle (e.g. a custom domain) to its DID.
+    async fn resolve_handle(&self, handle: &str) -> Result<String>;
     async fn list_records(
         &self,
         did: &str,
         collection: &str,
         cursor: Option<&str>,
     ) -> Result<ListRecordsResponse>;
-    async fn delete_record(&self, did: &str, collection: &str, rkey: &str) -> Result<()>;
+    async fn get_record(&self, did: &str, collection: &str, rkey: &str) -> Result<Record>;
+    /// Whether a record still exists, as opposed to failing to look it up.
+    async fn record_exists(&self, did: &str, collection: &str, rkey: &str) -> Result<bool>;
+    /// Which of up to `GET_POSTS_BATCH` post URIs still exist, in one
+    /// `app.bsky.feed.getPosts` call.
+    async fn existing_posts(&self, uris: &[&str]) -> Result<HashSet<String>>;
+    /// Replies hidden by the threadgates of up to `GET_POSTS_BATCH` thread
+    /// roots, in one `app.bsky.feed.getPosts` call.
+    async fn hidden_replies(&self, roots: &[&str]) -> Result<HashSet<String>>;
+    async fn delete_record(&self, did: &str, collection: &str, rkey: &str)
+        -> Result<DeleteOutcome>;
+    async fn list_blobs(&self, did: &str, cursor: Option<&str>) -> Result<ListBlobsResponse>;
+    async fn repo_status(&self, did: &str) -> Result<RepoStatus>;
+    /// The account's `app.bsky.actor` preferences, each a typed object.
+    async fn get_preferences(&self) -> Result<Vec<serde_json::Value>>;
+    /// Replace the account's preferences wholesale.
+    async fn put_preferences(&self, preferences: Vec<serde_json::Value>) -> Result<()>;
+    /// Write a record at a specific rkey via `com.atproto.repo.putRecord`.
+    async fn put_record(
+        &self,
+        did: &str,
+        collection: &str,
+        rkey: &str,
+        record: serde_json::Value,
+    ) -> Result<()>;
+    async fn create_record(
+        &self,
+        did: &str,
+        collection: &str,
+        record: serde_json::Value,
+    ) -> Result<()>;
 }
 
 pub(crate) struct HttpBlueskyClient {
     client: reqwest::Client,
     pds: String,
-    session: std::sync::OnceLock<Session>,
+    session: RwLock<Option<Session>>,
+    /// Held while refreshing, so requests that hit an expired token together
+    /// (e.g. a prefetch and a delete) refresh the session only once.
+    refresh: tokio::sync::Mutex<()>,
+    /// Handle → DID, so each handle is resolved once per run.
+    handles: Mutex<HashMap<String, String>>,
 }
 
 impl HttpBlueskyClient {
     pub fn new(pds: &str) -> Self {
         Self {
-            client: reqwest::Client::builder()
-                .user_agent("skyscraper/0.1.0")
-                .build()
-                .expect("Failed to build HTTP client"),
+            client: crate::http_client(crate::DEFAULT_USER_AGENT),
             pds: pds.to_string(),
-            session: std::sync::OnceLock::new(),
+            session: RwLock::new(None),
+            refresh: tokio::sync::Mutex::new(()),
+            handles: Mutex::new(HashMap::new()),
         }
     }
 
-    fn session(&self) -> &Session {
-        self.session.get().expect("Session not initialized")
+    pub fn with_user_agent(mut self, user_agent: &str) -> Self {
+        self.client = crate::http_client(user_agent);
+        self
+    }
+
+    fn session(&self) -> Session {
+        self.session
+            .read()
+            .unwrap()
+            .clone()
+            .expect("Session not initialized")
+    }
+
+    fn bearer(&self) -> String {
+        format!("Bearer {}", self.session().access_jwt)
+    }
+
+    /// The post views `app.bsky.feed.getPosts` returns for `uris`.
+    async fn get_posts<T: serde::de::DeserializeOwned>(&self, uris: &[&str]) -> Result<Vec<T>> {
+        #[derive(Deserialize)]
+        struct Posts<T> {
+            posts: Vec<T>,
+        }
+
+        let query: Vec<String> = uris.iter().map(|uri| format!("uris={uri}")).collect();
+        let resp = self
+            .client
+            .get(format!(
+                "{}/xrpc/app.bsky.feed.getPosts?{}",
+                self.pds,
+                query.join("&")
+            ))
+            .header("Authorization", self.bearer())
+            .send()
+            .await?;
+        if let Some(limited) = rate_limited(&resp) {
+            return Err(limited.into());
+        }
+        let found: Posts<T> = resp
+            .error_for_status()
+            .context("Failed to get Bluesky posts")?
+            .json()
+            .await?;
+        Ok(found.posts)
     }
 }
 
 impl BlueskyClient for HttpBlueskyClient {
     async fn create_session(&self, identifier: &str, password: &str) -> Result<Session> {
-        let session: Session = self
+        let resp = self
             .client
             .post(format!(
                 "{}/xrpc/com.atproto.server.createSession",
@@ -94,20 +512,77 @@ impl BlueskyClient for HttpBlueskyClient {
                 "password": password,
             }))
             .send()
-            .await?
+            .await?;
+        if let Some(limited) = rate_limited(&resp) {
+            return Err(limited.into());
+        }
+        let session: Session = resp
             .error_for_status()
             .context("Failed to authenticate with Bluesky")?
             .json()
             .await?;
-        let _ = self.session.set(Session {
-            did: session.did.clone(),
-            access_jwt: session.access_jwt.clone(),
-        });
+        *self.session.write().unwrap() = Some(session.clone());
         Ok(session)
     }
 
+    async fn refresh_session(&self) -> Result<()> {
+        let expired = self.session().access_jwt;
+        let _refreshing = self.refresh.lock().await;
+        // Refresh tokens are single use, so if another request refreshed the
+        // session while this one waited, just use its new token
+        if self.session().access_jwt != expired {
+            return Ok(());
+        }
+        let session: Session = self
+            .client
+            .post(format!(
+                "{}/xrpc/com.atproto.server.refreshSession",
+                self.pds
+            ))
+            .header(
+                "Authorization",
+                format!("Bearer {}", self.session().refresh_jwt),
+            )
+            .send()
+            .await?
+            .error_for_status()
+            .context("Failed to refresh Bluesky session")?
+            .json()
+            .await?;
+        *self.session.write().unwrap() = Some(session);
+        Ok(())
+    }
+
+    async fn resolve_handle(&self, handle: &str) -> Result<String> {
+        if let Some(did) = self.handles.lock().unwrap().get(handle) {
+            return Ok(did.clone());
+        }
+
+        #[derive(Deserialize)]
+        struct Resolved {
+            did: String,
+        }
+
+        let resolved: Resolved = self
+            .client
+            .get(format!(
+                "{}/xrpc/com.atproto.identity.resolveHandle?handle={handle}",
+                self.pds
+            ))
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("Failed to resolve Bluesky handle {handle}"))?
+            .json()
+            .await?;
+        self.handles
+            .lock()
+            .unwrap()
+            .insert(handle.to_string(), resolved.did.clone());
+        Ok(resolved.did)
+    }
+
     async fn get_pinned_post_uri(&self, did: &str) -> Option<String> {
-        let session = self.session();
         let profile_url = format!(
             "{}/xrpc/com.atproto.repo.getRecord?repo={}&collection=app.bsky.actor.profile&rkey=self",
             self.pds, did
@@ -115,7 +590,7 @@ impl BlueskyClient for HttpBlueskyClient {
         match self
             .client
             .get(&profile_url)
-            .header("Authorization", format!("Bearer {}", session.access_jwt))
+            .header("Authorization", self.bearer())
             .send()
             .await
         {
@@ -134,7 +609,6 @@ impl BlueskyClient for HttpBlueskyClient {
         collection: &str,
         cursor: Option<&str>,
     ) -> Result<ListRecordsResponse> {
-        let session = self.session();
         let mut url = format!(
             "{}/xrpc/com.atproto.repo.listRecords?repo={}&collection={}&limit=100",
             self.pds, did, collection
@@ -143,515 +617,5846 @@ impl BlueskyClient for HttpBlueskyClient {
             url.push_str(&format!("&cursor={c}"));
         }
 
-        let resp: ListRecordsResponse = self
+        let resp = self
             .client
             .get(&url)
-            .header("Authorization", format!("Bearer {}", session.access_jwt))
+            .header("Authorization", self.bearer())
             .send()
-            .await?
+            .await?;
+        if let Some(limited) = rate_limited(&resp) {
+            return Err(limited.into());
+        }
+        let resp = expired_token(resp, || {
+            format!("Failed to list Bluesky records for {collection}")
+        })
+        .await?;
+
+        Ok(resp
             .error_for_status()
             .with_context(|| format!("Failed to list Bluesky records for {collection}"))?
             .json()
+            .await?)
+    }
+
+    async fn get_record(&self, did: &str, collection: &str, rkey: &str) -> Result<Record> {
+        let record = self
+            .client
+            .get(format!(
+                "{}/xrpc/com.atproto.repo.getRecord?repo={did}&collection={collection}&rkey={rkey}",
+                self.pds
+            ))
+            .header("Authorization", self.bearer())
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("Failed to get Bluesky record {collection}/{rkey}"))?
+            .json()
             .await?;
+        Ok(record)
+    }
 
-        Ok(resp)
+    async fn record_exists(&self, did: &str, collection: &str, rkey: &str) -> Result<bool> {
+        let resp = self
+            .client
+            .get(format!(
+                "{}/xrpc/com.atproto.repo.getRecord?repo={did}&collection={collection}&rkey={rkey}",
+                self.pds
+            ))
+            .header("Authorization", self.bearer())
+            .send()
+            .await?;
+        if let Some(limited) = rate_limited(&resp) {
+            return Err(limited.into());
+        }
+        match resp.status() {
+            reqwest::StatusCode::NOT_FOUND => return Ok(false),
+            // PDSes report a missing record as a 400 with this error name
+            reqwest::StatusCode::BAD_REQUEST => {
+                let body: serde_json::Value = resp.json().await.unwrap_or_default();
+                if body["error"] == "RecordNotFound" {
+                    return Ok(false);
+                }
+                anyhow::bail!("Failed to get Bluesky record {collection}/{rkey}: {body}");
+            }
+            _ => {}
+        }
+        resp.error_for_status()
+            .with_context(|| format!("Failed to get Bluesky record {collection}/{rkey}"))?;
+        Ok(true)
     }
 
-    async fn delete_record(&self, did: &str, collection: &str, rkey: &str) -> Result<()> {
-        let session = self.session();
-        self.client
+    async fn existing_posts(&self, uris: &[&str]) -> Result<HashSet<String>> {
+        #[derive(Deserialize)]
+        struct Post {
+            uri: String,
+        }
+
+        // Posts that no longer exist are left out of the response
+        let posts: Vec<Post> = self.get_posts(uris).await?;
+        Ok(posts.into_iter().map(|post| post.uri).collect())
+    }
+
+    async fn hidden_replies(&self, roots: &[&str]) -> Result<HashSet<String>> {
+        #[derive(Deserialize)]
+        struct Post {
+            threadgate: Option<Threadgate>,
+        }
+        #[derive(Deserialize)]
+        struct Threadgate {
+            record: ThreadgateRecord,
+        }
+        #[derive(Deserialize)]
+        struct ThreadgateRecord {
+            #[serde(default, rename = "hiddenReplies")]
+            hidden_replies: Vec<String>,
+        }
+
+        let posts: Vec<Post> = self.get_posts(roots).await?;
+        Ok(posts
+            .into_iter()
+            .filter_map(|post| post.threadgate)
+            .flat_map(|gate| gate.record.hidden_replies)
+            .collect())
+    }
+
+    async fn delete_record(
+        &self,
+        did: &str,
+        collection: &str,
+        rkey: &str,
+    ) -> Result<DeleteOutcome> {
+        let resp = self
+            .client
             .post(format!("{}/xrpc/com.atproto.repo.deleteRecord", self.pds))
-            .header("Authorization", format!("Bearer {}", session.access_jwt))
+            .header("Authorization", self.bearer())
             .json(&serde_json::json!({
                 "repo": did,
                 "collection": collection,
                 "rkey": rkey,
             }))
             .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+            .await?;
+        if let Some(limited) = rate_limited(&resp) {
+            return Err(limited.into());
+        }
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(DeleteOutcome::AlreadyGone);
+        }
+        expired_token(resp, || {
+            format!("Failed to delete Bluesky record {collection}/{rkey}")
+        })
+        .await?
+        .error_for_status()?;
+        Ok(DeleteOutcome::Deleted)
     }
-}
-
-async fn delete_old_records(
-    client: &(impl BlueskyClient + Sync),
-    did: &str,
-    collection: &str,
-    label: &str,
-    config: &Config,
-    keep_list: &HashSet<String>,
-    pinned_uri: Option<&str>,
-) -> Result<DeleteResult> {
-    let mut cursor: Option<String> = None;
-    let mut deleted = 0u64;
-    let mut skipped_pinned = 0u64;
-    let mut skipped_kept = 0u64;
 
-    loop {
-        let resp = client
-            .list_records(did, collection, cursor.as_deref())
+    async fn repo_status(&self, did: &str) -> Result<RepoStatus> {
+        let status: RepoStatusResponse = self
+            .client
+            .get(format!(
+                "{}/xrpc/com.atproto.sync.getRepoStatus?did={did}",
+                self.pds
+            ))
+            .header("Authorization", self.bearer())
+            .send()
+            .await?
+            .error_for_status()
+            .context("Failed to get Bluesky repo status")?
+            .json()
+            .await?;
+        let described: DescribeRepoResponse = self
+            .client
+            .get(format!(
+                "{}/xrpc/com.atproto.repo.describeRepo?repo={did}",
+                self.pds
+            ))
+            .header("Authorization", self.bearer())
+            .send()
+            .await?
+            .error_for_status()
+            .context("Failed to describe Bluesky repo")?
+            .json()
             .await?;
+        Ok(RepoStatus {
+            rev: status.rev,
+            collections: described.collections,
+        })
+    }
 
-        if resp.records.is_empty() {
-            break;
+    async fn get_preferences(&self) -> Result<Vec<serde_json::Value>> {
+        #[derive(Deserialize)]
+        struct Preferences {
+            preferences: Vec<serde_json::Value>,
         }
 
-        for record in &resp.records {
-            let Some(ref created_at) = record.value.created_at else {
-                warn!("Record missing createdAt, skipping: {}", record.uri);
-                continue;
-            };
-
-            let post_time = match crate::parse_timestamp(created_at) {
-                Ok(t) => t.with_timezone(&chrono::Utc),
-                Err(e) => {
-                    warn!("Skipping record {}: {e}", record.uri);
-                    continue;
-                }
-            };
+        let prefs: Preferences = self
+            .client
+            .get(format!("{}/xrpc/app.bsky.actor.getPreferences", self.pds))
+            .header("Authorization", self.bearer())
+            .send()
+            .await?
+            .error_for_status()
+            .context("Failed to get Bluesky preferences")?
+            .json()
+            .await?;
+        Ok(prefs.preferences)
+    }
 
-            if post_time >= config.cutoff {
-                continue;
-            }
+    async fn put_preferences(&self, preferences: Vec<serde_json::Value>) -> Result<()> {
+        self.client
+            .post(format!("{}/xrpc/app.bsky.actor.putPreferences", self.pds))
+            .header("Authorization", self.bearer())
+            .json(&serde_json::json!({ "preferences": preferences }))
+            .send()
+            .await?
+            .error_for_status()
+            .context("Failed to put Bluesky preferences")?;
+        Ok(())
+    }
 
-            // rkey is the last segment of the AT URI
-            let rkey = record.uri.rsplit('/').next().context("Invalid AT URI")?;
+    async fn list_blobs(&self, did: &str, cursor: Option<&str>) -> Result<ListBlobsResponse> {
+        let mut url = format!(
+            "{}/xrpc/com.atproto.sync.listBlobs?did={}&limit=1000",
+            self.pds, did
+        );
+        if let Some(c) = cursor {
+            url.push_str(&format!("&cursor={c}"));
+        }
 
-            if pinned_uri == Some(record.uri.as_str()) {
-                skipped_pinned += 1;
-                warn!(
-                    "Skipping pinned post: {}. To keep it permanently, add to your keep file: bluesky:{}",
-                    record.uri, rkey
-                );
-                continue;
-            }
-
-            if is_protected(keep_list, "bluesky", rkey)
-                || is_protected(keep_list, "bluesky", &record.uri)
-            {
-                skipped_kept += 1;
-                info!("Protected, skipping: {}", record.uri);
-                continue;
-            }
-
-            if config.dry_run {
-                info!(
-                    "[DRY RUN] Would delete {label}: {} ({created_at})",
-                    record.uri
-                );
-                deleted += 1;
-                continue;
-            }
+        let resp: ListBlobsResponse = self
+            .client
+            .get(&url)
+            .header("Authorization", self.bearer())
+            .send()
+            .await?
+            .error_for_status()
+            .context("Failed to list Bluesky blobs")?
+            .json()
+            .await?;
 
-            match client.delete_record(did, collection, rkey).await {
-                Ok(()) => {
-                    deleted += 1;
-                    info!("Deleted {label}: {} ({created_at})", record.uri);
-                }
-                Err(e) => {
-                    warn!("Failed to delete {}: {e}", record.uri);
-                }
-            }
+        Ok(resp)
+    }
 
-            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    async fn put_record(
+        &self,
+        did: &str,
+        collection: &str,
+        rkey: &str,
+        record: serde_json::Value,
+    ) -> Result<()> {
+        let resp = self
+            .client
+            .post(format!("{}/xrpc/com.atproto.repo.putRecord", self.pds))
+            .header("Authorization", self.bearer())
+            .json(&serde_json::json!({
+                "repo": did,
+                "collection": collection,
+                "rkey": rkey,
+                "record": record,
+            }))
+            .send()
+            .await?;
+        if let Some(limited) = rate_limited(&resp) {
+            return Err(limited.into());
         }
+        let failed = || format!("Failed to put Bluesky record {collection}/{rkey}");
+        expired_token(resp, failed)
+            .await?
+            .error_for_status()
+            .with_context(failed)?;
+        Ok(())
+    }
 
-        cursor = resp.cursor;
-        if cursor.is_none() {
-            break;
+    async fn create_record(
+        &self,
+        did: &str,
+        collection: &str,
+        record: serde_json::Value,
+    ) -> Result<()> {
+        self.client
+            .post(format!("{}/xrpc/com.atproto.repo.createRecord", self.pds))
+            .header("Authorization", self.bearer())
+            .json(&serde_json::json!({
+                "repo": did,
+                "collection": collection,
+                "record": record,
+            }))
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("Failed to create Bluesky record in {collection}"))?;
+        Ok(())
+    }
+}
+
+/// Make a request, and if the session expired, refresh it and make the
+/// request once more.
+async fn refreshing<T, F>(
+    client: &(impl BlueskyClient + Sync),
+    mut request: impl FnMut() -> F,
+) -> Result<T>
+where
+    F: std::future::Future<Output = Result<T>>,
+{
+    match request().await {
+        Err(e) if e.is::<ExpiredToken>() => {
+            info!("Bluesky session expired, refreshing");
+            client.refresh_session().await?;
+            request().await
         }
+        result => result,
     }
+}
 
-    Ok(DeleteResult {
-        deleted,
-        skipped_pinned,
-        skipped_kept,
-    })
+/// One `listRecords` page. If the session expired, refresh it and request
+/// the same page again, so pagination resumes from `cursor` instead of
+/// restarting the collection.
+async fn list_page(
+    client: &(impl BlueskyClient + Sync),
+    did: &str,
+    collection: &str,
+    cursor: Option<&str>,
+) -> Result<ListRecordsResponse> {
+    refreshing(client, || client.list_records(did, collection, cursor)).await
 }
 
-pub async fn delete_old_posts(
+/// After a 429, `wait` mode sleeps until the limit resets and returns `true`
+/// so the request is retried. `defer` mode returns `false` so the sweep stops
+/// and leaves the rest for the next run, as does Ctrl-C during the wait.
+async fn wait_for_reset(limited: &RateLimited, config: &Config) -> bool {
+    limited.log(config);
+    if config.rate_limit_mode == RateLimitMode::Defer {
+        warn!("{limited}. Remaining records will be cleaned up on the next run.");
+        return false;
+    }
+    let wait = limited.wait();
+    warn!("{limited}, waiting {}s", wait.as_secs());
+    config.shutdown.sleep(wait).await
+}
+
+/// Log in with `createSession`, which Bluesky rate limits separately and far
+/// more tightly than other calls. On a 429, `wait` mode sleeps until the
+/// limit resets and tries once more; `defer` mode fails saying when to.
+async fn login(
     client: &(impl BlueskyClient + Sync),
     identifier: &str,
     password: &str,
     config: &Config,
-    keep_list: &HashSet<String>,
-) -> Result<()> {
-    // Authenticate
-    let session = client.create_session(identifier, password).await?;
-    info!("Authenticated as {}", session.did);
+) -> Result<Session> {
+    let attempt = || client.create_session(identifier, password);
+    let err = match crate::retry_login("Bluesky", &config.shutdown, attempt).await {
+        Ok(session) => return Ok(session),
+        Err(e) => e,
+    };
+    let Some(limited) = err.downcast_ref::<RateLimited>() else {
+        return Err(err);
+    };
+    limited.log(config);
+    let retry_at = match limited.reset {
+        Some(reset) => format!("after {reset}"),
+        None => "later".to_string(),
+    };
+    if config.rate_limit_mode == RateLimitMode::Defer {
+        return Err(err.context(format!(
+            "Bluesky login (createSession) is rate limited, try again {retry_at} or set BLUESKY_RATE_LIMIT_MODE=wait"
+        )));
+    }
+    let wait = limited.wait();
+    warn!(
+        "Bluesky login (createSession) is rate limited, waiting {}s to retry",
+        wait.as_secs()
+    );
+    if !config.shutdown.sleep(wait).await {
+        anyhow::bail!("Interrupted while waiting for the Bluesky login rate limit to reset");
+    }
+    client
+        .create_session(identifier, password)
+        .await
+        .context("Bluesky login (createSession) failed again after the rate limit reset")
+}
 
-    // Fetch pinned post URI from profile
-    let pinned_uri: Option<String> = if !config.delete_pinned {
-        client.get_pinned_post_uri(&session.did).await
+async fn delete_old_records(
+    client: &(impl BlueskyClient + Sync),
+    did: &str,
+    collection: &str,
+    delay: Duration,
+    config: &Config,
+    keep_list: &KeepList,
+    pinned_uri: Option<&str>,
+) -> Result<DeleteResult> {
+    let mut cursor: Option<String> = None;
+    let mut deleted = 0u64;
+    let mut deleted_videos = 0u64;
+    let mut skipped = SkipCounts::default();
+    let mut already_gone = 0u64;
+    let mut bytes_reclaimed = 0u64;
+    let mut remaining = Remaining::default();
+    let mut planned = GroupedPlan::default();
+    // Deletions can shift a listing so a later page repeats records. Handle
+    // each once so nothing is double counted or deleted twice.
+    let mut seen: HashSet<String> = HashSet::new();
+    let now = chrono::Utc::now();
+    // "post", "repost" or "like", for logs
+    let label = collection.rsplit('.').next().unwrap_or(collection);
+    // REDACT rewrites posts in place; likes and reposts are still deleted
+    let redact = config.redact && collection == "app.bsky.feed.post";
+    // PURGE_ALL_REPOSTS deletes recent reposts too, so every page needs a look
+    let purge = config.purge_all_reposts && collection == "app.bsky.feed.repost";
+    let verb = if redact { "redact" } else { "delete" };
+    let action = if redact {
+        report::Action::Redact
     } else {
-        None
+        report::Action::Delete
     };
+    let mut adaptive = config.adaptive_delay.map(|b| AdaptiveDelay::new(delay, b));
+    let mut errors = ErrorTracker::new(config.error_limits);
 
-    // Delete old posts
-    let posts = delete_old_records(
-        client,
-        &session.did,
-        "app.bsky.feed.post",
-        "post",
-        config,
-        keep_list,
-        pinned_uri.as_deref(),
-    )
-    .await?;
-
-    info!(
-        "Bluesky posts: deleted {}, skipped {} pinned, skipped {} kept",
-        posts.deleted, posts.skipped_pinned, posts.skipped_kept
-    );
+    let mut monthly = (collection == "app.bsky.feed.post" && config.keep_one_per_month)
+        .then(|| MonthlyKeep::new(config.display_tz));
+    let hidden_config = (collection == "app.bsky.feed.post")
+        .then(|| hidden_reply_config(config))
+        .flatten();
 
-    // Delete old reposts
-    if config.delete_reposts {
-        let reposts = delete_old_records(
-            client,
-            &session.did,
-            "app.bsky.feed.repost",
-            "repost",
-            config,
-            keep_list,
-            None,
-        )
-        .await?;
+    let shared_blobs = if collection == "app.bsky.feed.post" && config.keep_shared_blobs {
+        Some(blob_references(client, did).await?)
+    } else {
+        None
+    };
 
-        info!(
-            "Bluesky reposts: deleted {}, skipped {} kept",
-            reposts.deleted, reposts.skipped_kept
-        );
-    }
+    // Ordering by media size needs every post up front, so it's one big page
+    let blob_cids = if collection == "app.bsky.feed.post" && config.largest_media_first {
+        Some(list_all_blob_cids(client, did).await?)
+    } else {
+        None
+    };
+    // A redacted record stays listed, and LARGEST_MEDIA_FIRST's one big page
+    // has no cursor to re-list it by
+    let verify = config.verify_each_page && !config.dry_run && !redact && blob_cids.is_none();
 
-    // Delete old likes
-    if config.delete_likes {
-        let likes = delete_old_records(
-            client,
-            &session.did,
-            "app.bsky.feed.like",
-            "like",
-            config,
-            keep_list,
-            None,
-        )
-        .await?;
+    // Listings are newest first, so with INCREMENTAL a page entirely at or
+    // below the last completed sweep's newest deletion means the rest was
+    // already swept. Only ordinary deletions move the mark: a force-deleted
+    // recent post says nothing about the posts older than it.
+    let watermark = match (&config.incremental_state_file, &blob_cids) {
+        (Some(path), None) => Watermarks::load(path)?.get(collection),
+        _ => None,
+    };
+    let mut newest_deleted: Option<DateTime<Utc>> = None;
+    // The oldest record still here that a later run must see again: one that
+    // failed to delete, or is kept by an entry with an `until`
+    let mut held_back: Option<DateTime<Utc>> = None;
+    let mut completed = false;
+    // Set when a rate limit in `defer` mode ends the sweep early
+    let mut deferred = false;
+    // With PREFETCH_PAGES, the page at `cursor`, fetched while the previous
+    // page was processed
+    let mut prefetched: Option<Result<ListRecordsResponse>> = None;
 
-        info!(
-            "Bluesky likes: deleted {}, skipped {} kept",
-            likes.deleted, likes.skipped_kept
-        );
-    }
+    loop {
+        let (records, next_cursor) = match &blob_cids {
+            Some(cids) => (
+                records_by_blob_size(client, did, collection, cids).await?,
+                None,
+            ),
+            None => {
+                let page = match prefetched.take() {
+                    Some(page) => page,
+                    None => list_page(client, did, collection, cursor.as_deref()).await,
+                };
+                match page {
+                    Ok(resp) => (resp.records, resp.cursor),
+                    Err(e) => match e.downcast_ref::<RateLimited>() {
+                        Some(limited) if wait_for_reset(limited, config).await => continue,
+                        Some(_) => break,
+                        None => return Err(e),
+                    },
+                }
+            }
+        };
 
-    Ok(())
-}
+        if records.is_empty() {
+            completed = true;
+            break;
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::test_helpers::*;
-    use std::sync::Mutex;
+        // A maintained account's whole collection is often one page of recent
+        // records, which there's no point walking one by one
+        if cursor.is_none()
+            && next_cursor.is_none()
+            && !purge
+            && nothing_to_do(&records, config, now)
+        {
+            info!(
+                "Nothing to do for {label}s: all {} are newer than the cutoff",
+                records.len()
+            );
+            for record in &records {
+                if let Ok(t) = record_time(record, config) {
+                    remaining.observe(t);
+                    if let Some(csv) = &config.remaining_csv {
+                        csv.record("bluesky", collection, &record.uri, t);
+                    }
+                }
+            }
+            completed = true;
+            break;
+        }
 
-    const DID: &str = "did:plc:testuser123";
-    const PINNED_URI: &str = "at://did:plc:testuser123/app.bsky.feed.post/pinned1";
+        if let Some(mark) = watermark {
+            let below = |r: &Record| record_time(r, config).map_or(true, |t| t <= mark);
+            if records.iter().all(below) {
+                info!("Incremental: {label}s up to {mark} were swept by an earlier run, stopping");
+                completed = true;
+                break;
+            }
+        }
 
-    struct FakeBlueskyClient {
-        did: String,
-        pinned_post: Option<String>,
-        records: Mutex<std::collections::HashMap<String, Vec<Record>>>,
-        deleted: Mutex<Vec<(String, String)>>,
-        page_size: usize,
-    }
+        let hidden = match &hidden_config {
+            Some(_) => hidden_replies_among(client, &records, config).await,
+            None => HashSet::new(),
+        };
+        // VERIFY_EACH_PAGE: what this page deleted, to look for once it's done
+        let mut page_deleted: Vec<(&str, DateTime<Utc>)> = Vec::new();
 
-    impl FakeBlueskyClient {
-        fn new(did: &str) -> Self {
-            Self {
-                did: did.to_string(),
-                pinned_post: None,
-                records: Mutex::new(std::collections::HashMap::new()),
-                deleted: Mutex::new(Vec::new()),
-                page_size: 100,
+        // The next page only depends on the cursor, so it can be fetched while
+        // this page's deletions and the delays between them run
+        let prefetch = async {
+            match (&next_cursor, config.prefetch_pages) {
+                (Some(next), true) => Some(list_page(client, did, collection, Some(next)).await),
+                _ => None,
             }
-        }
+        };
+        let process = async {
+            for record in &records {
+                if !seen.insert(record.uri.clone()) {
+                    continue;
+                }
+                // Recent records never reach the keep check, so match them here
+                // too or their entries would look unused
+                if config.keep_audit {
+                    keep_decision(record, keep_list);
+                }
+                let post_time = match record_time(record, config) {
+                    Ok(t) => t,
+                    Err(decision) => {
+                        match decision {
+                            Decision::MissingTimestamp => skipped.add(SkipReason::MissingTimestamp),
+                            Decision::UnparseableTimestamp => {
+                                skipped.add(SkipReason::UnparseableTimestamp)
+                            }
+                            _ => {}
+                        }
+                        warn!("Skipping record {}: {decision}", record.uri);
+                        continue;
+                    }
+                };
+                if config.keep_audit {
+                    keep_list.matches_time("bluesky", post_time);
+                }
+                let created_at = record
+                    .value
+                    .created_at
+                    .clone()
+                    .unwrap_or_else(|| format!("{} from rkey", post_time.to_rfc3339()));
 
-        fn with_pinned_post(mut self, uri: &str) -> Self {
-            self.pinned_post = Some(uri.to_string());
-            self
-        }
+                // rkey is the last segment of the AT URI
+                let rkey = record.uri.rsplit('/').next().context("Invalid AT URI")?;
 
-        fn with_records(self, collection: &str, records: Vec<Record>) -> Self {
-            self.records
-                .lock()
-                .unwrap()
-                .insert(collection.to_string(), records);
-            self
-        }
+                let record_config = match &hidden_config {
+                    Some(hidden_config) if hidden.contains(&record.uri) => hidden_config,
+                    _ => config,
+                };
+                let decision = evaluate(
+                    record,
+                    post_time,
+                    collection,
+                    record_config,
+                    keep_list,
+                    pinned_uri,
+                    now,
+                );
+                let forced = match check_self_like(decision, record, did, record_config) {
+                    Decision::Delete => false,
+                    // Like force deletes, blocklisted posts skip the keep rules
+                    // applied past this point and don't move the watermark
+                    Decision::ForceDelete | Decision::Blocklisted => true,
+                    decision => {
+                        remaining.observe(post_time);
+                        if !matches!(decision, Decision::NewerThanCutoff) {
+                            config.counters.add_skipped();
+                        } else if let Some(csv) = &config.remaining_csv {
+                            csv.record("bluesky", collection, &record.uri, post_time);
+                        }
+                        match decision {
+                            // Clock-skewed clients sometimes write createdAt in the future.
+                            // These are never deleted (always newer than the cutoff), but
+                            // flag them so they don't silently linger forever.
+                            Decision::FutureDated => {
+                                skipped.add(SkipReason::FutureDated);
+                                warn!(
+                                "Record has a future createdAt ({created_at}), likely client clock skew: {}",
+                                record.uri
+                            );
+                            }
+                            Decision::Pinned => {
+                                skipped.add(SkipReason::Pinned);
+                                let what = if collection == "app.bsky.feed.repost" {
+                                    "repost of pinned post"
+                                } else {
+                                    "pinned post"
+                                };
+                                warn!(
+                                "Skipping {what}: {}. To keep it permanently, add to your keep file: bluesky:{}",
+                                record.uri, rkey
+                            );
+                            }
+                            Decision::Kept(by, entry) => {
+                                if keep_list.expires(&entry) {
+                                    held_back =
+                                        Some(held_back.map_or(post_time, |t| t.min(post_time)));
+                                }
+                                skipped.add_kept(by);
+                                info!("Protected, skipping: {}", record.uri);
+                            }
+                            Decision::Sampled => {
+                                skipped.add(SkipReason::Sampled);
+                                info!("Kept in the sample, skipping: {}", record.uri);
+                            }
+                            Decision::SelfLike => {
+                                skipped.add(SkipReason::SelfLike);
+                                info!("Like of your own post, keeping: {}", record.uri);
+                            }
+                            Decision::Imported => {
+                                skipped.add(SkipReason::Imported);
+                                info!("Imported before IMPORT_BOUNDARY, keeping: {}", record.uri);
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                };
 
-        fn with_page_size(mut self, size: usize) -> Self {
-            self.page_size = size;
-            self
-        }
+                // Force-deleted posts go regardless, like with every other rule
+                let shared = shared_blobs.as_ref().filter(|_| !forced);
+                if let Some((cid, other)) = shared.and_then(|refs| shared_blob(record, refs)) {
+                    remaining.observe(post_time);
+                    skipped.add(SkipReason::SharedBlob);
+                    config.counters.add_skipped();
+                    warn!(
+                        "Skipping {}: its blob {cid} is also used by {other}",
+                        record.uri
+                    );
+                    continue;
+                }
 
-        fn deleted(&self) -> Vec<(String, String)> {
-            self.deleted.lock().unwrap().clone()
-        }
-    }
+                if let Some(monthly) = monthly.as_mut().filter(|_| !forced) {
+                    if monthly.keeps(post_time) {
+                        remaining.observe(post_time);
+                        skipped.add(SkipReason::OnePerMonth);
+                        config.counters.add_skipped();
+                        info!("Newest post of its month, keeping: {}", record.uri);
+                        continue;
+                    }
+                }
 
-    impl BlueskyClient for FakeBlueskyClient {
-        async fn create_session(&self, _identifier: &str, _password: &str) -> Result<Session> {
-            Ok(Session {
-                did: self.did.clone(),
-                access_jwt: "fake".to_string(),
-            })
-        }
+                let media_kind = record.value.media_kind();
 
-        async fn get_pinned_post_uri(&self, _did: &str) -> Option<String> {
-            self.pinned_post.clone()
-        }
+                if redact && record.value.text.as_deref() == Some(REDACTED_TEXT) {
+                    already_gone += 1;
+                    continue;
+                }
 
-        async fn list_records(
-            &self,
-            _did: &str,
-            collection: &str,
-            cursor: Option<&str>,
-        ) -> Result<ListRecordsResponse> {
-            let records_map = self.records.lock().unwrap();
-            let all_records = match records_map.get(collection) {
-                Some(r) => r,
-                None => {
-                    return Ok(ListRecordsResponse {
-                        records: vec![],
-                        cursor: None,
-                    })
+                if config.dry_run {
+                    if config.dry_run_grouped {
+                        planned.add(post_time, format!("{label}: {} ({created_at})", record.uri));
+                    } else {
+                        info!(
+                            "[DRY RUN] Would {verb} {label}: {} ({created_at})",
+                            record.uri
+                        );
+                    }
+                    deleted += 1;
+                    config.counters.add_deleted();
+                    if media_kind == MediaKind::Video {
+                        deleted_videos += 1;
+                    }
+                    bytes_reclaimed += record.value.blob_bytes(blob_cids.as_ref());
+                    if let Some(report) = &config.report {
+                        report.record("bluesky", collection, &record.uri, Some(post_time), action);
+                    }
+                    continue;
                 }
-            };
 
-            let start: usize = cursor.and_then(|c| c.parse().ok()).unwrap_or(0);
-            let end = (start + self.page_size).min(all_records.len());
-            let page = all_records[start..end].to_vec();
-            let next_cursor = if end < all_records.len() {
-                Some(end.to_string())
-            } else {
-                None
-            };
+                if let Some(dir) = &config.archive_dir {
+                    let archived = ArchivedRecord {
+                        uri: record.uri.clone(),
+                        collection: collection.to_string(),
+                        rkey: rkey.to_string(),
+                        value: record.raw.clone(),
+                    };
+                    if let Err(e) = archive::save(dir, &archived) {
+                        remaining.observe(post_time);
+                        warn!("Not deleting {} without an archive copy: {e:#}", record.uri);
+                        continue;
+                    }
+                }
 
-            Ok(ListRecordsResponse {
-                records: page,
-                cursor: next_cursor,
-            })
+                let outcome = loop {
+                    let started = tokio::time::Instant::now();
+                    let attempt = if redact {
+                        refreshing(client, || {
+                            client.put_record(did, collection, rkey, redacted(&record.raw))
+                        })
+                        .await
+                        .map(|()| DeleteOutcome::Deleted)
+                    } else {
+                        refreshing(client, || client.delete_record(did, collection, rkey)).await
+                    };
+                    if let Some(adaptive) = adaptive.as_mut() {
+                        adaptive.observe(started.elapsed());
+                    }
+                    match attempt {
+                        Err(e) => match e.downcast_ref::<RateLimited>() {
+                            Some(limited) if wait_for_reset(limited, config).await => continue,
+                            _ => break Err(e),
+                        },
+                        outcome => break outcome,
+                    }
+                };
+                if outcome.is_ok() {
+                    errors.success();
+                }
+                match outcome {
+                    Ok(DeleteOutcome::Deleted) => {
+                        deleted += 1;
+                        config.counters.add_deleted();
+                        if media_kind == MediaKind::Video {
+                            deleted_videos += 1;
+                        }
+                        bytes_reclaimed += record.value.blob_bytes(blob_cids.as_ref());
+                        if let Some(report) = &config.report {
+                            report.record(
+                                "bluesky",
+                                collection,
+                                &record.uri,
+                                Some(post_time),
+                                action,
+                            );
+                        }
+                        if !forced {
+                            newest_deleted = newest_deleted.max(Some(post_time));
+                        }
+                        if verify {
+                            page_deleted.push((&record.uri, post_time));
+                        }
+                        if redact {
+                            info!("Redacted {label}: {} ({created_at})", record.uri);
+                        } else {
+                            info!("Deleted {label}: {} ({created_at})", record.uri);
+                        }
+                    }
+                    Ok(DeleteOutcome::AlreadyGone) => {
+                        already_gone += 1;
+                        info!("Already gone: {}", record.uri);
+                    }
+                    Err(e) if e.is::<RateLimited>() => {
+                        remaining.observe(post_time);
+                        deferred = true;
+                        break;
+                    }
+                    Err(e) => {
+                        remaining.observe(post_time);
+                        held_back = Some(held_back.map_or(post_time, |t| t.min(post_time)));
+                        config.counters.add_error();
+                        warn!("Failed to {verb} {}: {e}", record.uri);
+                        errors.failure()?;
+                    }
+                }
+
+                let pause = adaptive.as_ref().map_or(delay, AdaptiveDelay::current);
+                if !config.shutdown.sleep(pause).await {
+                    break;
+                }
+            }
+            Ok::<_, anyhow::Error>(())
+        };
+        let (next_page, processed) = tokio::join!(prefetch, process);
+        processed?;
+        prefetched = next_page;
+
+        if !page_deleted.is_empty() {
+            match list_page(client, did, collection, cursor.as_deref()).await {
+                Ok(resp) => {
+                    let listed: HashSet<&str> =
+                        resp.records.iter().map(|r| r.uri.as_str()).collect();
+                    let survivors: Vec<_> = page_deleted
+                        .iter()
+                        .filter(|(uri, _)| listed.contains(uri))
+                        .collect();
+                    for (uri, post_time) in &survivors {
+                        remaining.observe(*post_time);
+                        held_back = Some(held_back.map_or(*post_time, |t| t.min(*post_time)));
+                        warn!("Deleted {label} is still listed: {uri}");
+                    }
+                    if survivors.is_empty() {
+                        info!(
+                            "Verified this page's {} deleted {label}s are gone",
+                            page_deleted.len()
+                        );
+                    }
+                }
+                Err(e) => warn!("Could not re-list the page to verify its deletions: {e:#}"),
+            }
         }
 
-        async fn delete_record(&self, _did: &str, collection: &str, rkey: &str) -> Result<()> {
-            self.deleted
-                .lock()
-                .unwrap()
-                .push((collection.to_string(), rkey.to_string()));
-            Ok(())
+        cursor = next_cursor;
+        if config.shutdown.is_triggered() || deferred {
+            break;
+        }
+        if cursor.is_none() {
+            completed = true;
+            break;
         }
     }
 
+    // An interrupted sweep left older pages unvisited, so it can't vouch for
+    // them, and the mark stays below anything held back for a later run
+    if let (Some(path), Some(newest), true) =
+        (&config.incremental_state_file, newest_deleted, completed)
+    {
+        let newest = match held_back {
+            Some(t) => newest.min(t - TimeDelta::milliseconds(1)),
+            None => newest,
+        };
+        let mut marks = Watermarks::load(path)?;
+        marks.advance(collection, newest);
+        marks.save(path)?;
+    }
+
+    Ok(DeleteResult {
+        deleted,
+        deleted_videos,
+        skipped,
+        already_gone,
+        bytes_reclaimed,
+        remaining,
+        planned,
+    })
+}
+
+/// Whether a sweep of `records` could only skip them all as newer than the
+/// cutoff. Future-dated records still need their warning, a forced delete can
+/// target a recent record, `KEEP_AUDIT` needs to see every record, and a
+/// recent reply can be old enough for `DELETE_HIDDEN_SOONER`.
+fn nothing_to_do(records: &[Record], config: &Config, now: DateTime<Utc>) -> bool {
+    config.force_delete.is_empty()
+        && config.delete_blocklist.is_none()
+        && config.hidden_cutoff.is_none()
+        && !config.keep_audit
+        && records
+            .iter()
+            .all(|r| record_time(r, config).is_ok_and(|t| t >= config.cutoff && t <= now))
+}
+
+/// `config` with `DELETE_HIDDEN_SOONER`'s cutoff in place of the normal
+/// one, for deciding hidden replies, or `None` if it isn't set.
+fn hidden_reply_config(config: &Config) -> Option<Config> {
+    config.hidden_cutoff.map(|hidden| Config {
+        cutoff: hidden.max(config.cutoff),
+        ..config.clone()
+    })
+}
+
+/// `DELETE_HIDDEN_SOONER`: which of `records` are replies hidden by the
+/// threadgate of their thread's root. Only replies past the shorter cutoff
+/// but not the normal one are looked up, since the answer changes nothing
+/// for the rest. A root that can't be looked up counts as hiding nothing.
+async fn hidden_replies_among(
+    client: &(impl BlueskyClient + Sync),
+    records: &[Record],
+    config: &Config,
+) -> HashSet<String> {
+    let Some(hidden_cutoff) = config.hidden_cutoff else {
+        return HashSet::new();
+    };
+    let candidates: Vec<&Record> = records
+        .iter()
+        .filter(|r| r.value.reply.is_some())
+        .filter(|r| record_time(r, config).is_ok_and(|t| t < hidden_cutoff && t >= config.cutoff))
+        .collect();
+    let roots: BTreeSet<&str> = candidates
+        .iter()
+        .filter_map(|r| r.value.reply.as_ref())
+        .map(|reply| reply.root.uri.as_str())
+        .collect();
+    let roots: Vec<&str> = roots.into_iter().collect();
+    let mut hidden = HashSet::new();
+    for batch in roots.chunks(GET_POSTS_BATCH) {
+        match client.hidden_replies(batch).await {
+            Ok(found) => hidden.extend(found),
+            Err(e) => warn!(
+                "Could not check {} threads for hidden replies: {e:#}",
+                batch.len()
+            ),
+        }
+    }
+    candidates
+        .into_iter()
+        .filter(|r| hidden.contains(&r.uri))
+        .map(|r| r.uri.clone())
+        .collect()
+}
+
+/// `record` with its text replaced by [`REDACTED_TEXT`] and its embed and
+/// facets dropped. `reply` and `createdAt` stay, so threads keep their shape.
+fn redacted(record: &serde_json::Value) -> serde_json::Value {
+    let mut record = record.clone();
+    if let Some(fields) = record.as_object_mut() {
+        fields.insert("text".into(), REDACTED_TEXT.into());
+        fields.remove("embed");
+        fields.remove("facets");
+    }
+    record
+}
+
+/// `createdAt` of a record in UTC, or why a record without a usable one is
+/// skipped. With `FALLBACK_RKEY_TIME`, a record with no `createdAt` at all
+/// falls back to the time encoded in its rkey, if that's a TID. With
+/// `TREAT_FUTURE_AS_NOW`, a time in the future counts as now.
+fn record_time(record: &Record, config: &Config) -> Result<DateTime<Utc>, Decision> {
+    let time = match record.value.created_at.as_deref() {
+        Some(created_at) => crate::parse_timestamp(created_at)
+            .map(|t| t.with_timezone(&Utc))
+            .map_err(|_| Decision::UnparseableTimestamp)?,
+        None => {
+            let rkey = record.uri.rsplit('/').next().unwrap_or_default();
+            tid_time(rkey)
+                .filter(|_| config.fallback_rkey_time)
+                .ok_or(Decision::MissingTimestamp)?
+        }
+    };
+    Ok(if config.treat_future_as_now {
+        time.min(Utc::now())
+    } else {
+        time
+    })
+}
+
+/// The creation time encoded in a TID rkey, or `None` if `rkey` isn't a TID.
+///
+/// A TID is 13 characters of base32-sortable (`234567a-z`) encoding a 64-bit
+/// integer: a zero top bit, 53 bits of microseconds since the Unix epoch,
+/// then a 10-bit clock identifier.
+fn tid_time(rkey: &str) -> Option<DateTime<Utc>> {
+    const ALPHABET: &[u8] = b"234567abcdefghijklmnopqrstuvwxyz";
+    if rkey.len() != 13 {
+        return None;
+    }
+    let mut value = 0u64;
+    for (i, c) in rkey.bytes().enumerate() {
+        let digit = ALPHABET.iter().position(|&a| a == c)? as u64;
+        // The first character carries the top bit, which must be zero
+        if i == 0 && digit >= 16 {
+            return None;
+        }
+        value = (value << 5) | digit;
+    }
+    DateTime::from_timestamp_micros((value >> 10) as i64)
+}
+
+/// Run a record created at `post_time` through the cutoff, filter, pinned and
+/// keep checks of a sweep, in the same order, without side effects.
+fn evaluate(
+    record: &Record,
+    post_time: DateTime<Utc>,
+    collection: &str,
+    config: &Config,
+    keep_list: &KeepList,
+    pinned_uri: Option<&str>,
+    now: DateTime<Utc>,
+) -> Decision {
+    let rkey = record.uri.rsplit('/').next().unwrap_or_default();
+    if config.force_delete.is_protected("bluesky", rkey)
+        || config.force_delete.is_protected("bluesky", &record.uri)
+    {
+        return Decision::ForceDelete;
+    }
+    if collection == "app.bsky.feed.repost" && !config.delete_reposts {
+        return Decision::RepostsDisabled;
+    }
+    if collection == "app.bsky.feed.like" && !config.delete_likes {
+        return Decision::LikesDisabled;
+    }
+    // Urgent cleanup: age and content filters don't apply, but the pinned
+    // post and explicit keep entries still do
+    let text = record.value.text.as_deref().unwrap_or_default();
+    if collection == "app.bsky.feed.post"
+        && config
+            .delete_blocklist
+            .as_ref()
+            .is_some_and(|b| b.matches(text))
+    {
+        if pinned_uri.is_some_and(|pinned| is_pinned(record, collection, pinned)) {
+            return Decision::Pinned;
+        }
+        if let Some(kept) = keep_decision(record, keep_list) {
+            return kept;
+        }
+        return Decision::Blocklisted;
+    }
+    let purge = config.purge_all_reposts && collection == "app.bsky.feed.repost";
+    // Only a cutoff in the future (DELETE_ACCOUNT_DATA) reaches past now
+    if post_time > now && post_time >= config.cutoff && !purge {
+        return Decision::FutureDated;
+    }
+    if post_time >= config.cutoff && !purge {
+        return Decision::NewerThanCutoff;
+    }
+    // Importers backdate createdAt to when the original was posted
+    if collection == "app.bsky.feed.post"
+        && config.import_boundary.is_some_and(|b| post_time < b)
+        && config.import_cutoff.is_none_or(|c| post_time >= c)
+    {
+        return Decision::Imported;
+    }
+    // Media filters only make sense for posts; likes and reposts have no embeds
+    if collection == "app.bsky.feed.post"
+        && config.media_filter == MediaFilter::VideoOnly
+        && record.value.media_kind() != MediaKind::Video
+    {
+        return Decision::MediaFiltered;
+    }
+    if collection == "app.bsky.feed.post"
+        && config.delete_plain_text_only
+        && !record.value.is_plain_text()
+    {
+        return Decision::HasMediaOrLinks;
+    }
+    if let Some(prefix) = &config.delete_text_prefix {
+        let text = record.value.text.as_deref().unwrap_or_default();
+        if collection == "app.bsky.feed.post" && !text.starts_with(prefix.as_str()) {
+            return Decision::TextPrefixFiltered;
+        }
+    }
+    if pinned_uri.is_some_and(|pinned| is_pinned(record, collection, pinned)) {
+        return Decision::Pinned;
+    }
+    if let Some(kept) = keep_decision(record, keep_list) {
+        return kept;
+    }
+    if let Some(entry) = keep_list.matches_time("bluesky", post_time) {
+        return Decision::Kept(KeptBy::Date, entry.to_string());
+    }
+    if collection == "app.bsky.feed.post"
+        && config.keep_sample.is_some_and(|s| s.keeps(&record.uri))
+    {
+        return Decision::Sampled;
+    }
+    Decision::Delete
+}
+
+/// Whether `record` is the pinned post itself, or your repost of it.
+fn is_pinned(record: &Record, collection: &str, pinned_uri: &str) -> bool {
+    record.uri == pinned_uri
+        || (collection == "app.bsky.feed.repost"
+            && record
+                .value
+                .subject
+                .as_ref()
+                .is_some_and(|s| s.uri == pinned_uri))
+}
+
+/// `decision`, except that with `KEEP_SELF_LIKES` a like of one of `did`'s
+/// posts is kept.
+fn check_self_like(decision: Decision, record: &Record, did: &str, config: &Config) -> Decision {
+    match decision {
+        Decision::Delete
+            if config.keep_self_likes
+                && record.uri.contains("/app.bsky.feed.like/")
+                && record
+                    .value
+                    .subject
+                    .as_ref()
+                    .and_then(|s| uri_author(&s.uri))
+                    == Some(did) =>
+        {
+            Decision::SelfLike
+        }
+        decision => decision,
+    }
+}
+
+/// The repo DID of an AT URI, `at://<DID>/<collection>/<rkey>`.
+fn uri_author(uri: &str) -> Option<&str> {
+    uri.strip_prefix("at://").and_then(|r| r.split('/').next())
+}
+
+/// The keep list entry protecting `record`, by rkey, URI, or the subject or
+/// author of a like or repost.
+fn keep_decision(record: &Record, keep_list: &KeepList) -> Option<Decision> {
+    let rkey = record.uri.rsplit('/').next().unwrap_or_default();
+    if let Some(entry) = keep_list.matches("bluesky", rkey) {
+        return Some(Decision::Kept(KeptBy::Rkey, entry.to_string()));
+    }
+    if let Some(entry) = keep_list.matches("bluesky", &record.uri) {
+        return Some(Decision::Kept(KeptBy::Uri, entry.to_string()));
+    }
+    let subject = record.value.subject.as_ref()?;
+    if keep_list.is_subject_protected("bluesky", &subject.uri) {
+        return Some(Decision::Kept(
+            KeptBy::Subject,
+            format!("bluesky:subject:{}", subject.uri),
+        ));
+    }
+    let entry = uri_author(&subject.uri).and_then(|did| keep_list.author_entry("bluesky", did))?;
+    Some(Decision::Kept(KeptBy::Author, entry.to_string()))
+}
+
+/// `EXPLAIN_ID` for Bluesky: fetch one record by rkey (a post) or AT URI and
+/// decide it the way a sweep would.
+pub async fn explain(
+    client: &(impl BlueskyClient + Sync),
+    identifier: &str,
+    password: &str,
+    id: &str,
+    config: &Config,
+    keep_list: &KeepList,
+) -> Result<Decision> {
+    let session = login(client, identifier, password, config).await?;
+    let (repo, collection, rkey) = record_path(id, &session.did)?;
+    let record = client.get_record(repo, collection, rkey).await?;
+    let post_time = match record_time(&record, config) {
+        Ok(t) => t,
+        Err(decision) => return Ok(decision),
+    };
+    let pinned_uri = if !config.delete_pinned {
+        client.get_pinned_post_uri(&session.did).await
+    } else {
+        None
+    };
+    let keep_list = effective_keep_list(client, &session.did, config, keep_list).await?;
+    let collection_keep = collection_keep_lists(client, config, &keep_list).await?;
+    let hidden_config = hidden_reply_config(config);
+    let config = match &hidden_config {
+        Some(hidden_config)
+            if collection == "app.bsky.feed.post"
+                && !hidden_replies_among(client, std::slice::from_ref(&record), config)
+                    .await
+                    .is_empty() =>
+        {
+            hidden_config
+        }
+        _ => config,
+    };
+    let decision = evaluate(
+        &record,
+        post_time,
+        collection,
+        config,
+        collection_keep.get(collection).unwrap_or(&keep_list),
+        pinned_uri.as_deref(),
+        Utc::now(),
+    );
+    Ok(check_self_like(decision, &record, &session.did, config))
+}
+
+/// `STATS_JSON` for Bluesky: tally every post, repost, like and extra
+/// collection record by age and by whether a run would delete it.
+pub async fn stats(
+    client: &(impl BlueskyClient + Sync),
+    identifier: &str,
+    password: &str,
+    config: &Config,
+    keep_list: &KeepList,
+) -> Result<PlatformStats> {
+    let now = Utc::now();
+    let mut stats = PlatformStats::new(config.cutoff);
+    for_each_decision(
+        client,
+        identifier,
+        password,
+        config,
+        keep_list,
+        now,
+        |_, _, decided| match decided {
+            Some((post_time, decision)) => stats.add(post_time, &decision, now),
+            None => stats.undated += 1,
+        },
+    )
+    .await?;
+    Ok(stats)
+}
+
+/// `FULL_PLAN_JSON` for Bluesky: every record old enough to delete, across
+/// every collection, with what a run would do with it and why.
+pub async fn full_plan(
+    client: &(impl BlueskyClient + Sync),
+    identifier: &str,
+    password: &str,
+    config: &Config,
+    keep_list: &KeepList,
+) -> Result<Vec<PlanEntry>> {
+    let mut entries = Vec::new();
+    let now = Utc::now();
+    for_each_decision(
+        client,
+        identifier,
+        password,
+        config,
+        keep_list,
+        now,
+        |record, collection, decided| {
+            if let Some((post_time, decision)) = decided {
+                entries.extend(PlanEntry::new(
+                    &record.uri,
+                    collection,
+                    post_time,
+                    &decision,
+                ));
+            }
+        },
+    )
+    .await?;
+    Ok(entries)
+}
+
+/// Decide every record in the repo the way a sweep would, without deleting
+/// anything. Records without a usable timestamp reach `visit` with `None`.
+async fn for_each_decision(
+    client: &(impl BlueskyClient + Sync),
+    identifier: &str,
+    password: &str,
+    config: &Config,
+    keep_list: &KeepList,
+    now: DateTime<Utc>,
+    mut visit: impl FnMut(&Record, &str, Option<(DateTime<Utc>, Decision)>),
+) -> Result<()> {
+    let session = login(client, identifier, password, config).await?;
+    let pinned_uri = if !config.delete_pinned {
+        client.get_pinned_post_uri(&session.did).await
+    } else {
+        None
+    };
+    let keep_list = effective_keep_list(client, &session.did, config, keep_list).await?;
+    let collection_keep = collection_keep_lists(client, config, &keep_list).await?;
+    let hidden_config = hidden_reply_config(config);
+    for collection in swept_collections(config) {
+        let mut cursor: Option<String> = None;
+        loop {
+            let resp = list_page(client, &session.did, collection, cursor.as_deref()).await?;
+            let hidden = match &hidden_config {
+                Some(_) if collection == "app.bsky.feed.post" => {
+                    hidden_replies_among(client, &resp.records, config).await
+                }
+                _ => HashSet::new(),
+            };
+            for record in &resp.records {
+                let Ok(post_time) = record_time(record, config) else {
+                    visit(record, collection, None);
+                    continue;
+                };
+                let record_config = match &hidden_config {
+                    Some(hidden_config) if hidden.contains(&record.uri) => hidden_config,
+                    _ => config,
+                };
+                let decision = evaluate(
+                    record,
+                    post_time,
+                    collection,
+                    record_config,
+                    collection_keep.get(collection).unwrap_or(&keep_list),
+                    pinned_uri.as_deref(),
+                    now,
+                );
+                let decision = check_self_like(decision, record, &session.did, record_config);
+                visit(record, collection, Some((post_time, decision)));
+            }
+            cursor = resp.cursor;
+            if resp.records.is_empty() || cursor.is_none() {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Posts, reposts and likes, then `BLUESKY_EXTRA_COLLECTIONS`.
+fn swept_collections(config: &Config) -> impl Iterator<Item = &str> {
+    [
+        "app.bsky.feed.post",
+        "app.bsky.feed.repost",
+        "app.bsky.feed.like",
+    ]
+    .into_iter()
+    .chain(config.extra_collections.iter().map(String::as_str))
+}
+
+/// Split an AT URI into repo, collection and rkey. A bare rkey is one of
+/// `did`'s posts.
+fn record_path<'a>(id: &'a str, did: &'a str) -> Result<(&'a str, &'a str, &'a str)> {
+    match id.strip_prefix("at://") {
+        Some(path) => {
+            let mut parts = path.splitn(3, '/');
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(repo), Some(collection), Some(rkey)) => Ok((repo, collection, rkey)),
+                _ => anyhow::bail!("Invalid AT URI {id:?}"),
+            }
+        }
+        None => Ok((did, "app.bsky.feed.post", id)),
+    }
+}
+
+/// `PRUNE_KEEP_FILE` for Bluesky: which of `ids` (rkeys or AT URIs) no
+/// longer exist. One that can't be looked up counts as existing.
+///
+/// Each record is looked up once however many entries name it, and posts
+/// are looked up `GET_POSTS_BATCH` at a time. A bare rkey protects a record
+/// in any collection, so one that isn't a post is only gone once no other
+/// swept collection has it either.
+pub async fn missing_records(
+    client: &(impl BlueskyClient + Sync),
+    identifier: &str,
+    password: &str,
+    ids: &[&str],
+    config: &Config,
+) -> Result<HashSet<String>> {
+    let session = login(client, identifier, password, config).await?;
+    // An rkey and the full URI of the same post are one lookup
+    let mut entries: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    for &id in ids {
+        match record_path(id, &session.did) {
+            Ok((repo, collection, rkey)) => entries
+                .entry(format!("at://{repo}/{collection}/{rkey}"))
+                .or_default()
+                .push(id),
+            Err(e) => warn!("Could not check keep entry {id}, keeping it: {e:#}"),
+        }
+    }
+
+    let (posts, others): (Vec<&str>, Vec<&str>) = entries
+        .keys()
+        .map(String::as_str)
+        .partition(|uri| uri.contains("/app.bsky.feed.post/"));
+    let mut gone = Vec::new();
+    for batch in posts.chunks(GET_POSTS_BATCH) {
+        match client.existing_posts(batch).await {
+            Ok(found) => gone.extend(batch.iter().filter(|uri| !found.contains(**uri))),
+            Err(e) => warn!(
+                "Could not check {} keep entries, keeping them: {e:#}",
+                batch.len()
+            ),
+        }
+    }
+    for uri in others {
+        let (repo, collection, rkey) = record_path(uri, &session.did)?;
+        match client.record_exists(repo, collection, rkey).await {
+            Ok(true) => {}
+            Ok(false) => gone.push(uri),
+            Err(e) => warn!("Could not check keep entry {uri}, keeping it: {e:#}"),
+        }
+    }
+
+    let mut missing = HashSet::new();
+    for uri in gone {
+        for &id in &entries[uri] {
+            if id.starts_with("at://")
+                || !rkey_in_other_collections(client, &session.did, id, config).await
+            {
+                missing.insert(id.to_string());
+            }
+        }
+    }
+    Ok(missing)
+}
+
+/// Whether a swept collection other than posts has a record under `rkey`.
+/// A failed lookup counts as having one.
+async fn rkey_in_other_collections(
+    client: &(impl BlueskyClient + Sync),
+    did: &str,
+    rkey: &str,
+    config: &Config,
+) -> bool {
+    for collection in swept_collections(config).filter(|c| *c != "app.bsky.feed.post") {
+        match client.record_exists(did, collection, rkey).await {
+            Ok(false) => {}
+            Ok(true) => return true,
+            Err(e) => {
+                warn!("Could not check keep entry {rkey} in {collection}, keeping it: {e:#}");
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// `keep_list` with `author:` handles resolved to DIDs plus, with
+/// `KEEP_CURATED`, every post your lists and feeds reference.
+async fn effective_keep_list<'a>(
+    client: &(impl BlueskyClient + Sync),
+    did: &str,
+    config: &Config,
+    keep_list: &'a KeepList,
+) -> Result<Cow<'a, KeepList>> {
+    let mut keep_list = resolve_authors(client, keep_list).await?;
+    if config.keep_curated {
+        let uris = curated_post_uris(client, did).await?;
+        info!(
+            "Keeping {} posts referenced by your lists and feeds",
+            uris.len()
+        );
+        keep_list = Cow::Owned(keep_list.with_exact("bluesky", uris));
+    }
+    Ok(keep_list)
+}
+
+/// `keep_list` with `author:` handles resolved to DIDs.
+async fn resolve_authors<'a>(
+    client: &(impl BlueskyClient + Sync),
+    keep_list: &'a KeepList,
+) -> Result<Cow<'a, KeepList>> {
+    let handles = keep_list.author_handles("bluesky");
+    if handles.is_empty() {
+        return Ok(Cow::Borrowed(keep_list));
+    }
+    let mut dids = HashMap::new();
+    for handle in handles {
+        let did = client
+            .resolve_handle(handle)
+            .await
+            .with_context(|| format!("Failed to resolve keep entry bluesky:author:{handle}"))?;
+        info!("Resolved {handle} to {did}");
+        dids.insert(handle.to_string(), did);
+    }
+    Ok(Cow::Owned(keep_list.with_author_dids("bluesky", &dids)))
+}
+
+/// The effective `keep_list` merged with each collection's `KEEP_FILE_*`
+/// list. Collections without one use `keep_list` as is.
+async fn collection_keep_lists(
+    client: &(impl BlueskyClient + Sync),
+    config: &Config,
+    keep_list: &KeepList,
+) -> Result<HashMap<String, KeepList>> {
+    let mut lists = HashMap::new();
+    for (collection, extra) in &config.collection_keep {
+        let extra = resolve_authors(client, extra).await?;
+        lists.insert(collection.clone(), keep_list.merged(&extra));
+    }
+    Ok(lists)
+}
+
+/// `BLUESKY_PRUNE_PREFERENCES`: drop dated preference entries older than
+/// the cutoff and write the rest back with `putPreferences`. They aren't
+/// records, so they're logged rather than counted in the summary.
+async fn prune_stale_preferences(
+    client: &(impl BlueskyClient + Sync),
+    config: &Config,
+) -> Result<()> {
+    let mut preferences = client.get_preferences().await?;
+    let pruned = prune_preferences(&mut preferences, config.cutoff);
+    if pruned > 0 && config.dry_run {
+        info!("[DRY RUN] Would prune {pruned} preference entries older than the cutoff");
+    } else if pruned > 0 {
+        client.put_preferences(preferences).await?;
+        info!("Bluesky preferences: pruned {pruned} entries older than the cutoff");
+    }
+    Ok(())
+}
+
+/// The preference lists whose entries carry a date, by preference `$type`.
+/// Anything else, such as saved feeds or types added later, is left alone.
+const DATED_PREFERENCES: [(&str, &str); 2] = [
+    ("app.bsky.actor.defs#mutedWordsPref", "items"),
+    ("app.bsky.actor.defs#bskyAppStatePref", "nuxs"),
+];
+
+/// Remove the entries in the `DATED_PREFERENCES` lists dated before
+/// `cutoff`, by `createdAt` or, for entries that only expire, `expiresAt`.
+fn prune_preferences(preferences: &mut [serde_json::Value], cutoff: DateTime<Utc>) -> u64 {
+    let stale = |entry: &serde_json::Value| {
+        entry
+            .get("createdAt")
+            .or_else(|| entry.get("expiresAt"))
+            .and_then(|t| t.as_str())
+            .and_then(|t| crate::parse_timestamp(t).ok())
+            .is_some_and(|t| t < cutoff)
+    };
+    let mut pruned = 0;
+    for preference in preferences {
+        let Some(field) = DATED_PREFERENCES
+            .iter()
+            .find(|(kind, _)| preference["$type"] == *kind)
+            .map(|(_, field)| *field)
+        else {
+            continue;
+        };
+        if let Some(entries) = preference.get_mut(field).and_then(|v| v.as_array_mut()) {
+            let before = entries.len();
+            entries.retain(|entry| !stale(entry));
+            pruned += (before - entries.len()) as u64;
+        }
+    }
+    pruned
+}
+
+/// Re-create every record archived under `dir` at its original rkey. Without
+/// `confirmed` (or in dry run) this only lists what would be restored.
+pub async fn restore_archived(
+    client: &(impl BlueskyClient + Sync),
+    identifier: &str,
+    password: &str,
+    dir: &Path,
+    config: &Config,
+    confirmed: bool,
+) -> Result<u64> {
+    let records = archive::load_all(dir)?;
+    let session = login(client, identifier, password, config).await?;
+    info!("Authenticated as {}", session.did);
+
+    let mut restored = 0u64;
+    for record in &records {
+        if config.dry_run || !confirmed {
+            info!("[DRY RUN] Would restore: {}", record.uri);
+            continue;
+        }
+        match refreshing(client, || {
+            client.put_record(
+                &session.did,
+                &record.collection,
+                &record.rkey,
+                record.value.clone(),
+            )
+        })
+        .await
+        {
+            Ok(()) => {
+                restored += 1;
+                info!("Restored: {}", record.uri);
+            }
+            Err(e) => warn!("Failed to restore {}: {e:#}", record.uri),
+        }
+        if !config
+            .shutdown
+            .sleep(config.delay.unwrap_or(DEFAULT_DELAY))
+            .await
+        {
+            break;
+        }
+    }
+
+    if !confirmed && !config.dry_run {
+        warn!(
+            "Listed {} archived records without restoring them. Set CONFIRM_RESTORE=true to restore.",
+            records.len()
+        );
+    }
+    info!(
+        "Bluesky restore: restored {restored} of {} archived records",
+        records.len()
+    );
+    Ok(restored)
+}
+
+/// Collections whose records can point at your posts to curate them.
+const CURATION_COLLECTIONS: [&str; 3] = [
+    "app.bsky.feed.generator",
+    "app.bsky.graph.list",
+    "app.bsky.graph.listitem",
+];
+
+/// URIs of your own posts referenced anywhere in your list and feed records,
+/// for `KEEP_CURATED`.
+async fn curated_post_uris(
+    client: &(impl BlueskyClient + Sync),
+    did: &str,
+) -> Result<HashSet<String>> {
+    let prefix = format!("at://{did}/app.bsky.feed.post/");
+    let mut uris = HashSet::new();
+    for collection in CURATION_COLLECTIONS {
+        let mut cursor: Option<String> = None;
+        loop {
+            let resp = list_page(client, did, collection, cursor.as_deref()).await?;
+            if resp.records.is_empty() {
+                break;
+            }
+            for record in &resp.records {
+                collect_post_uris(&record.raw, &prefix, &mut uris);
+            }
+            cursor = resp.cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+    }
+    Ok(uris)
+}
+
+/// Every string in `value` starting with `prefix`, at any depth.
+fn collect_post_uris(value: &serde_json::Value, prefix: &str, uris: &mut HashSet<String>) {
+    match value {
+        serde_json::Value::String(s) if s.starts_with(prefix) => {
+            uris.insert(s.clone());
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                collect_post_uris(item, prefix, uris);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for v in map.values() {
+                collect_post_uris(v, prefix, uris);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Collections whose records can use the same blob as a post, e.g. a
+/// profile avatar uploaded once and also attached to a post.
+const BLOB_COLLECTIONS: [&str; 4] = [
+    "app.bsky.actor.profile",
+    "app.bsky.feed.generator",
+    "app.bsky.graph.list",
+    "app.bsky.feed.post",
+];
+
+/// URIs of the records referencing each blob CID in the repo, for
+/// `KEEP_SHARED_BLOBS`.
+async fn blob_references(
+    client: &(impl BlueskyClient + Sync),
+    did: &str,
+) -> Result<HashMap<String, Vec<String>>> {
+    let mut refs: HashMap<String, Vec<String>> = HashMap::new();
+    for collection in BLOB_COLLECTIONS {
+        let mut cursor: Option<String> = None;
+        loop {
+            let resp = list_page(client, did, collection, cursor.as_deref()).await?;
+            if resp.records.is_empty() {
+                break;
+            }
+            for record in &resp.records {
+                let mut blobs = Vec::new();
+                collect_blob_refs(&record.raw, &mut blobs);
+                for (cid, _) in blobs {
+                    refs.entry(cid.to_string())
+                        .or_default()
+                        .push(record.uri.clone());
+                }
+            }
+            cursor = resp.cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+    }
+    Ok(refs)
+}
+
+/// The first of `record`'s blobs that another record also references, and
+/// that record's URI.
+fn shared_blob<'a>(
+    record: &Record,
+    refs: &'a HashMap<String, Vec<String>>,
+) -> Option<(&'a str, &'a str)> {
+    let mut blobs = Vec::new();
+    collect_blob_refs(&record.raw, &mut blobs);
+    blobs.into_iter().find_map(|(cid, _)| {
+        let (cid, uris) = refs.get_key_value(cid)?;
+        let other = uris.iter().find(|uri| **uri != record.uri)?;
+        Some((cid.as_str(), other.as_str()))
+    })
+}
+
+/// Page through `com.atproto.sync.listBlobs` for every blob CID in the repo.
+async fn list_all_blob_cids(
+    client: &(impl BlueskyClient + Sync),
+    did: &str,
+) -> Result<HashSet<String>> {
+    let mut cids = HashSet::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let resp = client.list_blobs(did, cursor.as_deref()).await?;
+        if resp.cids.is_empty() {
+            break;
+        }
+        cids.extend(resp.cids);
+        cursor = resp.cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+    Ok(cids)
+}
+
+/// Every record in `collection`, largest referenced media first. Records with
+/// equal sizes keep their listing order.
+async fn records_by_blob_size(
+    client: &(impl BlueskyClient + Sync),
+    did: &str,
+    collection: &str,
+    cids: &HashSet<String>,
+) -> Result<Vec<Record>> {
+    let mut records = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let resp = list_page(client, did, collection, cursor.as_deref()).await?;
+        if resp.records.is_empty() {
+            break;
+        }
+        records.extend(resp.records);
+        cursor = resp.cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+    records.sort_by_key(|r| std::cmp::Reverse(r.value.blob_bytes(Some(cids))));
+    Ok(records)
+}
+
+pub async fn delete_old_posts(
+    client: &(impl BlueskyClient + Sync),
+    identifier: &str,
+    password: &str,
+    config: &Config,
+    keep_list: &KeepList,
+) -> Result<PlatformSummary> {
+    let mut summary = PlatformSummary::default();
+
+    // Authenticate
+    let session = login(client, identifier, password, config).await?;
+    info!("Authenticated as {}", session.did);
+
+    // A dry run leaves the repo as it is, so there's nothing to compare
+    let repo_before = if config.dry_run {
+        None
+    } else {
+        client
+            .repo_status(&session.did)
+            .await
+            .inspect_err(|e| warn!("Could not get Bluesky repo status: {e:#}"))
+            .ok()
+    };
+
+    // Fetch pinned post URI from profile
+    let pinned_uri: Option<String> = if !config.delete_pinned {
+        client.get_pinned_post_uri(&session.did).await
+    } else {
+        None
+    };
+
+    let curated = effective_keep_list(client, &session.did, config, keep_list).await?;
+    let keep_list = &*curated;
+    let collection_keep = collection_keep_lists(client, config, keep_list).await?;
+    let keep_for = |collection: &str| collection_keep.get(collection).unwrap_or(keep_list);
+    let pace = |delay: Option<Duration>| delay.or(config.delay).unwrap_or(DEFAULT_DELAY);
+
+    // Delete old posts
+    let posts = delete_old_records(
+        client,
+        &session.did,
+        "app.bsky.feed.post",
+        pace(config.post_delay),
+        config,
+        keep_for("app.bsky.feed.post"),
+        pinned_uri.as_deref(),
+    )
+    .await?;
+    summary.posts = posts.deleted;
+    let mut plan = posts.planned;
+    summary.remaining = Some(posts.remaining);
+    summary.skipped.merge(posts.skipped);
+
+    info!(
+        "Bluesky posts: deleted {} ({} videos, {} bytes of media), {} already gone, {}",
+        posts.deleted,
+        posts.deleted_videos,
+        posts.bytes_reclaimed,
+        posts.already_gone,
+        posts.skipped.text()
+    );
+
+    // Delete old reposts
+    if config.delete_reposts && !config.shutdown.is_triggered() {
+        let reposts = delete_old_records(
+            client,
+            &session.did,
+            "app.bsky.feed.repost",
+            pace(config.repost_delay),
+            config,
+            keep_for("app.bsky.feed.repost"),
+            pinned_uri.as_deref(),
+        )
+        .await?;
+        summary.reposts = reposts.deleted;
+        plan.extend(reposts.planned);
+        summary.skipped.merge(reposts.skipped);
+
+        info!(
+            "Bluesky reposts: deleted {}, {} already gone, {}",
+            reposts.deleted,
+            reposts.already_gone,
+            reposts.skipped.text()
+        );
+    }
+
+    // Delete old likes
+    if config.delete_likes && !config.shutdown.is_triggered() {
+        let likes = delete_old_records(
+            client,
+            &session.did,
+            "app.bsky.feed.like",
+            pace(config.like_delay),
+            config,
+            keep_for("app.bsky.feed.like"),
+            None,
+        )
+        .await?;
+        summary.likes = likes.deleted;
+        plan.extend(likes.planned);
+        summary.skipped.merge(likes.skipped);
+
+        info!(
+            "Bluesky likes: deleted {}, {} already gone, {}",
+            likes.deleted,
+            likes.already_gone,
+            likes.skipped.text()
+        );
+    }
+
+    // Anything else the user asked to sweep, e.g. stale labeler declarations.
+    // Records without a createdAt are skipped with a warning like any other.
+    for collection in &config.extra_collections {
+        if config.shutdown.is_triggered() {
+            break;
+        }
+        let extra = delete_old_records(
+            client,
+            &session.did,
+            collection,
+            pace(None),
+            config,
+            keep_list,
+            None,
+        )
+        .await?;
+        summary.other += extra.deleted;
+        plan.extend(extra.planned);
+        summary.skipped.merge(extra.skipped);
+
+        info!(
+            "Bluesky {collection}: deleted {}, {} already gone, {}",
+            extra.deleted,
+            extra.already_gone,
+            extra.skipped.text()
+        );
+    }
+
+    if config.prune_preferences && !config.shutdown.is_triggered() {
+        prune_stale_preferences(client, config).await?;
+    }
+
+    if config.dry_run_grouped {
+        plan.log("Bluesky");
+    }
+
+    if config.post_summary && summary.total() > 0 {
+        let text = summary.status_text(config.cutoff);
+        if config.dry_run {
+            info!("[DRY RUN] Would post summary: {text}");
+        } else {
+            client
+                .create_record(
+                    &session.did,
+                    "app.bsky.feed.post",
+                    serde_json::json!({
+                        "$type": "app.bsky.feed.post",
+                        "text": text,
+                        "createdAt": chrono::Utc::now().to_rfc3339(),
+                    }),
+                )
+                .await?;
+            info!("Posted summary: {text}");
+        }
+    }
+
+    if let Some(before) = repo_before {
+        match client.repo_status(&session.did).await {
+            Ok(after) => info!("Bluesky repo: {}", before.delta(&after)),
+            Err(e) => warn!("Could not get Bluesky repo status: {e:#}"),
+        }
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocklist::Blocklist;
+    use crate::full_plan::Action;
+    use crate::test_helpers::*;
+    use crate::KeptCounts;
+    use std::sync::Mutex;
+
+    const DID: &str = "did:plc:testuser123";
+    const PINNED_URI: &str = "at://did:plc:testuser123/app.bsky.feed.post/pinned1";
+
+    struct FakeBlueskyClient {
+        did: String,
+        pinned_post: Option<String>,
+        records: Mutex<std::collections::HashMap<String, Vec<Record>>>,
+        deleted: Mutex<Vec<(String, String)>>,
+        created: Mutex<Vec<(String, serde_json::Value)>>,
+        put: Mutex<Vec<(String, String, serde_json::Value)>>,
+        blobs: Vec<String>,
+        handles: std::collections::HashMap<String, String>,
+        page_size: usize,
+        /// Records each page repeats from the end of the previous one.
+        page_overlap: usize,
+        /// `list_records` calls answered so far, and the one to fail with
+        /// an expired token until the session is refreshed.
+        list_calls: Mutex<usize>,
+        expire_on_call: Option<usize>,
+        /// The `delete_record` call to fail with an expired token until the
+        /// session is refreshed.
+        expire_on_delete: Option<usize>,
+        refreshes: Mutex<usize>,
+        /// Upcoming `create_session` calls to answer with a 429.
+        rate_limited_logins: Mutex<usize>,
+        login_calls: Mutex<usize>,
+        /// Upcoming `delete_record` calls to answer with a 429.
+        rate_limited_deletes: Mutex<usize>,
+        /// Upcoming `delete_record` calls to fail outright.
+        failing_deletes: Mutex<usize>,
+        /// rkeys whose deletes always fail.
+        failing_rkeys: Vec<String>,
+        /// `list_calls` as of each `delete_record` call, in order.
+        list_calls_at_delete: Mutex<Vec<usize>>,
+        /// URIs asked for by each `existing_posts` call, in order.
+        existing_posts_calls: Mutex<Vec<Vec<String>>>,
+        /// `record_exists` lookups so far.
+        record_exists_calls: Mutex<usize>,
+        preferences: Mutex<Vec<serde_json::Value>>,
+        /// Thread root URI → the replies its threadgate hides.
+        hidden_replies: std::collections::HashMap<String, Vec<String>>,
+        /// Leave deleted records out of listings, except these rkeys, whose
+        /// deletes succeed but that stay listed.
+        unlist_deleted: Option<Vec<String>>,
+    }
+
+    impl FakeBlueskyClient {
+        fn new(did: &str) -> Self {
+            Self {
+                did: did.to_string(),
+                pinned_post: None,
+                records: Mutex::new(std::collections::HashMap::new()),
+                deleted: Mutex::new(Vec::new()),
+                created: Mutex::new(Vec::new()),
+                put: Mutex::new(Vec::new()),
+                blobs: Vec::new(),
+                handles: std::collections::HashMap::new(),
+                page_size: 100,
+                page_overlap: 0,
+                list_calls: Mutex::new(0),
+                expire_on_call: None,
+                expire_on_delete: None,
+                refreshes: Mutex::new(0),
+                rate_limited_logins: Mutex::new(0),
+                login_calls: Mutex::new(0),
+                rate_limited_deletes: Mutex::new(0),
+                failing_deletes: Mutex::new(0),
+                failing_rkeys: Vec::new(),
+                list_calls_at_delete: Mutex::new(Vec::new()),
+                existing_posts_calls: Mutex::new(Vec::new()),
+                record_exists_calls: Mutex::new(0),
+                preferences: Mutex::new(Vec::new()),
+                hidden_replies: std::collections::HashMap::new(),
+                unlist_deleted: None,
+            }
+        }
+
+        fn with_preferences(self, preferences: serde_json::Value) -> Self {
+            *self.preferences.lock().unwrap() = serde_json::from_value(preferences).unwrap();
+            self
+        }
+
+        fn with_deleted_records_unlisted(mut self, lingering: &[&str]) -> Self {
+            self.unlist_deleted = Some(lingering.iter().map(|rkey| rkey.to_string()).collect());
+            self
+        }
+
+        fn with_hidden_replies(mut self, root: &str, replies: &[&str]) -> Self {
+            self.hidden_replies.insert(
+                root.to_string(),
+                replies.iter().map(|uri| uri.to_string()).collect(),
+            );
+            self
+        }
+
+        fn with_handle(mut self, handle: &str, did: &str) -> Self {
+            self.handles.insert(handle.to_string(), did.to_string());
+            self
+        }
+
+        fn with_pinned_post(mut self, uri: &str) -> Self {
+            self.pinned_post = Some(uri.to_string());
+            self
+        }
+
+        fn with_records(self, collection: &str, records: Vec<Record>) -> Self {
+            self.records
+                .lock()
+                .unwrap()
+                .insert(collection.to_string(), records);
+            self
+        }
+
+        fn with_blobs(mut self, cids: &[&str]) -> Self {
+            self.blobs = cids.iter().map(|c| c.to_string()).collect();
+            self
+        }
+
+        fn with_page_size(mut self, size: usize) -> Self {
+            self.page_size = size;
+            self
+        }
+
+        fn with_page_overlap(mut self, overlap: usize) -> Self {
+            self.page_overlap = overlap;
+            self
+        }
+
+        /// Mark a listed record as already deleted, e.g. in the app mid-run.
+        fn deleted_elsewhere(self, collection: &str, rkey: &str) -> Self {
+            self.deleted
+                .lock()
+                .unwrap()
+                .push((collection.to_string(), rkey.to_string()));
+            self
+        }
+
+        fn expiring_on_list_call(mut self, call: usize) -> Self {
+            self.expire_on_call = Some(call);
+            self
+        }
+
+        fn expiring_on_delete_call(mut self, call: usize) -> Self {
+            self.expire_on_delete = Some(call);
+            self
+        }
+
+        fn with_rate_limited_logins(self, count: usize) -> Self {
+            *self.rate_limited_logins.lock().unwrap() = count;
+            self
+        }
+
+        fn with_rate_limited_deletes(self, count: usize) -> Self {
+            *self.rate_limited_deletes.lock().unwrap() = count;
+            self
+        }
+
+        fn with_failing_deletes(self, count: usize) -> Self {
+            *self.failing_deletes.lock().unwrap() = count;
+            self
+        }
+
+        fn with_failing_rkeys(mut self, rkeys: &[&str]) -> Self {
+            self.failing_rkeys = rkeys.iter().map(|rkey| rkey.to_string()).collect();
+            self
+        }
+
+        fn refreshes(&self) -> usize {
+            *self.refreshes.lock().unwrap()
+        }
+
+        fn deleted(&self) -> Vec<(String, String)> {
+            self.deleted.lock().unwrap().clone()
+        }
+
+        fn created(&self) -> Vec<(String, serde_json::Value)> {
+            self.created.lock().unwrap().clone()
+        }
+
+        fn put(&self) -> Vec<(String, String, serde_json::Value)> {
+            self.put.lock().unwrap().clone()
+        }
+    }
+
+    impl BlueskyClient for FakeBlueskyClient {
+        async fn create_session(&self, _identifier: &str, _password: &str) -> Result<Session> {
+            *self.login_calls.lock().unwrap() += 1;
+            {
+                let mut limited = self.rate_limited_logins.lock().unwrap();
+                if *limited > 0 {
+                    *limited -= 1;
+                    return Err(RateLimited {
+                        reset: Some(Utc::now() + chrono::TimeDelta::seconds(300)),
+                        endpoint: "com.atproto.server.createSession".into(),
+                        remaining: Some(0),
+                    }
+                    .into());
+                }
+            }
+            Ok(Session {
+                did: self.did.clone(),
+                access_jwt: "fake".to_string(),
+                refresh_jwt: "fake-refresh".to_string(),
+            })
+        }
+
+        async fn refresh_session(&self) -> Result<()> {
+            *self.refreshes.lock().unwrap() += 1;
+            Ok(())
+        }
+
+        async fn get_pinned_post_uri(&self, _did: &str) -> Option<String> {
+            self.pinned_post.clone()
+        }
+
+        async fn resolve_handle(&self, handle: &str) -> Result<String> {
+            self.handles
+                .get(handle)
+                .cloned()
+                .with_context(|| format!("Unable to resolve handle {handle}"))
+        }
+
+        async fn list_records(
+            &self,
+            _did: &str,
+            collection: &str,
+            cursor: Option<&str>,
+        ) -> Result<ListRecordsResponse> {
+            let call = {
+                let mut calls = self.list_calls.lock().unwrap();
+                *calls += 1;
+                *calls
+            };
+            if self.expire_on_call == Some(call) && self.refreshes() == 0 {
+                return Err(ExpiredToken.into());
+            }
+            let records_map = self.records.lock().unwrap();
+            let mut all_records = match records_map.get(collection) {
+                Some(r) => r.clone(),
+                None => {
+                    return Ok(ListRecordsResponse {
+                        records: vec![],
+                        cursor: None,
+                    })
+                }
+            };
+            if let Some(lingering) = &self.unlist_deleted {
+                let deleted = self.deleted();
+                all_records.retain(|r| {
+                    let rkey = r.uri.rsplit('/').next().unwrap_or_default();
+                    lingering.iter().any(|l| l == rkey)
+                        || !deleted.contains(&(collection.to_string(), rkey.to_string()))
+                });
+            }
+
+            let start: usize = cursor.and_then(|c| c.parse().ok()).unwrap_or(0);
+            let end = (start + self.page_size).min(all_records.len());
+            let page = all_records[start..end].to_vec();
+            let next_cursor = if end < all_records.len() {
+                Some((end - self.page_overlap).to_string())
+            } else {
+                None
+            };
+
+            Ok(ListRecordsResponse {
+                records: page,
+                cursor: next_cursor,
+            })
+        }
+
+        async fn get_record(&self, _did: &str, collection: &str, rkey: &str) -> Result<Record> {
+            self.records
+                .lock()
+                .unwrap()
+                .get(collection)
+                .and_then(|records| {
+                    records
+                        .iter()
+                        .find(|r| r.uri.ends_with(&format!("/{rkey}")))
+                })
+                .cloned()
+                .context("Record not found")
+        }
+
+        async fn record_exists(&self, did: &str, collection: &str, rkey: &str) -> Result<bool> {
+            *self.record_exists_calls.lock().unwrap() += 1;
+            Ok(self.get_record(did, collection, rkey).await.is_ok())
+        }
+
+        async fn existing_posts(&self, uris: &[&str]) -> Result<HashSet<String>> {
+            self.existing_posts_calls
+                .lock()
+                .unwrap()
+                .push(uris.iter().map(|uri| uri.to_string()).collect());
+            let records = self.records.lock().unwrap();
+            let posts = records.get("app.bsky.feed.post");
+            Ok(uris
+                .iter()
+                .filter(|uri| posts.is_some_and(|posts| posts.iter().any(|r| r.uri == **uri)))
+                .map(|uri| uri.to_string())
+                .collect())
+        }
+
+        async fn hidden_replies(&self, roots: &[&str]) -> Result<HashSet<String>> {
+            Ok(roots
+                .iter()
+                .filter_map(|root| self.hidden_replies.get(*root))
+                .flatten()
+                .cloned()
+                .collect())
+        }
+
+        async fn delete_record(
+            &self,
+            _did: &str,
+            collection: &str,
+            rkey: &str,
+        ) -> Result<DeleteOutcome> {
+            let list_calls = *self.list_calls.lock().unwrap();
+            let call = {
+                let mut at_delete = self.list_calls_at_delete.lock().unwrap();
+                at_delete.push(list_calls);
+                at_delete.len()
+            };
+            if self.expire_on_delete == Some(call) && self.refreshes() == 0 {
+                return Err(ExpiredToken.into());
+            }
+            {
+                let mut limited = self.rate_limited_deletes.lock().unwrap();
+                if *limited > 0 {
+                    *limited -= 1;
+                    return Err(RateLimited {
+                        reset: Some(Utc::now() + chrono::TimeDelta::seconds(30)),
+                        endpoint: "com.atproto.repo.deleteRecord".into(),
+                        remaining: Some(0),
+                    }
+                    .into());
+                }
+            }
+            {
+                let mut failing = self.failing_deletes.lock().unwrap();
+                if *failing > 0 {
+                    *failing -= 1;
+                    anyhow::bail!("deleteRecord failed: 502 Bad Gateway");
+                }
+            }
+            if self.failing_rkeys.iter().any(|r| r == rkey) {
+                anyhow::bail!("deleteRecord failed: 502 Bad Gateway");
+            }
+            let mut deleted = self.deleted.lock().unwrap();
+            let key = (collection.to_string(), rkey.to_string());
+            if deleted.contains(&key) {
+                return Ok(DeleteOutcome::AlreadyGone);
+            }
+            deleted.push(key);
+            Ok(DeleteOutcome::Deleted)
+        }
+
+        async fn get_preferences(&self) -> Result<Vec<serde_json::Value>> {
+            Ok(self.preferences.lock().unwrap().clone())
+        }
+
+        async fn put_preferences(&self, preferences: Vec<serde_json::Value>) -> Result<()> {
+            *self.preferences.lock().unwrap() = preferences;
+            Ok(())
+        }
+
+        async fn repo_status(&self, _did: &str) -> Result<RepoStatus> {
+            let deleted = self.deleted();
+            let collections = self
+                .records
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(collection, records)| {
+                    records.len() > deleted.iter().filter(|(c, _)| c == *collection).count()
+                })
+                .map(|(collection, _)| collection.clone())
+                .collect();
+            Ok(RepoStatus {
+                rev: format!("rev{}", deleted.len()),
+                collections,
+            })
+        }
+
+        async fn list_blobs(&self, _did: &str, _cursor: Option<&str>) -> Result<ListBlobsResponse> {
+            Ok(ListBlobsResponse {
+                cids: self.blobs.clone(),
+                cursor: None,
+            })
+        }
+
+        async fn put_record(
+            &self,
+            _did: &str,
+            collection: &str,
+            rkey: &str,
+            record: serde_json::Value,
+        ) -> Result<()> {
+            self.put
+                .lock()
+                .unwrap()
+                .push((collection.to_string(), rkey.to_string(), record));
+            Ok(())
+        }
+
+        async fn create_record(
+            &self,
+            _did: &str,
+            collection: &str,
+            record: serde_json::Value,
+        ) -> Result<()> {
+            self.created
+                .lock()
+                .unwrap()
+                .push((collection.to_string(), record));
+            Ok(())
+        }
+    }
+
+    /// Build a record the way `listRecords` returns it, so `raw` matches `value`.
+    fn record_from_json(collection: &str, rkey: &str, value: serde_json::Value) -> Record {
+        serde_json::from_value(serde_json::json!({
+            "uri": format!("at://{DID}/{collection}/{rkey}"),
+            "value": value,
+        }))
+        .unwrap()
+    }
+
     fn make_record(rkey: &str, created_at: &str) -> Record {
-        Record {
-            uri: format!("at://{DID}/app.bsky.feed.post/{rkey}"),
-            value: RecordValue {
-                created_at: Some(created_at.to_string()),
+        make_record_for_collection("app.bsky.feed.post", rkey, created_at)
+    }
+
+    fn make_record_with_embed(rkey: &str, created_at: &str, embed: serde_json::Value) -> Record {
+        record_from_json(
+            "app.bsky.feed.post",
+            rkey,
+            serde_json::json!({"createdAt": created_at, "embed": embed}),
+        )
+    }
+
+    fn make_record_for_collection(collection: &str, rkey: &str, created_at: &str) -> Record {
+        record_from_json(
+            collection,
+            rkey,
+            serde_json::json!({"createdAt": created_at}),
+        )
+    }
+
+    fn make_like_of(rkey: &str, subject_uri: &str) -> Record {
+        make_subject_record("app.bsky.feed.like", rkey, subject_uri)
+    }
+
+    fn make_repost_of(rkey: &str, subject_uri: &str) -> Record {
+        make_subject_record("app.bsky.feed.repost", rkey, subject_uri)
+    }
+
+    fn make_subject_record(collection: &str, rkey: &str, subject_uri: &str) -> Record {
+        record_from_json(
+            collection,
+            rkey,
+            serde_json::json!({
+                "createdAt": old_timestamp(),
+                "subject": {"uri": subject_uri, "cid": "bafyreisubject"},
+            }),
+        )
+    }
+
+    #[tokio::test]
+    async fn deletes_posts_older_than_cutoff() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.post",
+            vec![make_record("abc123", &old_timestamp())],
+        );
+
+        let config = config_with_cutoff_days_ago(30);
+        let keep_list = KeepList::default();
+        delete_old_posts(&fake, "user", "pass", &config, &keep_list)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            fake.deleted(),
+            vec![("app.bsky.feed.post".to_string(), "abc123".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn skips_posts_newer_than_cutoff() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.post",
+            vec![make_record("abc123", &recent_timestamp())],
+        );
+
+        let config = config_with_cutoff_days_ago(30);
+        let keep_list = KeepList::default();
+        delete_old_posts(&fake, "user", "pass", &config, &keep_list)
+            .await
+            .unwrap();
+
+        assert!(fake.deleted().is_empty());
+    }
+
+    #[tokio::test]
+    async fn skips_pinned_post() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_pinned_post(PINNED_URI)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![make_record("pinned1", &old_timestamp())],
+            );
+
+        let config = config_with_cutoff_days_ago(30);
+        let keep_list = KeepList::default();
+        delete_old_posts(&fake, "user", "pass", &config, &keep_list)
+            .await
+            .unwrap();
+
+        assert!(fake.deleted().is_empty());
+    }
+
+    #[tokio::test]
+    async fn skips_self_repost_of_pinned_post() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_pinned_post(PINNED_URI)
+            .with_records(
+                "app.bsky.feed.repost",
+                vec![
+                    make_repost_of("repost1", PINNED_URI),
+                    make_repost_of("repost2", &format!("at://{DID}/app.bsky.feed.post/other")),
+                ],
+            );
+
+        let config = config_with_cutoff_days_ago(30);
+        let summary = delete_old_posts(&fake, "user", "pass", &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            fake.deleted(),
+            vec![("app.bsky.feed.repost".to_string(), "repost2".to_string())]
+        );
+        assert_eq!(summary.skipped.pinned, 1);
+    }
+
+    #[tokio::test]
+    async fn keep_self_likes_keeps_likes_of_your_own_posts_only() {
+        tokio::time::pause();
+        let likes = || {
+            vec![
+                make_like_of("self", &format!("at://{DID}/app.bsky.feed.post/3kmine")),
+                make_like_of("other", "at://did:plc:friend/app.bsky.feed.post/3k1"),
+            ]
+        };
+        let mut config = config_with_cutoff_days_ago(30);
+
+        let fake = FakeBlueskyClient::new(DID).with_records("app.bsky.feed.like", likes());
+        delete_old_posts(&fake, "user", "pass", &config, &KeepList::default())
+            .await
+            .unwrap();
+        assert_eq!(fake.deleted().len(), 2);
+
+        config.keep_self_likes = true;
+        let fake = FakeBlueskyClient::new(DID).with_records("app.bsky.feed.like", likes());
+        let summary = delete_old_posts(&fake, "user", "pass", &config, &KeepList::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            fake.deleted(),
+            vec![("app.bsky.feed.like".to_string(), "other".to_string())]
+        );
+        assert_eq!(summary.skipped.self_like, 1);
+    }
+
+    #[tokio::test]
+    async fn import_boundary_keeps_imported_posts_but_not_old_native_ones() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.post",
+            vec![
+                make_record("native", &old_timestamp()),
+                make_record("tweet", "2015-03-01T00:00:00Z"),
+                make_record("ancienttweet", "2009-03-01T00:00:00Z"),
+            ],
+        );
+        let at = |s: &str| crate::parse_timestamp(s).unwrap().with_timezone(&Utc);
+        let mut config = config_with_cutoff_days_ago(30);
+        config.import_boundary = Some(at("2019-01-01T00:00:00Z"));
+        config.import_cutoff = Some(at("2010-01-01T00:00:00Z"));
+
+        let summary = delete_old_posts(&fake, "user", "pass", &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            fake.deleted(),
+            vec![
+                ("app.bsky.feed.post".to_string(), "native".to_string()),
+                ("app.bsky.feed.post".to_string(), "ancienttweet".to_string()),
+            ]
+        );
+        assert_eq!(summary.skipped.imported, 1);
+    }
+
+    #[tokio::test]
+    async fn delete_hidden_sooner_uses_the_shorter_cutoff_for_hidden_replies() {
+        tokio::time::pause();
+        let root = "at://did:plc:friend/app.bsky.feed.post/3kroot";
+        let ten_days_ago = (Utc::now() - chrono::TimeDelta::days(10)).to_rfc3339();
+        let reply = |rkey: &str| {
+            record_from_json(
+                "app.bsky.feed.post",
+                rkey,
+                serde_json::json!({
+                    "createdAt": ten_days_ago,
+                    "reply": {
+                        "root": {"uri": root, "cid": "bafyreiroot"},
+                        "parent": {"uri": root, "cid": "bafyreiroot"},
+                    },
+                }),
+            )
+        };
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![
+                    reply("hidden"),
+                    reply("visible"),
+                    make_record("toplevel", &ten_days_ago),
+                ],
+            )
+            .with_hidden_replies(root, &[&format!("at://{DID}/app.bsky.feed.post/hidden")]);
+        let mut config = config_with_cutoff_days_ago(30);
+        config.hidden_cutoff = Some(Utc::now() - chrono::TimeDelta::days(7));
+
+        delete_old_posts(&fake, "user", "pass", &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            fake.deleted(),
+            vec![("app.bsky.feed.post".to_string(), "hidden".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn force_delete_overrides_pinned_and_cutoff() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_pinned_post(PINNED_URI)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![
+                    make_record("pinned1", &old_timestamp()),
+                    make_record("recent1", &recent_timestamp()),
+                    make_record("recent2", &recent_timestamp()),
+                ],
+            );
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.force_delete = KeepList::parse("bluesky:pinned1\nbluesky:recent1\n");
+        let keep_list = KeepList::parse("bluesky:recent1\n");
+        delete_old_posts(&fake, "user", "pass", &config, &keep_list)
+            .await
+            .unwrap();
+
+        let mut deleted = fake.deleted();
+        deleted.sort();
+        assert_eq!(
+            deleted,
+            vec![
+                ("app.bsky.feed.post".to_string(), "pinned1".to_string()),
+                ("app.bsky.feed.post".to_string(), "recent1".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn deletes_pinned_when_configured() {
+        tokio::time::pause();
+        // When delete_pinned=true, get_pinned_post_uri is not called,
+        // so the pinned_post value on the fake doesn't matter
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.post",
+            vec![make_record("pinned1", &old_timestamp())],
+        );
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.delete_pinned = true;
+        let keep_list = KeepList::default();
+        delete_old_posts(&fake, "user", "pass", &config, &keep_list)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            fake.deleted(),
+            vec![("app.bsky.feed.post".to_string(), "pinned1".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn skips_keep_list_by_rkey() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.post",
+            vec![make_record("abc123", &old_timestamp())],
+        );
+
+        let config = config_with_cutoff_days_ago(30);
+        let keep_list = KeepList::from_entries(["bluesky:abc123".to_string()]);
+        delete_old_posts(&fake, "user", "pass", &config, &keep_list)
+            .await
+            .unwrap();
+
+        assert!(fake.deleted().is_empty());
+    }
+
+    #[tokio::test]
+    async fn skips_keep_list_by_full_uri() {
+        tokio::time::pause();
+        let uri = format!("at://{DID}/app.bsky.feed.post/abc123");
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.post",
+            vec![make_record("abc123", &old_timestamp())],
+        );
+
+        let config = config_with_cutoff_days_ago(30);
+        let keep_list = KeepList::from_entries([format!("bluesky:{uri}")]);
+        delete_old_posts(&fake, "user", "pass", &config, &keep_list)
+            .await
+            .unwrap();
+
+        assert!(fake.deleted().is_empty());
+    }
+
+    #[tokio::test]
+    async fn dry_run_does_not_call_delete() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.post",
+            vec![make_record("abc123", &old_timestamp())],
+        );
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.dry_run = true;
+        let keep_list = KeepList::default();
+        delete_old_posts(&fake, "user", "pass", &config, &keep_list)
+            .await
+            .unwrap();
+
+        assert!(fake.deleted().is_empty());
+    }
+
+    #[tokio::test]
+    async fn pagination_follows_cursor() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID).with_page_size(1).with_records(
+            "app.bsky.feed.post",
+            vec![
+                make_record("abc123", &old_timestamp()),
+                make_record("def456", &old_timestamp()),
+            ],
+        );
+
+        let config = config_with_cutoff_days_ago(30);
+        let keep_list = KeepList::default();
+        delete_old_posts(&fake, "user", "pass", &config, &keep_list)
+            .await
+            .unwrap();
+
+        let deleted = fake.deleted();
+        assert_eq!(deleted.len(), 2);
+        assert!(deleted.contains(&("app.bsky.feed.post".to_string(), "abc123".to_string())));
+        assert!(deleted.contains(&("app.bsky.feed.post".to_string(), "def456".to_string())));
+    }
+
+    #[tokio::test]
+    async fn respects_delete_reposts_and_likes_flags() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records(
+                "app.bsky.feed.repost",
+                vec![make_record_for_collection(
+                    "app.bsky.feed.repost",
+                    "repost1",
+                    &old_timestamp(),
+                )],
+            )
+            .with_records(
+                "app.bsky.feed.like",
+                vec![make_record_for_collection(
+                    "app.bsky.feed.like",
+                    "like1",
+                    &old_timestamp(),
+                )],
+            );
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.delete_reposts = false;
+        config.delete_likes = false;
+        let keep_list = KeepList::default();
+        delete_old_posts(&fake, "user", "pass", &config, &keep_list)
+            .await
+            .unwrap();
+
+        assert!(fake.deleted().is_empty());
+    }
+
+    #[tokio::test]
+    async fn counts_records_missing_created_at() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.post",
+            vec![
+                record_from_json("app.bsky.feed.post", "undated", serde_json::json!({})),
+                make_record("garbled", "last tuesday"),
+                make_record("old1", &old_timestamp()),
+            ],
+        );
+
+        let config = config_with_cutoff_days_ago(30);
+        let result = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &KeepList::default(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.skipped.missing_timestamp, 1);
+        assert_eq!(result.skipped.unparseable_timestamp, 1);
+        assert_eq!(result.skipped.future_dated, 0);
+        assert_eq!(result.deleted, 1);
+        assert_eq!(
+            result.skipped.text(),
+            "1 skipped: missing timestamp, 1 skipped: unparseable timestamp"
+        );
+    }
+
+    #[tokio::test]
+    async fn incremental_stops_at_pages_below_the_watermark() {
+        tokio::time::pause();
+        let dir = tempfile::tempdir().unwrap();
+        let state = dir.path().join("incremental.json");
+        let mut marks = Watermarks::default();
+        marks.advance(
+            "app.bsky.feed.post",
+            crate::parse_timestamp("2022-12-31T00:00:00Z")
+                .unwrap()
+                .into(),
+        );
+        marks.save(&state).unwrap();
+
+        let fake = FakeBlueskyClient::new(DID).with_page_size(2).with_records(
+            "app.bsky.feed.post",
+            vec![
+                make_record("new", &recent_timestamp()),
+                make_record("mid", "2023-06-01T00:00:00Z"),
+                make_record("kept1", "2022-06-01T00:00:00Z"),
+                make_record("kept2", "2022-01-01T00:00:00Z"),
+                make_record("kept3", "2021-01-01T00:00:00Z"),
+            ],
+        );
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.incremental_state_file = Some(state.clone());
+        let result = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &KeepList::default(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            fake.deleted(),
+            vec![("app.bsky.feed.post".to_string(), "mid".to_string())]
+        );
+        assert_eq!(result.deleted, 1);
+        assert_eq!(*fake.list_calls.lock().unwrap(), 2);
+        assert_eq!(
+            Watermarks::load(&state).unwrap().get("app.bsky.feed.post"),
+            Some(
+                crate::parse_timestamp("2023-06-01T00:00:00Z")
+                    .unwrap()
+                    .into()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn incremental_revisits_a_record_that_failed_to_delete() {
+        tokio::time::pause();
+        let dir = tempfile::tempdir().unwrap();
+        let state = dir.path().join("incremental.json");
+        let records = vec![
+            make_record("newer", "2023-06-01T00:00:00Z"),
+            make_record("failed", "2022-06-01T00:00:00Z"),
+            make_record("older", "2021-01-01T00:00:00Z"),
+        ];
+        let mut config = config_with_cutoff_days_ago(30);
+        config.incremental_state_file = Some(state.clone());
+        let sweep = |fake: FakeBlueskyClient| {
+            let config = &config;
+            async move {
+                delete_old_records(
+                    &fake,
+                    DID,
+                    "app.bsky.feed.post",
+                    DEFAULT_DELAY,
+                    config,
+                    &KeepList::default(),
+                    None,
+                )
+                .await
+                .unwrap();
+                fake.deleted()
+            }
+        };
+
+        let first = FakeBlueskyClient::new(DID)
+            .with_page_size(1)
+            .with_records("app.bsky.feed.post", records.clone())
+            .with_failing_rkeys(&["failed"]);
+        assert_eq!(sweep(first).await.len(), 2);
+        assert!(
+            Watermarks::load(&state).unwrap().get("app.bsky.feed.post")
+                < Some(
+                    crate::parse_timestamp("2022-06-01T00:00:00Z")
+                        .unwrap()
+                        .into()
+                )
+        );
+
+        let second = FakeBlueskyClient::new(DID)
+            .with_page_size(1)
+            .with_records("app.bsky.feed.post", records)
+            .with_deleted_records_unlisted(&[])
+            .deleted_elsewhere("app.bsky.feed.post", "newer")
+            .deleted_elsewhere("app.bsky.feed.post", "older");
+        assert!(sweep(second)
+            .await
+            .contains(&("app.bsky.feed.post".to_string(), "failed".to_string())));
+    }
+
+    #[tokio::test]
+    async fn interrupted_sweep_leaves_the_watermark_alone() {
+        tokio::time::pause();
+        let dir = tempfile::tempdir().unwrap();
+        let state = dir.path().join("incremental.json");
+        let fake = FakeBlueskyClient::new(DID).with_page_size(1).with_records(
+            "app.bsky.feed.post",
+            vec![
+                make_record("old1", &old_timestamp()),
+                make_record("old2", &old_timestamp()),
+            ],
+        );
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.incremental_state_file = Some(state.clone());
+        let shutdown = config.shutdown.clone();
+        let keep_list = KeepList::default();
+        let sweep = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            Duration::from_secs(60),
+            &config,
+            &keep_list,
+            None,
+        );
+        let (result, _) = tokio::join!(sweep, async {
+            tokio::task::yield_now().await;
+            shutdown.trigger();
+        });
+
+        assert_eq!(result.unwrap().deleted, 1);
+        assert!(!state.exists());
+    }
+
+    #[tokio::test]
+    async fn run_counters_track_an_interrupted_sweep() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID).with_page_size(1).with_records(
+            "app.bsky.feed.post",
+            vec![
+                make_record("keep1", &old_timestamp()),
+                make_record("old1", &old_timestamp()),
+                make_record("old2", &old_timestamp()),
+            ],
+        );
+
+        let config = config_with_cutoff_days_ago(30);
+        let shutdown = config.shutdown.clone();
+        let keep_list = KeepList::parse("bluesky:keep1");
+        let sweep = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            Duration::from_secs(60),
+            &config,
+            &keep_list,
+            None,
+        );
+        let (result, _) = tokio::join!(sweep, async {
+            tokio::task::yield_now().await;
+            shutdown.trigger();
+        });
+
+        assert_eq!(result.unwrap().deleted, 1);
+        let counts = config.counters.snapshot();
+        assert_eq!((counts.deleted, counts.skipped, counts.errors), (1, 1, 0));
+    }
+
+    #[tokio::test]
+    async fn redact_rewrites_posts_in_place_instead_of_deleting() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![
+                    record_from_json(
+                        "app.bsky.feed.post",
+                        "old1",
+                        serde_json::json!({
+                            "$type": "app.bsky.feed.post",
+                            "text": "hot take",
+                            "createdAt": old_timestamp(),
+                            "reply": {"root": {"uri": "at://x", "cid": "c"}, "parent": {"uri": "at://x", "cid": "c"}},
+                            "embed": {"$type": "app.bsky.embed.images", "images": []},
+                            "facets": [],
+                        }),
+                    ),
+                    record_from_json(
+                        "app.bsky.feed.post",
+                        "done",
+                        serde_json::json!({"text": REDACTED_TEXT, "createdAt": old_timestamp()}),
+                    ),
+                ],
+            )
+            .with_records(
+                "app.bsky.feed.like",
+                vec![make_record_for_collection("app.bsky.feed.like", "like1", &old_timestamp())],
+            );
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.redact = true;
+        let summary = delete_old_posts(&fake, "user", "pass", &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            fake.deleted(),
+            vec![("app.bsky.feed.like".to_string(), "like1".to_string())]
+        );
+        let put = fake.put();
+        assert_eq!(put.len(), 1);
+        let (collection, rkey, value) = &put[0];
+        assert_eq!(
+            (collection.as_str(), rkey.as_str()),
+            ("app.bsky.feed.post", "old1")
+        );
+        assert_eq!(value["text"], REDACTED_TEXT);
+        assert_eq!(value["createdAt"], old_timestamp());
+        assert!(value.get("reply").is_some());
+        assert!(value.get("embed").is_none() && value.get("facets").is_none());
+        assert_eq!(summary.posts, 1);
+    }
+
+    #[tokio::test]
+    async fn keep_audit_counts_entries_for_recent_posts_as_used() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.post",
+            vec![
+                make_record("recent1", &recent_timestamp()),
+                make_record("old1", &old_timestamp()),
+            ],
+        );
+        let keep_list = KeepList::parse("bluesky:recent1\nbluesky:old1\nbluesky:gone\n");
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.keep_audit = true;
+        delete_old_posts(&fake, "user", "pass", &config, &keep_list)
+            .await
+            .unwrap();
+
+        assert!(fake.deleted().is_empty());
+        assert_eq!(keep_list.unmatched(&["bluesky"]), vec!["bluesky:gone"]);
+    }
+
+    #[tokio::test]
+    async fn keep_sample_spares_the_same_posts_every_run() {
+        tokio::time::pause();
+        let records: Vec<Record> = (0..40)
+            .map(|i| make_record(&format!("old{i}"), &old_timestamp()))
+            .collect();
+        let mut config = config_with_cutoff_days_ago(30);
+        config.keep_sample = Some(crate::keep::KeepSample {
+            percent: 50,
+            seed: 1234,
+        });
+
+        let sweep = || async {
+            let fake =
+                FakeBlueskyClient::new(DID).with_records("app.bsky.feed.post", records.clone());
+            let summary = delete_old_posts(&fake, "user", "pass", &config, &KeepList::default())
+                .await
+                .unwrap();
+            (fake.deleted(), summary)
+        };
+        let (first, summary) = sweep().await;
+        let (second, _) = sweep().await;
+
+        assert_eq!(first, second);
+        assert_eq!(summary.posts + summary.skipped.sampled, 40);
+        assert!((10..=30).contains(&summary.skipped.sampled));
+    }
+
+    #[tokio::test]
+    async fn all_recent_single_page_exits_early_without_deleting() {
+        tokio::time::pause();
+        let yesterday = (Utc::now() - chrono::TimeDelta::days(1)).to_rfc3339();
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.post",
+            vec![
+                make_record("recent1", &yesterday),
+                make_record("recent2", &yesterday),
+            ],
+        );
+
+        let config = config_with_cutoff_days_ago(30);
+        let start = tokio::time::Instant::now();
+        let result = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &KeepList::default(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert!(fake.deleted().is_empty());
+        assert_eq!(*fake.list_calls.lock().unwrap(), 1);
+        assert_eq!(result.remaining.count, 2);
+        assert_eq!(result.skipped, SkipCounts::default());
+        assert_eq!(start.elapsed().as_secs(), 0);
+    }
+
+    #[test]
+    fn a_future_dated_or_forced_record_needs_the_full_sweep() {
+        let yesterday = (Utc::now() - chrono::TimeDelta::days(1)).to_rfc3339();
+        let recent = [make_record("recent", &yesterday)];
+        let config = config_with_cutoff_days_ago(30);
+        assert!(nothing_to_do(&recent, &config, Utc::now()));
+
+        let future = [make_record("future", &recent_timestamp())];
+        assert!(!nothing_to_do(&future, &config, Utc::now()));
+
+        let mut forcing = config_with_cutoff_days_ago(30);
+        forcing.force_delete = KeepList::parse("bluesky:recent\n");
+        assert!(!nothing_to_do(&recent, &forcing, Utc::now()));
+    }
+
+    #[tokio::test]
+    async fn rate_limit_in_defer_mode_ends_the_sweep() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_rate_limited_deletes(1)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![
+                    make_record("old1", &old_timestamp()),
+                    make_record("old2", &old_timestamp()),
+                ],
+            );
+
+        let config = config_with_cutoff_days_ago(30);
+        let result = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &KeepList::default(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert!(fake.deleted().is_empty());
+        assert_eq!(result.deleted, 0);
+        assert_eq!(result.remaining.count, 1);
+    }
+
+    #[tokio::test]
+    async fn verify_each_page_flags_a_deleted_record_still_listed() {
+        tokio::time::pause();
+        let fake = || {
+            FakeBlueskyClient::new(DID)
+                .with_deleted_records_unlisted(&["old2"])
+                .with_records(
+                    "app.bsky.feed.post",
+                    vec![
+                        make_record("old1", &old_timestamp()),
+                        make_record("old2", &old_timestamp()),
+                        make_record("old3", &old_timestamp()),
+                    ],
+                )
+        };
+        async fn sweep(fake: FakeBlueskyClient, config: &Config) -> DeleteResult {
+            delete_old_records(
+                &fake,
+                DID,
+                "app.bsky.feed.post",
+                DEFAULT_DELAY,
+                config,
+                &KeepList::default(),
+                None,
+            )
+            .await
+            .unwrap()
+        }
+        let mut config = config_with_cutoff_days_ago(30);
+
+        let unverified = sweep(fake(), &config).await;
+        assert_eq!(unverified.deleted, 3);
+        assert_eq!(unverified.remaining.count, 0);
+
+        config.verify_each_page = true;
+        let verified = sweep(fake(), &config).await;
+        assert_eq!(verified.deleted, 3);
+        assert_eq!(verified.remaining.count, 1);
+    }
+
+    #[tokio::test]
+    async fn remaining_csv_lists_recent_records_but_not_deleted_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("remaining.csv");
+        let yesterday = (Utc::now() - chrono::TimeDelta::days(1)).to_rfc3339();
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.post",
+            vec![
+                make_record("recent", &yesterday),
+                make_record("old", &old_timestamp()),
+            ],
+        );
+        let mut config = config_with_cutoff_days_ago(30);
+        config.remaining_csv = Some(crate::RemainingCsv::new(&path));
+
+        let result = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &KeepList::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.deleted, 1);
+
+        config.remaining_csv.unwrap().write().unwrap();
+        let csv = std::fs::read_to_string(&path).unwrap();
+        let rows: Vec<&str> = csv.lines().skip(1).collect();
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].starts_with(&format!(
+            "bluesky,app.bsky.feed.post,at://{DID}/app.bsky.feed.post/recent,"
+        )));
+    }
+
+    #[tokio::test]
+    async fn dry_run_report_lists_what_would_be_deleted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.json");
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.post",
+            vec![
+                make_record("recent", &recent_timestamp()),
+                make_record("old", &old_timestamp()),
+            ],
+        );
+        let mut config = config_with_cutoff_days_ago(30);
+        config.dry_run = true;
+        config.report = Some(crate::Report::new(&path));
+
+        delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &KeepList::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(fake.deleted().is_empty());
+
+        config.report.unwrap().write(true, Utc::now()).unwrap();
+        let report: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(report["dry_run"], serde_json::json!(true));
+        assert_eq!(
+            report["entries"],
+            serde_json::json!([{
+                "platform": "bluesky",
+                "id": format!("at://{DID}/app.bsky.feed.post/old"),
+                "kind": "app.bsky.feed.post",
+                "created_at": "2020-01-01T00:00:00Z",
+                "action": "delete",
+            }])
+        );
+    }
+
+    #[tokio::test]
+    async fn consecutive_failures_abort_the_sweep_at_the_limit() {
+        tokio::time::pause();
+        let records = (0..10)
+            .map(|i| make_record(&format!("old{i}"), &old_timestamp()))
+            .collect();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_failing_deletes(100)
+            .with_records("app.bsky.feed.post", records);
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.error_limits.max_consecutive = Some(3);
+        let err = delete_old_posts(&fake, "user", "pass", &config, &KeepList::default())
+            .await
+            .unwrap_err();
+
+        assert!(err.is::<crate::error_limit::TooManyErrors>());
+        assert_eq!(config.counters.snapshot().errors, 3);
+        assert!(fake.deleted().is_empty());
+    }
+
+    #[tokio::test]
+    async fn occasional_failures_stay_under_the_error_rate() {
+        tokio::time::pause();
+        let records = (0..20)
+            .map(|i| make_record(&format!("old{i}"), &old_timestamp()))
+            .collect();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_failing_deletes(2)
+            .with_records("app.bsky.feed.post", records);
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.error_limits.max_rate = Some(0.2);
+        let summary = delete_old_posts(&fake, "user", "pass", &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(summary.posts, 18);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_in_wait_mode_retries_after_the_reset() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_rate_limited_deletes(1)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![
+                    make_record("old1", &old_timestamp()),
+                    make_record("old2", &old_timestamp()),
+                ],
+            );
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.rate_limit_mode = RateLimitMode::Wait;
+        let start = tokio::time::Instant::now();
+        let result = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &KeepList::default(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.deleted, 2);
+        assert_eq!(fake.deleted().len(), 2);
+        assert!(start.elapsed().as_secs() >= 28);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_login_in_defer_mode_fails_with_when_to_retry() {
+        let fake = FakeBlueskyClient::new(DID)
+            .with_rate_limited_logins(1)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![make_record("old1", &old_timestamp())],
+            );
+
+        let config = config_with_cutoff_days_ago(30);
+        let err = delete_old_posts(&fake, "user", "pass", &config, &KeepList::default())
+            .await
+            .unwrap_err();
+
+        let message = format!("{err:#}");
+        assert!(
+            message.starts_with("Bluesky login (createSession) is rate limited, try again after ")
+        );
+        assert!(message.contains("BLUESKY_RATE_LIMIT_MODE=wait"));
+        assert!(err.downcast_ref::<RateLimited>().is_some());
+        assert_eq!(*fake.login_calls.lock().unwrap(), 1);
+        assert!(fake.deleted().is_empty());
+    }
+
+    #[tokio::test]
+    async fn rate_limited_login_in_wait_mode_retries_after_the_reset() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_rate_limited_logins(1)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![make_record("old1", &old_timestamp())],
+            );
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.rate_limit_mode = RateLimitMode::Wait;
+        let start = tokio::time::Instant::now();
+        let summary = delete_old_posts(&fake, "user", "pass", &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(summary.posts, 1);
+        assert_eq!(*fake.login_calls.lock().unwrap(), 2);
+        assert!(start.elapsed().as_secs() >= 298);
+    }
+
+    #[tokio::test]
+    async fn login_rate_limited_again_after_the_wait_gives_up() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID).with_rate_limited_logins(2);
+        let mut config = config_with_cutoff_days_ago(30);
+        config.rate_limit_mode = RateLimitMode::Wait;
+
+        let err = login(&fake, "user", "pass", &config).await.err().unwrap();
+        assert!(format!("{err:#}").contains("failed again after the rate limit reset"));
+        assert_eq!(*fake.login_calls.lock().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn keep_one_per_month_leaves_exactly_one_post_per_month() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_page_size(2)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![
+                    make_record("recent", &recent_timestamp()),
+                    make_record("mar20", "2020-03-20T00:00:00Z"),
+                    make_record("mar10", "2020-03-10T00:00:00Z"),
+                    make_record("mar01", "2020-03-01T00:00:00Z"),
+                    make_record("feb28", "2020-02-28T00:00:00Z"),
+                    make_record("feb01", "2020-02-01T00:00:00Z"),
+                    make_record("jan15", "2020-01-15T00:00:00Z"),
+                ],
+            )
+            .with_records(
+                "app.bsky.feed.like",
+                vec![
+                    make_record("like1", "2020-03-20T00:00:00Z"),
+                    make_record("like2", "2020-03-10T00:00:00Z"),
+                ],
+            );
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.keep_one_per_month = true;
+        // The keep list already keeps March's newest, so the next one stands for March
+        let keep_list = KeepList::parse("bluesky:mar20\n");
+        let summary = delete_old_posts(&fake, "user", "pass", &config, &keep_list)
+            .await
+            .unwrap();
+
+        let deleted: Vec<String> = fake.deleted().into_iter().map(|(_, rkey)| rkey).collect();
+        assert_eq!(deleted, vec!["mar01", "feb01", "like1", "like2"]);
+        assert_eq!(summary.skipped.one_per_month, 3);
+        assert_eq!(summary.skipped.kept, 1);
+    }
+
+    #[tokio::test]
+    async fn purge_all_reposts_deletes_recent_reposts_but_not_recent_posts() {
+        tokio::time::pause();
+        let yesterday = (Utc::now() - chrono::TimeDelta::days(1)).to_rfc3339();
+        let repost = "app.bsky.feed.repost";
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![
+                    make_record("recent_post", &yesterday),
+                    make_record("old_post", &old_timestamp()),
+                ],
+            )
+            .with_records(
+                repost,
+                vec![
+                    make_record_for_collection(repost, "future", &recent_timestamp()),
+                    make_record_for_collection(repost, "recent", &yesterday),
+                    make_record_for_collection(repost, "kept", &yesterday),
+                    make_record_for_collection(repost, "old", &old_timestamp()),
+                ],
+            );
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.purge_all_reposts = true;
+        let keep_list = KeepList::parse("bluesky:kept\n");
+        let summary = delete_old_posts(&fake, "user", "pass", &config, &keep_list)
+            .await
+            .unwrap();
+
+        let deleted: Vec<String> = fake.deleted().into_iter().map(|(_, rkey)| rkey).collect();
+        assert_eq!(deleted, vec!["old_post", "future", "recent", "old"]);
+        assert_eq!(summary.reposts, 3);
+        assert_eq!(summary.skipped.kept, 1);
+    }
+
+    #[tokio::test]
+    async fn sweeps_extra_labeler_collection() {
+        tokio::time::pause();
+        let labeler = "app.bsky.labeler.service";
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            labeler,
+            vec![
+                make_record_for_collection(labeler, "old", &old_timestamp()),
+                make_record_for_collection(labeler, "recent", &recent_timestamp()),
+                record_from_json(labeler, "undated", serde_json::json!({})),
+            ],
+        );
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.extra_collections = vec![labeler.to_string()];
+        let summary = delete_old_posts(&fake, "user", "pass", &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            fake.deleted(),
+            vec![(labeler.to_string(), "old".to_string())]
+        );
+        assert_eq!(summary.other, 1);
+        assert_eq!(summary.total(), 1);
+        assert_eq!(summary.skipped.missing_timestamp, 1);
+    }
+
+    #[test]
+    fn tid_time_decodes_rkeys() {
+        assert_eq!(
+            tid_time("3jzfcijpj2z2a").map(|t| t.to_rfc3339()),
+            Some("2023-06-30T15:03:01.887007+00:00".to_string())
+        );
+        assert_eq!(
+            tid_time("3k2la5diqyc2x").map(|t| t.format("%Y-%m-%d").to_string()),
+            Some("2023-07-15".to_string())
+        );
+        // Wrong length, a character outside the alphabet, the top bit set
+        assert_eq!(tid_time("self"), None);
+        assert_eq!(tid_time("3jzfcijpj2z21"), None);
+        assert_eq!(tid_time("zjzfcijpj2z2a"), None);
+    }
+
+    #[tokio::test]
+    async fn fallback_rkey_time_dates_records_missing_created_at() {
+        tokio::time::pause();
+        let records = vec![
+            record_from_json("app.bsky.feed.post", "3jzfcijpj2z2a", serde_json::json!({})),
+            record_from_json("app.bsky.feed.post", "self", serde_json::json!({})),
+        ];
+        let sweep = |fallback| {
+            let fake =
+                FakeBlueskyClient::new(DID).with_records("app.bsky.feed.post", records.clone());
+            let mut config = config_with_cutoff_days_ago(30);
+            config.fallback_rkey_time = fallback;
+            async move {
+                let result = delete_old_records(
+                    &fake,
+                    DID,
+                    "app.bsky.feed.post",
+                    DEFAULT_DELAY,
+                    &config,
+                    &KeepList::default(),
+                    None,
+                )
+                .await
+                .unwrap();
+                (fake.deleted(), result.skipped.missing_timestamp)
+            }
+        };
+
+        assert_eq!(sweep(false).await, (vec![], 2));
+        assert_eq!(
+            sweep(true).await,
+            (
+                vec![(
+                    "app.bsky.feed.post".to_string(),
+                    "3jzfcijpj2z2a".to_string()
+                )],
+                1
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn counts_pinned_skips_separately_from_kept() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.post",
+            vec![
+                make_record("pinned", &old_timestamp()),
+                make_record("kept", &old_timestamp()),
+            ],
+        );
+        let pinned_uri = format!("at://{DID}/app.bsky.feed.post/pinned");
+
+        let config = config_with_cutoff_days_ago(30);
+        let result = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &KeepList::from_entries(["bluesky:kept"]),
+            Some(&pinned_uri),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.skipped.pinned, 1);
+        assert_eq!(result.skipped.kept, 1);
+        assert_eq!(result.deleted, 0);
+    }
+
+    #[tokio::test]
+    async fn counts_kept_records_by_the_kind_of_entry_that_matched() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![
+                    make_record("byrkey", &old_timestamp()),
+                    make_record("byuri", &old_timestamp()),
+                ],
+            )
+            .with_records(
+                "app.bsky.feed.like",
+                vec![
+                    make_like_of("like1", "at://did:plc:famous/app.bsky.feed.post/3kfamous"),
+                    make_like_of("like2", "at://did:plc:friend/app.bsky.feed.post/3k1"),
+                    make_like_of("like3", "at://did:plc:friend/app.bsky.feed.post/3k2"),
+                ],
+            );
+
+        let config = config_with_cutoff_days_ago(30);
+        let keep_list = KeepList::from_entries([
+            "bluesky:byrkey".to_string(),
+            format!("at://{DID}/app.bsky.feed.post/byuri"),
+            "bluesky:subject:at://did:plc:famous/app.bsky.feed.post/3kfamous".to_string(),
+            "bluesky:author:did:plc:friend".to_string(),
+        ]);
+        let summary = delete_old_posts(&fake, "user", "pass", &config, &keep_list)
+            .await
+            .unwrap();
+
+        assert!(fake.deleted().is_empty());
+        assert_eq!(summary.skipped.kept, 5);
+        assert_eq!(
+            summary.skipped.kept_by,
+            KeptCounts {
+                rkey: 1,
+                uri: 1,
+                subject: 1,
+                author: 2,
+                date: 0,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn counts_future_dated_records_without_deleting() {
+        tokio::time::pause();
+        let future = (chrono::Utc::now() + chrono::TimeDelta::days(365))
+            .format("%Y-%m-%dT%H:%M:%SZ")
+            .to_string();
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.post",
+            vec![
+                make_record("future1", &future),
+                make_record("old1", &old_timestamp()),
+            ],
+        );
+
+        let config = config_with_cutoff_days_ago(30);
+        let keep_list = KeepList::default();
+        let result = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &keep_list,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.skipped.future_dated, 1);
+        assert_eq!(result.deleted, 1);
+        assert_eq!(
+            fake.deleted(),
+            vec![("app.bsky.feed.post".to_string(), "old1".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn treat_future_as_now_clamps_future_dated_records_to_now() {
+        tokio::time::pause();
+        let future = (chrono::Utc::now() + chrono::TimeDelta::days(365))
+            .format("%Y-%m-%dT%H:%M:%SZ")
+            .to_string();
+        let sweep = |clamp| {
+            let fake = FakeBlueskyClient::new(DID)
+                .with_records("app.bsky.feed.post", vec![make_record("future1", &future)]);
+            // A cutoff just ahead of now, as with DELETE_ACCOUNT_DATA
+            let mut config = config_with_cutoff_days_ago(-1);
+            config.treat_future_as_now = clamp;
+            async move {
+                let result = delete_old_records(
+                    &fake,
+                    DID,
+                    "app.bsky.feed.post",
+                    DEFAULT_DELAY,
+                    &config,
+                    &KeepList::default(),
+                    None,
+                )
+                .await
+                .unwrap();
+                (result.deleted, result.skipped.future_dated)
+            }
+        };
+
+        assert_eq!(sweep(false).await, (0, 1));
+        assert_eq!(sweep(true).await, (1, 0));
+    }
+
+    #[tokio::test]
+    async fn remaining_reports_oldest_surviving_post() {
+        tokio::time::pause();
+        let days_ago = |days| {
+            (chrono::Utc::now() - chrono::TimeDelta::days(days))
+                .format("%Y-%m-%dT%H:%M:%SZ")
+                .to_string()
+        };
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.post",
+            vec![
+                make_record("new", &days_ago(1)),
+                make_record("newer_than_cutoff", &days_ago(20)),
+                make_record("kept", "2021-06-01T00:00:00Z"),
+                make_record("old", &old_timestamp()),
+            ],
+        );
+
+        let config = config_with_cutoff_days_ago(30);
+        let keep_list = KeepList::from_entries(["bluesky:kept"]);
+        let result = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &keep_list,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.deleted, 1);
+        assert_eq!(result.remaining.count, 3);
+        assert_eq!(
+            result.remaining.oldest,
+            Some(
+                crate::parse_timestamp("2021-06-01T00:00:00Z")
+                    .unwrap()
+                    .into()
+            )
+        );
+    }
+
+    fn blob(cid: &str, size: u64) -> serde_json::Value {
+        serde_json::json!({"$type": "blob", "ref": {"$link": cid}, "mimeType": "image/jpeg", "size": size})
+    }
+
+    fn make_record_with_image(rkey: &str, cid: &str, size: u64) -> Record {
+        make_record_with_embed(
+            rkey,
+            &old_timestamp(),
+            serde_json::json!({"$type": "app.bsky.embed.images", "images": [{"image": blob(cid, size), "alt": ""}]}),
+        )
+    }
+
+    #[test]
+    fn blob_bytes_sums_refs_including_nested_media() {
+        let video = make_record_with_embed(
+            "video",
+            &old_timestamp(),
+            serde_json::json!({"$type": "app.bsky.embed.video", "video": blob("vid", 5_000)}),
+        );
+        let quote_with_images = make_record_with_embed(
+            "quote",
+            &old_timestamp(),
+            serde_json::json!({
+                "$type": "app.bsky.embed.recordWithMedia",
+                "record": {"$type": "app.bsky.embed.record", "record": {"uri": "at://x", "cid": "y"}},
+                "media": {"$type": "app.bsky.embed.images", "images": [
+                    {"image": blob("a", 100), "alt": ""},
+                    {"image": blob("b", 200), "alt": ""},
+                ]},
+            }),
+        );
+        let text = make_record("text", &old_timestamp());
+
+        assert_eq!(video.value.blob_bytes(None), 5_000);
+        assert_eq!(quote_with_images.value.blob_bytes(None), 300);
+        assert_eq!(
+            quote_with_images
+                .value
+                .blob_bytes(Some(&HashSet::from(["b".to_string()]))),
+            200
+        );
+        assert_eq!(text.value.blob_bytes(None), 0);
+    }
+
+    #[tokio::test]
+    async fn largest_media_first_orders_posts_by_blob_size() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![
+                    make_record("text", &old_timestamp()),
+                    make_record_with_image("small", "cid-small", 1_000),
+                    make_record_with_image("large", "cid-large", 900_000),
+                    make_record_with_image("missing", "cid-gone", 5_000_000),
+                    make_record_with_image("medium", "cid-medium", 50_000),
+                ],
+            )
+            .with_blobs(&["cid-small", "cid-large", "cid-medium"])
+            .with_page_size(2);
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.largest_media_first = true;
+        let result = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &KeepList::default(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let order: Vec<String> = fake.deleted().into_iter().map(|(_, rkey)| rkey).collect();
+        // Blobs no longer in the repo (e.g. "missing") don't count toward the ordering
+        assert_eq!(order, vec!["large", "medium", "small", "text", "missing"]);
+        assert_eq!(result.bytes_reclaimed, 951_000);
+    }
+
+    #[tokio::test]
+    async fn keep_shared_blobs_skips_posts_whose_media_is_used_elsewhere() {
+        tokio::time::pause();
+        let profile = record_from_json(
+            "app.bsky.actor.profile",
+            "self",
+            serde_json::json!({"avatar": blob("cid-avatar", 2_000)}),
+        );
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records("app.bsky.actor.profile", vec![profile])
+            .with_records(
+                "app.bsky.feed.post",
+                vec![
+                    make_record_with_image("avatar-post", "cid-avatar", 2_000),
+                    make_record_with_image("own-image", "cid-own", 1_000),
+                    make_record("text", &old_timestamp()),
+                ],
+            );
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.keep_shared_blobs = true;
+        let result = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &KeepList::default(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let deleted: Vec<String> = fake.deleted().into_iter().map(|(_, rkey)| rkey).collect();
+        assert_eq!(deleted, vec!["own-image", "text"]);
+        assert_eq!(result.skipped.shared_blob, 1);
+        assert_eq!(result.remaining.count, 1);
+    }
+
+    #[tokio::test]
+    async fn posts_sharing_a_blob_keep_each_other() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.post",
+            vec![
+                make_record_with_image("first", "cid-shared", 1_000),
+                make_record_with_image("second", "cid-shared", 1_000),
+            ],
+        );
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.keep_shared_blobs = true;
+        config.dry_run = true;
+        let result = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &KeepList::default(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.deleted, 0);
+        assert_eq!(result.skipped.shared_blob, 2);
+    }
+
+    #[tokio::test]
+    async fn listing_order_kept_without_largest_media_first() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.post",
+            vec![
+                make_record_with_image("small", "cid-small", 1_000),
+                make_record_with_image("large", "cid-large", 900_000),
+            ],
+        );
+
+        let config = config_with_cutoff_days_ago(30);
+        let result = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &KeepList::default(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let order: Vec<String> = fake.deleted().into_iter().map(|(_, rkey)| rkey).collect();
+        assert_eq!(order, vec!["small", "large"]);
+        assert_eq!(result.bytes_reclaimed, 901_000);
+    }
+
+    #[tokio::test]
+    async fn dry_run_grouped_buffers_plan_by_month() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.post",
+            vec![
+                make_record("june", "2023-06-10T00:00:00Z"),
+                make_record("may2", "2023-05-20T00:00:00Z"),
+                make_record("may1", "2023-05-01T00:00:00Z"),
+                make_record("new", &recent_timestamp()),
+            ],
+        );
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.dry_run = true;
+        config.dry_run_grouped = true;
+        let result = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &KeepList::default(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert!(fake.deleted().is_empty());
+        let months = result.planned.months();
+        assert_eq!(months.len(), 2);
+        assert_eq!(months["2023-05"].len(), 2);
+        assert!(months["2023-05"][0].contains("/may1"));
+        assert!(months["2023-06"][0].contains("/june"));
+    }
+
+    #[tokio::test]
+    async fn subject_keep_entry_protects_likes_of_that_post() {
+        tokio::time::pause();
+        let famous = "at://did:plc:famous/app.bsky.feed.post/3kfamous";
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.like",
+            vec![
+                make_like_of("like1", famous),
+                make_like_of("like2", "at://did:plc:other/app.bsky.feed.post/3kother"),
+            ],
+        );
+
+        let config = config_with_cutoff_days_ago(30);
+        let keep_list = KeepList::from_entries([format!("bluesky:subject:{famous}")]);
+        let result = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.like",
+            DEFAULT_DELAY,
+            &config,
+            &keep_list,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.skipped.kept, 1);
+        assert_eq!(
+            fake.deleted(),
+            vec![("app.bsky.feed.like".to_string(), "like2".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn keep_curated_skips_posts_referenced_by_lists_and_feeds() {
+        tokio::time::pause();
+        let curated = format!("at://{DID}/app.bsky.feed.post/curated");
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![
+                    make_record("curated", &old_timestamp()),
+                    make_record("plain", &old_timestamp()),
+                ],
+            )
+            .with_records(
+                "app.bsky.graph.listitem",
+                vec![record_from_json(
+                    "app.bsky.graph.listitem",
+                    "3kitem",
+                    serde_json::json!({
+                        "subject": "did:plc:someone",
+                        "list": format!("at://{DID}/app.bsky.graph.list/3klist"),
+                        "createdAt": old_timestamp(),
+                    }),
+                )],
+            )
+            .with_records(
+                "app.bsky.feed.generator",
+                vec![record_from_json(
+                    "app.bsky.feed.generator",
+                    "3kfeed",
+                    serde_json::json!({
+                        "did": "did:web:feeds.example",
+                        "pinnedPosts": [{"uri": curated, "cid": "bafy"}],
+                        "createdAt": old_timestamp(),
+                    }),
+                )],
+            );
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.keep_curated = true;
+        let summary = delete_old_posts(&fake, "user", "pass", &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            fake.deleted(),
+            vec![("app.bsky.feed.post".to_string(), "plain".to_string())]
+        );
+        assert_eq!(summary.skipped.kept, 1);
+    }
+
+    #[tokio::test]
+    async fn each_collection_paces_with_its_own_delay() {
+        tokio::time::pause();
+        let records = |collection: &str, n: usize| {
+            (0..n)
+                .map(|i| make_record_for_collection(collection, &format!("r{i}"), &old_timestamp()))
+                .collect()
+        };
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records("app.bsky.feed.post", records("app.bsky.feed.post", 1))
+            .with_records("app.bsky.feed.repost", records("app.bsky.feed.repost", 2))
+            .with_records("app.bsky.feed.like", records("app.bsky.feed.like", 3));
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.post_delay = Some(Duration::from_secs(100));
+        config.repost_delay = Some(Duration::from_secs(10));
+        config.like_delay = Some(Duration::from_secs(1));
+        let start = tokio::time::Instant::now();
+        delete_old_posts(&fake, "user", "pass", &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted().len(), 6);
+        // Paused time rounds each sleep up a millisecond, hence whole seconds
+        assert_eq!(start.elapsed().as_secs(), 100 + 2 * 10 + 3);
+    }
+
+    #[tokio::test]
+    async fn collections_without_their_own_delay_use_the_shared_one() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![make_record("post1", &old_timestamp())],
+            )
+            .with_records(
+                "app.bsky.feed.like",
+                vec![make_record_for_collection(
+                    "app.bsky.feed.like",
+                    "like1",
+                    &old_timestamp(),
+                )],
+            );
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.delay = Some(Duration::from_secs(30));
+        config.like_delay = Some(Duration::from_secs(1));
+        let start = tokio::time::Instant::now();
+        delete_old_posts(&fake, "user", "pass", &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(start.elapsed().as_secs(), 31);
+    }
+
+    #[tokio::test]
+    async fn author_keep_entry_resolves_handle_and_protects_likes_of_that_account() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_handle("alice.com", "did:plc:alice")
+            .with_records(
+                "app.bsky.feed.like",
+                vec![
+                    make_like_of("like1", "at://did:plc:alice/app.bsky.feed.post/3kalice"),
+                    make_like_of("like2", "at://did:plc:other/app.bsky.feed.post/3kother"),
+                ],
+            );
+
+        let config = config_with_cutoff_days_ago(30);
+        let keep_list = KeepList::from_entries(["bluesky:author:alice.com"]);
+        let summary = delete_old_posts(&fake, "user", "pass", &config, &keep_list)
+            .await
+            .unwrap();
+
+        assert_eq!(summary.skipped.kept, 1);
+        assert_eq!(
+            fake.deleted(),
+            vec![("app.bsky.feed.like".to_string(), "like2".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn unresolvable_author_handle_is_an_error() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.like",
+            vec![make_like_of(
+                "like1",
+                "at://did:plc:alice/app.bsky.feed.post/3k",
+            )],
+        );
+
+        let config = config_with_cutoff_days_ago(30);
+        let keep_list = KeepList::from_entries(["bluesky:author:nobody.example"]);
+        assert!(delete_old_posts(&fake, "user", "pass", &config, &keep_list)
+            .await
+            .is_err());
+        assert!(fake.deleted().is_empty());
+    }
+
+    #[test]
+    fn list_item_subject_is_not_a_strong_ref() {
+        let record = record_from_json(
+            "app.bsky.graph.listitem",
+            "3kitem",
+            serde_json::json!({"subject": "did:plc:someone"}),
+        );
+        assert!(record.value.subject.is_none());
+    }
+
+    #[test]
+    fn parses_subject_from_like_record() {
+        let record: Record = serde_json::from_value(serde_json::json!({
+            "uri": format!("at://{DID}/app.bsky.feed.like/3klike"),
+            "value": {
+                "$type": "app.bsky.feed.like",
+                "createdAt": "2024-01-01T00:00:00Z",
+                "subject": {"uri": "at://did:plc:famous/app.bsky.feed.post/3kfamous", "cid": "bafy"},
+            },
+        }))
+        .unwrap();
+        assert_eq!(
+            record.value.subject.unwrap().uri,
+            "at://did:plc:famous/app.bsky.feed.post/3kfamous"
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_session_mid_page_refreshes_and_retries_the_delete() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records(
+                "app.bsky.feed.post",
+                (0..5)
+                    .map(|i| make_record(&format!("r{i}"), &old_timestamp()))
+                    .collect(),
+            )
+            .expiring_on_delete_call(3);
+
+        let config = config_with_cutoff_days_ago(30);
+        let result = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &KeepList::default(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(fake.refreshes(), 1);
+        assert_eq!(result.deleted, 5);
+        assert_eq!(
+            fake.deleted(),
+            (0..5)
+                .map(|i| ("app.bsky.feed.post".to_string(), format!("r{i}")))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_session_mid_pagination_resumes_from_the_same_cursor() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records(
+                "app.bsky.feed.post",
+                (0..5)
+                    .map(|i| make_record(&format!("r{i}"), &old_timestamp()))
+                    .collect(),
+            )
+            .with_page_size(2)
+            .expiring_on_list_call(2);
+
+        let config = config_with_cutoff_days_ago(30);
+        let result = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &KeepList::default(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(fake.refreshes(), 1);
+        assert_eq!(result.deleted, 5);
+        assert_eq!(result.already_gone, 0);
+        assert_eq!(
+            fake.deleted(),
+            (0..5)
+                .map(|i| ("app.bsky.feed.post".to_string(), format!("r{i}")))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn records_repeated_across_overlapping_pages_are_processed_once() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records(
+                "app.bsky.feed.post",
+                (0..5)
+                    .map(|i| make_record(&format!("r{i}"), &old_timestamp()))
+                    .collect(),
+            )
+            .with_page_size(2)
+            .with_page_overlap(1);
+
+        let config = config_with_cutoff_days_ago(30);
+        let result = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &KeepList::from_entries(["bluesky:r1"]),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.deleted, 4);
+        assert_eq!(result.already_gone, 0);
+        assert_eq!(result.skipped.kept, 1);
+        assert_eq!(result.remaining.count, 1);
+    }
+
+    #[tokio::test]
+    async fn deleting_an_already_gone_record_is_not_an_error() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![
+                    make_record("gone", &old_timestamp()),
+                    make_record("live", &old_timestamp()),
+                ],
+            )
+            .deleted_elsewhere("app.bsky.feed.post", "gone");
+
+        let config = config_with_cutoff_days_ago(30);
+        let result = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &KeepList::default(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.deleted, 1);
+        assert_eq!(result.already_gone, 1);
+        assert_eq!(result.remaining.count, 0);
+    }
+
+    #[tokio::test]
+    async fn shutdown_stops_the_sweep_after_the_current_delete() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.post",
+            vec![
+                make_record("old1", &old_timestamp()),
+                make_record("old2", &old_timestamp()),
+            ],
+        );
+        let config = config_with_cutoff_days_ago(30);
+        config.shutdown.trigger();
+
+        let result = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &KeepList::default(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.deleted, 1);
+        assert_eq!(
+            fake.deleted(),
+            vec![("app.bsky.feed.post".to_string(), "old1".to_string())]
+        );
+    }
+
+    // --- explain ---
+
+    async fn explain_post(fake: &FakeBlueskyClient, id: &str, keep_list: &KeepList) -> Decision {
+        let config = config_with_cutoff_days_ago(30);
+        explain(fake, "user", "pass", id, &config, keep_list)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn explain_reports_the_deciding_factor() {
+        let days_ago = |days| {
+            (chrono::Utc::now() - chrono::TimeDelta::days(days))
+                .format("%Y-%m-%dT%H:%M:%SZ")
+                .to_string()
+        };
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![
+                    make_record("old", &old_timestamp()),
+                    make_record("new", &days_ago(5)),
+                    make_record("future", &days_ago(-365)),
+                    make_record("kept", &old_timestamp()),
+                    make_record("pinned", &old_timestamp()),
+                    record_from_json("app.bsky.feed.post", "undated", serde_json::json!({})),
+                ],
+            )
+            .with_pinned_post(&format!("at://{DID}/app.bsky.feed.post/pinned"));
+        let keep_list = KeepList::from_entries(["bluesky:kept"]);
+
+        assert_eq!(
+            explain_post(&fake, "old", &keep_list).await,
+            Decision::Delete
+        );
+        assert_eq!(
+            explain_post(&fake, "new", &keep_list).await,
+            Decision::NewerThanCutoff
+        );
+        assert_eq!(
+            explain_post(&fake, "future", &keep_list).await,
+            Decision::FutureDated
+        );
+        assert_eq!(
+            explain_post(&fake, "kept", &keep_list).await,
+            Decision::Kept(KeptBy::Rkey, "bluesky:kept".into())
+        );
+        assert_eq!(
+            explain_post(&fake, "pinned", &keep_list).await,
+            Decision::Pinned
+        );
+        assert_eq!(
+            explain_post(&fake, "undated", &keep_list).await,
+            Decision::MissingTimestamp
+        );
+    }
+
+    #[tokio::test]
+    async fn explain_accepts_at_uris_for_other_collections() {
+        let famous = "at://did:plc:famous/app.bsky.feed.post/3kfamous";
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records("app.bsky.feed.like", vec![make_like_of("3klike", famous)]);
+        let keep_list = KeepList::from_entries([format!("bluesky:subject:{famous}")]);
+
+        assert_eq!(
+            explain_post(
+                &fake,
+                &format!("at://{DID}/app.bsky.feed.like/3klike"),
+                &keep_list
+            )
+            .await,
+            Decision::Kept(KeptBy::Subject, format!("bluesky:subject:{famous}"))
+        );
+    }
+
+    #[tokio::test]
+    async fn collection_keep_file_only_protects_its_own_collection() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![make_record("shared", &old_timestamp())],
+            )
+            .with_records(
+                "app.bsky.feed.like",
+                vec![
+                    make_record_for_collection("app.bsky.feed.like", "shared", &old_timestamp()),
+                    make_record_for_collection("app.bsky.feed.like", "other", &old_timestamp()),
+                ],
+            );
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.delete_reposts = false;
+        config.collection_keep.insert(
+            "app.bsky.feed.like".into(),
+            KeepList::from_entries(["bluesky:shared"]),
+        );
+        let summary = delete_old_posts(&fake, "user", "pass", &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!((summary.posts, summary.likes), (1, 1));
+        assert_eq!(summary.skipped.kept, 1);
+        assert_eq!(
+            fake.deleted(),
+            vec![
+                ("app.bsky.feed.post".to_string(), "shared".to_string()),
+                ("app.bsky.feed.like".to_string(), "other".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn stats_tally_every_collection_without_deleting() {
+        let fake = FakeBlueskyClient::new(DID)
+            .with_pinned_post(PINNED_URI)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![
+                    make_record("pinned1", &old_timestamp()),
+                    make_record("kept", &old_timestamp()),
+                    make_record("old", &old_timestamp()),
+                    make_record("recent", &recent_timestamp()),
+                    record_from_json("app.bsky.feed.post", "undated", serde_json::json!({})),
+                ],
+            )
+            .with_records(
+                "app.bsky.feed.like",
+                vec![make_like_of("like1", "at://did:plc:x/app.bsky.feed.post/1")],
+            );
+
+        let config = config_with_cutoff_days_ago(30);
+        let keep_list = KeepList::from_entries(["bluesky:kept"]);
+        let stats = stats(&fake, "user", "pass", &config, &keep_list)
+            .await
+            .unwrap();
+
+        assert!(fake.deleted().is_empty());
+        assert_eq!(
+            (stats.total, stats.eligible, stats.protected, stats.undated),
+            (5, 2, 2, 1)
+        );
+        let bucketed: u64 = stats.age_buckets.iter().map(|b| b.count).sum();
+        assert_eq!(bucketed, stats.total);
+    }
+
+    #[tokio::test]
+    async fn full_plan_lists_old_records_with_their_reasons() {
+        let fake = FakeBlueskyClient::new(DID)
+            .with_pinned_post(PINNED_URI)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![
+                    make_record("pinned1", &old_timestamp()),
+                    make_record("kept", &old_timestamp()),
+                    make_record("old", &old_timestamp()),
+                    make_record("recent", &recent_timestamp()),
+                ],
+            )
+            .with_records(
+                "app.bsky.feed.like",
+                vec![make_like_of("like1", "at://did:plc:x/app.bsky.feed.post/1")],
+            );
+
+        let config = config_with_cutoff_days_ago(30);
+        let keep_list = KeepList::from_entries(["bluesky:kept"]);
+        let entries = full_plan(&fake, "user", "pass", &config, &keep_list)
+            .await
+            .unwrap();
+
+        assert!(fake.deleted().is_empty());
+        let plan: Vec<(&str, Action, &str)> = entries
+            .iter()
+            .map(|e| {
+                (
+                    e.id.rsplit('/').next().unwrap(),
+                    e.action,
+                    e.reason.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            plan,
+            vec![
+                ("pinned1", Action::Keep, "pinned (DELETE_PINNED is off)"),
+                ("kept", Action::Keep, "matched keep rule bluesky:kept"),
+                (
+                    "old",
+                    Action::Delete,
+                    "older than the cutoff and not protected"
+                ),
+                (
+                    "like1",
+                    Action::Delete,
+                    "older than the cutoff and not protected"
+                ),
+            ]
+        );
+        assert_eq!(entries[3].kind, "app.bsky.feed.like");
+    }
+
+    #[tokio::test]
+    async fn missing_records_reports_rkeys_and_uris_that_are_gone() {
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![make_record("alive", &old_timestamp())],
+            )
+            .with_records(
+                "app.bsky.feed.like",
+                vec![make_record("liked", &old_timestamp())],
+            );
+        let ids = [
+            "alive",
+            "gone",
+            "at://did:plc:testuser123/app.bsky.feed.like/liked",
+            "at://did:plc:testuser123/app.bsky.feed.like/unliked",
+        ];
+
+        let config = config_with_cutoff_days_ago(30);
+        let missing = missing_records(&fake, "user", "pass", &ids, &config)
+            .await
+            .unwrap();
+        assert_eq!(
+            missing,
+            HashSet::from([
+                "gone".to_string(),
+                "at://did:plc:testuser123/app.bsky.feed.like/unliked".to_string(),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_records_looks_up_each_post_once_in_batches() {
+        let alive: Vec<Record> = (0..30)
+            .map(|i| make_record(&format!("post{i}"), &old_timestamp()))
+            .collect();
+        let fake = FakeBlueskyClient::new(DID).with_records("app.bsky.feed.post", alive);
+        let uri = |rkey: &str| format!("at://{DID}/app.bsky.feed.post/{rkey}");
+        let mut ids: Vec<String> = (0..30).map(|i| format!("post{i}")).collect();
+        // The same posts again, by URI, plus one that's gone named both ways
+        ids.extend((0..30).map(|i| uri(&format!("post{i}"))));
+        ids.extend(["gone".to_string(), uri("gone")]);
+        let ids: Vec<&str> = ids.iter().map(String::as_str).collect();
+
+        let config = config_with_cutoff_days_ago(30);
+        let missing = missing_records(&fake, "user", "pass", &ids, &config)
+            .await
+            .unwrap();
+
+        assert_eq!(missing, HashSet::from(["gone".to_string(), uri("gone")]));
+        let calls = fake.existing_posts_calls.lock().unwrap();
+        assert_eq!(
+            calls.iter().map(Vec::len).collect::<Vec<_>>(),
+            [GET_POSTS_BATCH, 31 - GET_POSTS_BATCH]
+        );
+        // Only the bare "gone" is looked for among reposts and likes
+        assert_eq!(*fake.record_exists_calls.lock().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn missing_records_keeps_bare_rkeys_of_likes_and_extra_collections() {
+        let labeler = "app.bsky.labeler.service";
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records(
+                "app.bsky.feed.like",
+                vec![make_record_for_collection(
+                    "app.bsky.feed.like",
+                    "liked",
+                    &old_timestamp(),
+                )],
+            )
+            .with_records(
+                labeler,
+                vec![make_record_for_collection(
+                    labeler,
+                    "self",
+                    &old_timestamp(),
+                )],
+            );
+        let mut config = config_with_cutoff_days_ago(30);
+        config.extra_collections = vec![labeler.to_string()];
+
+        let missing = missing_records(&fake, "user", "pass", &["liked", "self", "gone"], &config)
+            .await
+            .unwrap();
+        assert_eq!(missing, HashSet::from(["gone".to_string()]));
+    }
+
+    fn dated_preferences() -> serde_json::Value {
+        serde_json::json!([
+            {
+                "$type": "app.bsky.actor.defs#savedFeedsPrefV2",
+                "items": [
+                    {"type": "timeline", "value": "following", "pinned": true, "id": "1"},
+                ],
+            },
+            {
+                "$type": "app.bsky.actor.defs#mutedWordsPref",
+                "items": [
+                    {"value": "spoilers", "targets": ["content"], "expiresAt": old_timestamp()},
+                    {"value": "election", "targets": ["tag"], "expiresAt": recent_timestamp()},
+                    {"value": "forever", "targets": ["content"]},
+                ],
+            },
+            {
+                "$type": "app.bsky.actor.defs#bskyAppStatePref",
+                "nuxs": [{"id": "old-nudge", "completed": true, "createdAt": old_timestamp()}],
+            },
+            {"$type": "app.bsky.actor.defs#adultContentPref", "enabled": false},
+            {
+                "$type": "app.bsky.actor.defs#someFuturePref",
+                "items": [{"createdAt": old_timestamp()}],
             },
+        ])
+    }
+
+    #[test]
+    fn prune_preferences_drops_only_dated_entries_older_than_the_cutoff() {
+        let mut preferences: Vec<serde_json::Value> =
+            serde_json::from_value(dated_preferences()).unwrap();
+        let config = config_with_cutoff_days_ago(30);
+
+        assert_eq!(prune_preferences(&mut preferences, config.cutoff), 2);
+        let words: Vec<&str> = preferences[1]["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|w| w["value"].as_str().unwrap())
+            .collect();
+        assert_eq!(words, ["election", "forever"]);
+        assert_eq!(preferences[0]["items"].as_array().unwrap().len(), 1);
+        assert!(preferences[2]["nuxs"].as_array().unwrap().is_empty());
+        assert_eq!(preferences[3]["enabled"], false);
+        assert_eq!(preferences[4]["items"].as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn prune_preferences_writes_back_unless_dry_run() {
+        tokio::time::pause();
+        let mut config = config_with_cutoff_days_ago(30);
+        config.prune_preferences = true;
+        config.dry_run = true;
+
+        let preferences = dated_preferences();
+        let fake = FakeBlueskyClient::new(DID).with_preferences(preferences.clone());
+        let summary = delete_old_posts(&fake, "user", "pass", &config, &KeepList::default())
+            .await
+            .unwrap();
+        assert_eq!(summary.other, 0);
+        assert_eq!(
+            serde_json::Value::from(fake.preferences.lock().unwrap().clone()),
+            preferences
+        );
+
+        config.dry_run = false;
+        delete_old_posts(&fake, "user", "pass", &config, &KeepList::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            fake.preferences.lock().unwrap()[1]["items"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    fn repo_status_from(status: serde_json::Value, described: serde_json::Value) -> RepoStatus {
+        let status: RepoStatusResponse = serde_json::from_value(status).unwrap();
+        let described: DescribeRepoResponse = serde_json::from_value(described).unwrap();
+        RepoStatus {
+            rev: status.rev,
+            collections: described.collections,
+        }
+    }
+
+    #[test]
+    fn repo_delta_reports_the_new_rev_and_emptied_collections() {
+        let before = repo_status_from(
+            serde_json::json!({"did": DID, "active": true, "rev": "3kabc"}),
+            serde_json::json!({
+                "handle": "user.bsky.social",
+                "did": DID,
+                "collections": ["app.bsky.feed.like", "app.bsky.feed.post"],
+                "handleIsCorrect": true,
+            }),
+        );
+        let after = repo_status_from(
+            serde_json::json!({"did": DID, "active": true, "rev": "3kdef"}),
+            serde_json::json!({
+                "handle": "user.bsky.social",
+                "did": DID,
+                "collections": ["app.bsky.feed.post"],
+                "handleIsCorrect": true,
+            }),
+        );
+
+        assert_eq!(
+            before.delta(&after),
+            "rev 3kabc -> 3kdef, collections removed: app.bsky.feed.like"
+        );
+        assert_eq!(before.delta(&before), "unchanged at rev 3kabc");
+    }
+
+    #[test]
+    fn evaluate_checks_cutoff_before_keep_rules() {
+        let config = config_with_cutoff_days_ago(30);
+        let record = make_record("abc", &old_timestamp());
+        let now = chrono::Utc::now();
+        let keep_list = KeepList::from_entries(["bluesky:abc"]);
+
+        let newer = evaluate(
+            &record,
+            now - chrono::TimeDelta::days(1),
+            "app.bsky.feed.post",
+            &config,
+            &keep_list,
+            None,
+            now,
+        );
+        assert_eq!(newer, Decision::NewerThanCutoff);
+
+        let mut no_reposts = config_with_cutoff_days_ago(30);
+        no_reposts.delete_reposts = false;
+        let repost = evaluate(
+            &record,
+            now - chrono::TimeDelta::days(365),
+            "app.bsky.feed.repost",
+            &no_reposts,
+            &KeepList::default(),
+            None,
+            now,
+        );
+        assert_eq!(repost, Decision::RepostsDisabled);
+    }
+
+    // --- archive and restore ---
+
+    #[tokio::test]
+    async fn archive_dir_saves_records_before_deleting() {
+        tokio::time::pause();
+        let dir = tempfile::tempdir().unwrap();
+        let fake = FakeBlueskyClient::new(DID).with_records(
+            "app.bsky.feed.post",
+            vec![record_from_json(
+                "app.bsky.feed.post",
+                "3kold",
+                serde_json::json!({"$type": "app.bsky.feed.post", "text": "hello", "createdAt": old_timestamp()}),
+            )],
+        );
+        let mut config = config_with_cutoff_days_ago(30);
+        config.archive_dir = Some(dir.path().to_path_buf());
+
+        delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &KeepList::default(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(fake.deleted().len(), 1);
+        let archived = archive::load_all(dir.path()).unwrap();
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived[0].rkey, "3kold");
+        assert_eq!(archived[0].value["text"], "hello");
+    }
+
+    #[tokio::test]
+    async fn restore_recreates_archived_records_at_original_rkeys() {
+        tokio::time::pause();
+        let dir = tempfile::tempdir().unwrap();
+        let post = serde_json::json!({"$type": "app.bsky.feed.post", "text": "hi", "createdAt": old_timestamp()});
+        let like = serde_json::json!({"$type": "app.bsky.feed.like", "createdAt": old_timestamp()});
+        for (collection, rkey, value) in [
+            ("app.bsky.feed.post", "3kpost", &post),
+            ("app.bsky.feed.like", "3klike", &like),
+        ] {
+            archive::save(
+                dir.path(),
+                &ArchivedRecord {
+                    uri: format!("at://{DID}/{collection}/{rkey}"),
+                    collection: collection.to_string(),
+                    rkey: rkey.to_string(),
+                    value: value.clone(),
+                },
+            )
+            .unwrap();
         }
+        let fake = FakeBlueskyClient::new(DID);
+        let config = config_with_cutoff_days_ago(30);
+
+        let restored = restore_archived(&fake, "user", "pw", dir.path(), &config, true)
+            .await
+            .unwrap();
+
+        assert_eq!(restored, 2);
+        assert_eq!(
+            fake.put(),
+            vec![
+                ("app.bsky.feed.like".to_string(), "3klike".to_string(), like),
+                ("app.bsky.feed.post".to_string(), "3kpost".to_string(), post),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn restore_without_confirmation_only_lists() {
+        tokio::time::pause();
+        let dir = tempfile::tempdir().unwrap();
+        archive::save(
+            dir.path(),
+            &ArchivedRecord {
+                uri: format!("at://{DID}/app.bsky.feed.post/3kpost"),
+                collection: "app.bsky.feed.post".to_string(),
+                rkey: "3kpost".to_string(),
+                value: serde_json::json!({}),
+            },
+        )
+        .unwrap();
+        let fake = FakeBlueskyClient::new(DID);
+        let config = config_with_cutoff_days_ago(30);
+
+        let restored = restore_archived(&fake, "user", "pw", dir.path(), &config, false)
+            .await
+            .unwrap();
+
+        assert_eq!(restored, 0);
+        assert!(fake.put().is_empty());
+    }
+
+    #[test]
+    fn classifies_media_kind_from_embed() {
+        let text = make_record("text", &old_timestamp());
+        let images = make_record_with_embed(
+            "images",
+            &old_timestamp(),
+            serde_json::json!({"$type": "app.bsky.embed.images", "images": []}),
+        );
+        let video = make_record_with_embed(
+            "video",
+            &old_timestamp(),
+            serde_json::json!({"$type": "app.bsky.embed.video", "video": {}}),
+        );
+        let quoted_video = make_record_with_embed(
+            "quoted_video",
+            &old_timestamp(),
+            serde_json::json!({
+                "$type": "app.bsky.embed.recordWithMedia",
+                "record": {},
+                "media": {"$type": "app.bsky.embed.video", "video": {}},
+            }),
+        );
+        let link = make_record_with_embed(
+            "link",
+            &old_timestamp(),
+            serde_json::json!({"$type": "app.bsky.embed.external", "external": {}}),
+        );
+
+        assert_eq!(text.value.media_kind(), MediaKind::Text);
+        assert_eq!(images.value.media_kind(), MediaKind::Images);
+        assert_eq!(video.value.media_kind(), MediaKind::Video);
+        assert_eq!(quoted_video.value.media_kind(), MediaKind::Video);
+        assert_eq!(link.value.media_kind(), MediaKind::Other);
     }
 
-    fn make_record_for_collection(collection: &str, rkey: &str, created_at: &str) -> Record {
-        Record {
-            uri: format!("at://{DID}/{collection}/{rkey}"),
-            value: RecordValue {
-                created_at: Some(created_at.to_string()),
-            },
-        }
+    #[tokio::test]
+    async fn video_only_filter_deletes_only_video_posts() {
+        tokio::time::pause();
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![
+                    make_record("text", &old_timestamp()),
+                    make_record_with_embed(
+                        "images",
+                        &old_timestamp(),
+                        serde_json::json!({"$type": "app.bsky.embed.images", "images": []}),
+                    ),
+                    make_record_with_embed(
+                        "video",
+                        &old_timestamp(),
+                        serde_json::json!({"$type": "app.bsky.embed.video", "video": {}}),
+                    ),
+                ],
+            )
+            .with_records(
+                "app.bsky.feed.like",
+                vec![make_record_for_collection(
+                    "app.bsky.feed.like",
+                    "like1",
+                    &old_timestamp(),
+                )],
+            );
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.media_filter = MediaFilter::VideoOnly;
+        let keep_list = KeepList::default();
+        let posts = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &keep_list,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(posts.deleted, 1);
+        assert_eq!(posts.deleted_videos, 1);
+
+        // Likes have no embeds and are unaffected by the media filter
+        delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.like",
+            DEFAULT_DELAY,
+            &config,
+            &keep_list,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            fake.deleted(),
+            vec![
+                ("app.bsky.feed.post".to_string(), "video".to_string()),
+                ("app.bsky.feed.like".to_string(), "like1".to_string()),
+            ]
+        );
     }
 
     #[tokio::test]
-    async fn deletes_posts_older_than_cutoff() {
+    async fn plain_text_only_keeps_posts_with_media_or_links() {
         tokio::time::pause();
         let fake = FakeBlueskyClient::new(DID).with_records(
             "app.bsky.feed.post",
-            vec![make_record("abc123", &old_timestamp())],
+            vec![
+                make_record("text", &old_timestamp()),
+                record_from_json(
+                    "app.bsky.feed.post",
+                    "mention",
+                    serde_json::json!({
+                        "createdAt": old_timestamp(),
+                        "text": "hi @bob.com",
+                        "facets": [{"features": [{"$type": "app.bsky.richtext.facet#mention", "did": "did:plc:bob"}]}],
+                    }),
+                ),
+                record_from_json(
+                    "app.bsky.feed.post",
+                    "link",
+                    serde_json::json!({
+                        "createdAt": old_timestamp(),
+                        "text": "see example.com",
+                        "facets": [{"features": [{"$type": "app.bsky.richtext.facet#link", "uri": "https://example.com"}]}],
+                    }),
+                ),
+                make_record_with_embed(
+                    "card",
+                    &old_timestamp(),
+                    serde_json::json!({"$type": "app.bsky.embed.external", "external": {}}),
+                ),
+                make_record_with_embed(
+                    "images",
+                    &old_timestamp(),
+                    serde_json::json!({"$type": "app.bsky.embed.images", "images": []}),
+                ),
+            ],
         );
 
-        let config = config_with_cutoff_days_ago(30);
-        let keep_list = HashSet::new();
-        delete_old_posts(&fake, "user", "pass", &config, &keep_list)
-            .await
-            .unwrap();
+        let mut config = config_with_cutoff_days_ago(30);
+        config.delete_plain_text_only = true;
+        let posts = delete_old_records(
+            &fake,
+            DID,
+            "app.bsky.feed.post",
+            DEFAULT_DELAY,
+            &config,
+            &KeepList::default(),
+            None,
+        )
+        .await
+        .unwrap();
 
+        assert_eq!(posts.deleted, 2);
         assert_eq!(
             fake.deleted(),
-            vec![("app.bsky.feed.post".to_string(), "abc123".to_string())]
+            vec![
+                ("app.bsky.feed.post".to_string(), "text".to_string()),
+                ("app.bsky.feed.post".to_string(), "mention".to_string()),
+            ]
         );
     }
 
     #[tokio::test]
-    async fn skips_posts_newer_than_cutoff() {
+    async fn text_prefix_filter_deletes_only_prefixed_posts() {
         tokio::time::pause();
-        let fake = FakeBlueskyClient::new(DID).with_records(
-            "app.bsky.feed.post",
-            vec![make_record("abc123", &recent_timestamp())],
+        let post = |rkey: &str, text: &str| {
+            record_from_json(
+                "app.bsky.feed.post",
+                rkey,
+                serde_json::json!({"text": text, "createdAt": old_timestamp()}),
+            )
+        };
+        let fake = FakeBlueskyClient::new(DID)
+            .with_records(
+                "app.bsky.feed.post",
+                vec![
+                    post("ping", "[bot] status ping"),
+                    post("hello", "hello [bot]"),
+                    make_record("notext", &old_timestamp()),
+                ],
+            )
+            .with_records(
+                "app.bsky.feed.like",
+                vec![make_record_for_collection(
+                    "app.bsky.feed.like",
+                    "like1",
+                    &old_timestamp(),
+                )],
+            );
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.delete_text_prefix = Some("[bot]".to_string());
+        let summary = delete_old_posts(&fake, "user", "pass", &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        // Likes have no text and are unaffected by the prefix filter
+        assert_eq!(
+            fake.deleted(),
+            vec![
+                ("app.bsky.feed.post".to_string(), "ping".to_string()),
+                ("app.bsky.feed.like".to_string(), "like1".to_string()),
+            ]
         );
+        assert_eq!(summary.remaining.unwrap().count, 2);
+    }
 
+    #[tokio::test]
+    async fn prefetch_requests_the_next_page_before_processing_the_current_one() {
+        tokio::time::pause();
+        let records = || {
+            (1..=5)
+                .map(|i| make_record(&format!("old{i}"), &old_timestamp()))
+                .collect()
+        };
         let config = config_with_cutoff_days_ago(30);
-        let keep_list = HashSet::new();
-        delete_old_posts(&fake, "user", "pass", &config, &keep_list)
+        let sweep = |fake: FakeBlueskyClient, config: Config| async move {
+            delete_old_records(
+                &fake,
+                DID,
+                "app.bsky.feed.post",
+                DEFAULT_DELAY,
+                &config,
+                &KeepList::default(),
+                None,
+            )
             .await
             .unwrap();
+            fake
+        };
 
-        assert!(fake.deleted().is_empty());
+        let sequential = sweep(
+            FakeBlueskyClient::new(DID)
+                .with_records("app.bsky.feed.post", records())
+                .with_page_size(2),
+            config.clone(),
+        )
+        .await;
+        assert_eq!(
+            *sequential.list_calls_at_delete.lock().unwrap(),
+            [1, 1, 2, 2, 3]
+        );
+
+        let mut prefetching = config.clone();
+        prefetching.prefetch_pages = true;
+        let prefetched = sweep(
+            FakeBlueskyClient::new(DID)
+                .with_records("app.bsky.feed.post", records())
+                .with_page_size(2),
+            prefetching,
+        )
+        .await;
+        // Each page's first deletion already sees the following page requested,
+        // and no page is requested twice
+        assert_eq!(
+            *prefetched.list_calls_at_delete.lock().unwrap(),
+            [2, 2, 3, 3, 3]
+        );
+        assert_eq!(*prefetched.list_calls.lock().unwrap(), 3);
+        assert_eq!(prefetched.deleted().len(), 5);
     }
 
     #[tokio::test]
-    async fn skips_pinned_post() {
+    async fn delete_account_data_deletes_every_record() {
         tokio::time::pause();
         let fake = FakeBlueskyClient::new(DID)
             .with_pinned_post(PINNED_URI)
             .with_records(
                 "app.bsky.feed.post",
-                vec![make_record("pinned1", &old_timestamp())],
+                vec![
+                    make_record("pinned1", &old_timestamp()),
+                    make_record("recent", &recent_timestamp()),
+                    make_record("old", &old_timestamp()),
+                ],
+            )
+            .with_records(
+                "app.bsky.feed.repost",
+                vec![make_repost_of(
+                    "repost1",
+                    "at://did:plc:x/app.bsky.feed.post/1",
+                )],
+            )
+            .with_records(
+                "app.bsky.feed.like",
+                vec![make_like_of("like1", "at://did:plc:x/app.bsky.feed.post/1")],
             );
 
-        let config = config_with_cutoff_days_ago(30);
-        let keep_list = HashSet::new();
-        delete_old_posts(&fake, "user", "pass", &config, &keep_list)
+        let mut config = config_with_cutoff_days_ago(30);
+        config.delete_likes = false;
+        config.keep_one_per_month = true;
+        config.delete_account_data = true;
+        let config = config.for_account_data();
+        let summary = delete_old_posts(&fake, "user", "pass", &config, &KeepList::default())
             .await
             .unwrap();
 
-        assert!(fake.deleted().is_empty());
+        assert_eq!((summary.posts, summary.reposts, summary.likes), (3, 1, 1));
+        assert_eq!(summary.remaining.unwrap().count, 0);
     }
 
     #[tokio::test]
-    async fn deletes_pinned_when_configured() {
+    async fn blocklisted_posts_are_deleted_whatever_their_age() {
         tokio::time::pause();
-        // When delete_pinned=true, get_pinned_post_uri is not called,
-        // so the pinned_post value on the fake doesn't matter
+        let yesterday = (Utc::now() - chrono::TimeDelta::days(1)).to_rfc3339();
+        let post = |rkey: &str, text: &str, created_at: &str| {
+            record_from_json(
+                "app.bsky.feed.post",
+                rkey,
+                serde_json::json!({"text": text, "createdAt": created_at}),
+            )
+        };
         let fake = FakeBlueskyClient::new(DID).with_records(
             "app.bsky.feed.post",
-            vec![make_record("pinned1", &old_timestamp())],
+            vec![
+                post("blocked", "What a BAD TAKE, honestly", &yesterday),
+                post("clean", "a good take", &yesterday),
+                post("substring", "bad takes all round", &yesterday),
+                post("kept", "bad take, but I'm keeping it", &yesterday),
+                post("old", "a good take", &old_timestamp()),
+            ],
         );
 
         let mut config = config_with_cutoff_days_ago(30);
-        config.delete_pinned = true;
-        let keep_list = HashSet::new();
-        delete_old_posts(&fake, "user", "pass", &config, &keep_list)
+        config.delete_blocklist = Some(Blocklist::parse("bad take\n").unwrap());
+        let keep_list = KeepList::from_entries(["bluesky:kept"]);
+        let summary = delete_old_posts(&fake, "user", "pass", &config, &keep_list)
             .await
             .unwrap();
 
+        // Non-matching posts still go through the cutoff as usual
         assert_eq!(
             fake.deleted(),
-            vec![("app.bsky.feed.post".to_string(), "pinned1".to_string())]
+            vec![
+                ("app.bsky.feed.post".to_string(), "blocked".to_string()),
+                ("app.bsky.feed.post".to_string(), "old".to_string()),
+            ]
         );
+        assert_eq!(summary.skipped.kept, 1);
+        assert_eq!(summary.remaining.unwrap().count, 3);
     }
 
     #[tokio::test]
-    async fn skips_keep_list_by_rkey() {
+    async fn posts_summary_status_when_enabled() {
         tokio::time::pause();
         let fake = FakeBlueskyClient::new(DID).with_records(
             "app.bsky.feed.post",
             vec![make_record("abc123", &old_timestamp())],
         );
 
-        let config = config_with_cutoff_days_ago(30);
-        let keep_list = HashSet::from(["bluesky:abc123".to_string()]);
-        delete_old_posts(&fake, "user", "pass", &config, &keep_list)
+        let mut config = config_with_cutoff_days_ago(30);
+        config.post_summary = true;
+        let keep_list = KeepList::default();
+        let summary = delete_old_posts(&fake, "user", "pass", &config, &keep_list)
             .await
             .unwrap();
 
-        assert!(fake.deleted().is_empty());
+        assert_eq!(summary.posts, 1);
+        let created = fake.created();
+        assert_eq!(created.len(), 1);
+        assert_eq!(created[0].0, "app.bsky.feed.post");
+        assert_eq!(
+            created[0].1["text"],
+            summary.status_text(config.cutoff).as_str()
+        );
     }
 
     #[tokio::test]
-    async fn skips_keep_list_by_full_uri() {
+    async fn does_not_post_summary_status_in_dry_run() {
         tokio::time::pause();
-        let uri = format!("at://{DID}/app.bsky.feed.post/abc123");
         let fake = FakeBlueskyClient::new(DID).with_records(
             "app.bsky.feed.post",
             vec![make_record("abc123", &old_timestamp())],
         );
 
-        let config = config_with_cutoff_days_ago(30);
-        let keep_list = HashSet::from([format!("bluesky:{uri}")]);
+        let mut config = config_with_cutoff_days_ago(30);
+        config.post_summary = true;
+        config.dry_run = true;
+        let keep_list = KeepList::default();
         delete_old_posts(&fake, "user", "pass", &config, &keep_list)
             .await
             .unwrap();
 
-        assert!(fake.deleted().is_empty());
+        assert!(fake.created().is_empty());
     }
 
     #[tokio::test]
-    async fn dry_run_does_not_call_delete() {
+    async fn does_not_post_summary_status_by_default() {
         tokio::time::pause();
         let fake = FakeBlueskyClient::new(DID).with_records(
             "app.bsky.feed.post",
             vec![make_record("abc123", &old_timestamp())],
         );
 
-        let mut config = config_with_cutoff_days_ago(30);
-        config.dry_run = true;
-        let keep_list = HashSet::new();
+        let config = config_with_cutoff_days_ago(30);
+        let keep_list = KeepList::default();
         delete_old_posts(&fake, "user", "pass", &config, &keep_list)
             .await
             .unwrap();
 
-        assert!(fake.deleted().is_empty());
+        assert_eq!(fake.deleted().len(), 1);
+        assert!(fake.created().is_empty());
     }
 
-    #[tokio::test]
-    async fn pagination_follows_cursor() {
-        tokio::time::pause();
-        let fake = FakeBlueskyClient::new(DID).with_page_size(1).with_records(
-            "app.bsky.feed.post",
-            vec![
-                make_record("abc123", &old_timestamp()),
-                make_record("def456", &old_timestamp()),
-            ],
-        );
+    // --- HttpBlueskyClient (against a mock server) ---
 
-        let config = config_with_cutoff_days_ago(30);
-        let keep_list = HashSet::new();
-        delete_old_posts(&fake, "user", "pass", &config, &keep_list)
-            .await
-            .unwrap();
+    mod http {
+        use super::super::*;
+        use wiremock::matchers::{body_json, header, method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
 
-        let deleted = fake.deleted();
-        assert_eq!(deleted.len(), 2);
-        assert!(deleted.contains(&("app.bsky.feed.post".to_string(), "abc123".to_string())));
-        assert!(deleted.contains(&("app.bsky.feed.post".to_string(), "def456".to_string())));
-    }
+        const DID: &str = "did:plc:testuser123";
 
-    #[tokio::test]
-    async fn respects_delete_reposts_and_likes_flags() {
-        tokio::time::pause();
-        let fake = FakeBlueskyClient::new(DID)
-            .with_records(
-                "app.bsky.feed.repost",
-                vec![make_record_for_collection(
-                    "app.bsky.feed.repost",
-                    "repost1",
-                    &old_timestamp(),
-                )],
-            )
-            .with_records(
-                "app.bsky.feed.like",
-                vec![make_record_for_collection(
-                    "app.bsky.feed.like",
-                    "like1",
-                    &old_timestamp(),
-                )],
+        async fn authenticated_client(server: &MockServer) -> HttpBlueskyClient {
+            Mock::given(method("POST"))
+                .and(path("/xrpc/com.atproto.server.createSession"))
+                .and(body_json(serde_json::json!({
+                    "identifier": "user.bsky.social",
+                    "password": "app-password",
+                })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "did": DID,
+                    "accessJwt": "jwt-token",
+                })))
+                .expect(1)
+                .mount(server)
+                .await;
+
+            let client = HttpBlueskyClient::new(&server.uri());
+            let session = client
+                .create_session("user.bsky.social", "app-password")
+                .await
+                .unwrap();
+            assert_eq!(session.did, DID);
+            client
+        }
+
+        #[tokio::test]
+        async fn login_retries_once_after_a_server_error() {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/xrpc/com.atproto.server.createSession"))
+                .respond_with(ResponseTemplate::new(502))
+                .up_to_n_times(1)
+                .expect(1)
+                .mount(&server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/xrpc/com.atproto.server.createSession"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "did": DID,
+                    "accessJwt": "jwt-token",
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let client = HttpBlueskyClient::new(&server.uri());
+            let config = crate::test_helpers::config_with_cutoff_days_ago(30);
+            let session = login(&client, "user.bsky.social", "app-password", &config)
+                .await
+                .unwrap();
+            assert_eq!(session.did, DID);
+        }
+
+        #[tokio::test]
+        async fn login_does_not_retry_wrong_credentials() {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/xrpc/com.atproto.server.createSession"))
+                .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
+                    "error": "AuthenticationRequired",
+                    "message": "Invalid identifier or password",
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let client = HttpBlueskyClient::new(&server.uri());
+            let config = crate::test_helpers::config_with_cutoff_days_ago(30);
+            assert!(login(&client, "user.bsky.social", "wrong", &config)
+                .await
+                .is_err());
+        }
+
+        #[tokio::test]
+        async fn create_session_failure_is_an_error() {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/xrpc/com.atproto.server.createSession"))
+                .respond_with(ResponseTemplate::new(401))
+                .mount(&server)
+                .await;
+
+            let client = HttpBlueskyClient::new(&server.uri());
+            let err = client.create_session("user", "wrong").await.err().unwrap();
+            assert!(format!("{err:#}").contains("Failed to authenticate with Bluesky"));
+        }
+
+        #[tokio::test]
+        async fn create_session_429_surfaces_as_rate_limited() {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/xrpc/com.atproto.server.createSession"))
+                .respond_with(
+                    ResponseTemplate::new(429)
+                        .insert_header("ratelimit-remaining", "0")
+                        .insert_header("ratelimit-reset", "1718452800"),
+                )
+                .mount(&server)
+                .await;
+
+            let client = HttpBlueskyClient::new(&server.uri());
+            let err = client.create_session("user", "pass").await.err().unwrap();
+            assert_eq!(
+                err.downcast_ref::<RateLimited>().unwrap().reset,
+                DateTime::from_timestamp(1718452800, 0)
             );
+        }
 
-        let mut config = config_with_cutoff_days_ago(30);
-        config.delete_reposts = false;
-        config.delete_likes = false;
-        let keep_list = HashSet::new();
-        delete_old_posts(&fake, "user", "pass", &config, &keep_list)
-            .await
-            .unwrap();
+        #[tokio::test]
+        async fn requests_use_the_default_user_agent_unless_configured() {
+            let server = MockServer::start().await;
+            for user_agent in [crate::DEFAULT_USER_AGENT, "my-bot/2.0"] {
+                Mock::given(method("GET"))
+                    .and(path("/xrpc/com.atproto.identity.resolveHandle"))
+                    .and(header("User-Agent", user_agent))
+                    .respond_with(
+                        ResponseTemplate::new(200)
+                            .set_body_json(serde_json::json!({"did": "did:plc:alice"})),
+                    )
+                    .expect(1)
+                    .mount(&server)
+                    .await;
+            }
 
-        assert!(fake.deleted().is_empty());
+            let default = HttpBlueskyClient::new(&server.uri());
+            default.resolve_handle("alice.com").await.unwrap();
+            let custom = HttpBlueskyClient::new(&server.uri()).with_user_agent("my-bot/2.0");
+            custom.resolve_handle("alice.com").await.unwrap();
+        }
+
+        #[tokio::test]
+        async fn put_record_writes_at_the_given_rkey() {
+            let server = MockServer::start().await;
+            let client = authenticated_client(&server).await;
+            let record = serde_json::json!({"$type": "app.bsky.feed.post", "text": "hi"});
+
+            Mock::given(method("POST"))
+                .and(path("/xrpc/com.atproto.repo.putRecord"))
+                .and(header("Authorization", "Bearer jwt-token"))
+                .and(body_json(serde_json::json!({
+                    "repo": DID,
+                    "collection": "app.bsky.feed.post",
+                    "rkey": "3kold",
+                    "record": record,
+                })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            client
+                .put_record(DID, "app.bsky.feed.post", "3kold", record)
+                .await
+                .unwrap();
+        }
+
+        #[tokio::test]
+        async fn list_blobs_sends_did_and_cursor() {
+            let server = MockServer::start().await;
+            let client = authenticated_client(&server).await;
+
+            Mock::given(method("GET"))
+                .and(path("/xrpc/com.atproto.sync.listBlobs"))
+                .and(query_param("did", DID))
+                .and(query_param("cursor", "next"))
+                .and(header("Authorization", "Bearer jwt-token"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "cids": ["bafkreiaaa", "bafkreibbb"],
+                    "cursor": "after",
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let resp = client.list_blobs(DID, Some("next")).await.unwrap();
+            assert_eq!(resp.cids, vec!["bafkreiaaa", "bafkreibbb"]);
+            assert_eq!(resp.cursor.as_deref(), Some("after"));
+        }
+
+        #[tokio::test]
+        async fn list_records_reports_an_expired_token() {
+            let server = MockServer::start().await;
+            let client = authenticated_client(&server).await;
+
+            Mock::given(method("GET"))
+                .and(path("/xrpc/com.atproto.repo.listRecords"))
+                .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                    "error": "ExpiredToken",
+                    "message": "Token has expired",
+                })))
+                .mount(&server)
+                .await;
+
+            let err = client
+                .list_records(DID, "app.bsky.feed.post", None)
+                .await
+                .err()
+                .unwrap();
+            assert!(err.is::<ExpiredToken>());
+        }
+
+        #[tokio::test]
+        async fn refresh_session_swaps_in_the_new_access_token() {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/xrpc/com.atproto.server.createSession"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "did": DID,
+                    "accessJwt": "old-access",
+                    "refreshJwt": "old-refresh",
+                })))
+                .mount(&server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/xrpc/com.atproto.server.refreshSession"))
+                .and(header("Authorization", "Bearer old-refresh"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "did": DID,
+                    "accessJwt": "new-access",
+                    "refreshJwt": "new-refresh",
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/xrpc/com.atproto.repo.listRecords"))
+                .and(header("Authorization", "Bearer new-access"))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_json(serde_json::json!({"records": []})),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let client = HttpBlueskyClient::new(&server.uri());
+            client.create_session("user", "pass").await.unwrap();
+            client.refresh_session().await.unwrap();
+            client
+                .list_records(DID, "app.bsky.feed.post", None)
+                .await
+                .unwrap();
+        }
+
+        #[tokio::test]
+        async fn concurrent_refreshes_refresh_the_session_once() {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/xrpc/com.atproto.server.createSession"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "did": DID,
+                    "accessJwt": "old-access",
+                    "refreshJwt": "old-refresh",
+                })))
+                .mount(&server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/xrpc/com.atproto.server.refreshSession"))
+                .and(header("Authorization", "Bearer old-refresh"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({
+                            "did": DID,
+                            "accessJwt": "new-access",
+                            "refreshJwt": "new-refresh",
+                        }))
+                        .set_delay(Duration::from_millis(50)),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let client = HttpBlueskyClient::new(&server.uri());
+            client.create_session("user", "pass").await.unwrap();
+            let (first, second) = tokio::join!(client.refresh_session(), client.refresh_session());
+            first.unwrap();
+            second.unwrap();
+            assert_eq!(client.session().access_jwt, "new-access");
+        }
+
+        #[tokio::test]
+        async fn list_records_sends_auth_and_round_trips_cursor() {
+            let server = MockServer::start().await;
+            let client = authenticated_client(&server).await;
+
+            Mock::given(method("GET"))
+                .and(path("/xrpc/com.atproto.repo.listRecords"))
+                .and(query_param("repo", DID))
+                .and(query_param("collection", "app.bsky.feed.post"))
+                .and(query_param("limit", "100"))
+                .and(query_param("cursor", "page2"))
+                .and(header("Authorization", "Bearer jwt-token"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "records": [{
+                        "uri": format!("at://{DID}/app.bsky.feed.post/second"),
+                        "value": { "createdAt": "2020-01-02T00:00:00Z" },
+                    }],
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+            // Registered last so the more specific cursor mock above wins for page 2
+            Mock::given(method("GET"))
+                .and(path("/xrpc/com.atproto.repo.listRecords"))
+                .and(query_param("repo", DID))
+                .and(query_param("collection", "app.bsky.feed.post"))
+                .and(header("Authorization", "Bearer jwt-token"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "records": [{
+                        "uri": format!("at://{DID}/app.bsky.feed.post/first"),
+                        "value": { "createdAt": "2020-01-01T00:00:00Z" },
+                    }],
+                    "cursor": "page2",
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let first = client
+                .list_records(DID, "app.bsky.feed.post", None)
+                .await
+                .unwrap();
+            assert_eq!(
+                first.records[0].uri,
+                format!("at://{DID}/app.bsky.feed.post/first")
+            );
+            assert_eq!(first.cursor.as_deref(), Some("page2"));
+
+            let second = client
+                .list_records(DID, "app.bsky.feed.post", first.cursor.as_deref())
+                .await
+                .unwrap();
+            assert_eq!(
+                second.records[0].uri,
+                format!("at://{DID}/app.bsky.feed.post/second")
+            );
+            assert_eq!(second.cursor, None);
+        }
+
+        #[tokio::test]
+        async fn get_record_queries_repo_collection_and_rkey() {
+            let server = MockServer::start().await;
+            let client = authenticated_client(&server).await;
+
+            Mock::given(method("GET"))
+                .and(path("/xrpc/com.atproto.repo.getRecord"))
+                .and(query_param("repo", DID))
+                .and(query_param("collection", "app.bsky.feed.post"))
+                .and(query_param("rkey", "3kold"))
+                .and(header("Authorization", "Bearer jwt-token"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "uri": format!("at://{DID}/app.bsky.feed.post/3kold"),
+                    "cid": "bafyrei",
+                    "value": {"text": "hi", "createdAt": "2020-01-01T00:00:00Z"},
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let record = client
+                .get_record(DID, "app.bsky.feed.post", "3kold")
+                .await
+                .unwrap();
+            assert_eq!(record.uri, format!("at://{DID}/app.bsky.feed.post/3kold"));
+            assert_eq!(record.raw["text"], "hi");
+        }
+
+        #[tokio::test]
+        async fn existing_posts_sends_every_uri_in_one_request() {
+            let server = MockServer::start().await;
+            let client = authenticated_client(&server).await;
+            let alive = format!("at://{DID}/app.bsky.feed.post/3kalive");
+            let gone = format!("at://{DID}/app.bsky.feed.post/3kgone");
+            Mock::given(method("GET"))
+                .and(path("/xrpc/app.bsky.feed.getPosts"))
+                .and(query_param("uris", alive.as_str()))
+                .and(query_param("uris", gone.as_str()))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "posts": [{"uri": alive, "cid": "bafy"}],
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let found = client.existing_posts(&[&alive, &gone]).await.unwrap();
+            assert_eq!(found, HashSet::from([alive]));
+        }
+
+        #[tokio::test]
+        async fn record_exists_treats_record_not_found_as_missing() {
+            let server = MockServer::start().await;
+            let client = authenticated_client(&server).await;
+
+            for (rkey, response) in [
+                (
+                    "3kgone",
+                    ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                        "error": "RecordNotFound",
+                        "message": "Could not locate record",
+                    })),
+                ),
+                (
+                    "bad rkey",
+                    ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                        "error": "InvalidRequest",
+                    })),
+                ),
+            ] {
+                Mock::given(method("GET"))
+                    .and(path("/xrpc/com.atproto.repo.getRecord"))
+                    .and(query_param("rkey", rkey))
+                    .respond_with(response)
+                    .mount(&server)
+                    .await;
+            }
+
+            assert!(!client
+                .record_exists(DID, "app.bsky.feed.post", "3kgone")
+                .await
+                .unwrap());
+            assert!(client
+                .record_exists(DID, "app.bsky.feed.post", "bad rkey")
+                .await
+                .is_err());
+        }
+
+        #[tokio::test]
+        async fn delete_record_posts_repo_collection_and_rkey() {
+            let server = MockServer::start().await;
+            let client = authenticated_client(&server).await;
+
+            Mock::given(method("POST"))
+                .and(path("/xrpc/com.atproto.repo.deleteRecord"))
+                .and(header("Authorization", "Bearer jwt-token"))
+                .and(body_json(serde_json::json!({
+                    "repo": DID,
+                    "collection": "app.bsky.feed.like",
+                    "rkey": "abc123",
+                })))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let outcome = client
+                .delete_record(DID, "app.bsky.feed.like", "abc123")
+                .await
+                .unwrap();
+            assert_eq!(outcome, DeleteOutcome::Deleted);
+        }
+
+        #[tokio::test]
+        async fn deleting_twice_reports_already_gone_on_404() {
+            let server = MockServer::start().await;
+            let client = authenticated_client(&server).await;
+
+            Mock::given(method("POST"))
+                .and(path("/xrpc/com.atproto.repo.deleteRecord"))
+                .respond_with(ResponseTemplate::new(200))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/xrpc/com.atproto.repo.deleteRecord"))
+                .respond_with(ResponseTemplate::new(404))
+                .mount(&server)
+                .await;
+
+            let first = client
+                .delete_record(DID, "app.bsky.feed.post", "abc123")
+                .await
+                .unwrap();
+            let second = client
+                .delete_record(DID, "app.bsky.feed.post", "abc123")
+                .await
+                .unwrap();
+            assert_eq!(first, DeleteOutcome::Deleted);
+            assert_eq!(second, DeleteOutcome::AlreadyGone);
+        }
+
+        #[tokio::test]
+        async fn rate_limit_reset_header_surfaces_as_rate_limited() {
+            let server = MockServer::start().await;
+            let client = authenticated_client(&server).await;
+
+            Mock::given(method("POST"))
+                .and(path("/xrpc/com.atproto.repo.deleteRecord"))
+                .respond_with(
+                    ResponseTemplate::new(429)
+                        .insert_header("ratelimit-remaining", "0")
+                        .insert_header("ratelimit-reset", "1718452800"),
+                )
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/xrpc/com.atproto.repo.listRecords"))
+                .respond_with(ResponseTemplate::new(429))
+                .mount(&server)
+                .await;
+
+            let err = client
+                .delete_record(DID, "app.bsky.feed.post", "abc123")
+                .await
+                .unwrap_err();
+            let limited = err.downcast_ref::<RateLimited>().unwrap();
+            assert_eq!(limited.endpoint, "com.atproto.repo.deleteRecord");
+            assert_eq!(
+                limited.reset,
+                Some(
+                    crate::parse_timestamp("2024-06-15T12:00:00Z")
+                        .unwrap()
+                        .into()
+                )
+            );
+
+            let err = client
+                .list_records(DID, "app.bsky.feed.post", None)
+                .await
+                .err()
+                .unwrap();
+            assert!(err.downcast_ref::<RateLimited>().unwrap().reset.is_none());
+        }
+
+        #[tokio::test]
+        async fn delete_record_reports_an_expired_token() {
+            let server = MockServer::start().await;
+            let client = authenticated_client(&server).await;
+
+            Mock::given(method("POST"))
+                .and(path("/xrpc/com.atproto.repo.deleteRecord"))
+                .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                    "error": "ExpiredToken",
+                    "message": "Token has expired",
+                })))
+                .mount(&server)
+                .await;
+
+            let err = client
+                .delete_record(DID, "app.bsky.feed.post", "abc123")
+                .await
+                .err()
+                .unwrap();
+            assert!(err.is::<ExpiredToken>());
+        }
+
+        #[tokio::test]
+        async fn delete_record_surfaces_http_errors() {
+            let server = MockServer::start().await;
+            let client = authenticated_client(&server).await;
+
+            Mock::given(method("POST"))
+                .and(path("/xrpc/com.atproto.repo.deleteRecord"))
+                .respond_with(ResponseTemplate::new(500))
+                .mount(&server)
+                .await;
+
+            assert!(client
+                .delete_record(DID, "app.bsky.feed.post", "abc123")
+                .await
+                .is_err());
+        }
+
+        #[tokio::test]
+        async fn resolve_handle_is_cached() {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/xrpc/com.atproto.identity.resolveHandle"))
+                .and(query_param("handle", "alice.com"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({"did": "did:plc:alice"})),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let client = HttpBlueskyClient::new(&server.uri());
+            assert_eq!(
+                client.resolve_handle("alice.com").await.unwrap(),
+                "did:plc:alice"
+            );
+            assert_eq!(
+                client.resolve_handle("alice.com").await.unwrap(),
+                "did:plc:alice"
+            );
+        }
+
+        #[tokio::test]
+        async fn get_pinned_post_uri_reads_profile_record() {
+            let server = MockServer::start().await;
+            let client = authenticated_client(&server).await;
+
+            Mock::given(method("GET"))
+                .and(path("/xrpc/com.atproto.repo.getRecord"))
+                .and(query_param("repo", DID))
+                .and(query_param("collection", "app.bsky.actor.profile"))
+                .and(query_param("rkey", "self"))
+                .and(header("Authorization", "Bearer jwt-token"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "value": { "pinnedPost": format!("at://{DID}/app.bsky.feed.post/pinned1") },
+                })))
+                .mount(&server)
+                .await;
+
+            assert_eq!(
+                client.get_pinned_post_uri(DID).await,
+                Some(format!("at://{DID}/app.bsky.feed.post/pinned1"))
+            );
+        }
     }
 }
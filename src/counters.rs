@@ -0,0 +1,100 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Deleted, skipped and failed records across the whole run, shared by every
+/// sweep so the final tally still counts work done by a platform that errored
+/// or was interrupted before it could return its summary.
+#[derive(Clone, Default)]
+pub struct RunCounters(Arc<Inner>);
+
+#[derive(Default)]
+struct Inner {
+    deleted: AtomicU64,
+    skipped: AtomicU64,
+    errors: AtomicU64,
+}
+
+/// Point-in-time copy of [`RunCounters`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CounterSnapshot {
+    pub deleted: u64,
+    pub skipped: u64,
+    pub errors: u64,
+}
+
+impl RunCounters {
+    pub fn add_deleted(&self) {
+        self.0.deleted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// An old record kept for any reason (pinned, kept, filtered, ...).
+    pub fn add_skipped(&self) {
+        self.0.skipped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_error(&self) {
+        self.0.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> CounterSnapshot {
+        CounterSnapshot {
+            deleted: self.0.deleted.load(Ordering::Relaxed),
+            skipped: self.0.skipped.load(Ordering::Relaxed),
+            errors: self.0.errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shutdown::Shutdown;
+    use std::time::Duration;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_workers_total_exactly_after_early_cancellation() {
+        let counters = RunCounters::default();
+        let shutdown = Shutdown::default();
+
+        // Each fake worker "deletes" until shutdown, skipping every third
+        // record and failing every fifth, and reports what it did itself.
+        let workers: Vec<_> = (0..8)
+            .map(|_| {
+                let counters = counters.clone();
+                let shutdown = shutdown.clone();
+                tokio::spawn(async move {
+                    let mut local = CounterSnapshot::default();
+                    let mut n = 0u64;
+                    while !shutdown.is_triggered() {
+                        n += 1;
+                        if n.is_multiple_of(3) {
+                            counters.add_skipped();
+                            local.skipped += 1;
+                        } else if n.is_multiple_of(5) {
+                            counters.add_error();
+                            local.errors += 1;
+                        } else {
+                            counters.add_deleted();
+                            local.deleted += 1;
+                        }
+                        tokio::task::yield_now().await;
+                    }
+                    local
+                })
+            })
+            .collect();
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        shutdown.trigger();
+
+        let mut expected = CounterSnapshot::default();
+        for worker in workers {
+            let local = worker.await.unwrap();
+            expected.deleted += local.deleted;
+            expected.skipped += local.skipped;
+            expected.errors += local.errors;
+        }
+        assert!(expected.deleted > 0);
+        assert_eq!(counters.snapshot(), expected);
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::str::FromStr;
+
+/// Keyring service the Bluesky app password is stored under unless
+/// `KEYRING_SERVICE` says otherwise.
+pub const DEFAULT_SERVICE: &str = "skyscraper";
+
+/// `CREDENTIAL_SOURCE`: where the Bluesky app password comes from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CredentialSource {
+    /// `BLUESKY_APP_PASSWORD` or the config file.
+    #[default]
+    Env,
+    /// The OS keyring, keyed by service and `BLUESKY_IDENTIFIER`.
+    Keyring,
+}
+
+impl FromStr for CredentialSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "env" => Ok(Self::Env),
+            "keyring" => Ok(Self::Keyring),
+            _ => anyhow::bail!("Invalid CREDENTIAL_SOURCE {s:?}, expected \"env\" or \"keyring\""),
+        }
+    }
+}
+
+/// Secrets kept by service and account name.
+pub trait SecretStore {
+    /// The secret for `account`, or `None` if there isn't one.
+    fn get(&self, service: &str, account: &str) -> Result<Option<String>>;
+    fn set(&self, service: &str, account: &str, secret: &str) -> Result<()>;
+}
+
+/// The OS keyring: the Keychain on macOS, Credential Manager on Windows and
+/// the Secret Service (GNOME Keyring, KWallet) elsewhere.
+pub struct OsKeyring;
+
+impl SecretStore for OsKeyring {
+    fn get(&self, service: &str, account: &str) -> Result<Option<String>> {
+        let entry = keyring::Entry::new(service, account)
+            .with_context(|| format!("Failed to open keyring entry {service}/{account}"))?;
+        match entry.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => {
+                Err(e).with_context(|| format!("Failed to read keyring entry {service}/{account}"))
+            }
+        }
+    }
+
+    fn set(&self, service: &str, account: &str, secret: &str) -> Result<()> {
+        keyring::Entry::new(service, account)
+            .and_then(|entry| entry.set_password(secret))
+            .with_context(|| format!("Failed to write keyring entry {service}/{account}"))
+    }
+}
+
+/// An in-memory keyring for tests.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct MemoryKeyring(std::sync::Mutex<std::collections::HashMap<(String, String), String>>);
+
+#[cfg(test)]
+impl SecretStore for MemoryKeyring {
+    fn get(&self, service: &str, account: &str) -> Result<Option<String>> {
+        let secrets = self.0.lock().unwrap();
+        Ok(secrets.get(&(service.into(), account.into())).cloned())
+    }
+
+    fn set(&self, service: &str, account: &str, secret: &str) -> Result<()> {
+        let mut secrets = self.0.lock().unwrap();
+        secrets.insert((service.into(), account.into()), secret.into());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_credential_sources() {
+        assert_eq!(
+            "keyring".parse::<CredentialSource>().unwrap(),
+            CredentialSource::Keyring
+        );
+        assert_eq!(
+            "env".parse::<CredentialSource>().unwrap(),
+            CredentialSource::Env
+        );
+        assert!("vault".parse::<CredentialSource>().is_err());
+    }
+
+    #[test]
+    fn memory_keyring_keys_by_service_and_account() {
+        let keyring = MemoryKeyring::default();
+        keyring.set("skyscraper", "alice.com", "app-pass").unwrap();
+        assert_eq!(
+            keyring.get("skyscraper", "alice.com").unwrap().as_deref(),
+            Some("app-pass")
+        );
+        assert_eq!(keyring.get("skyscraper", "bob.com").unwrap(), None);
+        assert_eq!(keyring.get("other", "alice.com").unwrap(), None);
+    }
+}
@@ -0,0 +1,117 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, TimeZone, Utc};
+use std::str::FromStr;
+
+/// `DISPLAY_TZ`: the zone logged timestamps are shown in and date-only keep
+/// rules are read in. Comparisons always happen on the UTC instant.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayTz {
+    #[default]
+    Utc,
+    /// The system's local zone, DST included.
+    Local,
+    /// A fixed offset such as `+02:00` or `-05:30`.
+    Fixed(FixedOffset),
+}
+
+impl FromStr for DisplayTz {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim() {
+            "" | "utc" | "UTC" | "Z" => Ok(Self::Utc),
+            "local" | "LOCAL" => Ok(Self::Local),
+            other => other.parse::<FixedOffset>().map(Self::Fixed).map_err(|_| {
+                anyhow::anyhow!(
+                    "Invalid DISPLAY_TZ {other:?}: expected utc, local, or an offset like +02:00"
+                )
+            }),
+        }
+    }
+}
+
+impl DisplayTz {
+    /// `t` as RFC 3339 in this zone, e.g. `2024-06-15T14:00:00+02:00`.
+    pub fn format(self, t: DateTime<Utc>) -> String {
+        const FORMAT: &str = "%Y-%m-%dT%H:%M:%S%:z";
+        match self {
+            Self::Utc => t.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            Self::Local => t.with_timezone(&Local).format(FORMAT).to_string(),
+            Self::Fixed(offset) => t.with_timezone(&offset).format(FORMAT).to_string(),
+        }
+    }
+
+    /// The calendar date `t` falls on in this zone.
+    pub fn date(self, t: DateTime<Utc>) -> NaiveDate {
+        match self {
+            Self::Utc => t.date_naive(),
+            Self::Local => t.with_timezone(&Local).date_naive(),
+            Self::Fixed(offset) => t.with_timezone(&offset).date_naive(),
+        }
+    }
+
+    /// The UTC instant of midnight starting `date` in this zone. Where DST
+    /// skips midnight, the first instant of the day after the gap.
+    pub fn start_of_day(self, date: NaiveDate) -> DateTime<Utc> {
+        let midnight = date.and_hms_opt(0, 0, 0).expect("midnight is valid");
+        match self {
+            Self::Utc => midnight.and_utc(),
+            Self::Fixed(offset) => first_instant(&offset, midnight),
+            Self::Local => first_instant(&Local, midnight),
+        }
+    }
+}
+
+fn first_instant<Tz: TimeZone>(tz: &Tz, local: chrono::NaiveDateTime) -> DateTime<Utc> {
+    (0..24)
+        .find_map(|h| {
+            tz.from_local_datetime(&(local + chrono::TimeDelta::hours(h)))
+                .earliest()
+        })
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|| local.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_utc_local_and_offsets() {
+        assert_eq!("UTC".parse::<DisplayTz>().unwrap(), DisplayTz::Utc);
+        assert_eq!("local".parse::<DisplayTz>().unwrap(), DisplayTz::Local);
+        assert_eq!(
+            "+02:00".parse::<DisplayTz>().unwrap(),
+            DisplayTz::Fixed(FixedOffset::east_opt(2 * 3600).unwrap())
+        );
+        assert!("Mars/Olympus_Mons".parse::<DisplayTz>().is_err());
+    }
+
+    #[test]
+    fn formats_in_the_zone() {
+        let t = DateTime::parse_from_rfc3339("2024-06-15T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(DisplayTz::Utc.format(t), "2024-06-15T12:00:00Z");
+        assert_eq!(
+            "-05:00".parse::<DisplayTz>().unwrap().format(t),
+            "2024-06-15T07:00:00-05:00"
+        );
+    }
+
+    #[test]
+    fn start_of_day_is_local_midnight_as_utc() {
+        let date = NaiveDate::from_ymd_opt(2023, 6, 1).unwrap();
+        assert_eq!(
+            DisplayTz::Utc.start_of_day(date).to_rfc3339(),
+            "2023-06-01T00:00:00+00:00"
+        );
+        assert_eq!(
+            "+09:00"
+                .parse::<DisplayTz>()
+                .unwrap()
+                .start_of_day(date)
+                .to_rfc3339(),
+            "2023-05-31T15:00:00+00:00"
+        );
+    }
+}
@@ -0,0 +1,137 @@
+/// Delete attempts needed before `MAX_ERROR_RATE` applies, so the first
+/// failure of a sweep doesn't count as a 100% error rate.
+const MIN_ATTEMPTS_FOR_RATE: u64 = 10;
+
+/// `MAX_CONSECUTIVE_ERRORS`/`MAX_ERROR_RATE`: how badly deletions may fail
+/// before a platform gives up instead of hammering a broken endpoint.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ErrorLimits {
+    pub max_consecutive: Option<u64>,
+    /// Fraction of attempts, from 0 to 1.
+    pub max_rate: Option<f64>,
+}
+
+/// Deletions stopped because they failed too often. The message is the reason.
+#[derive(Debug)]
+pub struct TooManyErrors(String);
+
+impl std::fmt::Display for TooManyErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Aborting: {}", self.0)
+    }
+}
+
+impl std::error::Error for TooManyErrors {}
+
+/// Tallies a platform's delete attempts against its [`ErrorLimits`].
+#[derive(Debug)]
+pub struct ErrorTracker {
+    limits: ErrorLimits,
+    attempts: u64,
+    failures: u64,
+    consecutive: u64,
+}
+
+impl ErrorTracker {
+    pub fn new(limits: ErrorLimits) -> Self {
+        Self {
+            limits,
+            attempts: 0,
+            failures: 0,
+            consecutive: 0,
+        }
+    }
+
+    /// A delete that went through, including one that was already gone.
+    pub fn success(&mut self) {
+        self.attempts += 1;
+        self.consecutive = 0;
+    }
+
+    /// A delete that failed. Errors once either limit is exceeded.
+    pub fn failure(&mut self) -> Result<(), TooManyErrors> {
+        self.attempts += 1;
+        self.failures += 1;
+        self.consecutive += 1;
+        if let Some(max) = self.limits.max_consecutive {
+            if self.consecutive >= max {
+                return Err(TooManyErrors(format!(
+                    "{} deletions failed in a row (MAX_CONSECUTIVE_ERRORS={max})",
+                    self.consecutive
+                )));
+            }
+        }
+        if let Some(max) = self.limits.max_rate {
+            let rate = self.failures as f64 / self.attempts as f64;
+            if self.attempts >= MIN_ATTEMPTS_FOR_RATE && rate > max {
+                return Err(TooManyErrors(format!(
+                    "{} of {} deletions failed (MAX_ERROR_RATE={max})",
+                    self.failures, self.attempts
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_limits_never_abort() {
+        let mut tracker = ErrorTracker::new(ErrorLimits::default());
+        for _ in 0..100 {
+            assert!(tracker.failure().is_ok());
+        }
+    }
+
+    #[test]
+    fn consecutive_limit_resets_on_success() {
+        let mut tracker = ErrorTracker::new(ErrorLimits {
+            max_consecutive: Some(3),
+            max_rate: None,
+        });
+        assert!(tracker.failure().is_ok());
+        assert!(tracker.failure().is_ok());
+        tracker.success();
+        assert!(tracker.failure().is_ok());
+        assert!(tracker.failure().is_ok());
+        let err = tracker.failure().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Aborting: 3 deletions failed in a row (MAX_CONSECUTIVE_ERRORS=3)"
+        );
+    }
+
+    fn rate_limited() -> ErrorTracker {
+        ErrorTracker::new(ErrorLimits {
+            max_consecutive: None,
+            max_rate: Some(0.5),
+        })
+    }
+
+    #[test]
+    fn rate_limit_waits_for_enough_attempts() {
+        let mut tracker = rate_limited();
+        for _ in 1..MIN_ATTEMPTS_FOR_RATE {
+            assert!(tracker.failure().is_ok());
+        }
+        assert!(tracker.failure().is_err());
+    }
+
+    #[test]
+    fn rate_limit_aborts_once_exceeded() {
+        let mut tracker = rate_limited();
+        // Alternating keeps the rate at half
+        for _ in 0..10 {
+            tracker.success();
+            assert!(tracker.failure().is_ok());
+        }
+        let err = tracker.failure().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Aborting: 11 of 21 deletions failed (MAX_ERROR_RATE=0.5)"
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+
+/// Status IDs listed in `contents` for `DELETE_FROM_EXPORT`, in order and
+/// without duplicates.
+///
+/// Accepts the `outbox.json` from a Mastodon data export, taking your posts
+/// (`Create`) and boosts (`Announce`), or a CSV or plain list whose first
+/// column is a status ID or status URL. Lines that aren't, like a header, are
+/// skipped.
+pub fn status_ids(contents: &str) -> Result<Vec<String>> {
+    let ids = if contents.trim_start().starts_with('{') {
+        outbox_ids(contents)?
+    } else {
+        contents
+            .lines()
+            .filter_map(|line| {
+                let field = line.split(',').next()?.trim().trim_matches('"');
+                if !field.is_empty() && field.bytes().all(|b| b.is_ascii_digit()) {
+                    Some(field.to_string())
+                } else {
+                    id_from_url(field)
+                }
+            })
+            .collect()
+    };
+    let mut seen = HashSet::new();
+    Ok(ids
+        .into_iter()
+        .filter(|id| seen.insert(id.clone()))
+        .collect())
+}
+
+fn outbox_ids(contents: &str) -> Result<Vec<String>> {
+    let outbox: serde_json::Value =
+        serde_json::from_str(contents).context("Failed to parse outbox.json")?;
+    let items = outbox["orderedItems"]
+        .as_array()
+        .context("outbox.json has no orderedItems")?;
+    Ok(items
+        .iter()
+        .filter_map(|item| {
+            // A boost's own status is the activity, `.../statuses/<id>/activity`
+            let url = match item["type"].as_str()? {
+                "Create" => item["object"]["id"].as_str()?,
+                "Announce" => item["id"].as_str()?,
+                _ => return None,
+            };
+            id_from_url(url)
+        })
+        .collect())
+}
+
+/// `123` from `https://example.social/users/me/statuses/123[/activity]`.
+fn id_from_url(url: &str) -> Option<String> {
+    let mut segments = url.split('/').skip_while(|s| *s != "statuses");
+    segments.next()?;
+    segments
+        .next()
+        .filter(|id| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()))
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_posts_and_boosts_from_an_outbox() {
+        let outbox = serde_json::json!({
+            "@context": "https://www.w3.org/ns/activitystreams",
+            "type": "OrderedCollection",
+            "orderedItems": [
+                {
+                    "id": "https://example.social/users/me/statuses/111/activity",
+                    "type": "Create",
+                    "object": {"id": "https://example.social/users/me/statuses/111", "type": "Note"},
+                },
+                {
+                    "id": "https://example.social/users/me/statuses/222/activity",
+                    "type": "Announce",
+                    "object": "https://other.social/users/them/statuses/999",
+                },
+                {"id": "https://example.social/users/me#likes/1", "type": "Like"},
+                {
+                    "id": "https://example.social/users/me/statuses/111/activity",
+                    "type": "Create",
+                    "object": {"id": "https://example.social/users/me/statuses/111", "type": "Note"},
+                },
+            ],
+        });
+        assert_eq!(status_ids(&outbox.to_string()).unwrap(), vec!["111", "222"]);
+    }
+
+    #[test]
+    fn reads_the_first_column_of_a_csv() {
+        let csv = "id,created_at\n111,2020-01-01\n\"222\",2020-02-01\n\nhttps://example.social/users/me/statuses/333\n";
+        assert_eq!(status_ids(csv).unwrap(), vec!["111", "222", "333"]);
+    }
+
+    #[test]
+    fn outbox_without_items_errors() {
+        assert!(status_ids("{\"type\": \"OrderedCollection\"}").is_err());
+    }
+}
@@ -0,0 +1,258 @@
+use crate::credentials::CredentialSource;
+use crate::{MediaFilter, RateLimitMode};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Declarative settings loaded from `CONFIG_FILE` (TOML). Every field is
+/// optional and the matching env var always takes precedence.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileConfig {
+    /// Human retention period like `6mo`, preferred over `retention_days`.
+    pub retention: Option<String>,
+    pub retention_days: Option<i64>,
+    pub dry_run: Option<bool>,
+    pub dry_run_grouped: Option<bool>,
+    pub delete_pinned: Option<bool>,
+    pub delete_reposts: Option<bool>,
+    pub purge_all_reposts: Option<bool>,
+    pub delete_likes: Option<bool>,
+    pub post_summary_status: Option<bool>,
+    pub media_filter: Option<MediaFilter>,
+    pub delete_text_prefix: Option<String>,
+    pub delete_plain_text_only: Option<bool>,
+    pub prefetch_pages: Option<bool>,
+    pub largest_media_first: Option<bool>,
+    pub oldest_first: Option<bool>,
+    pub keep_bookmarked: Option<bool>,
+    pub keep_curated: Option<bool>,
+    pub keep_self_likes: Option<bool>,
+    pub delete_hidden_sooner: Option<i64>,
+    pub import_boundary: Option<String>,
+    pub import_retention_days: Option<i64>,
+    pub keep_shared_blobs: Option<bool>,
+    pub keep_one_per_month: Option<bool>,
+    pub keep_if_has_replies: Option<bool>,
+    pub keep_sample_percent: Option<u8>,
+    pub keep_sample_seed: Option<u64>,
+    pub keep_audit: Option<bool>,
+    pub verify_each_page: Option<bool>,
+    pub fallback_rkey_time: Option<bool>,
+    pub treat_future_as_now: Option<bool>,
+    pub redact: Option<bool>,
+    pub keep_recently_edited_days: Option<i64>,
+    pub keep_keywords: Option<Vec<String>>,
+    pub delete_languages: Option<Vec<String>>,
+    pub delete_from_apps: Option<Vec<String>>,
+    pub keep_languages: Option<Vec<String>>,
+    pub default_language: Option<String>,
+    pub archive_dir: Option<PathBuf>,
+    pub force_delete_file: Option<String>,
+    pub delete_blocklist_file: Option<String>,
+    pub rate_limit_state_file: Option<PathBuf>,
+    pub rate_limit_log: Option<PathBuf>,
+    pub remaining_csv: Option<PathBuf>,
+    pub report_file: Option<PathBuf>,
+    pub incremental: Option<bool>,
+    pub incremental_state_file: Option<PathBuf>,
+    pub phase_state_file: Option<PathBuf>,
+    pub keep_file: Option<String>,
+    pub keep_file_posts: Option<String>,
+    pub keep_file_likes: Option<String>,
+    pub keep_file_reposts: Option<String>,
+    pub adaptive_delay_min_ms: Option<u64>,
+    pub adaptive_delay_max_ms: Option<u64>,
+    pub max_consecutive_errors: Option<u64>,
+    pub max_error_rate: Option<f64>,
+    pub post_run_hook: Option<String>,
+    pub post_all_hook: Option<String>,
+    pub quiet_summary: Option<bool>,
+    pub log_file: Option<PathBuf>,
+    pub display_tz: Option<String>,
+    pub user_agent: Option<String>,
+    pub platform_order: Option<Vec<String>>,
+    pub bluesky: BlueskyFileConfig,
+    pub mastodon: MastodonFileConfig,
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BlueskyFileConfig {
+    pub identifier: Option<String>,
+    pub app_password: Option<String>,
+    /// Path to a file holding the app password, e.g. a mounted secret.
+    pub app_password_file: Option<PathBuf>,
+    pub pds_host: Option<String>,
+    pub credential_source: Option<CredentialSource>,
+    pub keyring_service: Option<String>,
+    pub retention_days: Option<i64>,
+    pub delay_ms: Option<u64>,
+    pub post_delay_ms: Option<u64>,
+    pub repost_delay_ms: Option<u64>,
+    pub like_delay_ms: Option<u64>,
+    pub extra_collections: Option<Vec<String>>,
+    pub prune_preferences: Option<bool>,
+    pub rate_limit_mode: Option<RateLimitMode>,
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct MastodonFileConfig {
+    pub instance_url: Option<String>,
+    pub access_token: Option<String>,
+    /// Path to a file holding the access token, e.g. a mounted secret.
+    pub access_token_file: Option<PathBuf>,
+    pub retention_days: Option<i64>,
+    pub delay_ms: Option<u64>,
+    pub delete_reposts: Option<bool>,
+    pub delete_likes: Option<bool>,
+    pub idempotency: Option<bool>,
+}
+
+impl FileConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))
+    }
+}
+
+impl BlueskyFileConfig {
+    pub fn app_password(&self) -> Result<Option<String>> {
+        read_secret(
+            self.app_password.as_deref(),
+            self.app_password_file.as_deref(),
+        )
+    }
+}
+
+impl MastodonFileConfig {
+    pub fn access_token(&self) -> Result<Option<String>> {
+        read_secret(
+            self.access_token.as_deref(),
+            self.access_token_file.as_deref(),
+        )
+    }
+}
+
+/// Prefer an inline secret, otherwise read it (trimmed) from the referenced file.
+fn read_secret(inline: Option<&str>, file: Option<&Path>) -> Result<Option<String>> {
+    if let Some(value) = inline {
+        return Ok(Some(value.to_string()));
+    }
+    let Some(path) = file else {
+        return Ok(None);
+    };
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read secret file {}", path.display()))?;
+    Ok(Some(contents.trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const SAMPLE: &str = r#"
+retention_days = 90
+dry_run = true
+delete_likes = false
+keep_file = "config/keep.txt"
+
+[bluesky]
+identifier = "user.bsky.social"
+app_password_file = "/run/secrets/bluesky"
+retention_days = 30
+delay_ms = 50
+
+[mastodon]
+instance_url = "https://mastodon.social"
+access_token = "token"
+delay_ms = 1000
+"#;
+
+    #[test]
+    fn deserializes_sample_config() {
+        let config: FileConfig = toml::from_str(SAMPLE).unwrap();
+        assert_eq!(
+            config,
+            FileConfig {
+                retention_days: Some(90),
+                dry_run: Some(true),
+                delete_likes: Some(false),
+                keep_file: Some("config/keep.txt".to_string()),
+                bluesky: BlueskyFileConfig {
+                    identifier: Some("user.bsky.social".to_string()),
+                    app_password_file: Some(PathBuf::from("/run/secrets/bluesky")),
+                    retention_days: Some(30),
+                    delay_ms: Some(50),
+                    ..Default::default()
+                },
+                mastodon: MastodonFileConfig {
+                    instance_url: Some("https://mastodon.social".to_string()),
+                    access_token: Some("token".to_string()),
+                    delay_ms: Some(1000),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn empty_config_is_default() {
+        let config: FileConfig = toml::from_str("").unwrap();
+        assert_eq!(config, FileConfig::default());
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(toml::from_str::<FileConfig>("retention = 90").is_err());
+    }
+
+    #[test]
+    fn load_reads_file() {
+        let mut f = tempfile::NamedTempFile::new().unwrap();
+        write!(f, "{SAMPLE}").unwrap();
+        let config = FileConfig::load(f.path()).unwrap();
+        assert_eq!(config.retention_days, Some(90));
+    }
+
+    #[test]
+    fn load_missing_file_errors() {
+        assert!(FileConfig::load(Path::new("/nonexistent/skyscraper.toml")).is_err());
+    }
+
+    #[test]
+    fn secret_read_from_referenced_file() {
+        let mut f = tempfile::NamedTempFile::new().unwrap();
+        writeln!(f, "  s3cret  ").unwrap();
+        let bluesky = BlueskyFileConfig {
+            app_password_file: Some(f.path().to_path_buf()),
+            ..Default::default()
+        };
+        assert_eq!(bluesky.app_password().unwrap(), Some("s3cret".to_string()));
+    }
+
+    #[test]
+    fn inline_secret_wins_over_file() {
+        let mastodon = MastodonFileConfig {
+            access_token: Some("inline".to_string()),
+            access_token_file: Some(PathBuf::from("/nonexistent/token")),
+            ..Default::default()
+        };
+        assert_eq!(mastodon.access_token().unwrap(), Some("inline".to_string()));
+    }
+
+    #[test]
+    fn missing_secret_file_errors() {
+        let mastodon = MastodonFileConfig {
+            access_token_file: Some(PathBuf::from("/nonexistent/token")),
+            ..Default::default()
+        };
+        assert!(mastodon.access_token().is_err());
+    }
+}
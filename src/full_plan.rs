@@ -0,0 +1,194 @@
+use crate::keep::fnv1a64;
+use crate::Decision;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// What a run would do with a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Delete,
+    Keep,
+}
+
+/// One `FULL_PLAN_JSON` line: a record old enough to be deleted, and what
+/// decided its fate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanEntry {
+    /// AT URI on Bluesky, status ID on Mastodon.
+    pub id: String,
+    /// Collection NSID on Bluesky, `post` or `reblog` on Mastodon.
+    pub kind: String,
+    pub created_at: DateTime<Utc>,
+    pub action: Action,
+    /// The deciding factor, as `EXPLAIN_ID` prints it.
+    pub reason: String,
+}
+
+impl PlanEntry {
+    /// The entry for a record a sweep would decide as `decision`, or `None`
+    /// if it's too new for the plan to cover.
+    pub fn new(
+        id: impl Into<String>,
+        kind: impl Into<String>,
+        created_at: DateTime<Utc>,
+        decision: &Decision,
+    ) -> Option<Self> {
+        let action = match decision {
+            Decision::NewerThanCutoff | Decision::FutureDated => return None,
+            Decision::Delete | Decision::ForceDelete | Decision::Blocklisted => Action::Delete,
+            _ => Action::Keep,
+        };
+        Some(Self {
+            id: id.into(),
+            kind: kind.into(),
+            created_at,
+            action,
+            reason: decision.to_string(),
+        })
+    }
+}
+
+/// `PLAN_HASH`: a fingerprint of the records `platforms` would delete, in
+/// any order. Kept records and reasons don't count, so only a change to
+/// what gets deleted changes the hash.
+///
+/// [`fnv1a64`] catches drift, not tampering.
+pub fn hash(platforms: &BTreeMap<&str, Vec<PlanEntry>>) -> String {
+    let mut deletes: Vec<String> = platforms
+        .iter()
+        .flat_map(|(platform, entries)| {
+            entries
+                .iter()
+                .filter(|entry| entry.action == Action::Delete)
+                .map(move |entry| format!("{platform} {} {}\n", entry.kind, entry.id))
+        })
+        .collect();
+    deletes.sort();
+    format!("{:016x}", fnv1a64(deletes.concat().as_bytes()))
+}
+
+/// `REQUIRE_PLAN_HASH`: fail unless `platforms` hashes to `required`, i.e.
+/// the run would delete exactly what the approved dry run planned.
+pub fn check_hash(platforms: &BTreeMap<&str, Vec<PlanEntry>>, required: &str) -> Result<()> {
+    let actual = hash(platforms);
+    if !actual.eq_ignore_ascii_case(required.trim()) {
+        anyhow::bail!(
+            "Plan changed since it was approved: REQUIRE_PLAN_HASH is {required} but this run's plan hashes to {actual}. Review a new dry run before deleting"
+        );
+    }
+    Ok(())
+}
+
+/// Write `platforms` to `path` as `{"generated_at": ..., "platforms": {...}}`,
+/// each platform's entries oldest first.
+pub fn write(
+    path: &Path,
+    platforms: &mut BTreeMap<&str, Vec<PlanEntry>>,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    for entries in platforms.values_mut() {
+        entries.sort_by_key(|entry| entry.created_at);
+    }
+    let report = serde_json::json!({
+        "generated_at": now,
+        "platforms": platforms,
+    });
+    let json = serde_json::to_string_pretty(&report)?;
+    std::fs::write(path, json)
+        .with_context(|| format!("Failed to write FULL_PLAN_JSON {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KeptBy;
+    use chrono::TimeDelta;
+
+    #[test]
+    fn recent_records_are_left_out() {
+        let now = Utc::now();
+        assert_eq!(
+            PlanEntry::new("1", "post", now, &Decision::NewerThanCutoff),
+            None
+        );
+        assert_eq!(
+            PlanEntry::new("2", "post", now, &Decision::FutureDated),
+            None
+        );
+        let forced = PlanEntry::new("3", "post", now, &Decision::ForceDelete).unwrap();
+        assert_eq!(forced.action, Action::Delete);
+    }
+
+    #[test]
+    fn plan_hash_changes_only_with_the_records_to_delete() {
+        let now = Utc::now();
+        let entry = |id: &str, decision: &Decision| PlanEntry::new(id, "post", now, decision);
+        let kept = Decision::Kept(KeptBy::Rkey, "mastodon:3".into());
+        let plan = |entries: Vec<Option<PlanEntry>>| {
+            BTreeMap::from([("mastodon", entries.into_iter().flatten().collect())])
+        };
+
+        let approved = plan(vec![
+            entry("1", &Decision::Delete),
+            entry("2", &Decision::Delete),
+        ]);
+        let reordered = plan(vec![
+            entry("3", &kept),
+            entry("2", &Decision::Delete),
+            entry("1", &Decision::Delete),
+        ]);
+        let drifted = plan(vec![
+            entry("1", &Decision::Delete),
+            entry("2", &Decision::Delete),
+            entry("4", &Decision::Delete),
+        ]);
+
+        let approved_hash = hash(&approved);
+        assert_eq!(approved_hash.len(), 16);
+        assert_eq!(hash(&reordered), approved_hash);
+        assert_ne!(hash(&drifted), approved_hash);
+
+        assert!(check_hash(&reordered, &approved_hash).is_ok());
+        let err = check_hash(&drifted, &approved_hash).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Plan changed since it was approved"));
+    }
+
+    #[test]
+    fn written_plan_sorts_entries_oldest_first() {
+        let now = Utc::now();
+        let days_ago = |days: i64| now - TimeDelta::days(days);
+        let kept = Decision::Kept(KeptBy::Rkey, "mastodon:2".into());
+        let mut platforms = BTreeMap::from([(
+            "mastodon",
+            vec![
+                PlanEntry::new("2", "post", days_ago(100), &kept).unwrap(),
+                PlanEntry::new("1", "post", days_ago(200), &Decision::Delete).unwrap(),
+            ],
+        )]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plan.json");
+        write(&path, &mut platforms, now).unwrap();
+
+        let report: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let read: Vec<PlanEntry> =
+            serde_json::from_value(report["platforms"]["mastodon"].clone()).unwrap();
+        assert_eq!(
+            read.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+            ["1", "2"]
+        );
+        assert_eq!(read[1].action, Action::Keep);
+        assert_eq!(read[1].reason, "matched keep rule mastodon:2");
+        assert_eq!(
+            report["platforms"]["mastodon"][0]["action"],
+            serde_json::json!("delete")
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use anyhow::{bail, Context, Result};
+use log::info;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Run a user hook (`POST_RUN_HOOK`/`POST_ALL_HOOK`) through the shell with
+/// `payload` as JSON on stdin. A non-zero exit is an error for the caller to
+/// report; it never aborts the cleanup itself.
+pub async fn run(command: &str, payload: &serde_json::Value) -> Result<()> {
+    info!("Running hook: {command}");
+    let mut child = shell(command)
+        .stdin(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to start hook {command:?}"))?;
+
+    let mut stdin = child.stdin.take().context("Hook stdin unavailable")?;
+    // A hook that exits without reading stdin closes the pipe; that's its business
+    let _ = stdin.write_all(payload.to_string().as_bytes()).await;
+    drop(stdin);
+
+    let status = child
+        .wait()
+        .await
+        .with_context(|| format!("Failed to wait for hook {command:?}"))?;
+    if !status.success() {
+        bail!("Hook {command:?} exited with {status}");
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn hook_receives_payload_on_stdin() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("payload.json");
+        let payload = serde_json::json!({"platform": "bluesky", "posts": 3});
+
+        run(&format!("cat > '{}'", out.display()), &payload)
+            .await
+            .unwrap();
+
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+        assert_eq!(written, payload);
+    }
+
+    #[tokio::test]
+    async fn non_zero_exit_is_an_error() {
+        let err = run("exit 3", &serde_json::json!({})).await.unwrap_err();
+        assert!(err.to_string().contains("exited with"));
+    }
+
+    #[tokio::test]
+    async fn hook_ignoring_stdin_still_succeeds() {
+        run("true", &serde_json::json!({"big": "x".repeat(1 << 20)}))
+            .await
+            .unwrap();
+    }
+}
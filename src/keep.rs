@@ -0,0 +1,1091 @@
+use crate::display_tz::DisplayTz;
+use anyhow::Context;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use log::{info, warn};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Posts exempt from deletion, loaded from the keep file.
+///
+/// Each non-comment line is one entry, optionally scoped to a platform
+/// with a `platform:` prefix:
+///
+/// - `bluesky:3k2la5diqyc2x` or `3k2la5diqyc2x` — an exact ID or URI
+/// - `mastodon:1112*` — every ID starting with `1112`
+/// - `bluesky:/^3k2l[a-f]/` — every ID matching the regex between the slashes
+/// - `bluesky:subject:at://…` — every like or repost of the post at that URI
+/// - `bluesky:author:alice.com` — every like or repost of that account's posts,
+///   by handle (resolved to a DID at startup) or DID
+/// - `keep-after: 2023-06-01` / `keep-before: 2015-01-01` — every record created
+///   from / before midnight starting that day in `DISPLAY_TZ`
+///
+/// Any entry can end with `until 2025-06-01` (midnight starting that day in
+/// `DISPLAY_TZ`) or `until` an RFC 3339 timestamp to protect only until then.
+/// Past that, the entry protects nothing and normal retention applies.
+///
+/// A file ending in `.json` or starting with `[` is instead read as a JSON
+/// array of the same entries.
+#[derive(Debug, Default, Clone)]
+pub struct KeepList {
+    exact: HashSet<String>,
+    rules: Vec<Rule>,
+    /// `platform:uri` of posts whose likes and reposts are kept.
+    subjects: HashSet<String>,
+    /// `platform:handle` or `platform:did` of authors whose posts' likes and
+    /// reposts are kept, mapped to the entry that named them.
+    authors: HashMap<String, String>,
+    dates: Vec<DateRule>,
+    /// Zone `dates` are read in.
+    tz: DisplayTz,
+    /// Entries as written in the keep file, in order, for `KEEP_AUDIT`.
+    entries: Vec<String>,
+    /// Entries with an `until` still ahead, which stop protecting later.
+    expiring: HashSet<String>,
+    /// Entries whose `until` has passed. They're listed in `entries` for
+    /// `KEEP_AUDIT` but protect nothing.
+    expired: HashSet<String>,
+    /// Entries that matched something this run. Shared with copies made by
+    /// `with_exact` and `with_author_dids`, so their matches count too.
+    matched: Arc<Mutex<HashSet<String>>>,
+}
+
+/// A pattern entry, kept alongside the line it came from for logging.
+#[derive(Debug, Clone)]
+struct Rule {
+    entry: String,
+    platform: Option<String>,
+    pattern: Pattern,
+}
+
+/// A `keep-after:` or `keep-before:` entry.
+#[derive(Debug, Clone)]
+struct DateRule {
+    entry: String,
+    platform: Option<String>,
+    after: bool,
+    date: NaiveDate,
+}
+
+#[derive(Debug, Clone)]
+enum Pattern {
+    Prefix(String),
+    Regex(Regex),
+}
+
+impl KeepList {
+    /// Load the keep list at `path`, reading its dates as midnight in `tz`.
+    /// A missing or unreadable file yields an empty list so a run never fails
+    /// over it.
+    pub fn load(path: &Path, tz: DisplayTz) -> Self {
+        if !path.exists() {
+            info!("No keep file at {}, skipping", path.display());
+            return Self::default();
+        }
+        info!("Loading keep list from {}", path.display());
+        let Ok(contents) = fs::read_to_string(path) else {
+            warn!("Failed to read {}", path.display());
+            return Self::default();
+        };
+        if !is_json(path, &contents) {
+            return Self::parse_in(&contents, tz);
+        }
+        Self::parse_json_in(&contents, tz).unwrap_or_else(|e| {
+            warn!("{e:#}");
+            Self::default()
+        })
+    }
+
+    /// Parse keep file contents, skipping blank lines and `#` comments.
+    pub fn parse(contents: &str) -> Self {
+        Self::parse_in(contents, DisplayTz::default())
+    }
+
+    fn parse_in(contents: &str, tz: DisplayTz) -> Self {
+        Self::from_entries_in(
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && !l.starts_with('#')),
+            tz,
+        )
+    }
+
+    /// Parse a JSON array of entries, each trimmed like a line of the keep file.
+    pub fn parse_json(contents: &str) -> anyhow::Result<Self> {
+        Self::parse_json_in(contents, DisplayTz::default())
+    }
+
+    fn parse_json_in(contents: &str, tz: DisplayTz) -> anyhow::Result<Self> {
+        let entries: Vec<String> =
+            serde_json::from_str(contents).context("Keep list isn't a JSON array of strings")?;
+        Ok(Self::from_entries_in(
+            entries.iter().map(|e| e.trim()).filter(|e| !e.is_empty()),
+            tz,
+        ))
+    }
+
+    pub fn from_entries<S: AsRef<str>>(entries: impl IntoIterator<Item = S>) -> Self {
+        Self::from_entries_in(entries, DisplayTz::default())
+    }
+
+    fn from_entries_in<S: AsRef<str>>(entries: impl IntoIterator<Item = S>, tz: DisplayTz) -> Self {
+        let mut keep_list = Self {
+            tz,
+            ..Self::default()
+        };
+        let now = Utc::now();
+        for entry in entries {
+            let (entry, until) = split_until(entry.as_ref());
+            if let Some(until) = until {
+                match until_instant(until, tz) {
+                    Ok(until) if until <= now => {
+                        info!("Keep entry {entry:?} expired at {until}, no longer protecting");
+                        keep_list.expired.insert(entry.to_string());
+                        keep_list.entries.push(entry.to_string());
+                        continue;
+                    }
+                    Ok(_) => {
+                        keep_list.expiring.insert(entry.to_string());
+                    }
+                    Err(e) => {
+                        warn!("Invalid until in keep entry {entry:?}, keeping it for good: {e:#}")
+                    }
+                }
+            }
+            let before = keep_list.len();
+            keep_list.add(entry);
+            if keep_list.len() > before {
+                keep_list.entries.push(entry.to_string());
+            }
+        }
+        keep_list
+    }
+
+    fn add(&mut self, entry: &str) {
+        let (platform, value) = split_platform(entry);
+        if let (Some(platform), Some(uri)) = (platform, value.strip_prefix("subject:")) {
+            self.subjects.insert(format!("{platform}:{uri}"));
+            return;
+        }
+        if let (Some(platform), Some(author)) = (platform, value.strip_prefix("author:")) {
+            self.authors
+                .insert(format!("{platform}:{author}"), entry.to_string());
+            return;
+        }
+        for (directive, after) in [("keep-after:", true), ("keep-before:", false)] {
+            let Some(date) = value.strip_prefix(directive) else {
+                continue;
+            };
+            match NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d") {
+                Ok(date) => self.dates.push(DateRule {
+                    entry: entry.to_string(),
+                    platform: platform.map(String::from),
+                    after,
+                    date,
+                }),
+                Err(e) => warn!("Ignoring invalid keep date {entry:?}: {e}"),
+            }
+            return;
+        }
+        let pattern = if let Some(re) = value
+            .strip_prefix('/')
+            .and_then(|v| v.strip_suffix('/'))
+            .filter(|re| !re.is_empty())
+        {
+            match Regex::new(re) {
+                Ok(re) => Pattern::Regex(re),
+                Err(e) => {
+                    warn!("Ignoring invalid keep regex {entry:?}: {e}");
+                    return;
+                }
+            }
+        } else if let Some(prefix) = value.strip_suffix('*') {
+            Pattern::Prefix(prefix.to_string())
+        } else {
+            self.exact.insert(entry.to_string());
+            return;
+        };
+        self.rules.push(Rule {
+            entry: entry.to_string(),
+            platform: platform.map(String::from),
+            pattern,
+        });
+    }
+
+    /// A copy of this list that also keeps each of `ids` on `platform`.
+    pub fn with_exact(&self, platform: &str, ids: impl IntoIterator<Item = String>) -> Self {
+        let mut keep_list = self.clone();
+        keep_list
+            .exact
+            .extend(ids.into_iter().map(|id| format!("{platform}:{id}")));
+        keep_list
+    }
+
+    /// A copy of this list that also keeps everything `other` keeps. Matches
+    /// of either's entries count towards this list's `KEEP_AUDIT`.
+    pub fn merged(&self, other: &KeepList) -> Self {
+        let mut keep_list = self.clone();
+        keep_list.exact.extend(other.exact.iter().cloned());
+        keep_list.rules.extend(other.rules.iter().cloned());
+        keep_list.subjects.extend(other.subjects.iter().cloned());
+        keep_list
+            .authors
+            .extend(other.authors.iter().map(|(k, v)| (k.clone(), v.clone())));
+        keep_list.dates.extend(other.dates.iter().cloned());
+        keep_list
+    }
+
+    /// Handles named by `author:` entries on `platform` that still need
+    /// resolving to a DID.
+    pub fn author_handles(&self, platform: &str) -> Vec<&str> {
+        let prefix = format!("{platform}:");
+        self.authors
+            .keys()
+            .filter_map(|key| key.strip_prefix(&prefix))
+            .filter(|author| !author.starts_with("did:"))
+            .collect()
+    }
+
+    /// A copy of this list where each `author:` handle in `dids` also matches
+    /// the DID it resolved to.
+    pub fn with_author_dids(&self, platform: &str, dids: &HashMap<String, String>) -> Self {
+        let mut keep_list = self.clone();
+        for (handle, did) in dids {
+            if let Some(entry) = self.authors.get(&format!("{platform}:{handle}")) {
+                keep_list
+                    .authors
+                    .insert(format!("{platform}:{did}"), entry.clone());
+            }
+        }
+        keep_list
+    }
+
+    pub fn len(&self) -> usize {
+        self.exact.len()
+            + self.rules.len()
+            + self.subjects.len()
+            + self.authors.len()
+            + self.dates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_protected(&self, platform: &str, id: &str) -> bool {
+        self.matches(platform, id).is_some()
+    }
+
+    /// Whether a like or repost of the post at `subject_uri` is kept.
+    pub fn is_subject_protected(&self, platform: &str, subject_uri: &str) -> bool {
+        let protected = self.subjects.contains(&format!("{platform}:{subject_uri}"));
+        if protected {
+            self.mark(&format!("{platform}:subject:{subject_uri}"));
+        }
+        protected
+    }
+
+    /// The `author:` entry keeping likes and reposts of posts by `did`, if any.
+    pub fn author_entry(&self, platform: &str, did: &str) -> Option<&str> {
+        let entry = self.authors.get(&format!("{platform}:{did}"))?;
+        self.mark(entry);
+        Some(entry)
+    }
+
+    /// The keep entry protecting `id` on `platform`, if any.
+    pub fn matches(&self, platform: &str, id: &str) -> Option<&str> {
+        let scoped = format!("{platform}:{id}");
+        let entry = self
+            .exact
+            .get(&scoped)
+            .or_else(|| self.exact.get(id))
+            .map(String::as_str)
+            .or_else(|| {
+                self.rules
+                    .iter()
+                    .filter(|rule| rule.platform.as_deref().is_none_or(|p| p == platform))
+                    .find(|rule| match &rule.pattern {
+                        Pattern::Prefix(prefix) => id.starts_with(prefix.as_str()),
+                        Pattern::Regex(re) => re.is_match(id),
+                    })
+                    .map(|rule| rule.entry.as_str())
+            })?;
+        self.mark(entry);
+        Some(entry)
+    }
+
+    /// The `keep-after:`/`keep-before:` entry protecting a post created at
+    /// `created` on `platform`, if any.
+    pub fn matches_time(&self, platform: &str, created: DateTime<Utc>) -> Option<&str> {
+        let rule = self
+            .dates
+            .iter()
+            .filter(|rule| rule.platform.as_deref().is_none_or(|p| p == platform))
+            .find(|rule| {
+                let boundary = self.tz.start_of_day(rule.date);
+                if rule.after {
+                    created >= boundary
+                } else {
+                    created < boundary
+                }
+            })?;
+        self.mark(&rule.entry);
+        Some(&rule.entry)
+    }
+
+    /// Whether `entry` has an `until` and so stops protecting at some point.
+    pub fn expires(&self, entry: &str) -> bool {
+        self.expiring.contains(entry)
+    }
+
+    fn mark(&self, entry: &str) {
+        self.matched.lock().unwrap().insert(entry.to_string());
+    }
+
+    /// Keep file entries for any of `platforms` (or unscoped) that matched
+    /// nothing so far, in file order. Candidates for pruning.
+    pub fn unmatched(&self, platforms: &[&str]) -> Vec<&str> {
+        let matched = self.matched.lock().unwrap();
+        self.entries
+            .iter()
+            .filter(|entry| !matched.contains(*entry) && !self.expired.contains(*entry))
+            .filter(|entry| on_platforms(entry, platforms))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Keep file entries for any of `platforms` (or unscoped) whose `until`
+    /// has passed, in file order.
+    pub fn expired(&self, platforms: &[&str]) -> Vec<&str> {
+        self.entries
+            .iter()
+            .filter(|entry| self.expired.contains(*entry))
+            .filter(|entry| on_platforms(entry, platforms))
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// `KEEP_SAMPLE_PERCENT`: a deterministic random share of otherwise deletable
+/// posts to keep. Each ID seeds its own draw, so the same posts are kept run
+/// after run (and in any listing order) until the seed changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepSample {
+    pub percent: u8,
+    pub seed: u64,
+}
+
+impl KeepSample {
+    pub fn keeps(&self, id: &str) -> bool {
+        splitmix64(fnv1a64(id.as_bytes()) ^ self.seed) % 100 < u64::from(self.percent)
+    }
+}
+
+/// 64-bit FNV-1a: stable across Rust versions and platforms, unlike
+/// `DefaultHasher`, but not a cryptographic hash.
+pub fn fnv1a64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |h, &b| {
+        (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// One step of the SplitMix64 generator, spreading similar IDs apart.
+fn splitmix64(state: u64) -> u64 {
+    let mut z = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// `KEEP_ONE_PER_MONTH`: keeps the first otherwise deletable post a sweep
+/// reaches in each calendar month of `DISPLAY_TZ`. Sweeps list newest first,
+/// so that's the newest post of the month, and the same one run after run.
+#[derive(Debug, Default)]
+pub struct MonthlyKeep {
+    tz: DisplayTz,
+    months: HashSet<(i32, u32)>,
+}
+
+impl MonthlyKeep {
+    pub fn new(tz: DisplayTz) -> Self {
+        Self {
+            tz,
+            months: HashSet::new(),
+        }
+    }
+
+    /// Whether the post created at `created` is its month's keeper.
+    pub fn keeps(&mut self, created: DateTime<Utc>) -> bool {
+        let date = self.tz.date(created);
+        self.months.insert((date.year(), date.month()))
+    }
+}
+
+/// How `PRUNE_KEEP_FILE` rewrites entries whose records no longer exist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PruneMode {
+    /// Turn the line into a `# gone:` comment, so it can be restored by hand.
+    Comment,
+    Remove,
+}
+
+impl std::str::FromStr for PruneMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "comment" => Ok(Self::Comment),
+            "remove" => Ok(Self::Remove),
+            _ => anyhow::bail!("Invalid PRUNE_KEEP_FILE {s:?}, expected \"comment\" or \"remove\""),
+        }
+    }
+}
+
+/// Exact entries in keep file `contents` whose records can be looked up, as
+/// `(entry, platform, id)`. Unscoped IDs are listed once per platform since
+/// they could be either; AT URIs only belong to Bluesky. Patterns and
+/// `subject:`/`author:`/date entries can't be checked and aren't listed.
+pub fn checkable(contents: &str) -> Vec<(&str, &'static str, &str)> {
+    let mut out = Vec::new();
+    for entry in contents.lines().map(str::trim) {
+        if entry.is_empty() || entry.starts_with('#') {
+            continue;
+        }
+        let (platform, id) = split_platform(split_until(entry).0);
+        let directive = ["subject:", "author:", "keep-after:", "keep-before:"]
+            .iter()
+            .any(|d| id.starts_with(d));
+        if directive || id.ends_with('*') || (id.starts_with('/') && id.ends_with('/')) {
+            continue;
+        }
+        let platforms: &[&'static str] = match platform {
+            Some("bluesky") => &["bluesky"],
+            Some(_) => &["mastodon"],
+            None if id.starts_with("at://") => &["bluesky"],
+            None => &["bluesky", "mastodon"],
+        };
+        out.extend(platforms.iter().map(|&p| (entry, p, id)));
+    }
+    out
+}
+
+/// Entries of `contents` whose record is gone on every platform it could
+/// belong to. `missing` maps each checked platform to its IDs that no longer
+/// exist; an entry that could belong to an unchecked platform is never dead.
+pub fn dead_entries<'a>(
+    contents: &'a str,
+    missing: &HashMap<&str, HashSet<String>>,
+) -> HashSet<&'a str> {
+    let targets = checkable(contents);
+    targets
+        .iter()
+        .map(|(entry, _, _)| *entry)
+        .filter(|entry| {
+            targets
+                .iter()
+                .filter(|(e, _, _)| e == entry)
+                .all(|(_, platform, id)| missing.get(platform).is_some_and(|m| m.contains(*id)))
+        })
+        .collect()
+}
+
+/// Entries of `contents` whose `until` has passed, with `until` dates read
+/// in `tz`. They protect nothing, so they can be pruned like dead ones.
+pub fn expired_entries(contents: &str, tz: DisplayTz) -> HashSet<&str> {
+    let now = Utc::now();
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty() && !entry.starts_with('#'))
+        .filter(|entry| {
+            split_until(entry)
+                .1
+                .is_some_and(|until| until_instant(until, tz).is_ok_and(|t| t <= now))
+        })
+        .collect()
+}
+
+/// `contents` with each line in `dead` commented out or removed. Every other
+/// line, comments and blank lines included, is kept as written.
+pub fn prune(contents: &str, dead: &HashSet<&str>, mode: PruneMode) -> String {
+    let mut out = String::with_capacity(contents.len());
+    for line in contents.split_inclusive('\n') {
+        let entry = line.trim();
+        if !dead.contains(entry) {
+            out.push_str(line);
+            continue;
+        }
+        if mode == PruneMode::Comment {
+            out.push_str(&format!("# gone: {entry}"));
+            out.push_str(&line[line.trim_end().len()..]);
+        }
+    }
+    out
+}
+
+/// Whether the keep file at `path` with `contents` is a JSON array rather than
+/// one entry per line.
+pub fn is_json(path: &Path, contents: &str) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+        || contents.trim_start().starts_with('[')
+}
+
+/// Split a leading `bluesky:`/`mastodon:` scope off an entry. Anything else
+/// (including `at://` URIs) is left unscoped.
+fn split_platform(entry: &str) -> (Option<&str>, &str) {
+    for platform in ["bluesky", "mastodon"] {
+        if let Some(rest) = entry
+            .strip_prefix(platform)
+            .and_then(|r| r.strip_prefix(':'))
+        {
+            return (Some(platform), rest);
+        }
+    }
+    (None, entry)
+}
+
+/// Whether `entry` is unscoped or scoped to one of `platforms`.
+fn on_platforms(entry: &str, platforms: &[&str]) -> bool {
+    split_platform(entry)
+        .0
+        .is_none_or(|p| platforms.contains(&p))
+}
+
+/// Split a trailing `until <date>` off an entry. A regex is only split if
+/// the suffix is a date, since ` until ` can be part of the pattern.
+fn split_until(entry: &str) -> (&str, Option<&str>) {
+    let regex = split_platform(entry).1.starts_with('/') && entry.ends_with('/');
+    match entry.rsplit_once(" until ") {
+        Some((head, until))
+            if !regex || until_instant(until.trim(), DisplayTz::default()).is_ok() =>
+        {
+            (head.trim_end(), Some(until.trim()))
+        }
+        _ => (entry, None),
+    }
+}
+
+/// When an `until` stops protecting: an RFC 3339 timestamp as is, or
+/// midnight starting a `YYYY-MM-DD` date in `tz`, like `keep-after:`.
+fn until_instant(until: &str, tz: DisplayTz) -> anyhow::Result<DateTime<Utc>> {
+    if let Ok(t) = crate::parse_timestamp(until) {
+        return Ok(t.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(until, "%Y-%m-%d")
+        .map(|date| tz.start_of_day(date))
+        .with_context(|| format!("Expected a timestamp or YYYY-MM-DD date, got {until:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    // --- exact entries ---
+
+    #[test]
+    fn platform_prefixed_match() {
+        let keep = KeepList::from_entries(["bluesky:abc123"]);
+        assert!(keep.is_protected("bluesky", "abc123"));
+    }
+
+    #[test]
+    fn bare_id_match() {
+        let keep = KeepList::from_entries(["abc123"]);
+        assert!(keep.is_protected("bluesky", "abc123"));
+        assert!(keep.is_protected("mastodon", "abc123"));
+    }
+
+    #[test]
+    fn no_match() {
+        let keep = KeepList::from_entries(["bluesky:other"]);
+        assert!(!keep.is_protected("bluesky", "abc123"));
+    }
+
+    #[test]
+    fn wrong_platform_prefix() {
+        let keep = KeepList::from_entries(["mastodon:abc123"]);
+        assert!(!keep.is_protected("bluesky", "abc123"));
+    }
+
+    #[test]
+    fn full_uri_match() {
+        let uri = "at://did:plc:xyz/app.bsky.feed.post/3k2la5diqyc2x";
+        let keep = KeepList::from_entries([format!("bluesky:{uri}")]);
+        assert!(keep.is_protected("bluesky", uri));
+        assert!(!keep.is_protected("bluesky", "3k2la5diqyc2x"));
+    }
+
+    // --- dated entries ---
+
+    #[test]
+    fn until_protects_only_until_the_date() {
+        let keep = KeepList::parse(
+            "bluesky:expired until 2020-01-01\n\
+             bluesky:current until 2999-06-01T00:00:00Z\n\
+             bluesky:forever\n\
+             mastodon:1112* until 2999-06-01\n",
+        );
+        assert!(!keep.is_protected("bluesky", "expired"));
+        assert!(keep.is_protected("bluesky", "current"));
+        assert!(keep.is_protected("bluesky", "forever"));
+        assert!(keep.is_protected("mastodon", "111234567890123456"));
+        assert_eq!(keep.len(), 3);
+        assert!(keep.expires("bluesky:current"));
+        assert!(keep.expires("mastodon:1112*"));
+        assert!(!keep.expires("bluesky:forever"));
+    }
+
+    #[test]
+    fn until_dates_are_midnight_in_display_tz() {
+        let tokyo: DisplayTz = "+09:00".parse().unwrap();
+        assert_eq!(
+            until_instant("2023-06-01", tokyo).unwrap(),
+            ts("2023-05-31T15:00:00Z")
+        );
+        assert_eq!(
+            until_instant("2023-06-01T12:00:00Z", tokyo).unwrap(),
+            ts("2023-06-01T12:00:00Z")
+        );
+        assert!(until_instant("someday", tokyo).is_err());
+    }
+
+    #[test]
+    fn expired_entries_are_audited_but_protect_nothing() {
+        let keep = KeepList::from_entries([
+            "bluesky:old until 2020-01-01",
+            "bluesky:unused",
+            "mastodon:111 until 2020-01-01",
+        ]);
+        assert!(!keep.is_protected("bluesky", "old"));
+        assert_eq!(keep.expired(&["bluesky"]), vec!["bluesky:old"]);
+        assert_eq!(keep.unmatched(&["bluesky"]), vec!["bluesky:unused"]);
+    }
+
+    #[test]
+    fn expired_entries_are_found_in_the_keep_file() {
+        let contents = "bluesky:old until 2020-01-01\n\
+                        # bluesky:comment until 2020-01-01\n\
+                        bluesky:current until 2999-01-01\n\
+                        bluesky:forever\n";
+        assert_eq!(
+            expired_entries(contents, DisplayTz::default()),
+            HashSet::from(["bluesky:old until 2020-01-01"])
+        );
+    }
+
+    #[test]
+    fn regex_containing_until_stays_whole() {
+        let keep = KeepList::from_entries(["mastodon:/^1 until 2/"]);
+        assert!(keep.is_protected("mastodon", "1 until 2"));
+        assert!(!keep.expires("mastodon:/^1"));
+
+        let keep = KeepList::from_entries(["mastodon:/^1 until 2/ until 2999-01-01"]);
+        assert!(keep.is_protected("mastodon", "1 until 2"));
+        assert!(keep.expires("mastodon:/^1 until 2/"));
+    }
+
+    #[test]
+    fn unparseable_until_keeps_the_entry() {
+        let keep = KeepList::from_entries(["bluesky:abc123 until someday"]);
+        assert!(keep.is_protected("bluesky", "abc123"));
+    }
+
+    #[test]
+    fn checkable_ignores_the_until_suffix() {
+        assert_eq!(
+            checkable("bluesky:abc123 until 2999-06-01\n"),
+            vec![("bluesky:abc123 until 2999-06-01", "bluesky", "abc123")]
+        );
+    }
+
+    // --- prefix entries ---
+
+    #[test]
+    fn prefix_matches_ids_starting_with_it() {
+        let keep = KeepList::from_entries(["mastodon:1112*"]);
+        assert!(keep.is_protected("mastodon", "111234567890123456"));
+        assert!(!keep.is_protected("mastodon", "110234567890123456"));
+        assert!(!keep.is_protected("bluesky", "111234567890123456"));
+    }
+
+    #[test]
+    fn unscoped_prefix_applies_to_every_platform() {
+        let keep = KeepList::from_entries(["3k2*"]);
+        assert!(keep.is_protected("bluesky", "3k2la5diqyc2x"));
+        assert!(keep.is_protected("mastodon", "3k2"));
+    }
+
+    // --- regex entries ---
+
+    #[test]
+    fn regex_matches_ids() {
+        let keep = KeepList::from_entries(["bluesky:/^3k2l[a-f]/"]);
+        assert!(keep.is_protected("bluesky", "3k2la5diqyc2x"));
+        assert!(!keep.is_protected("bluesky", "3k2lz5diqyc2x"));
+        assert!(!keep.is_protected("mastodon", "3k2la5diqyc2x"));
+    }
+
+    #[test]
+    fn invalid_regex_is_ignored() {
+        let keep = KeepList::from_entries(["bluesky:/([/", "bluesky:abc"]);
+        assert_eq!(keep.len(), 1);
+        assert!(keep.is_protected("bluesky", "abc"));
+    }
+
+    #[test]
+    fn lone_slash_is_an_exact_entry() {
+        let keep = KeepList::from_entries(["bluesky://"]);
+        assert!(keep.is_protected("bluesky", "//"));
+    }
+
+    // --- subject entries ---
+
+    #[test]
+    fn subject_entry_protects_by_subject_uri() {
+        let uri = "at://did:plc:famous/app.bsky.feed.post/3k2la5diqyc2x";
+        let keep = KeepList::from_entries([format!("bluesky:subject:{uri}")]);
+        assert_eq!(keep.len(), 1);
+        assert!(keep.is_subject_protected("bluesky", uri));
+        assert!(!keep.is_subject_protected("mastodon", uri));
+        assert!(
+            !keep.is_subject_protected("bluesky", "at://did:plc:famous/app.bsky.feed.post/other")
+        );
+        // Subject entries never match a record's own ID
+        assert!(!keep.is_protected("bluesky", uri));
+    }
+
+    #[test]
+    fn unscoped_subject_prefix_is_an_exact_entry() {
+        let keep = KeepList::from_entries(["subject:abc"]);
+        assert!(!keep.is_subject_protected("bluesky", "abc"));
+        assert!(keep.is_protected("bluesky", "subject:abc"));
+    }
+
+    // --- author entries ---
+
+    #[test]
+    fn author_entry_by_did_needs_no_resolution() {
+        let keep = KeepList::from_entries(["bluesky:author:did:plc:alice"]);
+        assert!(keep.author_handles("bluesky").is_empty());
+        assert_eq!(
+            keep.author_entry("bluesky", "did:plc:alice"),
+            Some("bluesky:author:did:plc:alice")
+        );
+        assert_eq!(keep.author_entry("mastodon", "did:plc:alice"), None);
+    }
+
+    #[test]
+    fn author_handle_matches_its_resolved_did() {
+        let keep = KeepList::from_entries(["bluesky:author:alice.com"]);
+        assert_eq!(keep.author_handles("bluesky"), vec!["alice.com"]);
+        assert_eq!(keep.author_entry("bluesky", "did:plc:alice"), None);
+
+        let dids = HashMap::from([("alice.com".to_string(), "did:plc:alice".to_string())]);
+        let resolved = keep.with_author_dids("bluesky", &dids);
+        assert_eq!(
+            resolved.author_entry("bluesky", "did:plc:alice"),
+            Some("bluesky:author:alice.com")
+        );
+        assert_eq!(resolved.author_entry("bluesky", "did:plc:bob"), None);
+    }
+
+    // --- matches ---
+
+    #[test]
+    fn matches_reports_the_protecting_entry() {
+        let keep = KeepList::from_entries(["mastodon:42", "mastodon:1112*", "/^zz/"]);
+        assert_eq!(keep.matches("mastodon", "42"), Some("mastodon:42"));
+        assert_eq!(keep.matches("mastodon", "11123"), Some("mastodon:1112*"));
+        assert_eq!(keep.matches("bluesky", "zz9"), Some("/^zz/"));
+        assert_eq!(keep.matches("bluesky", "42"), None);
+    }
+
+    #[test]
+    fn with_exact_adds_scoped_entries() {
+        let keep =
+            KeepList::from_entries(["mastodon:1"]).with_exact("bluesky", ["abc".to_string()]);
+        assert_eq!(keep.len(), 2);
+        assert!(keep.is_protected("bluesky", "abc"));
+        assert!(!keep.is_protected("mastodon", "abc"));
+        assert!(keep.is_protected("mastodon", "1"));
+    }
+
+    #[test]
+    fn merged_keeps_what_either_list_keeps() {
+        let global = KeepList::from_entries(["bluesky:abc"]);
+        let likes =
+            KeepList::from_entries(["bluesky:subject:at://x/p/1", "keep-before: 2015-01-01"]);
+        let keep = global.merged(&likes);
+        assert_eq!(keep.len(), 3);
+        assert!(keep.is_protected("bluesky", "abc"));
+        assert!(keep.is_subject_protected("bluesky", "at://x/p/1"));
+        assert!(!global.is_subject_protected("bluesky", "at://x/p/1"));
+    }
+
+    // --- date rules ---
+
+    fn ts(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn date_rules_keep_from_or_before_utc_midnight() {
+        let keep = KeepList::parse("keep-after: 2023-06-01\nmastodon:keep-before: 2015-01-01\n");
+        assert_eq!(keep.len(), 2);
+        assert_eq!(
+            keep.matches_time("bluesky", ts("2023-06-01T00:00:00Z")),
+            Some("keep-after: 2023-06-01")
+        );
+        assert_eq!(
+            keep.matches_time("bluesky", ts("2023-05-31T23:59:59Z")),
+            None
+        );
+        assert_eq!(
+            keep.matches_time("mastodon", ts("2014-12-31T23:59:59Z")),
+            Some("mastodon:keep-before: 2015-01-01")
+        );
+        assert_eq!(
+            keep.matches_time("bluesky", ts("2014-12-31T23:59:59Z")),
+            None
+        );
+    }
+
+    #[test]
+    fn date_rules_shift_with_display_tz() {
+        // Midnight on 2023-06-01 in UTC+09:00 is 15:00 UTC the day before
+        let tokyo = KeepList::parse_in("keep-after: 2023-06-01", "+09:00".parse().unwrap());
+        assert!(tokyo
+            .matches_time("bluesky", ts("2023-05-31T15:00:00Z"))
+            .is_some());
+        assert!(tokyo
+            .matches_time("bluesky", ts("2023-05-31T14:59:59Z"))
+            .is_none());
+
+        // ...and in UTC-05:00 it's 05:00 UTC the same day
+        let new_york = KeepList::parse_in("keep-before: 2023-06-01", "-05:00".parse().unwrap());
+        assert!(new_york
+            .matches_time("bluesky", ts("2023-06-01T04:59:59Z"))
+            .is_some());
+        assert!(new_york
+            .matches_time("bluesky", ts("2023-06-01T05:00:00Z"))
+            .is_none());
+    }
+
+    #[test]
+    fn invalid_keep_date_is_ignored() {
+        let keep = KeepList::parse("keep-after: June 1st\n");
+        assert_eq!(keep.len(), 0);
+    }
+
+    // --- parse / load ---
+
+    #[test]
+    fn parse_skips_comments_and_blanks_and_trims() {
+        let keep = KeepList::parse("# comment\n\n  bluesky:abc123  \nmastodon:456\n");
+        assert_eq!(keep.len(), 2);
+        assert!(keep.is_protected("bluesky", "abc123"));
+        assert!(keep.is_protected("mastodon", "456"));
+    }
+
+    #[test]
+    fn load_reads_file() {
+        let mut f = tempfile::NamedTempFile::new().unwrap();
+        writeln!(f, "# comment").unwrap();
+        writeln!(f).unwrap();
+        writeln!(f, "bluesky:abc123").unwrap();
+        writeln!(f, "mastodon:456*").unwrap();
+        let keep = KeepList::load(f.path(), DisplayTz::Utc);
+        assert_eq!(keep.len(), 2);
+        assert!(keep.is_protected("bluesky", "abc123"));
+        assert!(keep.is_protected("mastodon", "4567"));
+    }
+
+    // --- prune ---
+
+    const KEEP_FILE: &str = "# Favourites\nbluesky:alive\nbluesky:gone1\n\n# Both\nshared\nmastodon:100*\nmastodon:200\n";
+
+    #[test]
+    fn checkable_skips_patterns_and_lists_unscoped_ids_for_both() {
+        assert_eq!(
+            checkable(KEEP_FILE),
+            vec![
+                ("bluesky:alive", "bluesky", "alive"),
+                ("bluesky:gone1", "bluesky", "gone1"),
+                ("shared", "bluesky", "shared"),
+                ("shared", "mastodon", "shared"),
+                ("mastodon:200", "mastodon", "200"),
+            ]
+        );
+    }
+
+    #[test]
+    fn dead_entries_needs_every_possible_platform_checked() {
+        let bluesky_only = HashMap::from([(
+            "bluesky",
+            HashSet::from(["gone1".to_string(), "shared".to_string()]),
+        )]);
+        assert_eq!(
+            dead_entries(KEEP_FILE, &bluesky_only),
+            HashSet::from(["bluesky:gone1"])
+        );
+
+        let both = HashMap::from([
+            (
+                "bluesky",
+                HashSet::from(["gone1".to_string(), "shared".to_string()]),
+            ),
+            ("mastodon", HashSet::from(["shared".to_string()])),
+        ]);
+        assert_eq!(
+            dead_entries(KEEP_FILE, &both),
+            HashSet::from(["bluesky:gone1", "shared"])
+        );
+    }
+
+    #[test]
+    fn prune_comments_out_dead_entries_and_keeps_comments() {
+        let dead = HashSet::from(["bluesky:gone1", "shared"]);
+        assert_eq!(
+            prune(KEEP_FILE, &dead, PruneMode::Comment),
+            "# Favourites\nbluesky:alive\n# gone: bluesky:gone1\n\n# Both\n# gone: shared\nmastodon:100*\nmastodon:200\n"
+        );
+    }
+
+    #[test]
+    fn prune_removes_dead_entries_and_keeps_comments() {
+        let dead = HashSet::from(["bluesky:gone1", "shared"]);
+        let pruned = prune(KEEP_FILE, &dead, PruneMode::Remove);
+        assert_eq!(
+            pruned,
+            "# Favourites\nbluesky:alive\n\n# Both\nmastodon:100*\nmastodon:200\n"
+        );
+        // Pruning never drops a live entry
+        assert_eq!(KeepList::parse(&pruned).len(), 3);
+    }
+
+    #[test]
+    fn sample_keeps_about_the_requested_share() {
+        let sample = KeepSample {
+            percent: 25,
+            seed: 42,
+        };
+        let ids: Vec<String> = (0..10_000).map(|i| format!("3k{i:08}")).collect();
+        let kept = ids.iter().filter(|id| sample.keeps(id)).count();
+        assert!((2_300..=2_700).contains(&kept), "kept {kept} of 10000");
+
+        let none = KeepSample {
+            percent: 0,
+            seed: 42,
+        };
+        let all = KeepSample {
+            percent: 100,
+            seed: 42,
+        };
+        assert!(ids.iter().all(|id| !none.keeps(id) && all.keeps(id)));
+    }
+
+    #[test]
+    fn sample_is_deterministic_per_seed() {
+        let ids: Vec<String> = (0..1_000).map(|i| i.to_string()).collect();
+        let picks = |seed| -> Vec<bool> {
+            let sample = KeepSample { percent: 50, seed };
+            ids.iter().map(|id| sample.keeps(id)).collect()
+        };
+        assert_eq!(picks(7), picks(7));
+        assert_ne!(picks(7), picks(8));
+    }
+
+    #[test]
+    fn fnv1a64_matches_the_reference_vectors() {
+        assert_eq!(fnv1a64(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a64(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn monthly_keep_keeps_the_first_post_seen_per_month() {
+        let mut monthly = MonthlyKeep::new(DisplayTz::Utc);
+        assert!(monthly.keeps(ts("2023-05-31T23:00:00Z")));
+        assert!(!monthly.keeps(ts("2023-05-02T00:00:00Z")));
+        assert!(monthly.keeps(ts("2023-04-30T23:59:59Z")));
+        assert!(monthly.keeps(ts("2022-05-15T00:00:00Z")));
+        assert!(!monthly.keeps(ts("2023-04-01T00:00:00Z")));
+
+        // 23:00 UTC on the 31st is already June in UTC+02:00
+        let mut shifted = MonthlyKeep::new("+02:00".parse().unwrap());
+        assert!(shifted.keeps(ts("2023-05-31T23:00:00Z")));
+        assert!(shifted.keeps(ts("2023-05-31T21:00:00Z")));
+    }
+
+    #[test]
+    fn unmatched_reports_entries_that_never_matched() {
+        let keep = KeepList::parse(
+            "bluesky:used\nbluesky:gone\nmastodon:1112*\nbluesky:subject:at://did:plc:x/app.bsky.feed.post/p\nmastodon:9999\n",
+        );
+        assert!(keep.is_protected("bluesky", "used"));
+        assert!(keep.is_protected("mastodon", "11120"));
+        let copy = keep.with_exact("bluesky", ["curated".to_string()]);
+        assert!(copy.is_subject_protected("bluesky", "at://did:plc:x/app.bsky.feed.post/p"));
+        assert!(copy.is_protected("bluesky", "curated"));
+
+        assert_eq!(
+            keep.unmatched(&["bluesky", "mastodon"]),
+            vec!["bluesky:gone", "mastodon:9999"]
+        );
+        // Entries for a platform that didn't run aren't reported
+        assert_eq!(keep.unmatched(&["bluesky"]), vec!["bluesky:gone"]);
+    }
+
+    #[test]
+    fn json_array_matches_the_line_format() {
+        let lines = KeepList::parse(
+            "bluesky:abc123\nmastodon:1112*\nshared\nbluesky:subject:at://did:plc:x/app.bsky.feed.post/p\n",
+        );
+        let json = KeepList::parse_json(
+            r#"["bluesky:abc123", "  mastodon:1112*  ", "", "shared", "bluesky:subject:at://did:plc:x/app.bsky.feed.post/p"]"#,
+        )
+        .unwrap();
+        assert_eq!(json.len(), lines.len());
+        assert_eq!(json.entries, lines.entries);
+        for keep in [&lines, &json] {
+            assert!(keep.is_protected("bluesky", "abc123"));
+            assert!(!keep.is_protected("mastodon", "abc123"));
+            assert!(keep.is_protected("mastodon", "11125"));
+            assert!(keep.is_protected("bluesky", "shared"));
+            assert!(keep.is_protected("mastodon", "shared"));
+            assert!(keep.is_subject_protected("bluesky", "at://did:plc:x/app.bsky.feed.post/p"));
+        }
+    }
+
+    #[test]
+    fn load_detects_json_by_extension_or_leading_bracket() {
+        let dir = tempfile::tempdir().unwrap();
+        let by_extension = dir.path().join("keep.json");
+        fs::write(&by_extension, "\n  [\"bluesky:abc\"]").unwrap();
+        assert!(KeepList::load(&by_extension, DisplayTz::Utc).is_protected("bluesky", "abc"));
+
+        let by_bracket = dir.path().join("keep.txt");
+        fs::write(&by_bracket, "[\"mastodon:42\"]\n").unwrap();
+        assert!(KeepList::load(&by_bracket, DisplayTz::Utc).is_protected("mastodon", "42"));
+
+        let invalid = dir.path().join("bad.json");
+        fs::write(&invalid, "{\"keep\": []}").unwrap();
+        assert_eq!(KeepList::load(&invalid, DisplayTz::Utc).len(), 0);
+    }
+
+    #[test]
+    fn load_nonexistent_file_returns_empty() {
+        let keep = KeepList::load(Path::new("/nonexistent/keep.txt"), DisplayTz::Utc);
+        assert_eq!(keep.len(), 0);
+    }
+
+    #[test]
+    fn load_empty_file_returns_empty() {
+        let f = tempfile::NamedTempFile::new().unwrap();
+        assert_eq!(KeepList::load(f.path(), DisplayTz::Utc).len(), 0);
+    }
+}
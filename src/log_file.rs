@@ -0,0 +1,114 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::{Log, Metadata, Record};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+/// `LOG_FILE` with the run's start time before the extension, so each run
+/// gets its own file: `logs/skyscraper.log` becomes
+/// `logs/skyscraper-20240615T120000Z.log`.
+pub fn path_for_run(base: &Path, started: DateTime<Utc>) -> PathBuf {
+    let stamp = started.format("%Y%m%dT%H%M%SZ");
+    let stem = base
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "skyscraper".to_string());
+    let name = match base.extension() {
+        Some(ext) => format!("{stem}-{stamp}.{}", ext.to_string_lossy()),
+        None => format!("{stem}-{stamp}"),
+    };
+    base.with_file_name(name)
+}
+
+/// Create the log file at `path`, and its directory if needed.
+pub fn create(path: &Path) -> Result<File> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create log dir {}", parent.display()))?;
+    }
+    File::create(path).with_context(|| format!("Failed to create log file {}", path.display()))
+}
+
+/// Sends each record to the console logger and the file logger, each with
+/// its own filter, so `QUIET_SUMMARY` keeps the console terse while the file
+/// still gets the full log.
+pub struct TeeLogger {
+    pub console: env_logger::Logger,
+    pub file: env_logger::Logger,
+}
+
+impl TeeLogger {
+    /// Install as the global logger.
+    pub fn init(self) {
+        let max = self.console.filter().max(self.file.filter());
+        log::set_boxed_logger(Box::new(self)).expect("Logger already initialized");
+        log::set_max_level(max);
+    }
+}
+
+impl Log for TeeLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.console.enabled(metadata) || self.file.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        self.console.log(record);
+        self.file.log(record);
+    }
+
+    fn flush(&self) {
+        self.console.flush();
+        self.file.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    #[test]
+    fn path_for_run_inserts_the_start_time() {
+        let started = DateTime::parse_from_rfc3339("2024-06-15T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            path_for_run(Path::new("logs/skyscraper.log"), started),
+            PathBuf::from("logs/skyscraper-20240615T120000Z.log")
+        );
+        assert_eq!(
+            path_for_run(Path::new("run"), started),
+            PathBuf::from("run-20240615T120000Z")
+        );
+    }
+
+    #[test]
+    fn file_gets_lines_the_console_filters_out() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs/run.log");
+        let file = create(&path).unwrap();
+
+        let tee = TeeLogger {
+            console: env_logger::Builder::new()
+                .filter_level(log::LevelFilter::Error)
+                .build(),
+            file: env_logger::Builder::new()
+                .filter_level(log::LevelFilter::Info)
+                .target(env_logger::Target::Pipe(Box::new(file)))
+                .build(),
+        };
+        tee.log(
+            &Record::builder()
+                .args(format_args!(
+                    "Deleted post: at://did:plc:test/app.bsky.feed.post/a"
+                ))
+                .level(Level::Info)
+                .target("skyscraper::bluesky")
+                .build(),
+        );
+        tee.flush();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("Deleted post: at://did:plc:test/app.bsky.feed.post/a"));
+    }
+}
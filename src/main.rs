@@ -1,20 +1,1287 @@
+mod adaptive;
+mod archive;
+mod blocklist;
 mod bluesky;
+mod counters;
+mod credentials;
+mod display_tz;
+mod error_limit;
+mod export;
+mod file_config;
+mod full_plan;
+mod hook;
+mod keep;
+mod log_file;
 mod mastodon;
+mod phases;
+mod rate_budget;
+mod rate_limit_log;
+mod remaining_csv;
+mod report;
+mod shutdown;
+mod stats;
+mod watermark;
 
-use anyhow::{Context, Result};
+use adaptive::DelayBounds;
+use anyhow::{bail, Context, Result};
+use blocklist::Blocklist;
 use chrono::{DateTime, FixedOffset, TimeDelta, Utc};
+use counters::RunCounters;
+use credentials::{CredentialSource, OsKeyring, SecretStore};
+use display_tz::DisplayTz;
+use error_limit::ErrorLimits;
+use file_config::FileConfig;
+use keep::{KeepList, KeepSample};
 use log::{error, info, warn};
-use std::collections::HashSet;
+use rate_limit_log::RateLimitLog;
+use remaining_csv::RemainingCsv;
+use report::Report;
+use serde::{Deserialize, Serialize};
+use shutdown::Shutdown;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
-use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
 
+/// Log target of the one line per platform that `QUIET_SUMMARY` keeps.
+pub const SUMMARY_TARGET: &str = "summary";
+
+/// Sent on every request unless `USER_AGENT` is set.
+pub const DEFAULT_USER_AGENT: &str = "skyscraper/0.1.0";
+
+/// Where `INCREMENTAL` keeps its watermarks unless `INCREMENTAL_STATE_FILE` is set.
+const DEFAULT_INCREMENTAL_STATE_FILE: &str = ".skyscraper/incremental.json";
+
+/// What `CONFIRM_DELETE_ACCOUNT_DATA` must be set to for `DELETE_ACCOUNT_DATA`.
+const ACCOUNT_DATA_CONFIRMATION: &str = "delete-everything";
+
+#[derive(Clone)]
 pub struct Config {
     pub cutoff: DateTime<Utc>,
     pub dry_run: bool,
+    /// Buffer dry-run output and print it grouped by month. Implies `dry_run`.
+    pub dry_run_grouped: bool,
     pub delete_pinned: bool,
     pub delete_reposts: bool,
+    /// Delete reposts and reblogs whatever their age, not just old ones.
+    pub purge_all_reposts: bool,
     pub delete_likes: bool,
+    /// `DELETE_ACCOUNT_DATA`, confirmed: delete everything on every platform,
+    /// Mastodon bookmarks included. See [`Config::for_account_data`].
+    pub delete_account_data: bool,
+    pub post_summary: bool,
+    pub media_filter: MediaFilter,
+    /// Only delete Bluesky posts whose text starts with this, e.g. `[bot]`.
+    pub delete_text_prefix: Option<String>,
+    /// Only delete posts with no media and no links; keep everything else.
+    pub delete_plain_text_only: bool,
+    /// Fetch the next Bluesky listing page while the current one is processed.
+    pub prefetch_pages: bool,
+    /// Delete Bluesky posts referencing the largest media first.
+    pub largest_media_first: bool,
+    /// List every Mastodon status up front and delete the oldest first.
+    pub oldest_first: bool,
+    pub keep_bookmarked: bool,
+    /// Keep Bluesky posts whose media another record in the repo also uses.
+    pub keep_shared_blobs: bool,
+    /// Keep the newest otherwise deletable post of each calendar month.
+    pub keep_one_per_month: bool,
+    /// Keep Bluesky posts referenced by your own lists and feed generators.
+    pub keep_curated: bool,
+    /// Keep Bluesky likes of your own posts, e.g. ones used as bookmarks.
+    pub keep_self_likes: bool,
+    /// Cutoff for Bluesky replies hidden by the thread's author, from
+    /// `DELETE_HIDDEN_SOONER`. Only moves the cutoff earlier, never later.
+    pub hidden_cutoff: Option<DateTime<Utc>>,
+    /// `IMPORT_BOUNDARY`: Bluesky posts created before this were bulk
+    /// imported, e.g. from a Twitter archive, and are kept.
+    pub import_boundary: Option<DateTime<Utc>>,
+    /// `IMPORT_RETENTION_DAYS`: imported posts older than this are deleted
+    /// after all. `None` keeps every import.
+    pub import_cutoff: Option<DateTime<Utc>>,
+    /// Keep Mastodon posts that have replies, so conversations aren't orphaned.
+    pub keep_if_has_replies: bool,
+    /// Keep a deterministic random share of old posts instead of deleting them all.
+    pub keep_sample: Option<KeepSample>,
+    /// Report keep file entries that matched nothing at the end of the run.
+    pub keep_audit: bool,
+    /// Re-list each page after deleting from it and warn about any deleted
+    /// record still listed.
+    pub verify_each_page: bool,
+    /// Send an `Idempotency-Key` with each Mastodon delete, for instances that require one.
+    pub mastodon_idempotency: bool,
+    /// Keep Mastodon posts edited at or after this time, whatever their age.
+    pub keep_edited_since: Option<DateTime<Utc>>,
+    /// Keep Mastodon posts whose text contains any of these, ignoring case.
+    pub keep_keywords: Vec<String>,
+    /// Only delete Mastodon posts in these languages. Empty means any language.
+    pub delete_languages: Vec<String>,
+    /// Never delete Mastodon posts in these languages.
+    pub keep_languages: Vec<String>,
+    /// Language assumed for statuses that don't declare one.
+    pub default_language: Option<String>,
+    /// Only delete Mastodon posts made with these applications, by name,
+    /// ignoring case. Empty means any application.
+    pub delete_from_apps: Vec<String>,
+    /// Save each Bluesky record here before deleting it, for `RESTORE_DIR`.
+    pub archive_dir: Option<PathBuf>,
+    /// Entries from `FORCE_DELETE_FILE`, deleted even if pinned, kept or newer
+    /// than the cutoff. Empty unless set and confirmed with `CONFIRM_FORCE`.
+    pub force_delete: KeepList,
+    /// `DELETE_BLOCKLIST_FILE`: Bluesky posts containing any of these terms
+    /// are deleted whatever their age.
+    pub delete_blocklist: Option<Blocklist>,
+    /// `KEEP_FILE_POSTS`, `KEEP_FILE_LIKES` and `KEEP_FILE_REPOSTS`, keyed by
+    /// the Bluesky collection each protects on top of the global keep list.
+    pub collection_keep: HashMap<String, KeepList>,
+    /// Where to persist recent deletion timestamps so consecutive runs share
+    /// Mastodon's rate limit window.
+    pub rate_limit_state_file: Option<PathBuf>,
+    /// `RATE_LIMIT_LOG`: where each rate limited request is recorded.
+    pub rate_limit_log: Option<RateLimitLog>,
+    /// `REMAINING_CSV`: collects the records left for being newer than the
+    /// cutoff, written out after the run.
+    pub remaining_csv: Option<RemainingCsv>,
+    /// `REPORT_FILE`: collects every record removed (or that would be, in a
+    /// dry run), written out after the run.
+    pub report: Option<Report>,
+    /// Where `INCREMENTAL` keeps each Bluesky collection's watermark. `None`
+    /// unless `INCREMENTAL` is set, in which case every page is scanned.
+    pub incremental_state_file: Option<PathBuf>,
+    /// Where Mastodon records a fully swept statuses phase, so the next run
+    /// can go straight to favourites.
+    pub phase_state_file: Option<PathBuf>,
+    /// Shell command run after each platform with its summary as JSON on stdin.
+    pub post_run_hook: Option<String>,
+    /// Shell command run once at the end with every platform's summary.
+    pub post_all_hook: Option<String>,
+    /// Pause between deletions. `None` uses the platform's default pacing.
+    pub delay: Option<Duration>,
+    /// Pause after deleting a Bluesky post, repost or like, overriding
+    /// `delay` for that collection.
+    pub post_delay: Option<Duration>,
+    pub repost_delay: Option<Duration>,
+    pub like_delay: Option<Duration>,
+    /// Let the pause between deletions follow server latency within these bounds.
+    pub adaptive_delay: Option<DelayBounds>,
+    /// More Bluesky collections to sweep after likes, e.g. `app.bsky.labeler.service`.
+    pub extra_collections: Vec<String>,
+    /// Drop dated Bluesky preference entries older than the cutoff.
+    pub prune_preferences: bool,
+    /// Whether a Bluesky 429 waits for the reset or ends the sweep.
+    pub rate_limit_mode: RateLimitMode,
+    /// When failing deletions abort a platform.
+    pub error_limits: ErrorLimits,
+    /// Date Bluesky records without a `createdAt` by their TID rkey instead of skipping them.
+    pub fallback_rkey_time: bool,
+    /// Count a Bluesky `createdAt` in the future as now.
+    pub treat_future_as_now: bool,
+    /// Overwrite old Bluesky posts with a placeholder instead of deleting them.
+    pub redact: bool,
+    /// Zone for logged timestamps and date-only keep rules.
+    pub display_tz: DisplayTz,
+    /// `User-Agent` header for every request, e.g. for instances that allowlist tools.
+    pub user_agent: String,
+    /// Set on Ctrl-C. Cuts the pause between requests short and stops sweeps.
+    pub shutdown: Shutdown,
+    /// Run-wide tallies every sweep adds to as it goes.
+    pub counters: RunCounters,
+}
+
+impl Config {
+    /// Resolve settings from env vars (via `lookup`), falling back to values
+    /// from the config file and then to the built-in defaults.
+    fn resolve(lookup: &dyn Fn(&str) -> Option<String>, file: &FileConfig) -> Result<Self> {
+        // RETENTION beats RETENTION_DAYS, and each env var beats its file key
+        let retention_days = |days: i64| {
+            TimeDelta::try_days(days).with_context(|| format!("Invalid RETENTION_DAYS {days}"))
+        };
+        let retention = match (lookup("RETENTION"), parse_var(lookup, "RETENTION_DAYS")) {
+            (Some(r), _) => parse_retention(&r)?,
+            (None, Some(days)) => retention_days(days)?,
+            (None, None) => match &file.retention {
+                Some(r) => parse_retention(r)?,
+                None => retention_days(file.retention_days.unwrap_or(180))?,
+            },
+        };
+        let media_filter = match lookup("MEDIA_FILTER") {
+            Some(v) => v.parse()?,
+            None => file.media_filter.unwrap_or_default(),
+        };
+        let force_delete = match lookup("FORCE_DELETE_FILE")
+            .or_else(|| file.force_delete_file.clone())
+        {
+            Some(path) => {
+                if !flag(lookup, "CONFIRM_FORCE").unwrap_or(false) {
+                    bail!("FORCE_DELETE_FILE deletes posts regardless of keep rules, set CONFIRM_FORCE=true to use it");
+                }
+                let contents = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read FORCE_DELETE_FILE {path}"))?;
+                if keep::is_json(Path::new(&path), &contents) {
+                    KeepList::parse_json(&contents)
+                        .with_context(|| format!("Invalid FORCE_DELETE_FILE {path}"))?
+                } else {
+                    KeepList::parse(&contents)
+                }
+            }
+            None => KeepList::default(),
+        };
+        // Env only, so a config file can't leave it switched on for later runs
+        let delete_account_data = flag(lookup, "DELETE_ACCOUNT_DATA").unwrap_or(false);
+        if delete_account_data
+            && lookup("CONFIRM_DELETE_ACCOUNT_DATA").as_deref() != Some(ACCOUNT_DATA_CONFIRMATION)
+        {
+            bail!("DELETE_ACCOUNT_DATA deletes every post, repost, like, favourite and bookmark whatever its age or keep rules, set CONFIRM_DELETE_ACCOUNT_DATA={ACCOUNT_DATA_CONFIRMATION} to use it");
+        }
+        let delete_pinned = flag(lookup, "DELETE_PINNED")
+            .or(file.delete_pinned)
+            .unwrap_or(false);
+        if delete_pinned {
+            confirm_delete_pinned(lookup, "DELETE_PINNED")?;
+        }
+        let delete_blocklist = lookup("DELETE_BLOCKLIST_FILE")
+            .or_else(|| file.delete_blocklist_file.clone())
+            .map(|path| Blocklist::load(Path::new(&path)))
+            .transpose()?;
+        let display_tz = display_tz(lookup, file)?;
+        let rate_limit_log = lookup("RATE_LIMIT_LOG")
+            .map(PathBuf::from)
+            .or_else(|| file.rate_limit_log.clone())
+            .map(|path| RateLimitLog::open(&path))
+            .transpose()?;
+        let mut collection_keep = HashMap::new();
+        for (var, path, collection) in [
+            (
+                "KEEP_FILE_POSTS",
+                &file.keep_file_posts,
+                "app.bsky.feed.post",
+            ),
+            (
+                "KEEP_FILE_LIKES",
+                &file.keep_file_likes,
+                "app.bsky.feed.like",
+            ),
+            (
+                "KEEP_FILE_REPOSTS",
+                &file.keep_file_reposts,
+                "app.bsky.feed.repost",
+            ),
+        ] {
+            if let Some(path) = lookup(var).or_else(|| path.clone()) {
+                let keep_list = KeepList::load(Path::new(&path), display_tz);
+                collection_keep.insert(collection.to_string(), keep_list);
+            }
+        }
+        let keep_sample =
+            match parse_var(lookup, "KEEP_SAMPLE_PERCENT").or(file.keep_sample_percent) {
+                Some(percent @ 0..=100) => Some(KeepSample {
+                    percent,
+                    seed: parse_var(lookup, "KEEP_SAMPLE_SEED")
+                        .or(file.keep_sample_seed)
+                        .unwrap_or(0),
+                }),
+                Some(percent) => bail!("Invalid KEEP_SAMPLE_PERCENT {percent}, expected 0 to 100"),
+                None => None,
+            };
+        let adaptive_delay = match (
+            parse_var(lookup, "ADAPTIVE_DELAY_MIN_MS").or(file.adaptive_delay_min_ms),
+            parse_var(lookup, "ADAPTIVE_DELAY_MAX_MS").or(file.adaptive_delay_max_ms),
+        ) {
+            (None, None) => None,
+            (Some(min), Some(max)) if min <= max => Some(DelayBounds {
+                min: Duration::from_millis(min),
+                max: Duration::from_millis(max),
+            }),
+            (Some(min), Some(max)) => {
+                bail!("ADAPTIVE_DELAY_MIN_MS ({min}) is greater than ADAPTIVE_DELAY_MAX_MS ({max})")
+            }
+            _ => bail!("ADAPTIVE_DELAY_MIN_MS and ADAPTIVE_DELAY_MAX_MS must be set together"),
+        };
+        let max_consecutive =
+            parse_var(lookup, "MAX_CONSECUTIVE_ERRORS").or(file.max_consecutive_errors);
+        if max_consecutive == Some(0) {
+            bail!("Invalid MAX_CONSECUTIVE_ERRORS 0, expected at least 1");
+        }
+        let max_rate = parse_var(lookup, "MAX_ERROR_RATE").or(file.max_error_rate);
+        if let Some(rate) = max_rate.filter(|r: &f64| !(0.0..=1.0).contains(r)) {
+            bail!("Invalid MAX_ERROR_RATE {rate}, expected 0 to 1");
+        }
+        let dry_run_grouped = flag(lookup, "DRY_RUN_GROUPED")
+            .or(file.dry_run_grouped)
+            .unwrap_or(false);
+        let keep_one_per_month = flag(lookup, "KEEP_ONE_PER_MONTH")
+            .or(file.keep_one_per_month)
+            .unwrap_or(false);
+        let oldest_first = flag(lookup, "OLDEST_FIRST")
+            .or(file.oldest_first)
+            .unwrap_or(false);
+        let largest_media_first = flag(lookup, "LARGEST_MEDIA_FIRST")
+            .or(file.largest_media_first)
+            .unwrap_or(false);
+        if keep_one_per_month && (oldest_first || largest_media_first) {
+            bail!("KEEP_ONE_PER_MONTH keeps the newest post of each month, so it needs the usual newest-first order and can't be combined with OLDEST_FIRST or LARGEST_MEDIA_FIRST");
+        }
+        Ok(Self {
+            cutoff: Utc::now()
+                .checked_sub_signed(retention)
+                .context("Retention reaches back further than dates can go")?,
+            dry_run: dry_run_grouped || flag(lookup, "DRY_RUN").or(file.dry_run).unwrap_or(false),
+            dry_run_grouped,
+            delete_pinned,
+            delete_reposts: flag(lookup, "DELETE_REPOSTS")
+                .or(file.delete_reposts)
+                .unwrap_or(true),
+            purge_all_reposts: flag(lookup, "PURGE_ALL_REPOSTS")
+                .or(file.purge_all_reposts)
+                .unwrap_or(false),
+            delete_likes: flag(lookup, "DELETE_LIKES")
+                .or(file.delete_likes)
+                .unwrap_or(true),
+            delete_account_data,
+            post_summary: flag(lookup, "POST_SUMMARY_STATUS")
+                .or(file.post_summary_status)
+                .unwrap_or(false),
+            media_filter,
+            delete_text_prefix: lookup("DELETE_TEXT_PREFIX")
+                .or_else(|| file.delete_text_prefix.clone()),
+            delete_plain_text_only: flag(lookup, "DELETE_PLAIN_TEXT_ONLY")
+                .or(file.delete_plain_text_only)
+                .unwrap_or(false),
+            prefetch_pages: flag(lookup, "PREFETCH_PAGES")
+                .or(file.prefetch_pages)
+                .unwrap_or(false),
+            largest_media_first,
+            oldest_first,
+            keep_bookmarked: flag(lookup, "KEEP_BOOKMARKED")
+                .or(file.keep_bookmarked)
+                .unwrap_or(false),
+            keep_curated: flag(lookup, "KEEP_CURATED")
+                .or(file.keep_curated)
+                .unwrap_or(false),
+            keep_self_likes: flag(lookup, "KEEP_SELF_LIKES")
+                .or(file.keep_self_likes)
+                .unwrap_or(false),
+            hidden_cutoff: parse_var(lookup, "DELETE_HIDDEN_SOONER")
+                .or(file.delete_hidden_sooner)
+                .map(|days| Utc::now() - TimeDelta::days(days)),
+            import_boundary: lookup("IMPORT_BOUNDARY")
+                .or_else(|| file.import_boundary.clone())
+                .map(|s| parse_instant(&s).context("Invalid IMPORT_BOUNDARY"))
+                .transpose()?,
+            import_cutoff: parse_var(lookup, "IMPORT_RETENTION_DAYS")
+                .or(file.import_retention_days)
+                .map(|days| Utc::now() - TimeDelta::days(days)),
+            keep_shared_blobs: flag(lookup, "KEEP_SHARED_BLOBS")
+                .or(file.keep_shared_blobs)
+                .unwrap_or(false),
+            keep_one_per_month,
+            keep_if_has_replies: flag(lookup, "KEEP_IF_HAS_REPLIES")
+                .or(file.keep_if_has_replies)
+                .unwrap_or(false),
+            keep_sample,
+            keep_audit: flag(lookup, "KEEP_AUDIT")
+                .or(file.keep_audit)
+                .unwrap_or(false),
+            verify_each_page: flag(lookup, "VERIFY_EACH_PAGE")
+                .or(file.verify_each_page)
+                .unwrap_or(false),
+            mastodon_idempotency: flag(lookup, "MASTODON_IDEMPOTENCY")
+                .or(file.mastodon.idempotency)
+                .unwrap_or(false),
+            keep_edited_since: parse_var(lookup, "KEEP_RECENTLY_EDITED_DAYS")
+                .or(file.keep_recently_edited_days)
+                .map(|days| Utc::now() - TimeDelta::days(days)),
+            keep_keywords: list(lookup, "KEEP_KEYWORDS")
+                .or_else(|| file.keep_keywords.clone())
+                .unwrap_or_default(),
+            delete_languages: list(lookup, "DELETE_LANGUAGES")
+                .or_else(|| file.delete_languages.clone())
+                .unwrap_or_default(),
+            keep_languages: list(lookup, "KEEP_LANGUAGES")
+                .or_else(|| file.keep_languages.clone())
+                .unwrap_or_default(),
+            default_language: lookup("DEFAULT_LANGUAGE").or_else(|| file.default_language.clone()),
+            delete_from_apps: list(lookup, "DELETE_FROM_APPS")
+                .or_else(|| file.delete_from_apps.clone())
+                .unwrap_or_default(),
+            archive_dir: lookup("ARCHIVE_DIR")
+                .map(PathBuf::from)
+                .or_else(|| file.archive_dir.clone()),
+            force_delete,
+            delete_blocklist,
+            collection_keep,
+            rate_limit_state_file: lookup("RATE_LIMIT_STATE_FILE")
+                .map(PathBuf::from)
+                .or_else(|| file.rate_limit_state_file.clone()),
+            rate_limit_log,
+            remaining_csv: lookup("REMAINING_CSV")
+                .map(PathBuf::from)
+                .or_else(|| file.remaining_csv.clone())
+                .map(RemainingCsv::new),
+            report: lookup("REPORT_FILE")
+                .map(PathBuf::from)
+                .or_else(|| file.report_file.clone())
+                .map(Report::new),
+            incremental_state_file: flag(lookup, "INCREMENTAL")
+                .or(file.incremental)
+                .unwrap_or(false)
+                .then(|| {
+                    lookup("INCREMENTAL_STATE_FILE")
+                        .map(PathBuf::from)
+                        .or_else(|| file.incremental_state_file.clone())
+                        .unwrap_or_else(|| PathBuf::from(DEFAULT_INCREMENTAL_STATE_FILE))
+                }),
+            phase_state_file: lookup("PHASE_STATE_FILE")
+                .map(PathBuf::from)
+                .or_else(|| file.phase_state_file.clone()),
+            post_run_hook: lookup("POST_RUN_HOOK").or_else(|| file.post_run_hook.clone()),
+            post_all_hook: lookup("POST_ALL_HOOK").or_else(|| file.post_all_hook.clone()),
+            delay: None,
+            post_delay: parse_var(lookup, "BLUESKY_POST_DELAY_MS")
+                .or(file.bluesky.post_delay_ms)
+                .map(Duration::from_millis),
+            repost_delay: parse_var(lookup, "BLUESKY_REPOST_DELAY_MS")
+                .or(file.bluesky.repost_delay_ms)
+                .map(Duration::from_millis),
+            like_delay: parse_var(lookup, "BLUESKY_LIKE_DELAY_MS")
+                .or(file.bluesky.like_delay_ms)
+                .map(Duration::from_millis),
+            adaptive_delay,
+            extra_collections: list(lookup, "BLUESKY_EXTRA_COLLECTIONS")
+                .or_else(|| file.bluesky.extra_collections.clone())
+                .unwrap_or_default(),
+            prune_preferences: flag(lookup, "BLUESKY_PRUNE_PREFERENCES")
+                .or(file.bluesky.prune_preferences)
+                .unwrap_or(false),
+            rate_limit_mode: match lookup("BLUESKY_RATE_LIMIT_MODE") {
+                Some(v) => v.parse()?,
+                None => file.bluesky.rate_limit_mode.unwrap_or_default(),
+            },
+            error_limits: ErrorLimits {
+                max_consecutive,
+                max_rate,
+            },
+            fallback_rkey_time: flag(lookup, "FALLBACK_RKEY_TIME")
+                .or(file.fallback_rkey_time)
+                .unwrap_or(false),
+            treat_future_as_now: flag(lookup, "TREAT_FUTURE_AS_NOW")
+                .or(file.treat_future_as_now)
+                .unwrap_or(false),
+            redact: flag(lookup, "REDACT").or(file.redact).unwrap_or(false),
+            display_tz,
+            user_agent: lookup("USER_AGENT")
+                .or_else(|| file.user_agent.clone())
+                .unwrap_or_else(|| DEFAULT_USER_AGENT.to_string()),
+            shutdown: Shutdown::default(),
+            counters: RunCounters::default(),
+        })
+    }
+
+    /// Copy of this config with a platform's own retention and pacing applied.
+    fn for_platform(&self, retention_days: Option<i64>, delay_ms: Option<u64>) -> Self {
+        Self {
+            cutoff: retention_days.map_or(self.cutoff, |days| Utc::now() - TimeDelta::days(days)),
+            delay: delay_ms.map(Duration::from_millis).or(self.delay),
+            ..self.clone()
+        }
+    }
+
+    /// With `DELETE_ACCOUNT_DATA`, a copy of this config with every age gate,
+    /// filter and keep rule lifted, so a sweep deletes all it can reach.
+    /// Applied last, after per-platform settings, so none of them can bring a
+    /// rule back. Otherwise this config as is.
+    fn for_account_data(self) -> Self {
+        if !self.delete_account_data {
+            return self;
+        }
+        Self {
+            cutoff: DateTime::<Utc>::MAX_UTC,
+            delete_pinned: true,
+            delete_reposts: true,
+            purge_all_reposts: true,
+            delete_likes: true,
+            // A summary status would be the one post left behind
+            post_summary: false,
+            media_filter: MediaFilter::All,
+            delete_text_prefix: None,
+            delete_plain_text_only: false,
+            keep_bookmarked: false,
+            keep_shared_blobs: false,
+            keep_one_per_month: false,
+            keep_curated: false,
+            keep_self_likes: false,
+            import_boundary: None,
+            keep_if_has_replies: false,
+            keep_sample: None,
+            keep_edited_since: None,
+            keep_keywords: Vec::new(),
+            delete_languages: Vec::new(),
+            keep_languages: Vec::new(),
+            delete_from_apps: Vec::new(),
+            collection_keep: HashMap::new(),
+            redact: false,
+            // Earlier runs' watermarks and checkpoints would skip records
+            incremental_state_file: None,
+            phase_state_file: None,
+            ..self
+        }
+    }
+
+    /// Copy of this config with a platform's own `DELETE_REPOSTS`/`DELETE_LIKES`
+    /// applied, e.g. `MASTODON_DELETE_LIKES`.
+    fn with_delete_flags(&self, reposts: Option<bool>, likes: Option<bool>) -> Self {
+        Self {
+            delete_reposts: reposts.unwrap_or(self.delete_reposts),
+            delete_likes: likes.unwrap_or(self.delete_likes),
+            ..self.clone()
+        }
+    }
+
+    /// Copy of this config with a `PLATFORM_OVERRIDES` entry applied on top.
+    fn with_override(&self, o: Option<&PlatformOverride>) -> Self {
+        let Some(o) = o else {
+            return self.clone();
+        };
+        Self {
+            // DRY_RUN_GROUPED always means a dry run
+            dry_run: self.dry_run_grouped || o.dry_run.unwrap_or(self.dry_run),
+            delete_pinned: o.delete_pinned.unwrap_or(self.delete_pinned),
+            delete_reposts: o.delete_reposts.unwrap_or(self.delete_reposts),
+            delete_likes: o.delete_likes.unwrap_or(self.delete_likes),
+            ..self.for_platform(o.retention_days, o.delay_ms)
+        }
+    }
+
+    /// Whether a post in `language` may be deleted under `DELETE_LANGUAGES`
+    /// and `KEEP_LANGUAGES`. Posts without a language use `DEFAULT_LANGUAGE`.
+    pub fn allows_language(&self, language: Option<&str>) -> bool {
+        let language = language.or(self.default_language.as_deref());
+        let listed = |languages: &[String]| {
+            language.is_some_and(|lang| languages.iter().any(|l| l.eq_ignore_ascii_case(lang)))
+        };
+        if listed(&self.keep_languages) {
+            return false;
+        }
+        self.delete_languages.is_empty() || listed(&self.delete_languages)
+    }
+}
+
+/// A partial config for one platform from `PLATFORM_OVERRIDES`, e.g.
+/// `{"bluesky": {"retention_days": 30, "delete_likes": false}}`.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct PlatformOverride {
+    retention_days: Option<i64>,
+    dry_run: Option<bool>,
+    delete_pinned: Option<bool>,
+    delete_reposts: Option<bool>,
+    delete_likes: Option<bool>,
+    delay_ms: Option<u64>,
+}
+
+fn platform_overrides(
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<HashMap<String, PlatformOverride>> {
+    let Some(json) = lookup("PLATFORM_OVERRIDES") else {
+        return Ok(HashMap::new());
+    };
+    let overrides: HashMap<String, PlatformOverride> =
+        serde_json::from_str(&json).context("Failed to parse PLATFORM_OVERRIDES")?;
+    for (platform, o) in &overrides {
+        if platform != "bluesky" && platform != "mastodon" {
+            warn!("Ignoring PLATFORM_OVERRIDES for unknown platform {platform:?}");
+        } else if o.delete_pinned == Some(true) {
+            confirm_delete_pinned(lookup, "PLATFORM_OVERRIDES delete_pinned")?;
+        }
+    }
+    Ok(overrides)
+}
+
+/// Deleting pinned posts has to be confirmed with `CONFIRM_DELETE_PINNED`, so
+/// turning on `DELETE_PINNED` for a history that used to keep them is never
+/// a silent flip.
+fn confirm_delete_pinned(lookup: &dyn Fn(&str) -> Option<String>, setting: &str) -> Result<()> {
+    if !flag(lookup, "CONFIRM_DELETE_PINNED").unwrap_or(false) {
+        bail!("{setting} deletes pinned posts you may still consider important, set CONFIRM_DELETE_PINNED=true to use it");
+    }
+    Ok(())
+}
+
+/// Every platform, in the default `PLATFORM_ORDER`.
+const PLATFORMS: [&str; 2] = ["bluesky", "mastodon"];
+
+/// `PLATFORM_ORDER`, e.g. `mastodon,bluesky`: the order platforms are started
+/// and reported in. Platforms left out follow in the default order.
+fn platform_order(
+    lookup: &dyn Fn(&str) -> Option<String>,
+    file: &FileConfig,
+) -> Result<Vec<&'static str>> {
+    let listed = list(lookup, "PLATFORM_ORDER")
+        .or_else(|| file.platform_order.clone())
+        .unwrap_or_default();
+    let mut order = Vec::new();
+    for name in &listed {
+        let Some(platform) = PLATFORMS.into_iter().find(|p| p.eq_ignore_ascii_case(name)) else {
+            bail!("Unknown platform {name:?} in PLATFORM_ORDER, expected bluesky or mastodon");
+        };
+        if order.contains(&platform) {
+            bail!("PLATFORM_ORDER lists {platform} more than once");
+        }
+        order.push(platform);
+    }
+    order.extend(
+        PLATFORMS
+            .into_iter()
+            .filter(|p| !order.contains(p))
+            .collect::<Vec<_>>(),
+    );
+    Ok(order)
+}
+
+fn flag(lookup: &dyn Fn(&str) -> Option<String>, name: &str) -> Option<bool> {
+    lookup(name).map(|v| v == "true" || v == "1")
+}
+
+/// Comma-separated values, e.g. `DELETE_LANGUAGES=de,fr`.
+fn list(lookup: &dyn Fn(&str) -> Option<String>, name: &str) -> Option<Vec<String>> {
+    lookup(name).map(|v| {
+        v.split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect()
+    })
+}
+
+/// An RFC 3339 timestamp, or a `YYYY-MM-DD` date meaning midnight UTC.
+pub fn parse_instant(s: &str) -> Result<DateTime<Utc>> {
+    let s = s.trim();
+    parse_timestamp(s)
+        .map(|t| t.with_timezone(&Utc))
+        .or_else(|_| {
+            chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .map(|date| date.and_time(chrono::NaiveTime::MIN).and_utc())
+        })
+        .with_context(|| format!("Expected a timestamp or YYYY-MM-DD date, got {s:?}"))
+}
+
+/// A human retention period like `180d`, `12w`, `6mo` or `1y`. Months and
+/// years are approximate: 30 and 365 days.
+fn parse_retention(s: &str) -> Result<TimeDelta> {
+    let s = s.trim();
+    let (count, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+    let invalid = || format!("Invalid RETENTION {s:?}, expected e.g. 180d, 12w, 6mo or 1y");
+    let count: i64 = count.parse().with_context(invalid)?;
+    let days = match unit {
+        "d" => 1,
+        "w" => 7,
+        "mo" => 30,
+        "y" => 365,
+        _ => bail!("Invalid RETENTION unit {unit:?} in {s:?}, expected d, w, mo or y"),
+    };
+    match count.checked_mul(days).and_then(TimeDelta::try_days) {
+        Some(retention) => Ok(retention),
+        None => bail!(invalid()),
+    }
+}
+
+/// `DISPLAY_TZ`, needed before the rest of the config to set up logging.
+fn display_tz(lookup: &dyn Fn(&str) -> Option<String>, file: &FileConfig) -> Result<DisplayTz> {
+    match lookup("DISPLAY_TZ").or_else(|| file.display_tz.clone()) {
+        Some(v) => v.parse(),
+        None => Ok(DisplayTz::default()),
+    }
+}
+
+fn parse_var<T: FromStr>(lookup: &dyn Fn(&str) -> Option<String>, name: &str) -> Option<T> {
+    lookup(name).and_then(|v| v.parse().ok())
+}
+
+/// A platform's own retention: `RETENTION_DAYS_BLUESKY` or
+/// `RETENTION_DAYS_MASTODON`, then `retention_days` in its config file
+/// section. `None` leaves it on the global cutoff.
+fn platform_retention_days(
+    lookup: &dyn Fn(&str) -> Option<String>,
+    platform: &str,
+    file_days: Option<i64>,
+) -> Option<i64> {
+    parse_var(
+        lookup,
+        &format!("RETENTION_DAYS_{}", platform.to_ascii_uppercase()),
+    )
+    .or(file_days)
+}
+
+/// Restricts which posts are eligible for deletion by their attached media.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MediaFilter {
+    #[default]
+    All,
+    /// Only posts with a video embed. Bluesky only; likes and reposts are unaffected.
+    VideoOnly,
+}
+
+impl FromStr for MediaFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "all" => Ok(Self::All),
+            "video-only" => Ok(Self::VideoOnly),
+            _ => anyhow::bail!("Invalid MEDIA_FILTER {s:?}, expected \"all\" or \"video-only\""),
+        }
+    }
+}
+
+/// What a Bluesky sweep does when it hits a rate limit.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RateLimitMode {
+    /// Stop the sweep and leave the rest for the next run, like Mastodon.
+    #[default]
+    Defer,
+    /// Sleep until the limit resets, then carry on.
+    Wait,
+}
+
+impl FromStr for RateLimitMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "defer" => Ok(Self::Defer),
+            "wait" => Ok(Self::Wait),
+            _ => anyhow::bail!(
+                "Invalid BLUESKY_RATE_LIMIT_MODE {s:?}, expected \"defer\" or \"wait\""
+            ),
+        }
+    }
+}
+
+/// Per-platform totals returned by each module's `delete_old_posts`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PlatformSummary {
+    pub posts: u64,
+    pub reposts: u64,
+    pub likes: u64,
+    /// Records deleted from `BLUESKY_EXTRA_COLLECTIONS`, or Mastodon bookmarks
+    /// removed by `DELETE_ACCOUNT_DATA`.
+    pub other: u64,
+    /// Posts still on the platform after the sweep, or `None` if this run
+    /// skipped them, as a resumed Mastodon sweep does.
+    pub remaining: Option<Remaining>,
+    pub skipped: SkipCounts,
+}
+
+impl Default for PlatformSummary {
+    fn default() -> Self {
+        Self {
+            posts: 0,
+            reposts: 0,
+            likes: 0,
+            other: 0,
+            remaining: Some(Remaining::default()),
+            skipped: SkipCounts::default(),
+        }
+    }
+}
+
+/// Why a record was left in place rather than deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    Pinned,
+    Kept,
+    /// `createdAt` is ahead of the clock, usually client clock skew.
+    FutureDated,
+    /// The record has no `createdAt` at all.
+    MissingTimestamp,
+    /// The record's `createdAt` isn't a timestamp we can read.
+    UnparseableTimestamp,
+    /// Drawn into the share kept by `KEEP_SAMPLE_PERCENT`.
+    Sampled,
+    /// Its media is also used by another record, under `KEEP_SHARED_BLOBS`.
+    SharedBlob,
+    /// The newest deletable post of its month, under `KEEP_ONE_PER_MONTH`.
+    OnePerMonth,
+    /// A like of your own post, under `KEEP_SELF_LIKES`.
+    SelfLike,
+    /// Created before `IMPORT_BOUNDARY`.
+    Imported,
+}
+
+impl SkipReason {
+    pub const ALL: [SkipReason; 10] = [
+        SkipReason::Pinned,
+        SkipReason::Kept,
+        SkipReason::FutureDated,
+        SkipReason::MissingTimestamp,
+        SkipReason::UnparseableTimestamp,
+        SkipReason::Sampled,
+        SkipReason::SharedBlob,
+        SkipReason::OnePerMonth,
+        SkipReason::SelfLike,
+        SkipReason::Imported,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SkipReason::Pinned => "pinned",
+            SkipReason::Kept => "kept",
+            SkipReason::FutureDated => "future-dated",
+            SkipReason::MissingTimestamp => "missing timestamp",
+            SkipReason::UnparseableTimestamp => "unparseable timestamp",
+            SkipReason::Sampled => "sampled",
+            SkipReason::SharedBlob => "shared blob",
+            SkipReason::OnePerMonth => "one per month",
+            SkipReason::SelfLike => "self like",
+            SkipReason::Imported => "imported",
+        }
+    }
+}
+
+/// Which kind of keep list entry protected a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeptBy {
+    /// The record's rkey, or a Mastodon status ID.
+    Rkey,
+    /// The record's full AT URI.
+    Uri,
+    /// A `subject:` entry naming the liked or reposted post.
+    Subject,
+    /// An `author:` entry naming the liked or reposted post's author.
+    Author,
+    /// A `keep-after:` or `keep-before:` date.
+    Date,
+}
+
+impl KeptBy {
+    pub const ALL: [KeptBy; 5] = [
+        KeptBy::Rkey,
+        KeptBy::Uri,
+        KeptBy::Subject,
+        KeptBy::Author,
+        KeptBy::Date,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            KeptBy::Rkey => "rkey",
+            KeptBy::Uri => "uri",
+            KeptBy::Subject => "subject",
+            KeptBy::Author => "author",
+            KeptBy::Date => "date",
+        }
+    }
+}
+
+/// Breakdown of [`SkipCounts::kept`] by the kind of entry that matched.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct KeptCounts {
+    pub rkey: u64,
+    pub uri: u64,
+    pub subject: u64,
+    pub author: u64,
+    pub date: u64,
+}
+
+impl KeptCounts {
+    pub fn get(&self, by: KeptBy) -> u64 {
+        match by {
+            KeptBy::Rkey => self.rkey,
+            KeptBy::Uri => self.uri,
+            KeptBy::Subject => self.subject,
+            KeptBy::Author => self.author,
+            KeptBy::Date => self.date,
+        }
+    }
+
+    fn slot(&mut self, by: KeptBy) -> &mut u64 {
+        match by {
+            KeptBy::Rkey => &mut self.rkey,
+            KeptBy::Uri => &mut self.uri,
+            KeptBy::Subject => &mut self.subject,
+            KeptBy::Author => &mut self.author,
+            KeptBy::Date => &mut self.date,
+        }
+    }
+}
+
+/// Per-reason tallies of skipped records, one counter per [`SkipReason`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SkipCounts {
+    pub pinned: u64,
+    pub kept: u64,
+    pub kept_by: KeptCounts,
+    pub future_dated: u64,
+    pub missing_timestamp: u64,
+    pub unparseable_timestamp: u64,
+    pub sampled: u64,
+    pub shared_blob: u64,
+    pub one_per_month: u64,
+    pub self_like: u64,
+    pub imported: u64,
+}
+
+impl SkipCounts {
+    pub fn add(&mut self, reason: SkipReason) {
+        *self.slot(reason) += 1;
+    }
+
+    /// Count a record protected by the keep list, and which kind of entry did it.
+    pub fn add_kept(&mut self, by: KeptBy) {
+        self.add(SkipReason::Kept);
+        *self.kept_by.slot(by) += 1;
+    }
+
+    pub fn get(&self, reason: SkipReason) -> u64 {
+        match reason {
+            SkipReason::Pinned => self.pinned,
+            SkipReason::Kept => self.kept,
+            SkipReason::FutureDated => self.future_dated,
+            SkipReason::MissingTimestamp => self.missing_timestamp,
+            SkipReason::UnparseableTimestamp => self.unparseable_timestamp,
+            SkipReason::Sampled => self.sampled,
+            SkipReason::SharedBlob => self.shared_blob,
+            SkipReason::OnePerMonth => self.one_per_month,
+            SkipReason::SelfLike => self.self_like,
+            SkipReason::Imported => self.imported,
+        }
+    }
+
+    pub fn merge(&mut self, other: SkipCounts) {
+        for reason in SkipReason::ALL {
+            *self.slot(reason) += other.get(reason);
+        }
+        for by in KeptBy::ALL {
+            *self.kept_by.slot(by) += other.kept_by.get(by);
+        }
+    }
+
+    fn slot(&mut self, reason: SkipReason) -> &mut u64 {
+        match reason {
+            SkipReason::Pinned => &mut self.pinned,
+            SkipReason::Kept => &mut self.kept,
+            SkipReason::FutureDated => &mut self.future_dated,
+            SkipReason::MissingTimestamp => &mut self.missing_timestamp,
+            SkipReason::UnparseableTimestamp => &mut self.unparseable_timestamp,
+            SkipReason::Sampled => &mut self.sampled,
+            SkipReason::SharedBlob => &mut self.shared_blob,
+            SkipReason::OnePerMonth => &mut self.one_per_month,
+            SkipReason::SelfLike => &mut self.self_like,
+            SkipReason::Imported => &mut self.imported,
+        }
+    }
+
+    /// e.g. "1 skipped: pinned, 3 skipped: kept (2 by rkey, 1 by subject)",
+    /// omitting zero counts.
+    pub fn text(&self) -> String {
+        let parts: Vec<String> = SkipReason::ALL
+            .into_iter()
+            .filter(|&reason| self.get(reason) > 0)
+            .map(|reason| {
+                let text = format!("{} skipped: {}", self.get(reason), reason.label());
+                let by: Vec<String> = KeptBy::ALL
+                    .into_iter()
+                    .filter(|&by| reason == SkipReason::Kept && self.kept_by.get(by) > 0)
+                    .map(|by| format!("{} by {}", self.kept_by.get(by), by.label()))
+                    .collect();
+                if by.is_empty() {
+                    text
+                } else {
+                    format!("{text} ({})", by.join(", "))
+                }
+            })
+            .collect();
+        if parts.is_empty() {
+            "none skipped".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+/// Why a single post is or isn't deleted, from the same checks a sweep runs.
+/// Printed by `EXPLAIN_ID`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// Older than the cutoff and not protected by anything.
+    Delete,
+    /// Listed in `FORCE_DELETE_FILE`, so deleted whatever else applies.
+    ForceDelete,
+    /// A Bluesky post containing a `DELETE_BLOCKLIST_FILE` term.
+    Blocklisted,
+    NewerThanCutoff,
+    FutureDated,
+    MissingTimestamp,
+    UnparseableTimestamp,
+    MediaFiltered,
+    TextPrefixFiltered,
+    /// Has media or a link, so `DELETE_PLAIN_TEXT_ONLY` keeps it.
+    HasMediaOrLinks,
+    RepostsDisabled,
+    LikesDisabled,
+    LanguageFiltered,
+    /// A Mastodon post made with an application not in `DELETE_FROM_APPS`.
+    AppFiltered,
+    Pinned,
+    /// Protected by this keep list entry.
+    Kept(KeptBy, String),
+    Bookmarked,
+    KeepKeyword,
+    HasReplies,
+    /// Drawn into the share of old posts kept by `KEEP_SAMPLE_PERCENT`.
+    Sampled,
+    RecentlyEdited,
+    /// A Bluesky like of your own post, kept by `KEEP_SELF_LIKES`.
+    SelfLike,
+    /// A Bluesky post created before `IMPORT_BOUNDARY`.
+    Imported,
+}
+
+impl std::fmt::Display for Decision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Decision::Delete => write!(f, "older than the cutoff and not protected"),
+            Decision::ForceDelete => write!(f, "listed in FORCE_DELETE_FILE"),
+            Decision::Blocklisted => write!(f, "contains a DELETE_BLOCKLIST_FILE term"),
+            Decision::NewerThanCutoff => write!(f, "newer than the cutoff"),
+            Decision::FutureDated => write!(f, "created in the future, likely client clock skew"),
+            Decision::MissingTimestamp => write!(f, "no createdAt"),
+            Decision::UnparseableTimestamp => write!(f, "createdAt can't be parsed"),
+            Decision::MediaFiltered => write!(f, "excluded by MEDIA_FILTER"),
+            Decision::TextPrefixFiltered => write!(f, "doesn't start with DELETE_TEXT_PREFIX"),
+            Decision::HasMediaOrLinks => {
+                write!(f, "has media or links (DELETE_PLAIN_TEXT_ONLY)")
+            }
+            Decision::RepostsDisabled => write!(f, "reposts aren't deleted (DELETE_REPOSTS)"),
+            Decision::LikesDisabled => write!(f, "likes aren't deleted (DELETE_LIKES)"),
+            Decision::LanguageFiltered => {
+                write!(f, "excluded by DELETE_LANGUAGES/KEEP_LANGUAGES")
+            }
+            Decision::AppFiltered => write!(f, "not posted from a DELETE_FROM_APPS application"),
+            Decision::Pinned => write!(f, "pinned (DELETE_PINNED is off)"),
+            Decision::Kept(_, entry) => write!(f, "matched keep rule {entry}"),
+            Decision::Bookmarked => write!(f, "bookmarked (KEEP_BOOKMARKED)"),
+            Decision::KeepKeyword => write!(f, "contains a KEEP_KEYWORDS keyword"),
+            Decision::HasReplies => write!(f, "has replies (KEEP_IF_HAS_REPLIES)"),
+            Decision::Sampled => write!(f, "in the sample kept by KEEP_SAMPLE_PERCENT"),
+            Decision::RecentlyEdited => write!(f, "edited within KEEP_RECENTLY_EDITED_DAYS"),
+            Decision::SelfLike => write!(f, "a like of your own post (KEEP_SELF_LIKES)"),
+            Decision::Imported => write!(f, "imported, created before IMPORT_BOUNDARY"),
+        }
+    }
+}
+
+impl Decision {
+    /// e.g. "would be kept: matched keep rule bluesky:3k2la5diqyc2x"
+    pub fn explain(&self) -> String {
+        match self {
+            Decision::Delete | Decision::ForceDelete | Decision::Blocklisted => {
+                format!("would be deleted: {self}")
+            }
+            _ => format!("would be kept: {self}"),
+        }
+    }
+}
+
+/// Result of a successful delete (or unlike) request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeleteOutcome {
+    Deleted,
+    /// The item was already gone (404), e.g. deleted in the app mid-run.
+    AlreadyGone,
+}
+
+/// Count and age of the posts left behind by a sweep, derived from the
+/// records seen along the way rather than a separate pass.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Remaining {
+    pub count: u64,
+    pub oldest: Option<DateTime<Utc>>,
+}
+
+impl Remaining {
+    /// Note a post created at `created_at` that survives this run.
+    pub fn observe(&mut self, created_at: DateTime<Utc>) {
+        self.count += 1;
+        self.oldest = Some(self.oldest.map_or(created_at, |t| t.min(created_at)));
+    }
+
+    pub fn merge(&mut self, other: Remaining) {
+        self.count += other.count;
+        self.oldest = match (self.oldest, other.oldest) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+    }
+}
+
+impl std::ops::AddAssign<&PlatformSummary> for PlatformSummary {
+    fn add_assign(&mut self, other: &PlatformSummary) {
+        self.merge(other);
+    }
+}
+
+impl PlatformSummary {
+    /// Add `other`'s counts to these, keeping the older of the two oldest
+    /// posts. Remaining posts stay unknown if either side's are.
+    pub fn merge(&mut self, other: &PlatformSummary) {
+        self.posts += other.posts;
+        self.reposts += other.reposts;
+        self.likes += other.likes;
+        self.other += other.other;
+        self.remaining = self.remaining.zip(other.remaining).map(|(mut a, b)| {
+            a.merge(b);
+            a
+        });
+        self.skipped.merge(other.skipped);
+    }
+
+    pub fn total(&self) -> u64 {
+        self.posts + self.reposts + self.likes + self.other
+    }
+
+    /// Text of the transparency status posted when `POST_SUMMARY_STATUS` is set.
+    pub fn status_text(&self, cutoff: DateTime<Utc>) -> String {
+        format!(
+            "Skyscraper cleaned up {} posts, {} reposts, and {} likes older than {}.",
+            self.posts,
+            self.reposts,
+            self.likes,
+            cutoff.format("%Y-%m-%d")
+        )
+    }
+
+    /// The platform's one-line digest, e.g. "deleted 3 posts, 0 reposts, 5 likes;
+    /// 1 skipped: pinned; after cleanup you have 12 posts, oldest now 2023-05-01."
+    pub fn summary_line(&self) -> String {
+        let other = if self.other > 0 {
+            format!(", {} other records", self.other)
+        } else {
+            String::new()
+        };
+        format!(
+            "deleted {} posts, {} reposts, {} likes{other}; {}; {}",
+            self.posts,
+            self.reposts,
+            self.likes,
+            self.skipped.text(),
+            self.remaining_text()
+        )
+    }
+
+    /// e.g. "after cleanup you have 12 posts, oldest now 2023-05-01."
+    pub fn remaining_text(&self) -> String {
+        match self.remaining {
+            Some(Remaining {
+                count,
+                oldest: Some(oldest),
+            }) => format!(
+                "after cleanup you have {count} posts, oldest now {}.",
+                oldest.format("%Y-%m-%d")
+            ),
+            Some(_) => "after cleanup you have no posts left.".to_string(),
+            None => "remaining posts were counted by an earlier run.".to_string(),
+        }
+    }
+}
+
+/// Would-delete items buffered by `DRY_RUN_GROUPED` so they can be printed
+/// by month instead of as one long stream.
+#[derive(Debug, Default)]
+pub struct GroupedPlan {
+    items: Vec<(DateTime<Utc>, String)>,
+}
+
+impl GroupedPlan {
+    pub fn add(&mut self, created_at: DateTime<Utc>, description: String) {
+        self.items.push((created_at, description));
+    }
+
+    pub fn extend(&mut self, other: GroupedPlan) {
+        self.items.extend(other.items);
+    }
+
+    /// Items bucketed by `YYYY-MM`, oldest month first and oldest item first.
+    pub fn months(&self) -> BTreeMap<String, Vec<&str>> {
+        let mut items: Vec<_> = self.items.iter().collect();
+        items.sort_by_key(|(t, _)| *t);
+        let mut months: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+        for (t, description) in items {
+            months
+                .entry(t.format("%Y-%m").to_string())
+                .or_default()
+                .push(description);
+        }
+        months
+    }
+
+    /// Log lines: a header with the count per month, its items, then the total.
+    pub fn render(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for (month, items) in self.months() {
+            lines.push(format!("{month} ({} items)", items.len()));
+            lines.extend(items.iter().map(|item| format!("  {item}")));
+        }
+        lines.push(format!("Total: {} items", self.items.len()));
+        lines
+    }
+
+    pub fn log(&self, platform: &str) {
+        info!("[DRY RUN] {platform} would delete, by month:");
+        for line in self.render() {
+            info!("[DRY RUN] {line}");
+        }
+    }
+}
+
+/// The HTTP client every platform's requests go through.
+pub fn http_client(user_agent: &str) -> reqwest::Client {
+    reqwest::Client::builder()
+        .user_agent(user_agent)
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
+/// Pause before the one retry of a login that failed transiently.
+const LOGIN_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Whether `e` is worth another try: a 5xx, or a request that never got a
+/// response. Auth failures (401/403) and other 4xx are final.
+pub fn is_transient(e: &anyhow::Error) -> bool {
+    e.chain()
+        .filter_map(|cause| cause.downcast_ref::<reqwest::Error>())
+        .any(|e| match e.status() {
+            Some(status) => status.is_server_error(),
+            None => e.is_connect() || e.is_timeout() || e.is_request(),
+        })
+}
+
+/// Run a platform's initial login, trying once more after a short pause if
+/// it fails transiently, so a network blip doesn't end the platform's run.
+pub async fn retry_login<T, F>(
+    platform: &str,
+    shutdown: &Shutdown,
+    attempt: impl Fn() -> F,
+) -> Result<T>
+where
+    F: std::future::Future<Output = Result<T>>,
+{
+    match attempt().await {
+        Err(e) if is_transient(&e) => {
+            warn!(
+                "{platform} login failed, retrying once in {}s: {e:#}",
+                LOGIN_RETRY_DELAY.as_secs()
+            );
+            if !shutdown.sleep(LOGIN_RETRY_DELAY).await {
+                return Err(e);
+            }
+            attempt().await
+        }
+        result => result,
+    }
 }
 
 /// Parse an ISO 8601 / RFC 3339 timestamp, tolerating the `+0000` offset
@@ -25,112 +1292,1027 @@ pub fn parse_timestamp(s: &str) -> Result<DateTime<FixedOffset>> {
         .with_context(|| format!("Failed to parse timestamp: {s}"))
 }
 
-pub fn is_protected(keep_list: &HashSet<String>, platform: &str, id: &str) -> bool {
-    keep_list.contains(&format!("{platform}:{id}")) || keep_list.contains(id)
+/// Hook payload for one platform's run.
+fn platform_payload(
+    platform: &str,
+    config: &Config,
+    summary: &PlatformSummary,
+) -> serde_json::Value {
+    serde_json::json!({
+        "platform": platform,
+        "dry_run": config.dry_run,
+        "cutoff": config.cutoff,
+        "summary": summary,
+    })
+}
+
+/// One platform's finished sweep: its summary and hook payload, or the error
+/// it stopped with.
+type PlatformResult = Result<(PlatformSummary, serde_json::Value)>;
+
+/// How one configured platform's sweep ended.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum PlatformStatus {
+    Succeeded,
+    Failed { error: String },
+}
+
+/// Every platform's results merged together for the end of the run, in the
+/// two forms it's reported in: the total log line and `POST_ALL_HOOK`'s JSON.
+#[derive(Debug, Default)]
+pub struct RunSummary {
+    /// Each finished platform's hook payload, by platform.
+    pub platforms: serde_json::Map<String, serde_json::Value>,
+    /// How each configured platform ended, failed ones included.
+    pub statuses: BTreeMap<String, PlatformStatus>,
+    /// Every finished platform's summary added together.
+    pub total: PlatformSummary,
+    /// Deleted by platforms that failed partway and so never returned a
+    /// summary. What they deleted is gone all the same.
+    pub partial: u64,
+}
+
+impl RunSummary {
+    /// Fold in `platform`'s result, `None` if it wasn't configured.
+    pub fn merge(&mut self, platform: &str, result: Option<PlatformResult>) {
+        let status = match result {
+            Some(Ok((summary, payload))) => {
+                self.total += &summary;
+                self.platforms.insert(platform.into(), payload);
+                PlatformStatus::Succeeded
+            }
+            Some(Err(e)) => PlatformStatus::Failed {
+                error: format!("{e:#}"),
+            },
+            None => return,
+        };
+        self.statuses.insert(platform.into(), status);
+    }
+
+    pub fn had_errors(&self) -> bool {
+        self.statuses
+            .values()
+            .any(|status| matches!(status, PlatformStatus::Failed { .. }))
+    }
+
+    /// Names of the platforms that ended as `Failed`, in order.
+    pub fn failed(&self) -> Vec<&str> {
+        self.statuses
+            .iter()
+            .filter(|(_, status)| matches!(status, PlatformStatus::Failed { .. }))
+            .map(|(platform, _)| platform.as_str())
+            .collect()
+    }
+
+    /// 0 if every platform succeeded, 1 if every one failed, and 2 if only
+    /// some did, so a scheduler can tell a partial run from an outage.
+    pub fn exit_code(&self) -> i32 {
+        match self.failed().len() {
+            0 => 0,
+            n if n == self.statuses.len() => 1,
+            _ => 2,
+        }
+    }
+
+    pub fn deleted(&self) -> u64 {
+        self.total.total() + self.partial
+    }
+
+    /// e.g. "Total deleted: 12 across 2 platforms (dry-run: false)", with
+    /// what failed platforms deleted before failing called out.
+    pub fn line(&self, dry_run: bool) -> String {
+        let failed = self.failed();
+        let mut count = self.platforms.len();
+        let mut partial = String::new();
+        if self.partial > 0 {
+            count += failed.len();
+            partial = format!(
+                ", {} of them before {} failed",
+                self.partial,
+                failed.join(" and ")
+            );
+        }
+        let platforms = if count == 1 { "platform" } else { "platforms" };
+        format!(
+            "Total deleted: {} across {count} {platforms}{partial} (dry-run: {dry_run})",
+            self.deleted()
+        )
+    }
+
+    /// `POST_ALL_HOOK`'s payload: every platform's payload, their combined
+    /// summary, and how each platform ended.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "platforms": self.platforms,
+            "statuses": self.statuses,
+            "total": self.total,
+            "had_errors": self.had_errors(),
+        })
+    }
+}
+
+/// Run the Bluesky and Mastodon sweeps concurrently, since they share
+/// nothing but the keep list. The first platform in `order` is started
+/// first, and results are merged in that order.
+async fn run_platforms(
+    order: &[&str],
+    bluesky: impl std::future::Future<Output = Option<PlatformResult>>,
+    mastodon: impl std::future::Future<Output = Option<PlatformResult>>,
+) -> RunSummary {
+    // join! polls its futures in the order given on the first pass
+    let (bluesky, mastodon) = if order.first() == Some(&"mastodon") {
+        let (mastodon, bluesky) = tokio::join!(mastodon, bluesky);
+        (bluesky, mastodon)
+    } else {
+        tokio::join!(bluesky, mastodon)
+    };
+    let mut finished = HashMap::from([("bluesky", bluesky), ("mastodon", mastodon)]);
+    let mut results = RunSummary::default();
+    for platform in order {
+        results.merge(platform, finished.remove(platform).flatten());
+    }
+    results
+}
+
+/// Log a finished platform's summary line or error, and run `POST_RUN_HOOK`
+/// with its payload.
+async fn finish_platform(
+    platform: &str,
+    label: &str,
+    config: &Config,
+    result: Result<PlatformSummary>,
+) -> PlatformResult {
+    match result {
+        Ok(summary) => {
+            info!(target: SUMMARY_TARGET, "{label}: {}", summary.summary_line());
+            let payload = platform_payload(platform, config, &summary);
+            run_hook(config.post_run_hook.as_deref(), payload.clone()).await;
+            Ok((summary, payload))
+        }
+        Err(e) => {
+            error!("{label} error: {e:#}");
+            Err(e)
+        }
+    }
+}
+
+/// Run an optional hook, downgrading any failure to a warning.
+async fn run_hook(command: Option<&str>, payload: serde_json::Value) {
+    if let Some(command) = command {
+        if let Err(e) = hook::run(command, &payload).await {
+            warn!("{e:#}");
+        }
+    }
+}
+
+/// Trigger `shutdown` on the first Ctrl-C so the run stops after the current
+/// request; exit immediately on the second.
+async fn watch_for_ctrl_c(shutdown: Shutdown) {
+    if tokio::signal::ctrl_c().await.is_err() {
+        return;
+    }
+    warn!("Interrupted, stopping after the current request. Press Ctrl-C again to exit now.");
+    shutdown.trigger();
+    if tokio::signal::ctrl_c().await.is_ok() {
+        std::process::exit(130);
+    }
+}
+
+/// Bluesky login from env, the config file or the keyring.
+struct BlueskyAccount {
+    identifier: String,
+    password: String,
+    pds: String,
+}
+
+/// The Bluesky account to clean up, or `None` if credentials aren't set.
+fn bluesky_account(
+    lookup: &dyn Fn(&str) -> Option<String>,
+    file: &FileConfig,
+) -> Result<Option<BlueskyAccount>> {
+    bluesky_account_from(lookup, file, &OsKeyring)
+}
+
+/// [`bluesky_account`], reading the app password from `keyring` when
+/// `CREDENTIAL_SOURCE=keyring`.
+fn bluesky_account_from(
+    lookup: &dyn Fn(&str) -> Option<String>,
+    file: &FileConfig,
+    keyring: &dyn SecretStore,
+) -> Result<Option<BlueskyAccount>> {
+    let Some(identifier) = lookup("BLUESKY_IDENTIFIER").or_else(|| file.bluesky.identifier.clone())
+    else {
+        return Ok(None);
+    };
+    let password = match credential_source(lookup, file)? {
+        CredentialSource::Env => match lookup("BLUESKY_APP_PASSWORD") {
+            Some(password) => Some(password),
+            None => file.bluesky.app_password()?,
+        },
+        CredentialSource::Keyring => {
+            let service = keyring_service(lookup, file);
+            let password = keyring.get(&service, &identifier)?;
+            if password.is_none() {
+                bail!("No Bluesky app password in the keyring for {service}/{identifier}, store one with KEYRING_STORE=true");
+            }
+            password
+        }
+    };
+    let Some(password) = password else {
+        return Ok(None);
+    };
+    let pds = lookup("BLUESKY_PDS_HOST")
+        .or_else(|| file.bluesky.pds_host.clone())
+        .unwrap_or_else(|| "https://bsky.social".into());
+    Ok(Some(BlueskyAccount {
+        identifier,
+        password,
+        pds,
+    }))
+}
+
+fn credential_source(
+    lookup: &dyn Fn(&str) -> Option<String>,
+    file: &FileConfig,
+) -> Result<CredentialSource> {
+    Ok(match lookup("CREDENTIAL_SOURCE") {
+        Some(v) => v.parse()?,
+        None => file.bluesky.credential_source.unwrap_or_default(),
+    })
+}
+
+fn keyring_service(lookup: &dyn Fn(&str) -> Option<String>, file: &FileConfig) -> String {
+    lookup("KEYRING_SERVICE")
+        .or_else(|| file.bluesky.keyring_service.clone())
+        .unwrap_or_else(|| credentials::DEFAULT_SERVICE.into())
+}
+
+/// `KEYRING_STORE` mode: save the Bluesky app password to the keyring under
+/// `BLUESKY_IDENTIFIER`, for later runs with `CREDENTIAL_SOURCE=keyring`. The
+/// password is `BLUESKY_APP_PASSWORD` (or the config file's) if set, otherwise
+/// the first line of `input`.
+fn store_keyring_password(
+    lookup: &dyn Fn(&str) -> Option<String>,
+    file: &FileConfig,
+    keyring: &dyn SecretStore,
+    input: impl std::io::BufRead,
+) -> Result<()> {
+    let Some(identifier) = lookup("BLUESKY_IDENTIFIER").or_else(|| file.bluesky.identifier.clone())
+    else {
+        bail!("KEYRING_STORE needs BLUESKY_IDENTIFIER to store the password under");
+    };
+    let password = match lookup("BLUESKY_APP_PASSWORD") {
+        Some(password) => password,
+        None => match file.bluesky.app_password()? {
+            Some(password) => password,
+            None => {
+                info!("Enter the Bluesky app password for {identifier}:");
+                input
+                    .lines()
+                    .next()
+                    .transpose()
+                    .context("Failed to read the app password")?
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string()
+            }
+        },
+    };
+    if password.is_empty() {
+        bail!("No Bluesky app password to store");
+    }
+    let service = keyring_service(lookup, file);
+    keyring.set(&service, &identifier, &password)?;
+    info!("Stored the Bluesky app password for {identifier} in the keyring ({service})");
+    Ok(())
+}
+
+/// Mastodon login from env or the config file.
+struct MastodonAccount {
+    instance: String,
+    token: String,
+}
+
+/// The Mastodon account to clean up, or `None` if credentials aren't set.
+fn mastodon_account(
+    lookup: &dyn Fn(&str) -> Option<String>,
+    file: &FileConfig,
+) -> Result<Option<MastodonAccount>> {
+    let token = match lookup("MASTODON_ACCESS_TOKEN") {
+        Some(token) => Some(token),
+        None => file.mastodon.access_token()?,
+    };
+    let (Some(instance), Some(token)) = (
+        lookup("MASTODON_INSTANCE_URL").or_else(|| file.mastodon.instance_url.clone()),
+        token,
+    ) else {
+        return Ok(None);
+    };
+    Ok(Some(MastodonAccount { instance, token }))
+}
+
+/// `EXPLAIN_ID` mode: decide a single `bluesky:` or `mastodon:` post and print why.
+async fn explain(
+    lookup: &dyn Fn(&str) -> Option<String>,
+    file: &FileConfig,
+    config: &Config,
+    overrides: &HashMap<String, PlatformOverride>,
+    keep_list: &KeepList,
+    id: &str,
+) -> Result<()> {
+    let decision = if let Some(id) = id.strip_prefix("bluesky:") {
+        let Some(account) = bluesky_account(lookup, file)? else {
+            bail!("EXPLAIN_ID for Bluesky needs Bluesky credentials");
+        };
+        let bluesky_config = config
+            .for_platform(
+                platform_retention_days(lookup, "bluesky", file.bluesky.retention_days),
+                None,
+            )
+            .with_override(overrides.get("bluesky"));
+        let client =
+            bluesky::HttpBlueskyClient::new(&account.pds).with_user_agent(&config.user_agent);
+        bluesky::explain(
+            &client,
+            &account.identifier,
+            &account.password,
+            id,
+            &bluesky_config,
+            keep_list,
+        )
+        .await?
+    } else if let Some(id) = id.strip_prefix("mastodon:") {
+        let Some(account) = mastodon_account(lookup, file)? else {
+            bail!("EXPLAIN_ID for Mastodon needs Mastodon credentials");
+        };
+        let mastodon_config = config
+            .for_platform(
+                platform_retention_days(lookup, "mastodon", file.mastodon.retention_days),
+                None,
+            )
+            .with_delete_flags(
+                flag(lookup, "MASTODON_DELETE_REPOSTS").or(file.mastodon.delete_reposts),
+                flag(lookup, "MASTODON_DELETE_LIKES").or(file.mastodon.delete_likes),
+            )
+            .with_override(overrides.get("mastodon"));
+        let client = mastodon::HttpMastodonClient::new(&account.instance, &account.token)
+            .with_user_agent(&config.user_agent)
+            .with_idempotency(config.mastodon_idempotency);
+        mastodon::explain(&client, id, &mastodon_config, keep_list).await?
+    } else {
+        bail!("EXPLAIN_ID must start with bluesky: or mastodon:, got {id:?}");
+    };
+    info!("{id} {}", decision.explain());
+    Ok(())
+}
+
+/// `PRUNE_KEEP_FILE` mode: look up each exact keep entry and rewrite the keep
+/// file with entries for records that no longer exist commented out or removed.
+async fn prune_keep_file(
+    lookup: &dyn Fn(&str) -> Option<String>,
+    file: &FileConfig,
+    config: &Config,
+    path: &Path,
+    mode: keep::PruneMode,
+) -> Result<()> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read keep file {}", path.display()))?;
+    if keep::is_json(path, &contents) {
+        bail!("PRUNE_KEEP_FILE only rewrites line-based keep files, not JSON");
+    }
+    let targets = keep::checkable(&contents);
+    let ids_on = |platform: &str| -> Vec<&str> {
+        targets
+            .iter()
+            .filter(|(_, p, _)| *p == platform)
+            .map(|(_, _, id)| *id)
+            .collect()
+    };
+
+    let mut missing = HashMap::new();
+    match bluesky_account(lookup, file)? {
+        Some(account) => {
+            let client =
+                bluesky::HttpBlueskyClient::new(&account.pds).with_user_agent(&config.user_agent);
+            let gone = bluesky::missing_records(
+                &client,
+                &account.identifier,
+                &account.password,
+                &ids_on("bluesky"),
+                config,
+            )
+            .await?;
+            missing.insert("bluesky", gone);
+        }
+        None => warn!("Bluesky credentials not set, leaving entries that could be Bluesky posts"),
+    }
+    match mastodon_account(lookup, file)? {
+        Some(account) => {
+            let client = mastodon::HttpMastodonClient::new(&account.instance, &account.token)
+                .with_user_agent(&config.user_agent);
+            let gone = mastodon::missing_statuses(&client, &ids_on("mastodon")).await?;
+            missing.insert("mastodon", gone);
+        }
+        None => {
+            warn!("Mastodon credentials not set, leaving entries that could be Mastodon posts")
+        }
+    }
+
+    let mut dead = keep::dead_entries(&contents, &missing);
+    let expired = keep::expired_entries(&contents, config.display_tz);
+    dead.extend(&expired);
+    if dead.is_empty() {
+        info!("Every checkable keep entry still matches a post");
+        return Ok(());
+    }
+    for entry in contents.lines().map(str::trim).filter(|e| dead.contains(e)) {
+        if expired.contains(entry) {
+            info!("Keep entry has expired: {entry}");
+        } else {
+            info!("Keep entry's post no longer exists: {entry}");
+        }
+    }
+    if config.dry_run {
+        info!(
+            "[DRY RUN] Would prune {} entries from {}",
+            dead.len(),
+            path.display()
+        );
+        return Ok(());
+    }
+    std::fs::write(path, keep::prune(&contents, &dead, mode))
+        .with_context(|| format!("Failed to write keep file {}", path.display()))?;
+    info!("Pruned {} entries from {}", dead.len(), path.display());
+    Ok(())
+}
+
+/// `DELETE_FROM_EXPORT` mode: delete exactly the Mastodon statuses listed in
+/// an export instead of sweeping by age.
+async fn delete_from_export(
+    lookup: &dyn Fn(&str) -> Option<String>,
+    file: &FileConfig,
+    config: &Config,
+    overrides: &HashMap<String, PlatformOverride>,
+    keep_list: &KeepList,
+    path: &Path,
+) -> Result<()> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read DELETE_FROM_EXPORT {}", path.display()))?;
+    let ids = export::status_ids(&contents)?;
+    info!("Found {} status IDs in {}", ids.len(), path.display());
+    let Some(account) = mastodon_account(lookup, file)? else {
+        bail!("DELETE_FROM_EXPORT needs Mastodon credentials");
+    };
+    let mastodon_config = config
+        .for_platform(
+            platform_retention_days(lookup, "mastodon", file.mastodon.retention_days),
+            parse_var(lookup, "MASTODON_DELAY_MS").or(file.mastodon.delay_ms),
+        )
+        .with_delete_flags(
+            flag(lookup, "MASTODON_DELETE_REPOSTS").or(file.mastodon.delete_reposts),
+            flag(lookup, "MASTODON_DELETE_LIKES").or(file.mastodon.delete_likes),
+        )
+        .with_override(overrides.get("mastodon"));
+    let client = mastodon::HttpMastodonClient::new(&account.instance, &account.token)
+        .with_user_agent(&config.user_agent)
+        .with_idempotency(config.mastodon_idempotency);
+    let summary = mastodon::delete_listed(&client, &ids, &mastodon_config, keep_list).await?;
+    write_report(config);
+    info!(target: SUMMARY_TARGET, "Mastodon: {}", summary.summary_line());
+    Ok(())
+}
+
+/// Write `REPORT_FILE`, if set, once the deleting is done. A failed write is
+/// only logged, as the deletions it would list have already happened.
+fn write_report(config: &Config) {
+    if let Some(report) = &config.report {
+        match report.write(config.dry_run, Utc::now()) {
+            Ok(entries) => info!(
+                "Wrote {entries} removed records to {}",
+                report.path().display()
+            ),
+            Err(e) => warn!("{e:#}"),
+        }
+    }
+}
+
+/// `STATS_JSON` mode: write per-platform counts and an age histogram to
+/// `path` without deleting anything.
+async fn stats_json(
+    lookup: &dyn Fn(&str) -> Option<String>,
+    file: &FileConfig,
+    config: &Config,
+    overrides: &HashMap<String, PlatformOverride>,
+    keep_list: &KeepList,
+    path: &Path,
+) -> Result<()> {
+    let mut platforms = BTreeMap::new();
+    match bluesky_account(lookup, file)? {
+        Some(account) => {
+            let bluesky_config = config
+                .for_platform(
+                    platform_retention_days(lookup, "bluesky", file.bluesky.retention_days),
+                    None,
+                )
+                .with_override(overrides.get("bluesky"));
+            let client =
+                bluesky::HttpBlueskyClient::new(&account.pds).with_user_agent(&config.user_agent);
+            let stats = bluesky::stats(
+                &client,
+                &account.identifier,
+                &account.password,
+                &bluesky_config,
+                keep_list,
+            )
+            .await?;
+            platforms.insert("bluesky", stats);
+        }
+        None => warn!("Bluesky credentials not set, skipping"),
+    }
+    match mastodon_account(lookup, file)? {
+        Some(account) => {
+            let mastodon_config = config
+                .for_platform(
+                    platform_retention_days(lookup, "mastodon", file.mastodon.retention_days),
+                    None,
+                )
+                .with_delete_flags(
+                    flag(lookup, "MASTODON_DELETE_REPOSTS").or(file.mastodon.delete_reposts),
+                    flag(lookup, "MASTODON_DELETE_LIKES").or(file.mastodon.delete_likes),
+                )
+                .with_override(overrides.get("mastodon"));
+            let client = mastodon::HttpMastodonClient::new(&account.instance, &account.token)
+                .with_user_agent(&config.user_agent);
+            let stats = mastodon::stats(&client, &mastodon_config, keep_list).await?;
+            platforms.insert("mastodon", stats);
+        }
+        None => warn!("Mastodon credentials not set, skipping"),
+    }
+
+    for (platform, stats) in &platforms {
+        info!(
+            "{platform}: {} total, {} eligible for deletion, {} protected, {} undated",
+            stats.total, stats.eligible, stats.protected, stats.undated
+        );
+    }
+    stats::write(path, &platforms, Utc::now())?;
+    info!("Wrote stats to {}", path.display());
+    Ok(())
+}
+
+/// `FULL_PLAN_JSON` mode: write every record old enough to delete, with the
+/// action a run would take and the deciding reason, instead of cleaning up.
+async fn full_plan_json(
+    lookup: &dyn Fn(&str) -> Option<String>,
+    file: &FileConfig,
+    config: &Config,
+    overrides: &HashMap<String, PlatformOverride>,
+    keep_list: &KeepList,
+    path: &Path,
+) -> Result<()> {
+    let mut platforms = full_plan(lookup, file, config, overrides, keep_list).await?;
+    for (platform, entries) in &platforms {
+        let deletes = entries
+            .iter()
+            .filter(|entry| entry.action == full_plan::Action::Delete)
+            .count();
+        info!(
+            "{platform}: {} old records, {deletes} would be deleted, {} kept",
+            entries.len(),
+            entries.len() - deletes
+        );
+    }
+    full_plan::write(path, &mut platforms, Utc::now())?;
+    info!("Wrote full plan to {}", path.display());
+    Ok(())
+}
+
+/// Every configured platform's plan, as `FULL_PLAN_JSON` writes it.
+async fn full_plan(
+    lookup: &dyn Fn(&str) -> Option<String>,
+    file: &FileConfig,
+    config: &Config,
+    overrides: &HashMap<String, PlatformOverride>,
+    keep_list: &KeepList,
+) -> Result<BTreeMap<&'static str, Vec<full_plan::PlanEntry>>> {
+    let mut platforms = BTreeMap::new();
+    match bluesky_account(lookup, file)? {
+        Some(account) => {
+            let bluesky_config = config
+                .for_platform(
+                    platform_retention_days(lookup, "bluesky", file.bluesky.retention_days),
+                    None,
+                )
+                .with_override(overrides.get("bluesky"));
+            let client =
+                bluesky::HttpBlueskyClient::new(&account.pds).with_user_agent(&config.user_agent);
+            let entries = bluesky::full_plan(
+                &client,
+                &account.identifier,
+                &account.password,
+                &bluesky_config,
+                keep_list,
+            )
+            .await?;
+            platforms.insert("bluesky", entries);
+        }
+        None => warn!("Bluesky credentials not set, skipping"),
+    }
+    match mastodon_account(lookup, file)? {
+        Some(account) => {
+            let mastodon_config = config
+                .for_platform(
+                    platform_retention_days(lookup, "mastodon", file.mastodon.retention_days),
+                    None,
+                )
+                .with_delete_flags(
+                    flag(lookup, "MASTODON_DELETE_REPOSTS").or(file.mastodon.delete_reposts),
+                    flag(lookup, "MASTODON_DELETE_LIKES").or(file.mastodon.delete_likes),
+                )
+                .with_override(overrides.get("mastodon"));
+            let client = mastodon::HttpMastodonClient::new(&account.instance, &account.token)
+                .with_user_agent(&config.user_agent);
+            let entries = mastodon::full_plan(&client, &mastodon_config, keep_list).await?;
+            platforms.insert("mastodon", entries);
+        }
+        None => warn!("Mastodon credentials not set, skipping"),
+    }
+    Ok(platforms)
+}
+
+/// `RESTORE_DIR` mode: put archived Bluesky records back instead of cleaning up.
+async fn restore(
+    lookup: &dyn Fn(&str) -> Option<String>,
+    file: &FileConfig,
+    config: &Config,
+    dir: &Path,
+) -> Result<()> {
+    let Some(BlueskyAccount {
+        identifier,
+        password,
+        pds,
+    }) = bluesky_account(lookup, file)?
+    else {
+        bail!("RESTORE_DIR needs Bluesky credentials");
+    };
+    let confirmed = flag(lookup, "CONFIRM_RESTORE").unwrap_or(false);
+    let bluesky_config = config.for_platform(
+        platform_retention_days(lookup, "bluesky", file.bluesky.retention_days),
+        parse_var(lookup, "BLUESKY_DELAY_MS").or(file.bluesky.delay_ms),
+    );
+    info!("Restoring Bluesky records from {}", dir.display());
+    let client = bluesky::HttpBlueskyClient::new(&pds).with_user_agent(&config.user_agent);
+    bluesky::restore_archived(
+        &client,
+        &identifier,
+        &password,
+        dir,
+        &bluesky_config,
+        confirmed,
+    )
+    .await?;
+    Ok(())
+}
+
+/// `QUIET_SUMMARY` ignores `RUST_LOG` and keeps only errors and the
+/// per-platform summary lines, so cron output is a clean digest.
+fn logger(quiet: bool, tz: DisplayTz) -> env_logger::Builder {
+    let mut builder = if quiet {
+        let mut builder = env_logger::Builder::new();
+        builder
+            .filter_level(log::LevelFilter::Error)
+            .filter_module(SUMMARY_TARGET, log::LevelFilter::Info);
+        builder
+    } else {
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+    };
+    if tz != DisplayTz::Utc {
+        builder.format(move |buf, record| {
+            use std::io::Write;
+            writeln!(
+                buf,
+                "[{} {:<5} {}] {}",
+                tz.format(Utc::now()),
+                record.level(),
+                record.target(),
+                record.args()
+            )
+        });
+    }
+    builder
 }
 
-fn load_keep_list(path: &Path) -> HashSet<String> {
-    if !path.exists() {
-        info!("No keep file at {}, skipping", path.display());
-        return HashSet::new();
+#[tokio::main]
+async fn main() -> Result<()> {
+    let config_path = env::var("CONFIG_FILE").ok();
+    let file = match &config_path {
+        Some(path) => FileConfig::load(Path::new(path))?,
+        None => FileConfig::default(),
+    };
+    let lookup = |name: &str| env::var(name).ok();
+
+    let quiet = flag(&lookup, "QUIET_SUMMARY")
+        .or(file.quiet_summary)
+        .unwrap_or(false);
+    let tz = display_tz(&lookup, &file)?;
+    let log_path = lookup("LOG_FILE")
+        .map(PathBuf::from)
+        .or_else(|| file.log_file.clone())
+        .map(|base| log_file::path_for_run(&base, Utc::now()));
+    match &log_path {
+        Some(path) => log_file::TeeLogger {
+            console: logger(quiet, tz).build(),
+            file: logger(false, tz)
+                .target(env_logger::Target::Pipe(Box::new(log_file::create(path)?)))
+                .build(),
+        }
+        .init(),
+        None => logger(quiet, tz).init(),
+    }
+    if let Some(path) = &log_path {
+        info!("Logging to {}", path.display());
+    }
+    if let Some(path) = config_path {
+        info!("Loaded config from {path}");
+    }
+
+    let config = Config::resolve(&lookup, &file)?;
+    let overrides = platform_overrides(&lookup)?;
+    let order = platform_order(&lookup, &file)?;
+    let keep_file = lookup("KEEP_FILE")
+        .or_else(|| file.keep_file.clone())
+        .unwrap_or_else(|| "keep.txt".into());
+    let keep_list = if config.delete_account_data {
+        KeepList::default()
+    } else {
+        KeepList::load(Path::new(&keep_file), config.display_tz)
+    };
+
+    info!("Skyscraper - Social Media Post Cleanup");
+    if config.delete_account_data {
+        warn!("DELETE_ACCOUNT_DATA: deleting every post, repost, like, favourite and bookmark, ignoring the cutoff, keep file and filters");
+    }
+    info!("Cutoff date: {}", config.display_tz.format(config.cutoff));
+    info!("Dry run: {}", config.dry_run);
+    if config.dry_run_grouped {
+        info!("Dry run output grouped by month");
+    }
+    info!("Delete reposts: {}", config.delete_reposts);
+    if config.purge_all_reposts {
+        info!("Purge all reposts, whatever their age: true");
+    }
+    info!("Delete likes: {}", config.delete_likes);
+    info!("Delete pinned: {}", config.delete_pinned);
+    info!("Post summary status: {}", config.post_summary);
+    info!("Media filter: {:?}", config.media_filter);
+    if let Some(prefix) = &config.delete_text_prefix {
+        info!("Delete text prefix: {prefix:?}");
+    }
+    if config.delete_plain_text_only {
+        info!("Delete plain text posts only: true");
+    }
+    if config.delete_blocklist.is_some() {
+        info!("Delete blocklisted Bluesky posts, whatever their age: true");
+    }
+    if config.prefetch_pages {
+        info!("Prefetch Bluesky listing pages: true");
+    }
+    if config.prune_preferences {
+        info!("Prune dated Bluesky preference entries: true");
+    }
+    info!("Largest media first: {}", config.largest_media_first);
+    info!("Oldest first: {}", config.oldest_first);
+    info!("Keep bookmarked: {}", config.keep_bookmarked);
+    info!("Keep curated: {}", config.keep_curated);
+    if config.keep_self_likes {
+        info!("Keep likes of your own Bluesky posts: true");
+    }
+    if let Some(boundary) = config.import_boundary {
+        info!(
+            "Keep Bluesky posts imported before: {}",
+            config.display_tz.format(boundary)
+        );
+    }
+    info!("Keep shared blobs: {}", config.keep_shared_blobs);
+    if config.keep_one_per_month {
+        info!("Keep one post per month: true");
+    }
+    info!("Keep if has replies: {}", config.keep_if_has_replies);
+    if let Some(sample) = config.keep_sample {
+        info!(
+            "Keep sample: {}% of old posts (seed {})",
+            sample.percent, sample.seed
+        );
+    }
+    if let Some(since) = config.keep_edited_since {
+        info!("Keep posts edited since: {}", since.format("%Y-%m-%d"));
+    }
+    if !config.keep_keywords.is_empty() {
+        info!("Keep keywords: {}", config.keep_keywords.join(", "));
+    }
+    if !config.delete_languages.is_empty() {
+        info!("Delete languages: {}", config.delete_languages.join(", "));
+    }
+    if !config.keep_languages.is_empty() {
+        info!("Keep languages: {}", config.keep_languages.join(", "));
+    }
+    if !config.delete_from_apps.is_empty() {
+        info!("Delete from apps: {}", config.delete_from_apps.join(", "));
+    }
+    if let Some(path) = &config.rate_limit_state_file {
+        info!("Rate limit state file: {}", path.display());
+    }
+    if let Some(path) = &config.phase_state_file {
+        info!("Phase state file: {}", path.display());
+    }
+    if let Some(path) = &config.archive_dir {
+        info!("Archiving Bluesky records to: {}", path.display());
+    }
+    info!("Protected posts: {}", keep_list.len());
+    for (collection, keep) in &config.collection_keep {
+        info!("Also protected in {collection}: {}", keep.len());
     }
-    info!("Loading keep list from {}", path.display());
-    let Ok(contents) = fs::read_to_string(path) else {
-        warn!("Failed to read {}", path.display());
-        return HashSet::new();
-    };
-    contents
-        .lines()
-        .map(str::trim)
-        .filter(|l| !l.is_empty() && !l.starts_with('#'))
-        .map(String::from)
-        .collect()
-}
-
-#[tokio::main]
-async fn main() -> Result<()> {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
-
-    let retention_days: i64 = env::var("RETENTION_DAYS")
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(180);
 
-    let dry_run = env::var("DRY_RUN")
-        .map(|v| v == "true" || v == "1")
-        .unwrap_or(false);
+    tokio::spawn(watch_for_ctrl_c(config.shutdown.clone()));
 
-    let delete_pinned = env::var("DELETE_PINNED")
-        .map(|v| v == "true" || v == "1")
-        .unwrap_or(false);
+    if flag(&lookup, "KEYRING_STORE").unwrap_or(false) {
+        return store_keyring_password(&lookup, &file, &OsKeyring, std::io::stdin().lock());
+    }
+    if let Some(dir) = lookup("RESTORE_DIR") {
+        return restore(&lookup, &file, &config, Path::new(&dir)).await;
+    }
+    if let Some(dir) = lookup("ARCHIVE_INDEX_DIR") {
+        let listed = archive::write_index(Path::new(&dir), Utc::now())?;
+        info!("Wrote index.html and feed.xml listing {listed} archived records to {dir}");
+        return Ok(());
+    }
+    if let Some(mode) = lookup("PRUNE_KEEP_FILE") {
+        return prune_keep_file(
+            &lookup,
+            &file,
+            &config,
+            Path::new(&keep_file),
+            mode.parse()?,
+        )
+        .await;
+    }
+    if let Some(id) = lookup("EXPLAIN_ID") {
+        return explain(&lookup, &file, &config, &overrides, &keep_list, &id).await;
+    }
+    if let Some(path) = lookup("DELETE_FROM_EXPORT") {
+        let path = Path::new(&path);
+        return delete_from_export(&lookup, &file, &config, &overrides, &keep_list, path).await;
+    }
+    if let Some(path) = lookup("STATS_JSON") {
+        let path = Path::new(&path);
+        return stats_json(&lookup, &file, &config, &overrides, &keep_list, path).await;
+    }
+    if let Some(path) = lookup("FULL_PLAN_JSON") {
+        let path = Path::new(&path);
+        return full_plan_json(&lookup, &file, &config, &overrides, &keep_list, path).await;
+    }
+    // Both take a full pass over every platform before the run itself
+    let required_plan_hash = lookup("REQUIRE_PLAN_HASH").filter(|_| !config.dry_run);
+    if required_plan_hash.is_some() || (config.dry_run && flag(&lookup, "PLAN_HASH") == Some(true))
+    {
+        let plan = full_plan(&lookup, &file, &config, &overrides, &keep_list).await?;
+        match &required_plan_hash {
+            Some(required) => {
+                full_plan::check_hash(&plan, required)?;
+                info!("Plan matches REQUIRE_PLAN_HASH {required}");
+            }
+            None => info!(target: SUMMARY_TARGET, "PLAN_HASH={}", full_plan::hash(&plan)),
+        }
+    }
 
-    let delete_reposts = env::var("DELETE_REPOSTS")
-        .map(|v| v == "true" || v == "1")
-        .unwrap_or(true);
+    let bluesky_account = bluesky_account(&lookup, &file)?;
+    if bluesky_account.is_none() {
+        warn!("Bluesky credentials not set, skipping");
+    }
+    let mastodon_account = mastodon_account(&lookup, &file)?;
+    if mastodon_account.is_none() {
+        warn!("Mastodon credentials not set, skipping");
+    }
 
-    let delete_likes = env::var("DELETE_LIKES")
-        .map(|v| v == "true" || v == "1")
-        .unwrap_or(true);
+    // --- Bluesky ---
+    let bluesky = async {
+        let BlueskyAccount {
+            identifier,
+            password,
+            pds,
+        } = bluesky_account?;
+        let bluesky_config = config
+            .for_platform(
+                platform_retention_days(&lookup, "bluesky", file.bluesky.retention_days),
+                parse_var(&lookup, "BLUESKY_DELAY_MS").or(file.bluesky.delay_ms),
+            )
+            .with_override(overrides.get("bluesky"))
+            .for_account_data();
+        info!("Processing Bluesky account: {identifier}");
+        if bluesky_config.cutoff != config.cutoff && !config.delete_account_data {
+            info!(
+                "Bluesky cutoff date: {}",
+                config.display_tz.format(bluesky_config.cutoff)
+            );
+        }
+        let client = bluesky::HttpBlueskyClient::new(&pds).with_user_agent(&config.user_agent);
+        let result =
+            bluesky::delete_old_posts(&client, &identifier, &password, &bluesky_config, &keep_list)
+                .await;
+        Some(finish_platform("bluesky", "Bluesky", &bluesky_config, result).await)
+    };
 
-    let cutoff = Utc::now() - TimeDelta::days(retention_days);
-    let keep_file = env::var("KEEP_FILE").unwrap_or_else(|_| "keep.txt".into());
-    let keep_list = load_keep_list(Path::new(&keep_file));
+    // --- Mastodon ---
+    let mastodon = async {
+        let MastodonAccount { instance, token } = mastodon_account?;
+        let mastodon_config = config
+            .for_platform(
+                platform_retention_days(&lookup, "mastodon", file.mastodon.retention_days),
+                parse_var(&lookup, "MASTODON_DELAY_MS").or(file.mastodon.delay_ms),
+            )
+            .with_delete_flags(
+                flag(&lookup, "MASTODON_DELETE_REPOSTS").or(file.mastodon.delete_reposts),
+                flag(&lookup, "MASTODON_DELETE_LIKES").or(file.mastodon.delete_likes),
+            )
+            .with_override(overrides.get("mastodon"))
+            .for_account_data();
+        info!("Processing Mastodon instance: {instance}");
+        if mastodon_config.cutoff != config.cutoff && !config.delete_account_data {
+            info!(
+                "Mastodon cutoff date: {}",
+                config.display_tz.format(mastodon_config.cutoff)
+            );
+        }
+        if mastodon_config.delete_reposts != config.delete_reposts
+            || mastodon_config.delete_likes != config.delete_likes
+        {
+            info!(
+                "Mastodon delete reposts: {}, delete likes: {}",
+                mastodon_config.delete_reposts, mastodon_config.delete_likes
+            );
+        }
+        let client = mastodon::HttpMastodonClient::new(&instance, &token)
+            .with_user_agent(&config.user_agent)
+            .with_idempotency(config.mastodon_idempotency);
+        let result = mastodon::delete_old_posts(&client, &mastodon_config, &keep_list).await;
+        Some(finish_platform("mastodon", "Mastodon", &mastodon_config, result).await)
+    };
 
-    info!("Skyscraper - Social Media Post Cleanup");
-    info!("Cutoff date: {cutoff}");
-    info!("Dry run: {dry_run}");
-    info!("Delete reposts: {delete_reposts}");
-    info!("Delete likes: {delete_likes}");
-    info!("Delete pinned: {delete_pinned}");
-    info!("Protected posts: {}", keep_list.len());
+    let mut run = run_platforms(&order, bluesky, mastodon).await;
 
-    let config = Config {
-        cutoff,
-        dry_run,
-        delete_pinned,
-        delete_reposts,
-        delete_likes,
-    };
-    let mut had_errors = false;
+    run_hook(config.post_all_hook.as_deref(), run.to_json()).await;
 
-    // --- Bluesky ---
-    match (
-        env::var("BLUESKY_IDENTIFIER"),
-        env::var("BLUESKY_APP_PASSWORD"),
-    ) {
-        (Ok(identifier), Ok(password)) => {
-            let pds = env::var("BLUESKY_PDS_HOST").unwrap_or_else(|_| "https://bsky.social".into());
-            info!("Processing Bluesky account: {identifier}");
-            let client = bluesky::HttpBlueskyClient::new(&pds);
-            if let Err(e) =
-                bluesky::delete_old_posts(&client, &identifier, &password, &config, &keep_list)
-                    .await
-            {
-                error!("Bluesky error: {e:#}");
-                had_errors = true;
+    if config.keep_audit {
+        let platforms: Vec<&str> = run.platforms.keys().map(String::as_str).collect();
+        let unused = keep_list.unmatched(&platforms);
+        if unused.is_empty() {
+            info!("Keep audit: every keep entry matched a post");
+        } else {
+            info!(
+                "Keep audit: {} keep entries matched nothing this run and may be stale:",
+                unused.len()
+            );
+            for entry in unused {
+                info!("  {entry}");
+            }
+        }
+        let expired = keep_list.expired(&platforms);
+        if !expired.is_empty() {
+            info!(
+                "Keep audit: {} keep entries are past their until and protect nothing:",
+                expired.len()
+            );
+            for entry in expired {
+                info!("  {entry}");
             }
         }
-        _ => warn!("Bluesky credentials not set, skipping"),
     }
 
-    // --- Mastodon ---
-    match (
-        env::var("MASTODON_INSTANCE_URL"),
-        env::var("MASTODON_ACCESS_TOKEN"),
-    ) {
-        (Ok(instance), Ok(token)) => {
-            info!("Processing Mastodon instance: {instance}");
-            let client = mastodon::HttpMastodonClient::new(&instance, &token);
-            if let Err(e) = mastodon::delete_old_posts(&client, &config, &keep_list).await {
-                error!("Mastodon error: {e:#}");
-                had_errors = true;
-            }
+    if let Some(csv) = &config.remaining_csv {
+        match csv.write() {
+            Ok(rows) => info!("Wrote {rows} remaining records to {}", csv.path().display()),
+            Err(e) => warn!("{e:#}"),
         }
-        _ => warn!("Mastodon credentials not set, skipping"),
     }
+    write_report(&config);
 
-    if had_errors {
-        anyhow::bail!("One or more platforms encountered errors");
+    let counts = config.counters.snapshot();
+    run.partial = counts.deleted.saturating_sub(run.total.total());
+    if counts.errors > 0 {
+        warn!("{} records could not be deleted this run", counts.errors);
+    }
+    info!(target: SUMMARY_TARGET, "{}", run.line(config.dry_run));
+    if run.had_errors() {
+        for (platform, status) in &run.statuses {
+            match status {
+                PlatformStatus::Succeeded => info!("{platform}: succeeded"),
+                PlatformStatus::Failed { error } => error!("{platform}: failed: {error}"),
+            }
+        }
+        error!("Platforms with errors: {}", run.failed().join(", "));
+        log::logger().flush();
+        std::process::exit(run.exit_code());
     }
 
     info!("Done!");
@@ -146,9 +2328,66 @@ pub(crate) mod test_helpers {
         Config {
             cutoff: Utc::now() - TimeDelta::days(days),
             dry_run: false,
+            dry_run_grouped: false,
             delete_pinned: false,
             delete_reposts: true,
+            purge_all_reposts: false,
             delete_likes: true,
+            delete_account_data: false,
+            post_summary: false,
+            media_filter: crate::MediaFilter::All,
+            delete_text_prefix: None,
+            delete_plain_text_only: false,
+            prefetch_pages: false,
+            largest_media_first: false,
+            oldest_first: false,
+            keep_bookmarked: false,
+            keep_curated: false,
+            keep_self_likes: false,
+            hidden_cutoff: None,
+            import_boundary: None,
+            import_cutoff: None,
+            keep_shared_blobs: false,
+            keep_one_per_month: false,
+            keep_if_has_replies: false,
+            keep_sample: None,
+            keep_audit: false,
+            verify_each_page: false,
+            mastodon_idempotency: false,
+            keep_edited_since: None,
+            keep_keywords: Vec::new(),
+            delete_languages: Vec::new(),
+            keep_languages: Vec::new(),
+            default_language: None,
+            delete_from_apps: Vec::new(),
+            archive_dir: None,
+            force_delete: Default::default(),
+            delete_blocklist: None,
+            collection_keep: Default::default(),
+            rate_limit_state_file: None,
+            rate_limit_log: None,
+            remaining_csv: None,
+            report: None,
+            incremental_state_file: None,
+            phase_state_file: None,
+            post_run_hook: None,
+            post_all_hook: None,
+            delay: None,
+            post_delay: None,
+            repost_delay: None,
+            like_delay: None,
+            adaptive_delay: None,
+            extra_collections: Vec::new(),
+            prune_preferences: false,
+            rate_limit_mode: Default::default(),
+            error_limits: Default::default(),
+            fallback_rkey_time: false,
+            treat_future_as_now: false,
+            redact: false,
+            display_tz: Default::default(),
+            user_agent: crate::DEFAULT_USER_AGENT.to_string(),
+            shutdown: Default::default(),
+            counters: Default::default(),
         }
     }
 
@@ -166,10 +2405,67 @@ pub(crate) mod test_helpers {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::Write;
 
     // --- parse_timestamp ---
 
+    #[test]
+    fn decision_explains_the_deciding_factor() {
+        assert_eq!(
+            Decision::Kept(KeptBy::Rkey, "bluesky:abc".into()).explain(),
+            "would be kept: matched keep rule bluesky:abc"
+        );
+        assert_eq!(
+            Decision::NewerThanCutoff.explain(),
+            "would be kept: newer than the cutoff"
+        );
+        assert_eq!(
+            Decision::Delete.explain(),
+            "would be deleted: older than the cutoff and not protected"
+        );
+    }
+
+    #[test]
+    fn skip_counts_tally_each_reason_separately() {
+        let mut skipped = SkipCounts::default();
+        skipped.add(SkipReason::FutureDated);
+        skipped.add(SkipReason::FutureDated);
+        skipped.add(SkipReason::MissingTimestamp);
+        let mut other = SkipCounts::default();
+        other.add(SkipReason::Pinned);
+        skipped.merge(other);
+
+        assert_eq!(skipped.get(SkipReason::FutureDated), 2);
+        assert_eq!(skipped.get(SkipReason::MissingTimestamp), 1);
+        assert_eq!(skipped.get(SkipReason::Pinned), 1);
+        assert_eq!(skipped.get(SkipReason::Kept), 0);
+        assert_eq!(
+            skipped.text(),
+            "1 skipped: pinned, 2 skipped: future-dated, 1 skipped: missing timestamp"
+        );
+        assert_eq!(SkipCounts::default().text(), "none skipped");
+    }
+
+    #[test]
+    fn skip_counts_break_kept_down_by_entry_kind() {
+        let mut skipped = SkipCounts::default();
+        skipped.add_kept(KeptBy::Rkey);
+        skipped.add_kept(KeptBy::Rkey);
+        let mut other = SkipCounts::default();
+        other.add_kept(KeptBy::Subject);
+        skipped.merge(other);
+
+        assert_eq!(skipped.kept, 3);
+        assert_eq!(
+            skipped.kept_by,
+            KeptCounts {
+                rkey: 2,
+                subject: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(skipped.text(), "3 skipped: kept (2 by rkey, 1 by subject)");
+    }
+
     #[test]
     fn parse_timestamp_rfc3339_utc() {
         let dt = parse_timestamp("2024-06-15T12:30:00Z").unwrap();
@@ -198,65 +2494,1039 @@ mod tests {
         assert!(parse_timestamp("").is_err());
     }
 
-    // --- is_protected ---
+    // --- PlatformSummary ---
+
+    #[test]
+    fn remaining_tracks_count_and_oldest() {
+        let ts = |s: &str| parse_timestamp(s).unwrap().with_timezone(&Utc);
+        let mut remaining = Remaining::default();
+        remaining.observe(ts("2024-03-01T00:00:00Z"));
+        remaining.observe(ts("2023-05-01T12:00:00Z"));
+        remaining.observe(ts("2024-01-01T00:00:00Z"));
+        assert_eq!(remaining.count, 3);
+        assert_eq!(remaining.oldest, Some(ts("2023-05-01T12:00:00Z")));
+
+        let summary = PlatformSummary {
+            remaining: Some(remaining),
+            ..Default::default()
+        };
+        assert_eq!(
+            summary.remaining_text(),
+            "after cleanup you have 3 posts, oldest now 2023-05-01."
+        );
+    }
+
+    fn ts(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn platform_summaries_merge_counts_and_oldest_remaining() {
+        let mut skipped = SkipCounts::default();
+        skipped.add_kept(KeptBy::Rkey);
+        let mut total = PlatformSummary {
+            posts: 3,
+            reposts: 1,
+            remaining: Some(Remaining {
+                count: 4,
+                oldest: Some(ts("2023-05-01T00:00:00Z")),
+            }),
+            skipped,
+            ..Default::default()
+        };
+        for summary in [
+            PlatformSummary {
+                likes: 5,
+                other: 1,
+                remaining: Some(Remaining {
+                    count: 2,
+                    oldest: Some(ts("2022-01-01T00:00:00Z")),
+                }),
+                skipped,
+                ..Default::default()
+            },
+            PlatformSummary {
+                posts: 2,
+                ..Default::default()
+            },
+        ] {
+            total += &summary;
+        }
+
+        assert_eq!(
+            (total.posts, total.reposts, total.likes, total.other),
+            (5, 1, 5, 1)
+        );
+        assert_eq!(total.total(), 12);
+        assert_eq!(
+            total.remaining,
+            Some(Remaining {
+                count: 6,
+                oldest: Some(ts("2022-01-01T00:00:00Z")),
+            })
+        );
+        assert_eq!(total.skipped.kept, 2);
+        assert_eq!(total.skipped.kept_by.rkey, 2);
+    }
+
+    #[test]
+    fn run_summary_renders_line_and_json() {
+        let config = crate::test_helpers::config_with_cutoff_days_ago(30);
+        let finished = |summary: PlatformSummary| {
+            let payload = platform_payload("bluesky", &config, &summary);
+            Some(Ok((summary, payload)))
+        };
+        let mut run = RunSummary::default();
+        run.merge(
+            "bluesky",
+            finished(PlatformSummary {
+                posts: 3,
+                reposts: 1,
+                likes: 5,
+                ..Default::default()
+            }),
+        );
+        run.merge("mastodon", Some(Err(anyhow::anyhow!("instance down"))));
+        run.merge("threads", None);
+
+        assert_eq!(
+            run.line(false),
+            "Total deleted: 9 across 1 platform (dry-run: false)"
+        );
+        run.partial = 2;
+        assert_eq!(
+            run.line(true),
+            "Total deleted: 11 across 2 platforms, 2 of them before mastodon failed (dry-run: true)"
+        );
+
+        let json = run.to_json();
+        assert_eq!(json["had_errors"], true);
+        assert_eq!(json["statuses"]["bluesky"]["status"], "succeeded");
+        assert_eq!(json["statuses"]["mastodon"]["status"], "failed");
+        assert_eq!(json["statuses"]["mastodon"]["error"], "instance down");
+        assert!(json["statuses"].get("threads").is_none());
+        assert_eq!(json["total"]["likes"], 5);
+        assert_eq!(json["platforms"]["bluesky"]["summary"]["posts"], 3);
+        assert!(json["platforms"].get("mastodon").is_none());
+    }
+
+    #[tokio::test]
+    async fn platforms_run_concurrently_and_merge() {
+        tokio::time::pause();
+        let config = crate::test_helpers::config_with_cutoff_days_ago(30);
+        // Each fake platform takes a minute, so running them one after the
+        // other would take two
+        let fake_platform = |platform: &'static str, summary: PlatformSummary| {
+            let config = &config;
+            async move {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                Some(finish_platform(platform, platform, config, Ok(summary)).await)
+            }
+        };
+        let started = tokio::time::Instant::now();
+        let results = run_platforms(
+            &PLATFORMS,
+            fake_platform(
+                "bluesky",
+                PlatformSummary {
+                    posts: 3,
+                    likes: 2,
+                    ..Default::default()
+                },
+            ),
+            fake_platform(
+                "mastodon",
+                PlatformSummary {
+                    reposts: 4,
+                    ..Default::default()
+                },
+            ),
+        )
+        .await;
+
+        assert_eq!(started.elapsed().as_secs(), 60);
+        assert!(!results.had_errors());
+        assert_eq!(results.exit_code(), 0);
+        assert_eq!(results.deleted(), 9);
+        assert_eq!(results.platforms["bluesky"]["summary"]["posts"], 3);
+        assert_eq!(results.platforms["mastodon"]["summary"]["reposts"], 4);
+    }
+
+    #[tokio::test]
+    async fn platforms_are_started_in_platform_order() {
+        let started = std::sync::Mutex::new(Vec::new());
+        let fake_platform = |platform: &'static str| {
+            let started = &started;
+            async move {
+                started.lock().unwrap().push(platform);
+                None
+            }
+        };
+
+        run_platforms(
+            &PLATFORMS,
+            fake_platform("bluesky"),
+            fake_platform("mastodon"),
+        )
+        .await;
+        assert_eq!(*started.lock().unwrap(), ["bluesky", "mastodon"]);
+
+        started.lock().unwrap().clear();
+        let order = platform_order(
+            &lookup_from(&[("PLATFORM_ORDER", "mastodon")]),
+            &FileConfig::default(),
+        )
+        .unwrap();
+        run_platforms(&order, fake_platform("bluesky"), fake_platform("mastodon")).await;
+        assert_eq!(*started.lock().unwrap(), ["mastodon", "bluesky"]);
+    }
+
+    #[tokio::test]
+    async fn failed_or_unconfigured_platforms_are_left_out_of_the_merge() {
+        let config = crate::test_helpers::config_with_cutoff_days_ago(30);
+        let failed = async {
+            let result = Err(anyhow::anyhow!("session expired"));
+            Some(finish_platform("bluesky", "Bluesky", &config, result).await)
+        };
+        let results = run_platforms(&PLATFORMS, failed, async { None }).await;
+        assert!(results.had_errors());
+        assert!(results.platforms.is_empty());
+        assert_eq!(results.deleted(), 0);
+        assert_eq!(results.failed(), vec!["bluesky"]);
+        assert_eq!(results.exit_code(), 1);
+    }
+
+    #[tokio::test]
+    async fn mixed_outcomes_report_each_platform_and_a_partial_exit_code() {
+        let config = crate::test_helpers::config_with_cutoff_days_ago(30);
+        let succeeded = async {
+            let summary = PlatformSummary {
+                posts: 2,
+                ..Default::default()
+            };
+            Some(finish_platform("bluesky", "Bluesky", &config, Ok(summary)).await)
+        };
+        let failed = async {
+            let result = Err(anyhow::anyhow!("token revoked").context("Mastodon login"));
+            Some(finish_platform("mastodon", "Mastodon", &config, result).await)
+        };
+        let results = run_platforms(&PLATFORMS, succeeded, failed).await;
+
+        assert_eq!(
+            results.statuses,
+            BTreeMap::from([
+                ("bluesky".to_string(), PlatformStatus::Succeeded),
+                (
+                    "mastodon".to_string(),
+                    PlatformStatus::Failed {
+                        error: "Mastodon login: token revoked".into()
+                    }
+                ),
+            ])
+        );
+        assert_eq!(results.failed(), vec!["mastodon"]);
+        assert_eq!(results.exit_code(), 2);
+        assert_eq!(results.deleted(), 2);
+        assert_eq!(
+            results.to_json()["statuses"],
+            serde_json::json!({
+                "bluesky": {"status": "succeeded"},
+                "mastodon": {"status": "failed", "error": "Mastodon login: token revoked"},
+            })
+        );
+    }
+
+    // --- GroupedPlan ---
+
+    #[test]
+    fn grouped_plan_buckets_items_by_month() {
+        let ts = |s: &str| parse_timestamp(s).unwrap().with_timezone(&Utc);
+        let mut plan = GroupedPlan::default();
+        plan.add(ts("2023-05-31T23:59:59Z"), "post: late may".into());
+        plan.add(ts("2022-12-01T00:00:00Z"), "like: december".into());
+        plan.add(ts("2023-05-01T00:00:00Z"), "post: early may".into());
+        plan.add(ts("2023-06-01T00:00:00Z"), "repost: june".into());
+
+        let months = plan.months();
+        assert_eq!(
+            months.keys().collect::<Vec<_>>(),
+            vec!["2022-12", "2023-05", "2023-06"]
+        );
+        assert_eq!(months["2023-05"], vec!["post: early may", "post: late may"]);
+        assert_eq!(months["2022-12"], vec!["like: december"]);
+    }
+
+    #[test]
+    fn grouped_plan_renders_counts_and_total() {
+        let ts = |s: &str| parse_timestamp(s).unwrap().with_timezone(&Utc);
+        let mut plan = GroupedPlan::default();
+        plan.add(ts("2023-05-02T00:00:00Z"), "post: b".into());
+        let mut other = GroupedPlan::default();
+        other.add(ts("2023-05-01T00:00:00Z"), "post: a".into());
+        other.add(ts("2024-01-01T00:00:00Z"), "like: c".into());
+        plan.extend(other);
+
+        assert_eq!(
+            plan.render(),
+            vec![
+                "2023-05 (2 items)",
+                "  post: a",
+                "  post: b",
+                "2024-01 (1 items)",
+                "  like: c",
+                "Total: 3 items",
+            ]
+        );
+    }
+
+    #[test]
+    fn dry_run_grouped_implies_dry_run() {
+        let config = Config::resolve(
+            &lookup_from(&[("DRY_RUN_GROUPED", "true"), ("DRY_RUN", "false")]),
+            &FileConfig::default(),
+        )
+        .unwrap();
+        assert!(config.dry_run);
+        assert!(config.dry_run_grouped);
+    }
+
+    #[test]
+    fn summary_line_covers_totals_skips_and_remaining() {
+        let mut summary = PlatformSummary {
+            posts: 3,
+            likes: 5,
+            ..Default::default()
+        };
+        summary.skipped.add(SkipReason::Pinned);
+        assert_eq!(
+            summary.summary_line(),
+            "deleted 3 posts, 0 reposts, 5 likes; 1 skipped: pinned; after cleanup you have no posts left."
+        );
+    }
+
+    #[test]
+    fn quiet_summary_logs_only_errors_and_summary_lines() {
+        use log::{Level, Log, Metadata};
+        let enabled = |logger: &env_logger::Logger, target: &str, level: Level| {
+            logger.enabled(&Metadata::builder().target(target).level(level).build())
+        };
+        let per_item = concat!(env!("CARGO_CRATE_NAME"), "::bluesky");
+
+        let quiet = logger(true, DisplayTz::Utc).build();
+        assert!(!enabled(&quiet, per_item, Level::Info));
+        assert!(!enabled(&quiet, per_item, Level::Warn));
+        assert!(enabled(&quiet, per_item, Level::Error));
+        assert!(enabled(&quiet, SUMMARY_TARGET, Level::Info));
+    }
+
+    #[test]
+    fn remaining_text_when_nothing_left() {
+        assert_eq!(
+            PlatformSummary::default().remaining_text(),
+            "after cleanup you have no posts left."
+        );
+    }
+
+    #[test]
+    fn platform_summary_status_text_includes_totals_and_cutoff() {
+        let summary = PlatformSummary {
+            posts: 3,
+            reposts: 2,
+            likes: 1,
+            ..Default::default()
+        };
+        let cutoff = DateTime::parse_from_rfc3339("2024-06-15T12:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(summary.total(), 6);
+        assert_eq!(
+            summary.status_text(cutoff),
+            "Skyscraper cleaned up 3 posts, 2 reposts, and 1 likes older than 2024-06-15."
+        );
+    }
+
+    // --- Config::resolve ---
+
+    fn lookup_from(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: std::collections::HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    fn days_until(cutoff: DateTime<Utc>) -> i64 {
+        (Utc::now() - cutoff).num_days()
+    }
+
+    #[test]
+    fn resolve_uses_defaults_without_env_or_file() {
+        let config = Config::resolve(&lookup_from(&[]), &FileConfig::default()).unwrap();
+        assert_eq!(days_until(config.cutoff), 180);
+        assert!(!config.dry_run);
+        assert!(!config.delete_pinned);
+        assert!(config.delete_reposts);
+        assert!(config.delete_likes);
+        assert!(!config.post_summary);
+        assert_eq!(config.delay, None);
+    }
+
+    #[test]
+    fn resolve_falls_back_to_file_values() {
+        let file: FileConfig = toml::from_str(
+            "retention_days = 30\ndry_run = true\ndelete_likes = false\npost_summary_status = true",
+        )
+        .unwrap();
+        let config = Config::resolve(&lookup_from(&[]), &file).unwrap();
+        assert_eq!(days_until(config.cutoff), 30);
+        assert!(config.dry_run);
+        assert!(!config.delete_likes);
+        assert!(config.post_summary);
+    }
+
+    #[test]
+    fn resolve_env_overrides_file_values() {
+        let file: FileConfig =
+            toml::from_str("retention_days = 30\ndry_run = true\ndelete_likes = false").unwrap();
+        let lookup = lookup_from(&[
+            ("RETENTION_DAYS", "90"),
+            ("DRY_RUN", "false"),
+            ("DELETE_LIKES", "1"),
+        ]);
+        let config = Config::resolve(&lookup, &file).unwrap();
+        assert_eq!(days_until(config.cutoff), 90);
+        assert!(!config.dry_run);
+        assert!(config.delete_likes);
+    }
+
+    #[test]
+    fn parse_retention_units() {
+        assert_eq!(parse_retention("180d").unwrap(), TimeDelta::days(180));
+        assert_eq!(parse_retention("12w").unwrap(), TimeDelta::days(84));
+        assert_eq!(parse_retention("6mo").unwrap(), TimeDelta::days(180));
+        assert_eq!(parse_retention("2y").unwrap(), TimeDelta::days(730));
+        assert_eq!(parse_retention(" 1y ").unwrap(), TimeDelta::days(365));
+    }
+
+    #[test]
+    fn parse_retention_rejects_invalid_input() {
+        let err = parse_retention("3h").unwrap_err();
+        assert!(err.to_string().contains("unit \"h\""));
+        assert!(parse_retention("6").is_err());
+        assert!(parse_retention("mo").is_err());
+        assert!(parse_retention("").is_err());
+        let err = parse_retention("99999999999999y").unwrap_err();
+        assert!(err.to_string().starts_with("Invalid RETENTION"));
+    }
+
+    #[test]
+    fn resolve_rejects_a_retention_past_the_earliest_date() {
+        let lookup = lookup_from(&[("RETENTION", "99999999d")]);
+        assert!(Config::resolve(&lookup, &FileConfig::default()).is_err());
+        let lookup = lookup_from(&[("RETENTION_DAYS", "999999999999999")]);
+        assert!(Config::resolve(&lookup, &FileConfig::default()).is_err());
+    }
+
+    #[test]
+    fn resolve_retention_takes_precedence_over_retention_days() {
+        let file: FileConfig = toml::from_str("retention = \"1y\"\nretention_days = 30").unwrap();
+        let from_file = Config::resolve(&lookup_from(&[]), &file).unwrap();
+        assert_eq!(days_until(from_file.cutoff), 365);
+
+        let days_env = Config::resolve(&lookup_from(&[("RETENTION_DAYS", "90")]), &file).unwrap();
+        assert_eq!(days_until(days_env.cutoff), 90);
+
+        let lookup = lookup_from(&[("RETENTION", "12w"), ("RETENTION_DAYS", "90")]);
+        let env = Config::resolve(&lookup, &file).unwrap();
+        assert_eq!(days_until(env.cutoff), 84);
+
+        assert!(Config::resolve(&lookup_from(&[("RETENTION", "6x")]), &file).is_err());
+    }
+
+    #[test]
+    fn resolve_parses_media_filter_from_env_or_file() {
+        let file: FileConfig = toml::from_str("media_filter = \"video-only\"").unwrap();
+        let from_file = Config::resolve(&lookup_from(&[]), &file).unwrap();
+        assert_eq!(from_file.media_filter, MediaFilter::VideoOnly);
+
+        let from_env = Config::resolve(&lookup_from(&[("MEDIA_FILTER", "all")]), &file).unwrap();
+        assert_eq!(from_env.media_filter, MediaFilter::All);
+
+        assert!(Config::resolve(&lookup_from(&[("MEDIA_FILTER", "videos")]), &file).is_err());
+    }
+
+    #[test]
+    fn resolve_keep_sample() {
+        let file = FileConfig::default();
+        let config = Config::resolve(
+            &lookup_from(&[("KEEP_SAMPLE_PERCENT", "20"), ("KEEP_SAMPLE_SEED", "9")]),
+            &file,
+        )
+        .unwrap();
+        assert_eq!(
+            config.keep_sample,
+            Some(KeepSample {
+                percent: 20,
+                seed: 9
+            })
+        );
+        assert!(Config::resolve(&lookup_from(&[]), &file)
+            .unwrap()
+            .keep_sample
+            .is_none());
+        assert!(Config::resolve(&lookup_from(&[("KEEP_SAMPLE_PERCENT", "101")]), &file).is_err());
+    }
+
+    #[test]
+    fn resolve_error_limits() {
+        let file = FileConfig {
+            max_error_rate: Some(0.25),
+            ..Default::default()
+        };
+        let config =
+            Config::resolve(&lookup_from(&[("MAX_CONSECUTIVE_ERRORS", "5")]), &file).unwrap();
+        assert_eq!(
+            config.error_limits,
+            ErrorLimits {
+                max_consecutive: Some(5),
+                max_rate: Some(0.25),
+            }
+        );
+
+        let none = FileConfig::default();
+        assert_eq!(
+            Config::resolve(&lookup_from(&[]), &none)
+                .unwrap()
+                .error_limits,
+            ErrorLimits::default()
+        );
+        assert!(Config::resolve(&lookup_from(&[("MAX_ERROR_RATE", "1.5")]), &none).is_err());
+        assert!(Config::resolve(&lookup_from(&[("MAX_CONSECUTIVE_ERRORS", "0")]), &none).is_err());
+    }
+
+    #[test]
+    fn resolve_adaptive_delay_needs_both_ordered_bounds() {
+        let file = FileConfig {
+            adaptive_delay_max_ms: Some(5_000),
+            ..Default::default()
+        };
+        let config =
+            Config::resolve(&lookup_from(&[("ADAPTIVE_DELAY_MIN_MS", "200")]), &file).unwrap();
+        assert_eq!(
+            config.adaptive_delay,
+            Some(DelayBounds {
+                min: Duration::from_millis(200),
+                max: Duration::from_millis(5_000),
+            })
+        );
+
+        let none = FileConfig::default();
+        assert!(Config::resolve(&lookup_from(&[]), &none)
+            .unwrap()
+            .adaptive_delay
+            .is_none());
+        assert!(Config::resolve(&lookup_from(&[("ADAPTIVE_DELAY_MIN_MS", "200")]), &none).is_err());
+        assert!(
+            Config::resolve(&lookup_from(&[("ADAPTIVE_DELAY_MIN_MS", "9000")]), &file).is_err()
+        );
+    }
+
+    #[test]
+    fn force_delete_file_requires_confirmation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("force.txt");
+        std::fs::write(&path, "bluesky:3kurgent\n").unwrap();
+        let path = path.to_str().unwrap();
+
+        let err = Config::resolve(
+            &lookup_from(&[("FORCE_DELETE_FILE", path)]),
+            &FileConfig::default(),
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("CONFIRM_FORCE=true"));
+
+        let config = Config::resolve(
+            &lookup_from(&[("FORCE_DELETE_FILE", path), ("CONFIRM_FORCE", "true")]),
+            &FileConfig::default(),
+        )
+        .unwrap();
+        assert!(config.force_delete.is_protected("bluesky", "3kurgent"));
+
+        let missing = lookup_from(&[
+            ("FORCE_DELETE_FILE", "/nonexistent"),
+            ("CONFIRM_FORCE", "true"),
+        ]);
+        assert!(Config::resolve(&missing, &FileConfig::default()).is_err());
+    }
+
+    #[test]
+    fn delete_pinned_requires_confirmation() {
+        let err = Config::resolve(
+            &lookup_from(&[("DELETE_PINNED", "true")]),
+            &FileConfig::default(),
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("CONFIRM_DELETE_PINNED=true"));
+
+        let file: FileConfig = toml::from_str("delete_pinned = true").unwrap();
+        assert!(Config::resolve(&lookup_from(&[]), &file).is_err());
+
+        let config =
+            Config::resolve(&lookup_from(&[("CONFIRM_DELETE_PINNED", "true")]), &file).unwrap();
+        assert!(config.delete_pinned);
+
+        // Turning it off again needs no confirmation
+        let off = lookup_from(&[("DELETE_PINNED", "false")]);
+        assert!(!Config::resolve(&off, &file).unwrap().delete_pinned);
+    }
+
+    #[test]
+    fn delete_account_data_needs_the_confirmation_phrase() {
+        let err = Config::resolve(
+            &lookup_from(&[("DELETE_ACCOUNT_DATA", "true")]),
+            &FileConfig::default(),
+        )
+        .err()
+        .unwrap();
+        assert!(err
+            .to_string()
+            .contains("CONFIRM_DELETE_ACCOUNT_DATA=delete-everything"));
+
+        let unconfirmed = lookup_from(&[
+            ("DELETE_ACCOUNT_DATA", "true"),
+            ("CONFIRM_DELETE_ACCOUNT_DATA", "true"),
+        ]);
+        assert!(Config::resolve(&unconfirmed, &FileConfig::default()).is_err());
+
+        let confirmed = lookup_from(&[
+            ("DELETE_ACCOUNT_DATA", "true"),
+            ("CONFIRM_DELETE_ACCOUNT_DATA", "delete-everything"),
+        ]);
+        let config = Config::resolve(&confirmed, &FileConfig::default()).unwrap();
+        assert!(config.delete_account_data);
+    }
+
+    #[test]
+    fn for_account_data_lifts_every_rule_after_platform_settings() {
+        let file: FileConfig = toml::from_str(
+            "keep_bookmarked = true\nkeep_languages = [\"de\"]\nincremental = true\npost_summary_status = true",
+        )
+        .unwrap();
+        let base = Config::resolve(&lookup_from(&[]), &file).unwrap();
+        let unchanged = base.clone().for_account_data();
+        assert_eq!(unchanged.cutoff, base.cutoff);
+        assert!(unchanged.keep_bookmarked);
+
+        let confirmed = lookup_from(&[
+            ("DELETE_ACCOUNT_DATA", "true"),
+            ("CONFIRM_DELETE_ACCOUNT_DATA", "delete-everything"),
+        ]);
+        let config = Config::resolve(&confirmed, &file)
+            .unwrap()
+            .for_platform(Some(30), None)
+            .with_delete_flags(Some(false), Some(false))
+            .for_account_data();
+        assert_eq!(config.cutoff, DateTime::<Utc>::MAX_UTC);
+        assert!(config.delete_pinned && config.delete_reposts && config.delete_likes);
+        assert!(!config.keep_bookmarked && !config.post_summary);
+        assert!(config.keep_languages.is_empty());
+        assert_eq!(config.incremental_state_file, None);
+    }
+
+    #[test]
+    fn collection_keep_files_load_per_collection() {
+        let dir = tempfile::tempdir().unwrap();
+        let likes = dir.path().join("likes.txt");
+        std::fs::write(&likes, "bluesky:3klike\n").unwrap();
+        let file = FileConfig {
+            keep_file_likes: Some(likes.to_str().unwrap().to_string()),
+            ..Default::default()
+        };
+
+        let config = Config::resolve(&lookup_from(&[]), &file).unwrap();
+        assert_eq!(config.collection_keep.len(), 1);
+        assert!(config.collection_keep["app.bsky.feed.like"].is_protected("bluesky", "3klike"));
+    }
+
+    #[test]
+    fn resolve_keep_recently_edited_days() {
+        let none = Config::resolve(&lookup_from(&[]), &FileConfig::default()).unwrap();
+        assert!(none.keep_edited_since.is_none());
+
+        let config = Config::resolve(
+            &lookup_from(&[("KEEP_RECENTLY_EDITED_DAYS", "7")]),
+            &FileConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(days_until(config.keep_edited_since.unwrap()), 7);
+    }
+
+    #[test]
+    fn resolve_keep_one_per_month_needs_newest_first_order() {
+        let file: FileConfig = toml::from_str("keep_one_per_month = true").unwrap();
+        let config = Config::resolve(&lookup_from(&[]), &file).unwrap();
+        assert!(config.keep_one_per_month);
+
+        for conflicting in ["OLDEST_FIRST", "LARGEST_MEDIA_FIRST"] {
+            let err = Config::resolve(&lookup_from(&[(conflicting, "true")]), &file)
+                .err()
+                .unwrap();
+            assert!(err.to_string().contains(conflicting), "{err}");
+        }
+    }
+
+    #[test]
+    fn resolve_splits_language_lists() {
+        let file: FileConfig = toml::from_str("keep_languages = [\"en\"]").unwrap();
+        let config = Config::resolve(
+            &lookup_from(&[("DELETE_LANGUAGES", "de, fr,"), ("DEFAULT_LANGUAGE", "en")]),
+            &file,
+        )
+        .unwrap();
+        assert_eq!(config.delete_languages, vec!["de", "fr"]);
+        assert_eq!(config.keep_languages, vec!["en"]);
+        assert_eq!(config.default_language.as_deref(), Some("en"));
+    }
+
+    #[test]
+    fn parse_instant_accepts_timestamps_and_dates() {
+        let expected = parse_timestamp("2023-04-01T00:00:00Z").unwrap();
+        assert_eq!(parse_instant("2023-04-01").unwrap(), expected);
+        assert_eq!(
+            parse_instant("2023-04-01T02:00:00+02:00").unwrap(),
+            expected
+        );
+        assert!(parse_instant("April 2023").is_err());
+
+        let lookup = lookup_from(&[("IMPORT_BOUNDARY", "April 2023")]);
+        let err = Config::resolve(&lookup, &FileConfig::default())
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("Invalid IMPORT_BOUNDARY"));
+    }
+
+    #[test]
+    fn platform_retention_days_fall_back_to_the_file_then_global() {
+        let lookup = lookup_from(&[("RETENTION_DAYS", "90"), ("RETENTION_DAYS_BLUESKY", "30")]);
+        let base = Config::resolve(&lookup, &FileConfig::default()).unwrap();
+
+        let bluesky =
+            base.for_platform(platform_retention_days(&lookup, "bluesky", Some(60)), None);
+        let mastodon = base.for_platform(platform_retention_days(&lookup, "mastodon", None), None);
+        assert_eq!(days_until(bluesky.cutoff), 30);
+        assert_eq!(days_until(mastodon.cutoff), 90);
+
+        let mastodon = base.for_platform(
+            platform_retention_days(&lookup, "mastodon", Some(365)),
+            None,
+        );
+        assert_eq!(days_until(mastodon.cutoff), 365);
+    }
+
+    #[test]
+    fn for_platform_applies_retention_and_delay_overrides() {
+        let base = Config::resolve(&lookup_from(&[]), &FileConfig::default()).unwrap();
+
+        let unchanged = base.for_platform(None, None);
+        assert_eq!(unchanged.cutoff, base.cutoff);
+        assert_eq!(unchanged.delay, None);
+
+        let overridden = base.for_platform(Some(30), Some(250));
+        assert_eq!(days_until(overridden.cutoff), 30);
+        assert_eq!(overridden.delay, Some(Duration::from_millis(250)));
+    }
+
+    #[test]
+    fn resolve_per_collection_bluesky_delays() {
+        let file: FileConfig =
+            toml::from_str("[bluesky]\npost_delay_ms = 500\nlike_delay_ms = 20").unwrap();
+        let config =
+            Config::resolve(&lookup_from(&[("BLUESKY_LIKE_DELAY_MS", "5")]), &file).unwrap();
+        assert_eq!(config.post_delay, Some(Duration::from_millis(500)));
+        assert_eq!(config.repost_delay, None);
+        assert_eq!(config.like_delay, Some(Duration::from_millis(5)));
+    }
+
+    #[test]
+    fn resolve_user_agent_defaults_and_overrides() {
+        let default = Config::resolve(&lookup_from(&[]), &FileConfig::default()).unwrap();
+        assert_eq!(default.user_agent, DEFAULT_USER_AGENT);
+
+        let file: FileConfig = toml::from_str("user_agent = \"from-file/1.0\"").unwrap();
+        let from_file = Config::resolve(&lookup_from(&[]), &file).unwrap();
+        assert_eq!(from_file.user_agent, "from-file/1.0");
+
+        let from_env =
+            Config::resolve(&lookup_from(&[("USER_AGENT", "my-bot/2.0")]), &file).unwrap();
+        assert_eq!(from_env.user_agent, "my-bot/2.0");
+    }
+
+    #[test]
+    fn with_delete_flags_overrides_reposts_and_likes_independently() {
+        let base = Config::resolve(&lookup_from(&[]), &FileConfig::default()).unwrap();
+        let flags = |reposts, likes| {
+            let c = base.with_delete_flags(reposts, likes);
+            (c.delete_reposts, c.delete_likes)
+        };
+        assert_eq!(flags(None, None), (true, true));
+        assert_eq!(flags(Some(true), Some(false)), (true, false));
+        assert_eq!(flags(Some(false), Some(true)), (false, true));
+        assert_eq!(flags(Some(false), Some(false)), (false, false));
+        assert_eq!(flags(None, Some(false)), (true, false));
+        assert_eq!(flags(Some(false), None), (false, true));
+
+        // Re-enable on one platform what's globally off
+        let lookup = lookup_from(&[("DELETE_REPOSTS", "false"), ("DELETE_LIKES", "false")]);
+        let off = Config::resolve(&lookup, &FileConfig::default()).unwrap();
+        let on = off.with_delete_flags(Some(true), None);
+        assert!(on.delete_reposts);
+        assert!(!on.delete_likes);
+    }
+
+    #[test]
+    fn mastodon_delete_flags_read_from_file_section() {
+        let file: FileConfig =
+            toml::from_str("[mastodon]\ndelete_reposts = true\ndelete_likes = false").unwrap();
+        assert_eq!(file.mastodon.delete_reposts, Some(true));
+        assert_eq!(file.mastodon.delete_likes, Some(false));
+    }
+
+    // --- keyring ---
+
+    #[test]
+    fn stored_keyring_password_is_used_for_login() {
+        let keyring = credentials::MemoryKeyring::default();
+        let store = lookup_from(&[("BLUESKY_IDENTIFIER", "alice.com")]);
+        store_keyring_password(
+            &store,
+            &FileConfig::default(),
+            &keyring,
+            "app-pass\n".as_bytes(),
+        )
+        .unwrap();
+
+        let run = lookup_from(&[
+            ("BLUESKY_IDENTIFIER", "alice.com"),
+            ("BLUESKY_APP_PASSWORD", "ignored"),
+            ("CREDENTIAL_SOURCE", "keyring"),
+        ]);
+        let account = bluesky_account_from(&run, &FileConfig::default(), &keyring)
+            .unwrap()
+            .unwrap();
+        assert_eq!(account.identifier, "alice.com");
+        assert_eq!(account.password, "app-pass");
+    }
+
+    #[test]
+    fn keyring_store_prefers_the_configured_password_and_service() {
+        let keyring = credentials::MemoryKeyring::default();
+        let file: FileConfig =
+            toml::from_str("[bluesky]\nidentifier = \"alice.com\"\nkeyring_service = \"work\"")
+                .unwrap();
+        let lookup = lookup_from(&[("BLUESKY_APP_PASSWORD", "from-env")]);
+        store_keyring_password(&lookup, &file, &keyring, "from-stdin\n".as_bytes()).unwrap();
+        assert_eq!(
+            keyring.get("work", "alice.com").unwrap().as_deref(),
+            Some("from-env")
+        );
+        assert_eq!(
+            keyring
+                .get(credentials::DEFAULT_SERVICE, "alice.com")
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn keyring_source_without_a_stored_password_errors() {
+        let keyring = credentials::MemoryKeyring::default();
+        let lookup = lookup_from(&[
+            ("BLUESKY_IDENTIFIER", "alice.com"),
+            ("CREDENTIAL_SOURCE", "keyring"),
+        ]);
+        let err = bluesky_account_from(&lookup, &FileConfig::default(), &keyring)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("skyscraper/alice.com"));
+        assert!(
+            store_keyring_password(&lookup, &FileConfig::default(), &keyring, "\n".as_bytes())
+                .is_err()
+        );
+    }
 
     #[test]
-    fn is_protected_platform_prefixed_match() {
-        let keep = HashSet::from(["bluesky:abc123".to_string()]);
-        assert!(is_protected(&keep, "bluesky", "abc123"));
+    fn env_source_ignores_the_keyring() {
+        let keyring = credentials::MemoryKeyring::default();
+        keyring
+            .set(credentials::DEFAULT_SERVICE, "alice.com", "from-keyring")
+            .unwrap();
+        let lookup = lookup_from(&[
+            ("BLUESKY_IDENTIFIER", "alice.com"),
+            ("BLUESKY_APP_PASSWORD", "from-env"),
+        ]);
+        let account = bluesky_account_from(&lookup, &FileConfig::default(), &keyring)
+            .unwrap()
+            .unwrap();
+        assert_eq!(account.password, "from-env");
+        let no_password = lookup_from(&[("BLUESKY_IDENTIFIER", "alice.com")]);
+        assert!(
+            bluesky_account_from(&no_password, &FileConfig::default(), &keyring)
+                .unwrap()
+                .is_none()
+        );
     }
 
+    // --- hooks ---
+
     #[test]
-    fn is_protected_bare_id_match() {
-        let keep = HashSet::from(["abc123".to_string()]);
-        assert!(is_protected(&keep, "bluesky", "abc123"));
+    fn platform_payload_includes_summary_and_context() {
+        let config = crate::test_helpers::config_with_cutoff_days_ago(30);
+        let summary = PlatformSummary {
+            posts: 2,
+            likes: 1,
+            remaining: Some(Remaining {
+                count: 5,
+                oldest: Some(parse_timestamp("2023-05-01T00:00:00Z").unwrap().into()),
+            }),
+            ..Default::default()
+        };
+
+        let payload = platform_payload("mastodon", &config, &summary);
+        assert_eq!(payload["platform"], "mastodon");
+        assert_eq!(payload["dry_run"], false);
+        assert_eq!(
+            payload["summary"],
+            serde_json::json!({
+                "posts": 2,
+                "reposts": 0,
+                "likes": 1,
+                "other": 0,
+                "remaining": {"count": 5, "oldest": "2023-05-01T00:00:00Z"},
+                "skipped": {
+                    "pinned": 0,
+                    "kept": 0,
+                    "kept_by": {"rkey": 0, "uri": 0, "subject": 0, "author": 0, "date": 0},
+                    "future_dated": 0,
+                    "missing_timestamp": 0,
+                    "unparseable_timestamp": 0,
+                    "sampled": 0,
+                    "shared_blob": 0,
+                    "one_per_month": 0,
+                    "self_like": 0,
+                    "imported": 0,
+                },
+            })
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn failing_hook_is_only_a_warning() {
+        run_hook(Some("exit 1"), serde_json::json!({})).await;
+        run_hook(None, serde_json::json!({})).await;
     }
 
+    // --- PLATFORM_OVERRIDES ---
+
     #[test]
-    fn is_protected_no_match() {
-        let keep = HashSet::from(["bluesky:other".to_string()]);
-        assert!(!is_protected(&keep, "bluesky", "abc123"));
+    fn platform_overrides_parse_per_platform_partial_configs() {
+        let overrides = platform_overrides(&lookup_from(&[(
+            "PLATFORM_OVERRIDES",
+            r#"{"bluesky": {"retention_days": 30, "delete_likes": false}, "mastodon": {}}"#,
+        )]))
+        .unwrap();
+        assert_eq!(
+            overrides["bluesky"],
+            PlatformOverride {
+                retention_days: Some(30),
+                delete_likes: Some(false),
+                ..Default::default()
+            }
+        );
+        assert_eq!(overrides["mastodon"], PlatformOverride::default());
     }
 
     #[test]
-    fn is_protected_wrong_platform_prefix() {
-        let keep = HashSet::from(["mastodon:abc123".to_string()]);
-        assert!(!is_protected(&keep, "bluesky", "abc123"));
+    fn platform_overrides_deleting_pinned_posts_require_confirmation() {
+        let json = r#"{"mastodon": {"delete_pinned": true}}"#;
+        let err = platform_overrides(&lookup_from(&[("PLATFORM_OVERRIDES", json)])).unwrap_err();
+        assert!(err.to_string().contains("CONFIRM_DELETE_PINNED=true"));
+
+        let overrides = platform_overrides(&lookup_from(&[
+            ("PLATFORM_OVERRIDES", json),
+            ("CONFIRM_DELETE_PINNED", "true"),
+        ]))
+        .unwrap();
+        assert_eq!(overrides["mastodon"].delete_pinned, Some(true));
     }
 
-    // --- load_keep_list ---
+    #[test]
+    fn platform_overrides_default_to_empty() {
+        assert!(platform_overrides(&lookup_from(&[])).unwrap().is_empty());
+    }
 
     #[test]
-    fn load_keep_list_parses_entries_skipping_comments_and_blanks() {
-        let mut f = tempfile::NamedTempFile::new().unwrap();
-        writeln!(f, "# comment").unwrap();
-        writeln!(f).unwrap();
-        writeln!(f, "bluesky:abc123").unwrap();
-        writeln!(f, "mastodon:456").unwrap();
-        let result = load_keep_list(f.path());
-        assert_eq!(result.len(), 2);
-        assert!(result.contains("bluesky:abc123"));
-        assert!(result.contains("mastodon:456"));
+    fn platform_overrides_reject_invalid_json_and_unknown_keys() {
+        assert!(platform_overrides(&lookup_from(&[("PLATFORM_OVERRIDES", "{bluesky")])).is_err());
+        assert!(platform_overrides(&lookup_from(&[(
+            "PLATFORM_OVERRIDES",
+            r#"{"bluesky": {"retention": 30}}"#
+        )]))
+        .is_err());
     }
 
     #[test]
-    fn load_keep_list_trims_whitespace() {
-        let mut f = tempfile::NamedTempFile::new().unwrap();
-        writeln!(f, "  bluesky:abc123  ").unwrap();
-        let result = load_keep_list(f.path());
-        assert!(result.contains("bluesky:abc123"));
+    fn with_override_merges_over_base_config() {
+        let base = Config::resolve(
+            &lookup_from(&[("RETENTION_DAYS", "180"), ("DRY_RUN", "true")]),
+            &FileConfig::default(),
+        )
+        .unwrap()
+        .for_platform(None, Some(500));
+        let o = PlatformOverride {
+            retention_days: Some(14),
+            dry_run: Some(false),
+            delete_reposts: Some(false),
+            ..Default::default()
+        };
+
+        let merged = base.with_override(Some(&o));
+        assert_eq!(days_until(merged.cutoff), 14);
+        assert!(!merged.dry_run);
+        assert!(!merged.delete_reposts);
+        // Unset fields keep the base values
+        assert!(merged.delete_likes);
+        assert!(!merged.delete_pinned);
+        assert_eq!(merged.delay, Some(Duration::from_millis(500)));
+
+        let untouched = base.with_override(None);
+        assert_eq!(untouched.cutoff, base.cutoff);
+        assert!(untouched.dry_run);
     }
 
+    // --- PLATFORM_ORDER ---
+
     #[test]
-    fn load_keep_list_nonexistent_file_returns_empty() {
-        let result = load_keep_list(Path::new("/nonexistent/keep.txt"));
-        assert!(result.is_empty());
+    fn platform_order_defaults_to_bluesky_then_mastodon() {
+        let order = platform_order(&lookup_from(&[]), &FileConfig::default()).unwrap();
+        assert_eq!(order, PLATFORMS);
     }
 
     #[test]
-    fn load_keep_list_empty_file_returns_empty() {
-        let f = tempfile::NamedTempFile::new().unwrap();
-        let result = load_keep_list(f.path());
-        assert!(result.is_empty());
+    fn platform_order_rejects_unknown_and_repeated_platforms() {
+        let order = |value| {
+            platform_order(
+                &lookup_from(&[("PLATFORM_ORDER", value)]),
+                &FileConfig::default(),
+            )
+        };
+        assert_eq!(
+            order(" Mastodon , bluesky").unwrap(),
+            ["mastodon", "bluesky"]
+        );
+        assert!(order("mastodon,threads").is_err());
+        assert!(order("mastodon,mastodon").is_err());
     }
 }
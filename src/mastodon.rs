@@ -1,8 +1,194 @@
-use crate::{is_protected, Config};
+use crate::adaptive::AdaptiveDelay;
+use crate::error_limit::ErrorTracker;
+use crate::full_plan::PlanEntry;
+use crate::keep::{KeepList, MonthlyKeep};
+use crate::phases::{self, PhaseCheckpoints};
+use crate::rate_budget::DeletionBudget;
+use crate::rate_limit_log::RateLimitEvent;
+use crate::report::Action;
+use crate::stats::PlatformStats;
+use crate::{
+    Config, Decision, DeleteOutcome, GroupedPlan, KeptBy, PlatformSummary, Remaining, SkipReason,
+};
 use anyhow::{Context, Result};
+use chrono::{DateTime, TimeDelta, Utc};
 use log::{info, warn};
 use serde::Deserialize;
 use std::collections::HashSet;
+use std::time::Duration;
+
+/// Statuses per page, matching the `limit` of each listing request.
+const PAGE_SIZE: usize = 40;
+
+/// Default pause between deletions when no `delay_ms` is configured.
+const DEFAULT_DELAY: Duration = Duration::from_millis(300);
+
+/// Mastodon allows 30 deletions (including unfavourites) per 30 minutes.
+const DELETE_LIMIT: usize = 30;
+const DELETE_WINDOW: TimeDelta = TimeDelta::minutes(30);
+
+/// 403s in a row after which deletions stop and the account is reported as
+/// likely suspended or limited.
+const FORBIDDEN_STREAK_LIMIT: u64 = 3;
+
+/// How many times one request is retried after a `Retry-After` 429 before
+/// the rest is left for the next run.
+const MAX_RETRY_AFTER_ATTEMPTS: u32 = 3;
+
+/// The longest `Retry-After` worth sleeping through. Anything longer leaves
+/// the rest for the next run rather than stalling this one.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(15 * 60);
+
+/// A delete answered with 403 Forbidden.
+#[derive(Debug)]
+pub struct Forbidden;
+
+impl std::fmt::Display for Forbidden {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "403 Forbidden")
+    }
+}
+
+impl std::error::Error for Forbidden {}
+
+/// Consecutive [`Forbidden`] deletes. A suspended or limited account still
+/// passes `verify_credentials` but has every delete refused, so a streak of
+/// them says more about the account than about any one status.
+#[derive(Debug, Default)]
+struct ForbiddenStreak(u64);
+
+impl ForbiddenStreak {
+    fn reset(&mut self) {
+        self.0 = 0;
+    }
+
+    /// Count a failed delete, erroring once `FORBIDDEN_STREAK_LIMIT` 403s
+    /// have come in a row.
+    fn observe(&mut self, e: &anyhow::Error) -> Result<()> {
+        if !e.is::<Forbidden>() {
+            self.reset();
+            return Ok(());
+        }
+        self.0 += 1;
+        if self.0 >= FORBIDDEN_STREAK_LIMIT {
+            anyhow::bail!(
+                "Mastodon refused {} deletions in a row with 403 Forbidden. The account may be suspended or limited; check your instance's moderation notices before retrying",
+                self.0
+            );
+        }
+        Ok(())
+    }
+}
+
+/// A request answered with 429 Too Many Requests. `retry_after` is how long
+/// the `Retry-After` header asked to wait, if it was sent.
+#[derive(Debug)]
+pub struct RateLimited {
+    pub retry_after: Option<Duration>,
+    /// The API path that was limited.
+    pub endpoint: String,
+    /// `X-RateLimit-Remaining`, if it was sent.
+    pub remaining: Option<u64>,
+    /// `X-RateLimit-Reset`, or else `Retry-After` from now, if either was sent.
+    pub reset: Option<DateTime<Utc>>,
+}
+
+impl std::fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.retry_after {
+            Some(wait) => write!(f, "429 Too Many Requests, retry after {}s", wait.as_secs()),
+            None => write!(f, "429 Too Many Requests"),
+        }
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+impl RateLimited {
+    fn from_response(resp: &reqwest::Response) -> Self {
+        let header = |name: &str| resp.headers().get(name).and_then(|v| v.to_str().ok());
+        let retry_after = retry_after(resp);
+        let reset = header("x-ratelimit-reset")
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|t| t.with_timezone(&Utc))
+            .or_else(|| {
+                retry_after
+                    .and_then(|wait| TimeDelta::from_std(wait).ok())
+                    .map(|wait| Utc::now() + wait)
+            });
+        Self {
+            retry_after,
+            endpoint: resp.url().path().to_string(),
+            remaining: header("x-ratelimit-remaining").and_then(|s| s.parse().ok()),
+            reset,
+        }
+    }
+
+    /// Add this to `RATE_LIMIT_LOG`, if it's set.
+    fn log(&self, config: &Config) {
+        if let Some(log) = &config.rate_limit_log {
+            log.record(&RateLimitEvent {
+                at: Utc::now(),
+                platform: "mastodon",
+                endpoint: &self.endpoint,
+                remaining: self.remaining,
+                reset: self.reset,
+            });
+        }
+    }
+}
+
+/// `Retry-After` as a wait: either delay-seconds or an HTTP date.
+fn retry_after(resp: &reqwest::Response) -> Option<Duration> {
+    let value = resp
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(secs) = value.parse() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (at.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// After a [`RateLimited`] `result` that says when to retry, sleep that long
+/// and return `true` so the same request is retried. `retries` counts this
+/// request's retries so far. Without `Retry-After`, past
+/// `MAX_RETRY_AFTER_ATTEMPTS` or `MAX_RETRY_AFTER`, or on Ctrl-C during the
+/// wait, `false`: the caller falls back to leaving the rest for the next run.
+async fn wait_retry_after<T>(result: &Result<T>, retries: &mut u32, config: &Config) -> bool {
+    let Some(limited) = result
+        .as_ref()
+        .err()
+        .and_then(|e| e.downcast_ref::<RateLimited>())
+    else {
+        return false;
+    };
+    limited.log(config);
+    let Some(wait) = limited.retry_after else {
+        return false;
+    };
+    if *retries >= MAX_RETRY_AFTER_ATTEMPTS {
+        warn!("Still rate limited after {retries} retries, giving up for this run");
+        return false;
+    }
+    if wait > MAX_RETRY_AFTER {
+        warn!(
+            "Rate limited for {}s, longer than this run will wait",
+            wait.as_secs()
+        );
+        return false;
+    }
+    *retries += 1;
+    warn!("Rate limited, retrying in {}s as asked", wait.as_secs());
+    config.shutdown.sleep(wait).await
+}
 
 #[derive(Deserialize)]
 pub(crate) struct Account {
@@ -16,32 +202,202 @@ pub(crate) struct Status {
     #[serde(default)]
     pinned: bool,
     reblog: Option<serde_json::Value>,
+    /// Status body as HTML. Use `text()` for anything content-based.
+    #[serde(default)]
+    content: String,
+    language: Option<String>,
+    edited_at: Option<String>,
+    /// Whether the authenticated user bookmarked it.
+    #[serde(default)]
+    bookmarked: bool,
+    #[serde(default)]
+    replies_count: u64,
+    #[serde(default)]
+    media_attachments: Vec<serde_json::Value>,
+    /// Preview card Mastodon fetched for the first link in the status.
+    card: Option<serde_json::Value>,
+    /// The app it was posted from. Only shown for your own statuses.
+    application: Option<Application>,
+}
+
+#[derive(Deserialize, Clone)]
+pub(crate) struct Application {
+    name: String,
+}
+
+impl Status {
+    /// ID of the status this one boosts, if it's a reblog.
+    fn reblog_of(&self) -> Option<&str> {
+        self.reblog.as_ref()?.get("id")?.as_str()
+    }
+
+    /// The rendered plain text of `content`.
+    fn text(&self) -> String {
+        html_to_text(&self.content)
+    }
+
+    /// No media, no preview card and no links in `content`. Mentions and
+    /// hashtags render as links too, but Mastodon marks them with a class.
+    fn is_plain_text(&self) -> bool {
+        if !self.media_attachments.is_empty() || self.card.is_some() {
+            return false;
+        }
+        !self.content.split("<a ").skip(1).any(|anchor| {
+            let tag = anchor.split('>').next().unwrap_or_default();
+            !(tag.contains("mention") || tag.contains("hashtag"))
+        })
+    }
+}
+
+/// Strip tags and decode entities from Mastodon's status HTML so content
+/// rules see what a reader sees. Paragraph and line breaks become newlines.
+fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+        let tag = rest[start + 1..start + end].trim().to_ascii_lowercase();
+        if tag.starts_with("br") || (tag.starts_with("/p") && start + end + 1 < rest.len()) {
+            text.push('\n');
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+    decode_entities(&text)
+}
+
+fn decode_entities(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let c = match &rest[1..end] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => ' ',
+                entity => {
+                    let code = match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => entity.strip_prefix('#').and_then(|d| d.parse().ok()),
+                    };
+                    char::from_u32(code?)?
+                }
+            };
+            Some((c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
 }
 
 pub(crate) trait MastodonClient {
     async fn verify_credentials(&self) -> Result<Account>;
     async fn list_statuses(&self, account_id: &str, max_id: Option<&str>) -> Result<Vec<Status>>;
-    async fn delete_status(&self, id: &str) -> Result<()>;
+    /// The account's pinned statuses, which some instances leave out of `list_statuses`.
+    async fn list_pinned_statuses(&self, account_id: &str) -> Result<Vec<Status>>;
+    async fn get_status(&self, id: &str) -> Result<Status>;
+    /// Whether a status still exists, as opposed to failing to look it up.
+    async fn status_exists(&self, id: &str) -> Result<bool>;
+    async fn delete_status(&self, id: &str) -> Result<DeleteOutcome>;
     async fn list_favourites(&self, max_id: Option<&str>) -> Result<(Vec<Status>, Option<String>)>;
-    async fn unfavourite(&self, id: &str) -> Result<()>;
+    async fn unfavourite(&self, id: &str) -> Result<DeleteOutcome>;
+    async fn list_bookmarks(&self, max_id: Option<&str>) -> Result<(Vec<Status>, Option<String>)>;
+    async fn unbookmark(&self, id: &str) -> Result<DeleteOutcome>;
+    async fn create_status(&self, text: &str) -> Result<()>;
+    /// Scopes granted to the access token, or `None` if the instance doesn't
+    /// say (Mastodon before 4.3).
+    async fn token_scopes(&self) -> Result<Option<Vec<String>>>;
 }
 
 pub(crate) struct HttpMastodonClient {
     client: reqwest::Client,
     instance: String,
     auth: String,
+    /// Send a fresh `Idempotency-Key` with every delete.
+    idempotency: bool,
 }
 
 impl HttpMastodonClient {
     pub fn new(instance: &str, token: &str) -> Self {
         Self {
-            client: reqwest::Client::builder()
-                .user_agent("skyscraper/0.1.0")
-                .build()
-                .expect("Failed to build HTTP client"),
+            client: crate::http_client(crate::DEFAULT_USER_AGENT),
             instance: instance.to_string(),
             auth: format!("Bearer {token}"),
+            idempotency: false,
+        }
+    }
+
+    pub fn with_user_agent(mut self, user_agent: &str) -> Self {
+        self.client = crate::http_client(user_agent);
+        self
+    }
+
+    /// Some instances reject deletes that don't carry an `Idempotency-Key`.
+    pub fn with_idempotency(mut self, idempotency: bool) -> Self {
+        self.idempotency = idempotency;
+        self
+    }
+
+    /// An authorized DELETE, with an `Idempotency-Key` if enabled.
+    fn delete(&self, url: String) -> reqwest::RequestBuilder {
+        let request = self.client.delete(url).header("Authorization", &self.auth);
+        if self.idempotency {
+            request.header("Idempotency-Key", idempotency_key())
+        } else {
+            request
+        }
+    }
+
+    /// Fetch a page from an endpoint like `/api/v1/favourites` that paginates
+    /// via the Link header rather than status IDs.
+    async fn list_linked(
+        &self,
+        endpoint: &str,
+        max_id: Option<&str>,
+    ) -> Result<(Vec<Status>, Option<String>)> {
+        let mut url = format!("{}/api/v1/{endpoint}?limit=40", self.instance);
+        if let Some(id) = max_id {
+            url.push_str(&format!("&max_id={id}"));
         }
+
+        let resp = self
+            .client
+            .get(&url)
+            .header("Authorization", &self.auth)
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("Failed to fetch Mastodon {endpoint}"))?;
+
+        let link_header = resp
+            .headers()
+            .get("link")
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+
+        let statuses: Vec<Status> = resp.json().await?;
+        let next_max_id = link_header.as_deref().and_then(parse_max_id_from_link);
+
+        Ok((statuses, next_max_id))
     }
 }
 
@@ -83,159 +439,549 @@ impl MastodonClient for HttpMastodonClient {
             .context("Failed to parse Mastodon statuses response")
     }
 
-    async fn delete_status(&self, id: &str) -> Result<()> {
+    async fn list_pinned_statuses(&self, account_id: &str) -> Result<Vec<Status>> {
         self.client
-            .delete(format!("{}/api/v1/statuses/{}", self.instance, id))
+            .get(format!(
+                "{}/api/v1/accounts/{}/statuses?pinned=true",
+                self.instance, account_id
+            ))
             .header("Authorization", &self.auth)
             .send()
             .await?
-            .error_for_status()?;
-        Ok(())
+            .error_for_status()
+            .context("Failed to fetch pinned Mastodon statuses")?
+            .json()
+            .await
+            .context("Failed to parse pinned Mastodon statuses response")
     }
 
-    async fn list_favourites(&self, max_id: Option<&str>) -> Result<(Vec<Status>, Option<String>)> {
-        let mut url = format!("{}/api/v1/favourites?limit=40", self.instance);
-        if let Some(id) = max_id {
-            url.push_str(&format!("&max_id={id}"));
-        }
+    async fn get_status(&self, id: &str) -> Result<Status> {
+        self.client
+            .get(format!("{}/api/v1/statuses/{}", self.instance, id))
+            .header("Authorization", &self.auth)
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("Failed to fetch Mastodon status {id}"))?
+            .json()
+            .await
+            .context("Failed to parse Mastodon status response")
+    }
 
+    async fn status_exists(&self, id: &str) -> Result<bool> {
         let resp = self
             .client
-            .get(&url)
+            .get(format!("{}/api/v1/statuses/{}", self.instance, id))
             .header("Authorization", &self.auth)
             .send()
-            .await?
-            .error_for_status()
-            .context("Failed to fetch Mastodon favourites")?;
+            .await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        resp.error_for_status()
+            .with_context(|| format!("Failed to fetch Mastodon status {id}"))?;
+        Ok(true)
+    }
 
-        let link_header = resp
-            .headers()
-            .get("link")
-            .and_then(|v| v.to_str().ok())
-            .map(String::from);
+    async fn delete_status(&self, id: &str) -> Result<DeleteOutcome> {
+        let resp = self
+            .delete(format!("{}/api/v1/statuses/{}", self.instance, id))
+            .send()
+            .await?;
+        delete_outcome(resp)
+    }
 
-        let favourites: Vec<Status> = resp.json().await?;
-        let next_max_id = link_header.as_deref().and_then(parse_max_id_from_link);
+    async fn list_favourites(&self, max_id: Option<&str>) -> Result<(Vec<Status>, Option<String>)> {
+        self.list_linked("favourites", max_id).await
+    }
 
-        Ok((favourites, next_max_id))
+    async fn list_bookmarks(&self, max_id: Option<&str>) -> Result<(Vec<Status>, Option<String>)> {
+        self.list_linked("bookmarks", max_id).await
     }
 
-    async fn unfavourite(&self, id: &str) -> Result<()> {
-        self.client
+    async fn unfavourite(&self, id: &str) -> Result<DeleteOutcome> {
+        let resp = self
+            .client
             .post(format!(
                 "{}/api/v1/statuses/{}/unfavourite",
                 self.instance, id
             ))
             .header("Authorization", &self.auth)
             .send()
+            .await?;
+        delete_outcome(resp)
+    }
+
+    async fn unbookmark(&self, id: &str) -> Result<DeleteOutcome> {
+        let resp = self
+            .client
+            .post(format!(
+                "{}/api/v1/statuses/{}/unbookmark",
+                self.instance, id
+            ))
+            .header("Authorization", &self.auth)
+            .send()
+            .await?;
+        delete_outcome(resp)
+    }
+
+    async fn create_status(&self, text: &str) -> Result<()> {
+        self.client
+            .post(format!("{}/api/v1/statuses", self.instance))
+            .header("Authorization", &self.auth)
+            .json(&serde_json::json!({ "status": text }))
+            .send()
             .await?
-            .error_for_status()?;
+            .error_for_status()
+            .context("Failed to post Mastodon status")?;
         Ok(())
     }
+
+    async fn token_scopes(&self) -> Result<Option<Vec<String>>> {
+        let app: serde_json::Value = self
+            .client
+            .get(format!("{}/api/v1/apps/verify_credentials", self.instance))
+            .header("Authorization", &self.auth)
+            .send()
+            .await?
+            .error_for_status()
+            .context("Failed to verify Mastodon app credentials")?
+            .json()
+            .await?;
+        // An array on Mastodon, a space-separated string on some forks
+        Ok(match &app["scopes"] {
+            serde_json::Value::Array(scopes) => Some(
+                scopes
+                    .iter()
+                    .filter_map(|s| s.as_str().map(String::from))
+                    .collect(),
+            ),
+            serde_json::Value::String(scopes) => {
+                Some(scopes.split_whitespace().map(String::from).collect())
+            }
+            _ => None,
+        })
+    }
+}
+
+/// Scopes the favourites phase needs: listing favourites and removing them.
+const FAVOURITES_SCOPES: [&str; 2] = ["read:favourites", "write:favourites"];
+
+/// Which of `needed` aren't among `granted`. A top-level scope like `read`
+/// covers every `read:` scope under it.
+fn missing_scopes<'a>(granted: &[String], needed: &[&'a str]) -> Vec<&'a str> {
+    needed
+        .iter()
+        .copied()
+        .filter(|scope| {
+            let parent = scope.split(':').next().unwrap_or(scope);
+            !granted.iter().any(|g| g == scope || g == parent)
+        })
+        .collect()
+}
+
+/// Whether the token may clean up favourites: `Some(false)` when the
+/// instance reports its scopes and they fall short, `None` when it can't tell.
+async fn can_unfavourite(client: &(impl MastodonClient + Sync)) -> Option<bool> {
+    match client.token_scopes().await {
+        Ok(Some(granted)) => {
+            let missing = missing_scopes(&granted, &FAVOURITES_SCOPES);
+            if !missing.is_empty() {
+                warn!(
+                    "Skipping favourites: the access token lacks the {} scope. Grant it to clean up likes too.",
+                    missing.join(" and ")
+                );
+            }
+            Some(missing.is_empty())
+        }
+        Ok(None) => None,
+        Err(e) => {
+            info!("Couldn't check the access token's scopes: {e:#}");
+            None
+        }
+    }
+}
+
+/// A random (version 4) UUID, e.g. `7f3c5e1a-9b2d-4c8e-a1f0-3d6b8e2c4a19`.
+fn idempotency_key() -> String {
+    let bits = fastrand::u128(..) & !(0xf000 << 64) & !(0xc << 60) | (0x4000 << 64) | (0x8 << 60);
+    let hex = format!("{bits:032x}");
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
 }
 
 pub async fn delete_old_posts(
     client: &(impl MastodonClient + Sync),
     config: &Config,
-    keep_list: &HashSet<String>,
-) -> Result<()> {
+    keep_list: &KeepList,
+) -> Result<PlatformSummary> {
+    let mut summary = PlatformSummary::default();
+
     // Verify credentials and get account ID
-    let account = client.verify_credentials().await?;
+    let account = verify(client, config).await?;
     info!("Authenticated as account {}", account.id);
 
+    let bookmarked = if config.keep_bookmarked {
+        let ids = fetch_bookmark_ids(client).await?;
+        info!("Found {} bookmarked statuses to keep", ids.len());
+        ids
+    } else {
+        HashSet::new()
+    };
+
+    let pinned = pinned_ids(client, &account.id, config).await?;
+
+    let mut budget = load_budget(config)?;
+
+    // Check the token's scopes up front rather than guessing from a failed fetch
+    let favourites_scope = if config.delete_likes {
+        can_unfavourite(client).await
+    } else {
+        None
+    };
+    let delete_favourites = config.delete_likes && favourites_scope != Some(false);
+
+    // Only a full sweep is checkpointed; dry runs never are
+    let checkpoint_path = config
+        .phase_state_file
+        .as_deref()
+        .filter(|_| !config.dry_run);
+    let mut checkpoints = match checkpoint_path {
+        Some(path) => PhaseCheckpoints::load(path)?,
+        None => PhaseCheckpoints::default(),
+    };
+    // Without favourites to go on to, statuses are swept as usual
+    let statuses_done = delete_favourites && checkpoints.is_complete(phases::STATUSES);
+    if statuses_done {
+        info!("Statuses were fully swept by an earlier run, going straight to favourites");
+        info!("Remaining Mastodon posts aren't recounted, their count is from the earlier run");
+        if let Some(csv) = &config.remaining_csv {
+            csv.carry_over("mastodon");
+        }
+    }
+
     let mut max_id: Option<String> = None;
     let mut deleted = 0u64;
     let mut skipped_pinned = 0u64;
     let mut skipped_kept = 0u64;
     let mut skipped_reposts = 0u64;
-
-    loop {
-        let statuses = client.list_statuses(&account.id, max_id.as_deref()).await?;
+    let mut skipped_bookmarked = 0u64;
+    let mut skipped_language = 0u64;
+    let mut skipped_not_plain = 0u64;
+    let mut skipped_app = 0u64;
+    let mut skipped_edited = 0u64;
+    let mut skipped_keyword = 0u64;
+    let mut skipped_replies = 0u64;
+    let mut skipped_sampled = 0u64;
+    let mut skipped_monthly = 0u64;
+    let mut already_gone = 0u64;
+    let mut remaining = Remaining::default();
+    let mut plan = GroupedPlan::default();
+
+    let mut rate_limited = false;
+    // Set once the listing runs out, rather than the budget, rate limit or Ctrl-C
+    let mut statuses_complete = false;
+    let pace = config.delay.unwrap_or(DEFAULT_DELAY);
+    let mut adaptive = config.adaptive_delay.map(|b| AdaptiveDelay::new(pace, b));
+    let mut errors = ErrorTracker::new(config.error_limits);
+    let mut forbidden = ForbiddenStreak::default();
+    // OLDEST_FIRST lists every status up front, then hands them out a page at a time
+    let mut oldest_first: Option<std::vec::IntoIter<Status>> = None;
+    let mut monthly = config
+        .keep_one_per_month
+        .then(|| MonthlyKeep::new(config.display_tz));
+
+    'statuses: loop {
+        let statuses: Vec<Status> = if statuses_done {
+            Vec::new()
+        } else if config.oldest_first {
+            let buffered = match &mut oldest_first {
+                Some(buffered) => buffered,
+                None => oldest_first.insert(
+                    statuses_oldest_first(client, &account.id)
+                        .await?
+                        .into_iter(),
+                ),
+            };
+            buffered.by_ref().take(PAGE_SIZE).collect()
+        } else {
+            client.list_statuses(&account.id, max_id.as_deref()).await?
+        };
 
         if statuses.is_empty() {
+            statuses_complete = true;
             break;
         }
 
+        // OLDEST_FIRST pages aren't listing pages, so there's none to re-list
+        let page_max_id = (config.verify_each_page && !config.oldest_first).then(|| max_id.clone());
+        // VERIFY_EACH_PAGE: what this page deleted, to look for once it's done
+        let mut page_deleted: Vec<(&Status, chrono::DateTime<Utc>)> = Vec::new();
         max_id = statuses.last().map(|s| s.id.clone());
 
         for status in &statuses {
-            let post_time = match crate::parse_timestamp(&status.created_at) {
-                Ok(t) => t.with_timezone(&chrono::Utc),
-                Err(e) => {
-                    warn!("Skipping status {}: {e}", status.id);
+            // Recent statuses never reach the keep check, so match them here
+            // too or their entries would look unused
+            if config.keep_audit {
+                keep_list.matches("mastodon", &status.id);
+            }
+            let post_time = match status_time(status) {
+                Ok(t) => t,
+                Err(decision) => {
+                    summary.skipped.add(SkipReason::UnparseableTimestamp);
+                    warn!("Skipping status {}: {decision}", status.id);
                     continue;
                 }
             };
-
-            if post_time >= config.cutoff {
-                continue;
+            if config.keep_audit {
+                keep_list.matches_time("mastodon", post_time);
             }
 
-            // Skip reblogs if delete_reposts is disabled
-            if status.reblog.is_some() && !config.delete_reposts {
-                skipped_reposts += 1;
-                continue;
+            let forced = match evaluate(
+                status,
+                post_time,
+                config,
+                keep_list,
+                is_pinned(status, &pinned),
+                bookmarked.contains(&status.id),
+            ) {
+                Decision::Delete => false,
+                Decision::ForceDelete => true,
+                decision => {
+                    remaining.observe(post_time);
+                    if !matches!(decision, Decision::NewerThanCutoff) {
+                        config.counters.add_skipped();
+                    } else if let Some(csv) = &config.remaining_csv {
+                        let kind = if status.reblog.is_some() {
+                            "reblog"
+                        } else {
+                            "post"
+                        };
+                        csv.record("mastodon", kind, &status.id, post_time);
+                    }
+                    match decision {
+                        Decision::RepostsDisabled => skipped_reposts += 1,
+                        Decision::LanguageFiltered => skipped_language += 1,
+                        Decision::HasMediaOrLinks => skipped_not_plain += 1,
+                        Decision::AppFiltered => skipped_app += 1,
+                        Decision::Pinned => {
+                            skipped_pinned += 1;
+                            summary.skipped.add(SkipReason::Pinned);
+                            warn!(
+                                "Skipping pinned post: {}. To keep it permanently, add to your keep file: mastodon:{}",
+                                status.id, status.id
+                            );
+                        }
+                        Decision::Kept(by, _) => {
+                            skipped_kept += 1;
+                            summary.skipped.add_kept(by);
+                            info!("Protected, skipping: {}", status.id);
+                        }
+                        Decision::Bookmarked => {
+                            skipped_bookmarked += 1;
+                            info!("Bookmarked, skipping: {}", status.id);
+                        }
+                        Decision::KeepKeyword => {
+                            skipped_keyword += 1;
+                            info!("Contains a keep keyword, skipping: {}", status.id);
+                        }
+                        Decision::HasReplies => {
+                            skipped_replies += 1;
+                            info!("Has replies, skipping: {}", status.id);
+                        }
+                        Decision::RecentlyEdited => {
+                            skipped_edited += 1;
+                            info!("Recently edited, skipping: {}", status.id);
+                        }
+                        Decision::Sampled => {
+                            skipped_sampled += 1;
+                            summary.skipped.add(SkipReason::Sampled);
+                            info!("Kept in the sample, skipping: {}", status.id);
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+            };
+
+            let is_reblog = status.reblog.is_some();
+            let label = if is_reblog { "reblog" } else { "post" };
+
+            // Boosts are someone else's post, so they never stand for a month
+            if let Some(monthly) = monthly.as_mut().filter(|_| !forced && !is_reblog) {
+                if monthly.keeps(post_time) {
+                    remaining.observe(post_time);
+                    skipped_monthly += 1;
+                    summary.skipped.add(SkipReason::OnePerMonth);
+                    config.counters.add_skipped();
+                    info!("Newest post of its month, keeping: {}", status.id);
+                    continue;
+                }
             }
 
-            if status.pinned && !config.delete_pinned {
-                skipped_pinned += 1;
-                warn!(
-                    "Skipping pinned post: {}. To keep it permanently, add to your keep file: mastodon:{}",
-                    status.id, status.id
-                );
+            if config.dry_run {
+                if config.dry_run_grouped {
+                    plan.add(
+                        post_time,
+                        format!("{label}: {} ({})", status.id, status.created_at),
+                    );
+                } else {
+                    info!(
+                        "[DRY RUN] Would delete {label}: {} ({})",
+                        status.id, status.created_at
+                    );
+                }
+                deleted += 1;
+                config.counters.add_deleted();
+                if is_reblog {
+                    summary.reposts += 1;
+                } else {
+                    summary.posts += 1;
+                }
+                if let Some(report) = &config.report {
+                    report.record(
+                        "mastodon",
+                        label,
+                        &status.id,
+                        Some(post_time),
+                        Action::Delete,
+                    );
+                }
                 continue;
             }
 
-            if is_protected(keep_list, "mastodon", &status.id) {
-                skipped_kept += 1;
-                info!("Protected, skipping: {}", status.id);
-                continue;
+            if budget_exhausted(budget.as_ref()) {
+                warn!("Deletion budget exhausted by recent runs. Remaining posts will be cleaned up on the next run.");
+                break 'statuses;
             }
 
-            let label = if status.reblog.is_some() {
-                "reblog"
-            } else {
-                "post"
+            let mut retries = 0;
+            let result = loop {
+                let started = tokio::time::Instant::now();
+                let result = client.delete_status(&status.id).await;
+                if let Some(adaptive) = adaptive.as_mut() {
+                    adaptive.observe(started.elapsed());
+                }
+                if !wait_retry_after(&result, &mut retries, config).await {
+                    break result;
+                }
             };
-
-            if config.dry_run {
-                info!(
-                    "[DRY RUN] Would delete {label}: {} ({})",
-                    status.id, status.created_at
-                );
-                deleted += 1;
-                continue;
+            if result.is_ok() {
+                errors.success();
+                forbidden.reset();
             }
-
-            match client.delete_status(&status.id).await {
-                Ok(()) => {
+            match result {
+                Ok(DeleteOutcome::Deleted) => {
+                    if let Some(budget) = budget.as_mut() {
+                        budget.record(Utc::now())?;
+                    }
                     deleted += 1;
+                    config.counters.add_deleted();
+                    if is_reblog {
+                        summary.reposts += 1;
+                    } else {
+                        summary.posts += 1;
+                    }
+                    page_deleted.push((status, post_time));
+                    if let Some(report) = &config.report {
+                        report.record(
+                            "mastodon",
+                            label,
+                            &status.id,
+                            Some(post_time),
+                            Action::Delete,
+                        );
+                    }
                     info!("Deleted {label}: {} ({})", status.id, status.created_at);
                 }
+                Ok(DeleteOutcome::AlreadyGone) => {
+                    if let Some(budget) = budget.as_mut() {
+                        budget.record(Utc::now())?;
+                    }
+                    already_gone += 1;
+                    info!("Already gone: {}", status.id);
+                }
                 Err(e) => {
-                    let msg = e.to_string();
-                    if msg.contains("429") {
+                    remaining.observe(post_time);
+                    if e.is::<RateLimited>() {
                         warn!("Rate limited — Mastodon allows 30 deletions per 30 minutes. Remaining posts will be cleaned up on the next run.");
+                        rate_limited = true;
                         break;
                     }
+                    config.counters.add_error();
                     warn!("Failed to delete {}: {e}", status.id);
+                    forbidden.observe(&e)?;
+                    errors.failure()?;
                 }
             }
 
             // Mastodon rate-limits deletions to 30 per 30 minutes
-            tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+            if !config
+                .shutdown
+                .sleep(adaptive.as_ref().map_or(pace, AdaptiveDelay::current))
+                .await
+            {
+                break;
+            }
+        }
+
+        if let Some(page_max_id) = page_max_id.filter(|_| !page_deleted.is_empty()) {
+            match client
+                .list_statuses(&account.id, page_max_id.as_deref())
+                .await
+            {
+                Ok(listed) => {
+                    let listed: HashSet<&str> = listed.iter().map(|s| s.id.as_str()).collect();
+                    let survivors: Vec<_> = page_deleted
+                        .iter()
+                        .filter(|(status, _)| listed.contains(status.id.as_str()))
+                        .collect();
+                    for (status, post_time) in &survivors {
+                        remaining.observe(*post_time);
+                        warn!("Deleted status is still listed: {}", status.id);
+                    }
+                    if survivors.is_empty() {
+                        info!(
+                            "Verified this page's {} deleted statuses are gone",
+                            page_deleted.len()
+                        );
+                    }
+                }
+                Err(e) => warn!("Could not re-list the page to verify its deletions: {e:#}"),
+            }
+        }
+
+        if rate_limited || config.shutdown.is_triggered() {
+            break;
         }
     }
 
+    summary.remaining = (!statuses_done).then_some(remaining);
+
+    // Favourites may not get through this run, so let the next one skip ahead.
+    // Without favourites to get to there's nothing to resume.
+    if let (Some(path), true, true) = (checkpoint_path, statuses_complete, delete_favourites) {
+        checkpoints.complete(phases::STATUSES);
+        checkpoints.save(path)?;
+    }
+
     info!(
-        "Mastodon statuses: deleted {deleted}, skipped {skipped_pinned} pinned, skipped {skipped_kept} kept, skipped {skipped_reposts} reposts"
+        "Mastodon statuses: deleted {deleted}, {already_gone} already gone, skipped {skipped_pinned} pinned, skipped {skipped_kept} kept, skipped {skipped_bookmarked} bookmarked, skipped {skipped_edited} recently edited, skipped {skipped_keyword} by keyword, skipped {skipped_replies} with replies, skipped {skipped_sampled} sampled, skipped {skipped_monthly} one per month, skipped {skipped_language} by language, skipped {skipped_not_plain} with media or links, skipped {skipped_app} from other apps, skipped {skipped_reposts} reposts"
     );
 
     // Delete old favourites
-    if config.delete_likes {
+    if delete_favourites && !config.shutdown.is_triggered() {
         let mut fav_max_id: Option<String> = None;
         let mut fav_deleted = 0u64;
         let mut fav_skipped_kept = 0u64;
+        let mut fav_already_gone = 0u64;
+        let mut fav_complete = false;
 
         'favourites: loop {
             let (favourites, next_max_id) = match client
@@ -243,6 +989,10 @@ pub async fn delete_old_posts(
                 .await
             {
                 Ok(result) => result,
+                Err(e) if favourites_scope == Some(true) => {
+                    warn!("Could not fetch favourites: {e:#}");
+                    break;
+                }
                 Err(e) => {
                     warn!("Could not fetch favourites (token may lack read:favourites scope): {e}");
                     break;
@@ -250,6 +1000,7 @@ pub async fn delete_old_posts(
             };
 
             if favourites.is_empty() {
+                fav_complete = true;
                 break;
             }
 
@@ -257,6 +1008,7 @@ pub async fn delete_old_posts(
                 let post_time = match crate::parse_timestamp(&status.created_at) {
                     Ok(t) => t.with_timezone(&chrono::Utc),
                     Err(e) => {
+                        summary.skipped.add(SkipReason::UnparseableTimestamp);
                         warn!("Skipping favourite {}: {e}", status.id);
                         continue;
                     }
@@ -266,52 +1018,709 @@ pub async fn delete_old_posts(
                     continue;
                 }
 
-                if is_protected(keep_list, "mastodon", &status.id) {
+                if keep_list.is_protected("mastodon", &status.id) {
                     fav_skipped_kept += 1;
+                    config.counters.add_skipped();
+                    summary.skipped.add_kept(KeptBy::Rkey);
                     info!("Protected favourite, skipping: {}", status.id);
                     continue;
                 }
 
                 if config.dry_run {
-                    info!(
-                        "[DRY RUN] Would unfavourite: {} ({})",
-                        status.id, status.created_at
-                    );
+                    if config.dry_run_grouped {
+                        plan.add(
+                            post_time,
+                            format!("favourite: {} ({})", status.id, status.created_at),
+                        );
+                    } else {
+                        info!(
+                            "[DRY RUN] Would unfavourite: {} ({})",
+                            status.id, status.created_at
+                        );
+                    }
                     fav_deleted += 1;
+                    config.counters.add_deleted();
+                    if let Some(report) = &config.report {
+                        report.record(
+                            "mastodon",
+                            "favourite",
+                            &status.id,
+                            Some(post_time),
+                            Action::Unfavourite,
+                        );
+                    }
                     continue;
                 }
 
-                match client.unfavourite(&status.id).await {
-                    Ok(()) => {
+                if budget_exhausted(budget.as_ref()) {
+                    warn!("Deletion budget exhausted by recent runs. Remaining favourites will be cleaned up on the next run.");
+                    break 'favourites;
+                }
+
+                let mut retries = 0;
+                let result = loop {
+                    let started = tokio::time::Instant::now();
+                    let result = client.unfavourite(&status.id).await;
+                    if let Some(adaptive) = adaptive.as_mut() {
+                        adaptive.observe(started.elapsed());
+                    }
+                    if !wait_retry_after(&result, &mut retries, config).await {
+                        break result;
+                    }
+                };
+                if result.is_ok() {
+                    errors.success();
+                    forbidden.reset();
+                }
+                match result {
+                    Ok(DeleteOutcome::Deleted) => {
+                        if let Some(budget) = budget.as_mut() {
+                            budget.record(Utc::now())?;
+                        }
                         fav_deleted += 1;
+                        config.counters.add_deleted();
+                        if let Some(report) = &config.report {
+                            report.record(
+                                "mastodon",
+                                "favourite",
+                                &status.id,
+                                Some(post_time),
+                                Action::Unfavourite,
+                            );
+                        }
                         info!("Unfavourited: {} ({})", status.id, status.created_at);
                     }
+                    Ok(DeleteOutcome::AlreadyGone) => {
+                        if let Some(budget) = budget.as_mut() {
+                            budget.record(Utc::now())?;
+                        }
+                        fav_already_gone += 1;
+                        info!("Favourited status already gone: {}", status.id);
+                    }
                     Err(e) => {
-                        let msg = e.to_string();
-                        if msg.contains("429") {
+                        if e.is::<RateLimited>() {
                             warn!("Rate limited — Mastodon allows 30 deletions per 30 minutes. Remaining favourites will be cleaned up on the next run.");
                             break 'favourites;
                         }
+                        config.counters.add_error();
                         warn!("Failed to unfavourite {}: {e}", status.id);
+                        forbidden.observe(&e)?;
+                        errors.failure()?;
                     }
                 }
 
                 // Mastodon rate-limits deletions to 30 per 30 minutes
-                tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+                if !config
+                    .shutdown
+                    .sleep(adaptive.as_ref().map_or(pace, AdaptiveDelay::current))
+                    .await
+                {
+                    break 'favourites;
+                }
             }
 
             fav_max_id = next_max_id;
             if fav_max_id.is_none() {
+                fav_complete = true;
                 break 'favourites;
             }
         }
 
-        info!("Mastodon favourites: deleted {fav_deleted}, skipped {fav_skipped_kept} kept");
+        // Both phases are done, so the next run starts over from statuses
+        if let (Some(path), true) = (checkpoint_path, fav_complete) {
+            if checkpoints.is_complete(phases::STATUSES) {
+                checkpoints.clear();
+                checkpoints.save(path)?;
+            }
+        }
+
+        info!("Mastodon favourites: deleted {fav_deleted}, {fav_already_gone} already gone, skipped {fav_skipped_kept} kept");
+        summary.likes = fav_deleted;
+    }
+
+    if config.delete_account_data && !config.shutdown.is_triggered() {
+        summary.other = delete_bookmarks(client, config, &mut plan, &mut forbidden).await?;
+    }
+
+    if config.dry_run_grouped {
+        plan.log("Mastodon");
+    }
+
+    if config.post_summary && summary.total() > 0 {
+        let text = summary.status_text(config.cutoff);
+        if config.dry_run {
+            info!("[DRY RUN] Would post summary: {text}");
+        } else {
+            client.create_status(&text).await?;
+            info!("Posted summary: {text}");
+        }
+    }
+
+    Ok(summary)
+}
+
+/// `DELETE_ACCOUNT_DATA`'s last Mastodon phase: remove every bookmark.
+/// Bookmarks have no age of their own, so there's no cutoff to check.
+async fn delete_bookmarks(
+    client: &(impl MastodonClient + Sync),
+    config: &Config,
+    plan: &mut GroupedPlan,
+    forbidden: &mut ForbiddenStreak,
+) -> Result<u64> {
+    let pace = config.delay.unwrap_or(DEFAULT_DELAY);
+    let mut errors = ErrorTracker::new(config.error_limits);
+    let mut removed = 0u64;
+    let mut already_gone = 0u64;
+    let mut max_id: Option<String> = None;
+
+    'bookmarks: loop {
+        let (bookmarks, next_max_id) = match client.list_bookmarks(max_id.as_deref()).await {
+            Ok(page) => page,
+            Err(e) => {
+                warn!("Could not fetch bookmarks (token may lack read:bookmarks scope): {e}");
+                break;
+            }
+        };
+        for status in &bookmarks {
+            let created_at = crate::parse_timestamp(&status.created_at)
+                .ok()
+                .map(|t| t.with_timezone(&Utc));
+            if config.dry_run {
+                match created_at {
+                    Some(t) if config.dry_run_grouped => plan.add(
+                        t,
+                        format!("bookmark: {} ({})", status.id, status.created_at),
+                    ),
+                    _ => info!("[DRY RUN] Would remove bookmark: {}", status.id),
+                }
+                removed += 1;
+                config.counters.add_deleted();
+                if let Some(report) = &config.report {
+                    report.record(
+                        "mastodon",
+                        "bookmark",
+                        &status.id,
+                        created_at,
+                        Action::Unbookmark,
+                    );
+                }
+                continue;
+            }
+
+            let mut retries = 0;
+            let result = loop {
+                let result = client.unbookmark(&status.id).await;
+                if !wait_retry_after(&result, &mut retries, config).await {
+                    break result;
+                }
+            };
+            match result {
+                Ok(DeleteOutcome::Deleted) => {
+                    errors.success();
+                    forbidden.reset();
+                    removed += 1;
+                    config.counters.add_deleted();
+                    if let Some(report) = &config.report {
+                        report.record(
+                            "mastodon",
+                            "bookmark",
+                            &status.id,
+                            created_at,
+                            Action::Unbookmark,
+                        );
+                    }
+                    info!("Removed bookmark: {}", status.id);
+                }
+                Ok(DeleteOutcome::AlreadyGone) => {
+                    errors.success();
+                    forbidden.reset();
+                    already_gone += 1;
+                }
+                Err(e) if e.is::<RateLimited>() => {
+                    warn!("Rate limited. Remaining bookmarks will be removed on the next run.");
+                    break 'bookmarks;
+                }
+                Err(e) => {
+                    config.counters.add_error();
+                    warn!("Failed to remove bookmark {}: {e}", status.id);
+                    forbidden.observe(&e)?;
+                    errors.failure()?;
+                }
+            }
+
+            if !config.shutdown.sleep(pace).await {
+                break 'bookmarks;
+            }
+        }
+        max_id = next_max_id;
+        if bookmarks.is_empty() || max_id.is_none() {
+            break;
+        }
+    }
+
+    info!("Mastodon bookmarks: removed {removed}, {already_gone} already gone");
+    Ok(removed)
+}
+
+/// `created_at` of a status in UTC, or why a status without a usable one is skipped.
+fn status_time(status: &Status) -> Result<DateTime<Utc>, Decision> {
+    crate::parse_timestamp(&status.created_at)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|_| Decision::UnparseableTimestamp)
+}
+
+/// Run a status created at `post_time` through the cutoff, filter and keep
+/// checks of a sweep, in the same order, without side effects.
+fn evaluate(
+    status: &Status,
+    post_time: DateTime<Utc>,
+    config: &Config,
+    keep_list: &KeepList,
+    pinned: bool,
+    bookmarked: bool,
+) -> Decision {
+    if config.force_delete.is_protected("mastodon", &status.id) {
+        return Decision::ForceDelete;
+    }
+    if post_time >= config.cutoff && !(config.purge_all_reposts && status.reblog.is_some()) {
+        return Decision::NewerThanCutoff;
+    }
+    if status.reblog.is_some() && !config.delete_reposts {
+        return Decision::RepostsDisabled;
+    }
+    // Language filters apply to your own posts; a reblog's language
+    // belongs to someone else's post
+    if status.reblog.is_none() && !config.allows_language(status.language.as_deref()) {
+        return Decision::LanguageFiltered;
+    }
+    if config.delete_plain_text_only && status.reblog.is_none() && !status.is_plain_text() {
+        return Decision::HasMediaOrLinks;
+    }
+    if status.reblog.is_none() && !from_listed_app(status, config) {
+        return Decision::AppFiltered;
+    }
+    if pinned && !config.delete_pinned {
+        return Decision::Pinned;
+    }
+    if let Some(entry) = keep_list.matches("mastodon", &status.id) {
+        return Decision::Kept(KeptBy::Rkey, entry.to_string());
+    }
+    if let Some(entry) = keep_list.matches_time("mastodon", post_time) {
+        return Decision::Kept(KeptBy::Date, entry.to_string());
+    }
+    if bookmarked {
+        return Decision::Bookmarked;
+    }
+    if status.reblog.is_none() && has_keep_keyword(status, config) {
+        return Decision::KeepKeyword;
+    }
+    // `replies_count` includes your own replies, e.g. a thread you continued
+    if config.keep_if_has_replies && status.reblog.is_none() && status.replies_count > 0 {
+        return Decision::HasReplies;
+    }
+    if recently_edited(status, config) {
+        return Decision::RecentlyEdited;
+    }
+    if status.reblog.is_none() && config.keep_sample.is_some_and(|s| s.keeps(&status.id)) {
+        return Decision::Sampled;
+    }
+    Decision::Delete
+}
+
+/// Whether `DELETE_FROM_APPS` is unset or names the app `status` was posted from.
+fn from_listed_app(status: &Status, config: &Config) -> bool {
+    config.delete_from_apps.is_empty()
+        || status.application.as_ref().is_some_and(|app| {
+            config
+                .delete_from_apps
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(&app.name))
+        })
+}
+
+/// `verify_credentials`, retried once if it fails transiently.
+async fn verify(client: &(impl MastodonClient + Sync), config: &Config) -> Result<Account> {
+    crate::retry_login("Mastodon", &config.shutdown, || client.verify_credentials()).await
+}
+
+/// `EXPLAIN_ID` for Mastodon: fetch one status and decide it the way a sweep would.
+pub async fn explain(
+    client: &(impl MastodonClient + Sync),
+    id: &str,
+    config: &Config,
+    keep_list: &KeepList,
+) -> Result<Decision> {
+    let status = client.get_status(id).await?;
+    let post_time = match status_time(&status) {
+        Ok(t) => t,
+        Err(decision) => return Ok(decision),
+    };
+    // A status fetched on its own isn't always flagged as pinned
+    let account = verify(client, config).await?;
+    let pinned = pinned_ids(client, &account.id, config).await?;
+    Ok(evaluate(
+        &status,
+        post_time,
+        config,
+        keep_list,
+        is_pinned(&status, &pinned),
+        config.keep_bookmarked && status.bookmarked,
+    ))
+}
+
+/// `DELETE_FROM_EXPORT` for Mastodon: delete exactly the statuses in `ids`,
+/// whatever their age, unless another rule of a sweep keeps them.
+pub async fn delete_listed(
+    client: &(impl MastodonClient + Sync),
+    ids: &[String],
+    config: &Config,
+    keep_list: &KeepList,
+) -> Result<PlatformSummary> {
+    let mut summary = PlatformSummary::default();
+    let account = verify(client, config).await?;
+    info!("Authenticated as account {}", account.id);
+    let pinned = pinned_ids(client, &account.id, config).await?;
+    let mut budget = load_budget(config)?;
+    let pace = config.delay.unwrap_or(DEFAULT_DELAY);
+    let mut errors = ErrorTracker::new(config.error_limits);
+    let mut already_gone = 0u64;
+    // The age cutoff is the one rule the export replaces
+    let export_config = Config {
+        cutoff: DateTime::<Utc>::MAX_UTC,
+        ..config.clone()
+    };
+
+    for id in ids {
+        if config.shutdown.is_triggered() {
+            break;
+        }
+        let status = match client.get_status(id).await {
+            Ok(status) => status,
+            Err(_) if matches!(client.status_exists(id).await, Ok(false)) => {
+                already_gone += 1;
+                info!("Already gone: {id}");
+                continue;
+            }
+            Err(e) => {
+                config.counters.add_error();
+                warn!("Failed to fetch exported status {id}: {e:#}");
+                errors.failure()?;
+                continue;
+            }
+        };
+        let post_time = match status_time(&status) {
+            Ok(t) => t,
+            Err(decision) => {
+                config.counters.add_skipped();
+                warn!("Skipping exported status {id}: {decision}");
+                continue;
+            }
+        };
+        let decision = evaluate(
+            &status,
+            post_time,
+            &export_config,
+            keep_list,
+            is_pinned(&status, &pinned),
+            config.keep_bookmarked && status.bookmarked,
+        );
+        if !matches!(decision, Decision::Delete | Decision::ForceDelete) {
+            match decision {
+                Decision::Pinned => summary.skipped.add(SkipReason::Pinned),
+                Decision::Kept(by, _) => summary.skipped.add_kept(by),
+                Decision::Sampled => summary.skipped.add(SkipReason::Sampled),
+                _ => {}
+            }
+            config.counters.add_skipped();
+            info!("Skipping exported status {id}: {decision}");
+            continue;
+        }
+        let (kind, deleted) = if status.reblog.is_some() {
+            ("reblog", &mut summary.reposts)
+        } else {
+            ("post", &mut summary.posts)
+        };
+        if config.dry_run {
+            info!("[DRY RUN] Would delete exported status: {id}");
+            *deleted += 1;
+            config.counters.add_deleted();
+            if let Some(report) = &config.report {
+                report.record("mastodon", kind, id, Some(post_time), Action::Delete);
+            }
+            continue;
+        }
+        if budget_exhausted(budget.as_ref()) {
+            warn!("Deletion budget exhausted by recent runs. Remaining exported statuses will be cleaned up on the next run.");
+            break;
+        }
+
+        let mut retries = 0;
+        let result = loop {
+            let result = client.delete_status(id).await;
+            if !wait_retry_after(&result, &mut retries, config).await {
+                break result;
+            }
+        };
+        if result.is_ok() {
+            errors.success();
+            if let Some(budget) = budget.as_mut() {
+                budget.record(Utc::now())?;
+            }
+        }
+        match result {
+            Ok(DeleteOutcome::Deleted) => {
+                *deleted += 1;
+                config.counters.add_deleted();
+                if let Some(report) = &config.report {
+                    report.record("mastodon", kind, id, Some(post_time), Action::Delete);
+                }
+                info!("Deleted exported status: {id}");
+            }
+            Ok(DeleteOutcome::AlreadyGone) => {
+                already_gone += 1;
+                info!("Already gone: {id}");
+            }
+            Err(e) => {
+                if e.is::<RateLimited>() {
+                    warn!("Rate limited — Mastodon allows 30 deletions per 30 minutes. Remaining exported statuses will be cleaned up on the next run.");
+                    break;
+                }
+                config.counters.add_error();
+                warn!("Failed to delete {id}: {e}");
+                errors.failure()?;
+            }
+        }
+
+        if !config.shutdown.sleep(pace).await {
+            break;
+        }
     }
 
+    info!(
+        "Mastodon export: deleted {} of {} listed, {already_gone} already gone, {}",
+        summary.total(),
+        ids.len(),
+        summary.skipped.text()
+    );
+    Ok(summary)
+}
+
+/// `STATS_JSON` for Mastodon: tally every status by age and by whether a run
+/// would delete it.
+pub async fn stats(
+    client: &(impl MastodonClient + Sync),
+    config: &Config,
+    keep_list: &KeepList,
+) -> Result<PlatformStats> {
+    let now = Utc::now();
+    let mut stats = PlatformStats::new(config.cutoff);
+    for_each_decision(client, config, keep_list, |_, decided| match decided {
+        Some((post_time, decision)) => stats.add(post_time, &decision, now),
+        None => stats.undated += 1,
+    })
+    .await?;
+    Ok(stats)
+}
+
+/// `FULL_PLAN_JSON` for Mastodon: every status old enough to delete, with
+/// what a run would do with it and why.
+pub async fn full_plan(
+    client: &(impl MastodonClient + Sync),
+    config: &Config,
+    keep_list: &KeepList,
+) -> Result<Vec<PlanEntry>> {
+    let mut entries = Vec::new();
+    for_each_decision(client, config, keep_list, |status, decided| {
+        if let Some((post_time, decision)) = decided {
+            let kind = if status.reblog.is_some() {
+                "reblog"
+            } else {
+                "post"
+            };
+            entries.extend(PlanEntry::new(&status.id, kind, post_time, &decision));
+        }
+    })
+    .await?;
+    Ok(entries)
+}
+
+/// Decide every status the way a sweep would, without deleting anything.
+/// Statuses without a usable timestamp reach `visit` with `None`.
+async fn for_each_decision(
+    client: &(impl MastodonClient + Sync),
+    config: &Config,
+    keep_list: &KeepList,
+    mut visit: impl FnMut(&Status, Option<(DateTime<Utc>, Decision)>),
+) -> Result<()> {
+    let account = verify(client, config).await?;
+    let bookmarked = if config.keep_bookmarked {
+        fetch_bookmark_ids(client).await?
+    } else {
+        HashSet::new()
+    };
+    let pinned = pinned_ids(client, &account.id, config).await?;
+    let mut max_id: Option<String> = None;
+    loop {
+        let page = client.list_statuses(&account.id, max_id.as_deref()).await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        max_id = Some(last.id.clone());
+        for status in &page {
+            let Ok(post_time) = status_time(status) else {
+                visit(status, None);
+                continue;
+            };
+            let decision = evaluate(
+                status,
+                post_time,
+                config,
+                keep_list,
+                is_pinned(status, &pinned),
+                bookmarked.contains(&status.id),
+            );
+            visit(status, Some((post_time, decision)));
+        }
+    }
     Ok(())
 }
 
+/// `PRUNE_KEEP_FILE` for Mastodon: which of the status `ids` no longer exist.
+/// One that can't be looked up counts as existing.
+pub async fn missing_statuses(
+    client: &(impl MastodonClient + Sync),
+    ids: &[&str],
+) -> Result<HashSet<String>> {
+    let mut missing = HashSet::new();
+    for &id in ids {
+        match client.status_exists(id).await {
+            Ok(true) => {}
+            Ok(false) => {
+                missing.insert(id.to_string());
+            }
+            Err(e) => warn!("Could not check keep entry {id}, keeping it: {e:#}"),
+        }
+    }
+    Ok(missing)
+}
+
+/// A 404 means the status is already gone, which is as good as deleted.
+fn delete_outcome(resp: reqwest::Response) -> Result<DeleteOutcome> {
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(DeleteOutcome::AlreadyGone);
+    }
+    if resp.status() == reqwest::StatusCode::FORBIDDEN {
+        return Err(Forbidden.into());
+    }
+    if resp.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(RateLimited::from_response(&resp).into());
+    }
+    resp.error_for_status()?;
+    Ok(DeleteOutcome::Deleted)
+}
+
+/// Whether the rendered text of `status` contains any `KEEP_KEYWORDS` entry,
+/// ignoring case.
+fn has_keep_keyword(status: &Status, config: &Config) -> bool {
+    if config.keep_keywords.is_empty() {
+        return false;
+    }
+    let text = status.text().to_lowercase();
+    config
+        .keep_keywords
+        .iter()
+        .any(|keyword| text.contains(&keyword.to_lowercase()))
+}
+
+/// Whether `status` was edited within the `KEEP_RECENTLY_EDITED_DAYS` window.
+fn recently_edited(status: &Status, config: &Config) -> bool {
+    let (Some(since), Some(edited_at)) = (config.keep_edited_since, &status.edited_at) else {
+        return false;
+    };
+    crate::parse_timestamp(edited_at).is_ok_and(|t| t >= since)
+}
+
+/// The persisted deletion budget, with `RATE_LIMIT_STATE_FILE`.
+fn load_budget(config: &Config) -> Result<Option<DeletionBudget>> {
+    let Some(path) = &config.rate_limit_state_file else {
+        return Ok(None);
+    };
+    let budget = DeletionBudget::load(path, DELETE_LIMIT, DELETE_WINDOW)?;
+    info!(
+        "Deletion budget: {} of {DELETE_LIMIT} remaining in the current window",
+        budget.remaining(Utc::now())
+    );
+    Ok(Some(budget))
+}
+
+fn budget_exhausted(budget: Option<&DeletionBudget>) -> bool {
+    budget.is_some_and(|b| b.remaining(Utc::now()) == 0)
+}
+
+/// Every status on the account, oldest first, for `OLDEST_FIRST`. The API
+/// only pages newest first, so this walks all the way back before returning.
+async fn statuses_oldest_first(
+    client: &(impl MastodonClient + Sync),
+    account_id: &str,
+) -> Result<Vec<Status>> {
+    let mut statuses = Vec::new();
+    let mut max_id: Option<String> = None;
+    loop {
+        let page = client.list_statuses(account_id, max_id.as_deref()).await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        max_id = Some(last.id.clone());
+        statuses.extend(page);
+    }
+    info!("Listed {} statuses to delete oldest first", statuses.len());
+    // Unparseable timestamps sort first and are skipped with a warning
+    statuses.sort_by_key(|s| status_time(s).ok());
+    Ok(statuses)
+}
+
+/// IDs of the account's pinned statuses, unless `DELETE_PINNED` makes them
+/// fair game anyway.
+async fn pinned_ids(
+    client: &(impl MastodonClient + Sync),
+    account_id: &str,
+    config: &Config,
+) -> Result<HashSet<String>> {
+    if config.delete_pinned {
+        return Ok(HashSet::new());
+    }
+    Ok(client
+        .list_pinned_statuses(account_id)
+        .await?
+        .into_iter()
+        .map(|s| s.id)
+        .collect())
+}
+
+/// Whether `status` is pinned, or is your boost of a pinned status.
+fn is_pinned(status: &Status, pinned: &HashSet<String>) -> bool {
+    status.pinned
+        || pinned.contains(&status.id)
+        || status.reblog_of().is_some_and(|id| pinned.contains(id))
+}
+
+/// Collect the IDs of every status the account has bookmarked.
+async fn fetch_bookmark_ids(client: &(impl MastodonClient + Sync)) -> Result<HashSet<String>> {
+    let mut ids = HashSet::new();
+    let mut max_id: Option<String> = None;
+    loop {
+        let (bookmarks, next_max_id) = client.list_bookmarks(max_id.as_deref()).await.context(
+            "Failed to fetch bookmarks for KEEP_BOOKMARKED (token needs read:bookmarks)",
+        )?;
+        ids.extend(bookmarks.into_iter().map(|s| s.id));
+        max_id = next_max_id;
+        if max_id.is_none() {
+            break;
+        }
+    }
+    Ok(ids)
+}
+
 /// Parse `max_id` from a Mastodon Link header.
 /// Example: `<https://instance/api/v1/favourites?max_id=123>; rel="next"`
 fn parse_max_id_from_link(link: &str) -> Option<String> {
@@ -333,6 +1742,7 @@ fn parse_max_id_from_link(link: &str) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::full_plan::Action;
     use crate::test_helpers::*;
     use std::sync::Mutex;
 
@@ -373,9 +1783,26 @@ mod tests {
         account_id: String,
         statuses: Mutex<Vec<Status>>,
         favourites: Mutex<Vec<Status>>,
+        bookmarks: Mutex<Vec<Status>>,
         deleted_statuses: Mutex<Vec<String>>,
         unfavourited: Mutex<Vec<String>>,
+        unbookmarked: Mutex<Vec<String>>,
+        created: Mutex<Vec<String>>,
+        /// Pinned, but not flagged as such in `list_statuses`.
+        pinned_ids: Vec<String>,
+        /// Statuses whose delete fails with a server error.
+        failing_ids: Vec<String>,
+        /// Refuse every delete with a 403, like a suspended account.
+        forbidden: bool,
+        /// Statuses whose delete succeeds but that stay listed.
+        lingering_ids: Vec<String>,
+        /// Upcoming `delete_status` calls to answer with a 429, and the
+        /// `Retry-After` each one carries.
+        rate_limited_deletes: Mutex<usize>,
+        retry_after: Option<Duration>,
         page_size: usize,
+        /// What `token_scopes` reports, `None` like an older instance.
+        scopes: Option<Vec<String>>,
     }
 
     impl FakeMastodonClient {
@@ -384,12 +1811,27 @@ mod tests {
                 account_id: account_id.to_string(),
                 statuses: Mutex::new(Vec::new()),
                 favourites: Mutex::new(Vec::new()),
+                bookmarks: Mutex::new(Vec::new()),
                 deleted_statuses: Mutex::new(Vec::new()),
                 unfavourited: Mutex::new(Vec::new()),
+                unbookmarked: Mutex::new(Vec::new()),
+                created: Mutex::new(Vec::new()),
+                pinned_ids: Vec::new(),
+                failing_ids: Vec::new(),
+                forbidden: false,
+                lingering_ids: Vec::new(),
+                rate_limited_deletes: Mutex::new(0),
+                retry_after: None,
                 page_size: 100,
+                scopes: None,
             }
         }
 
+        fn with_scopes(mut self, scopes: &[&str]) -> Self {
+            self.scopes = Some(scopes.iter().map(|s| s.to_string()).collect());
+            self
+        }
+
         fn with_statuses(self, statuses: Vec<Status>) -> Self {
             *self.statuses.lock().unwrap() = statuses;
             self
@@ -400,6 +1842,41 @@ mod tests {
             self
         }
 
+        fn with_bookmarks(self, bookmarks: Vec<Status>) -> Self {
+            *self.bookmarks.lock().unwrap() = bookmarks;
+            self
+        }
+
+        fn with_pinned_ids(mut self, ids: &[&str]) -> Self {
+            self.pinned_ids = ids.iter().map(|id| id.to_string()).collect();
+            self
+        }
+
+        fn with_failing_ids(mut self, ids: &[&str]) -> Self {
+            self.failing_ids = ids.iter().map(|id| id.to_string()).collect();
+            self
+        }
+
+        fn with_forbidden_deletes(mut self) -> Self {
+            self.forbidden = true;
+            self
+        }
+
+        fn with_rate_limited_deletes(
+            mut self,
+            count: usize,
+            retry_after: Option<Duration>,
+        ) -> Self {
+            self.rate_limited_deletes = Mutex::new(count);
+            self.retry_after = retry_after;
+            self
+        }
+
+        fn with_lingering_ids(mut self, ids: &[&str]) -> Self {
+            self.lingering_ids = ids.iter().map(|id| id.to_string()).collect();
+            self
+        }
+
         fn with_page_size(mut self, size: usize) -> Self {
             self.page_size = size;
             self
@@ -412,6 +1889,14 @@ mod tests {
         fn unfavourited(&self) -> Vec<String> {
             self.unfavourited.lock().unwrap().clone()
         }
+
+        fn unbookmarked(&self) -> Vec<String> {
+            self.unbookmarked.lock().unwrap().clone()
+        }
+
+        fn created(&self) -> Vec<String> {
+            self.created.lock().unwrap().clone()
+        }
     }
 
     impl MastodonClient for FakeMastodonClient {
@@ -441,39 +1926,132 @@ mod tests {
             Ok(page)
         }
 
-        async fn delete_status(&self, id: &str) -> Result<()> {
-            self.statuses.lock().unwrap().retain(|s| s.id != id);
-            self.deleted_statuses.lock().unwrap().push(id.to_string());
-            Ok(())
+        async fn list_pinned_statuses(&self, _account_id: &str) -> Result<Vec<Status>> {
+            Ok(self
+                .statuses
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|s| s.pinned || self.pinned_ids.contains(&s.id))
+                .cloned()
+                .collect())
+        }
+
+        async fn get_status(&self, id: &str) -> Result<Status> {
+            self.statuses
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|s| s.id == id)
+                .cloned()
+                .context("Status not found")
+        }
+
+        async fn status_exists(&self, id: &str) -> Result<bool> {
+            Ok(self.get_status(id).await.is_ok())
+        }
+
+        async fn delete_status(&self, id: &str) -> Result<DeleteOutcome> {
+            if self.forbidden {
+                return Err(Forbidden.into());
+            }
+            {
+                let mut limited = self.rate_limited_deletes.lock().unwrap();
+                if *limited > 0 {
+                    *limited -= 1;
+                    return Err(RateLimited {
+                        retry_after: self.retry_after,
+                        endpoint: format!("/api/v1/statuses/{id}"),
+                        remaining: Some(0),
+                        reset: None,
+                    }
+                    .into());
+                }
+            }
+            if self.failing_ids.iter().any(|f| f == id) {
+                anyhow::bail!("Failed to delete status {id}: 503 Service Unavailable");
+            }
+            if !self.lingering_ids.iter().any(|l| l == id) {
+                self.statuses.lock().unwrap().retain(|s| s.id != id);
+            }
+            let mut deleted = self.deleted_statuses.lock().unwrap();
+            if deleted.iter().any(|d| d == id) {
+                return Ok(DeleteOutcome::AlreadyGone);
+            }
+            deleted.push(id.to_string());
+            Ok(DeleteOutcome::Deleted)
         }
 
         async fn list_favourites(
             &self,
             max_id: Option<&str>,
         ) -> Result<(Vec<Status>, Option<String>)> {
-            let all = self.favourites.lock().unwrap();
-            let filtered: Vec<Status> = match max_id {
-                Some(mid) => all
-                    .iter()
-                    .filter(|s| s.id.as_str() < mid)
-                    .cloned()
-                    .collect(),
-                None => all.to_vec(),
-            };
-            let page: Vec<Status> = filtered.iter().take(self.page_size).cloned().collect();
-            let next_max_id = if filtered.len() > self.page_size {
-                page.last().map(|s| s.id.clone())
-            } else {
-                None
-            };
-            Ok((page, next_max_id))
+            Ok(linked_page(
+                &self.favourites.lock().unwrap(),
+                max_id,
+                self.page_size,
+            ))
         }
 
-        async fn unfavourite(&self, id: &str) -> Result<()> {
+        async fn unfavourite(&self, id: &str) -> Result<DeleteOutcome> {
             self.favourites.lock().unwrap().retain(|s| s.id != id);
-            self.unfavourited.lock().unwrap().push(id.to_string());
+            let mut unfavourited = self.unfavourited.lock().unwrap();
+            if unfavourited.iter().any(|u| u == id) {
+                return Ok(DeleteOutcome::AlreadyGone);
+            }
+            unfavourited.push(id.to_string());
+            Ok(DeleteOutcome::Deleted)
+        }
+
+        async fn list_bookmarks(
+            &self,
+            max_id: Option<&str>,
+        ) -> Result<(Vec<Status>, Option<String>)> {
+            Ok(linked_page(
+                &self.bookmarks.lock().unwrap(),
+                max_id,
+                self.page_size,
+            ))
+        }
+
+        async fn unbookmark(&self, id: &str) -> Result<DeleteOutcome> {
+            self.bookmarks.lock().unwrap().retain(|s| s.id != id);
+            self.unbookmarked.lock().unwrap().push(id.to_string());
+            Ok(DeleteOutcome::Deleted)
+        }
+
+        async fn create_status(&self, text: &str) -> Result<()> {
+            self.created.lock().unwrap().push(text.to_string());
             Ok(())
         }
+
+        async fn token_scopes(&self) -> Result<Option<Vec<String>>> {
+            Ok(self.scopes.clone())
+        }
+    }
+
+    /// Simulates Link-header pagination: returns the page below `max_id` and
+    /// the next `max_id` if more statuses remain.
+    fn linked_page(
+        all: &[Status],
+        max_id: Option<&str>,
+        page_size: usize,
+    ) -> (Vec<Status>, Option<String>) {
+        let filtered: Vec<Status> = match max_id {
+            Some(mid) => all
+                .iter()
+                .filter(|s| s.id.as_str() < mid)
+                .cloned()
+                .collect(),
+            None => all.to_vec(),
+        };
+        let page: Vec<Status> = filtered.iter().take(page_size).cloned().collect();
+        let next_max_id = if filtered.len() > page_size {
+            page.last().map(|s| s.id.clone())
+        } else {
+            None
+        };
+        (page, next_max_id)
     }
 
     fn make_status(id: &str, created_at: &str, pinned: bool, reblog: bool) -> Status {
@@ -486,9 +2064,228 @@ mod tests {
             } else {
                 None
             },
+            content: String::new(),
+            language: None,
+            edited_at: None,
+            bookmarked: false,
+            replies_count: 0,
+            media_attachments: Vec::new(),
+            card: None,
+            application: None,
         }
     }
 
+    fn make_status_in(id: &str, created_at: &str, language: Option<&str>) -> Status {
+        Status {
+            language: language.map(String::from),
+            ..make_status(id, created_at, false, false)
+        }
+    }
+
+    // --- delete from export ---
+
+    #[tokio::test]
+    async fn deletes_only_exported_ids_outside_the_keep_list() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345")
+            .with_statuses(vec![
+                make_status("1004", &recent_timestamp(), false, false),
+                make_status("1003", &old_timestamp(), false, false),
+                make_status("1002", &old_timestamp(), false, false),
+                make_status("1001", &old_timestamp(), false, false),
+            ])
+            .with_pinned_ids(&["1001"]);
+        let ids = crate::export::status_ids("id\n1004\n1002\n1001\n1003\n").unwrap();
+
+        let config = config_with_cutoff_days_ago(30);
+        let keep_list = KeepList::from_entries(["mastodon:1003"]);
+        let summary = delete_listed(&fake, &ids, &config, &keep_list)
+            .await
+            .unwrap();
+
+        // Listed statuses go whatever their age, except pinned and kept ones
+        assert_eq!(fake.deleted_statuses(), vec!["1004", "1002"]);
+        assert_eq!(summary.posts, 2);
+        assert_eq!((summary.skipped.pinned, summary.skipped.kept), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn exported_ids_follow_the_rest_of_the_sweep_rules() {
+        tokio::time::pause();
+        let bookmarked = Status {
+            bookmarked: true,
+            ..make_status("1004", &old_timestamp(), false, false)
+        };
+        let fake = FakeMastodonClient::new("12345").with_statuses(vec![
+            make_status("1005", "2014-06-01T00:00:00.000Z", false, false),
+            bookmarked,
+            make_status("1003", &old_timestamp(), false, true),
+            make_status("1002", &old_timestamp(), false, false),
+            make_status("1001", &old_timestamp(), false, true),
+        ]);
+        let ids = crate::export::status_ids("1005\n1004\n1003\n1002\n1001\n9999\n").unwrap();
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.keep_bookmarked = true;
+        let keep_list = KeepList::from_entries(["keep-before: 2015-01-01"]);
+        let summary = delete_listed(&fake, &ids, &config, &keep_list)
+            .await
+            .unwrap();
+        assert_eq!(fake.deleted_statuses(), vec!["1003", "1002", "1001"]);
+        assert_eq!((summary.posts, summary.reposts), (1, 2));
+        assert_eq!(summary.skipped.kept_by.date, 1);
+
+        config.delete_reposts = false;
+        let fake = FakeMastodonClient::new("12345").with_statuses(vec![make_status(
+            "1003",
+            &old_timestamp(),
+            false,
+            true,
+        )]);
+        delete_listed(&fake, &ids[2..3], &config, &keep_list)
+            .await
+            .unwrap();
+        assert!(fake.deleted_statuses().is_empty());
+    }
+
+    // --- stats ---
+
+    #[tokio::test]
+    async fn stats_count_eligible_and_protected_statuses() {
+        let fake = FakeMastodonClient::new("12345")
+            .with_statuses(vec![
+                make_status("1004", &recent_timestamp(), false, false),
+                make_status("1003", &old_timestamp(), false, true),
+                make_status("1002", &old_timestamp(), false, false),
+                make_status("1001", &old_timestamp(), false, false),
+            ])
+            .with_pinned_ids(&["1001"])
+            .with_page_size(2);
+
+        let config = config_with_cutoff_days_ago(30);
+        let stats = stats(&fake, &config, &KeepList::default()).await.unwrap();
+
+        assert!(fake.deleted_statuses().is_empty());
+        assert_eq!((stats.total, stats.eligible, stats.protected), (4, 2, 1));
+        let bucketed: u64 = stats.age_buckets.iter().map(|b| b.count).sum();
+        assert_eq!(bucketed, stats.total);
+    }
+
+    #[tokio::test]
+    async fn full_plan_lists_old_statuses_with_their_reasons() {
+        let fake = FakeMastodonClient::new("12345")
+            .with_statuses(vec![
+                make_status("1004", &recent_timestamp(), false, false),
+                make_status("1003", &old_timestamp(), false, true),
+                make_status("1002", &old_timestamp(), false, false),
+                make_status("1001", &old_timestamp(), false, false),
+            ])
+            .with_pinned_ids(&["1001"])
+            .with_page_size(2);
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.delete_reposts = false;
+        let entries = full_plan(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert!(fake.deleted_statuses().is_empty());
+        let plan: Vec<(&str, &str, Action, &str)> = entries
+            .iter()
+            .map(|e| (e.id.as_str(), e.kind.as_str(), e.action, e.reason.as_str()))
+            .collect();
+        assert_eq!(
+            plan,
+            vec![
+                (
+                    "1003",
+                    "reblog",
+                    Action::Keep,
+                    "reposts aren't deleted (DELETE_REPOSTS)"
+                ),
+                (
+                    "1002",
+                    "post",
+                    Action::Delete,
+                    "older than the cutoff and not protected"
+                ),
+                (
+                    "1001",
+                    "post",
+                    Action::Keep,
+                    "pinned (DELETE_PINNED is off)"
+                ),
+            ]
+        );
+    }
+
+    // --- explain ---
+
+    #[tokio::test]
+    async fn explain_reports_the_deciding_factor() {
+        let bookmarked = Status {
+            bookmarked: true,
+            ..make_status("1004", &old_timestamp(), false, false)
+        };
+        let fake = FakeMastodonClient::new("12345")
+            .with_statuses(vec![
+                make_status("1001", &old_timestamp(), false, false),
+                make_status("1002", &recent_timestamp(), false, false),
+                make_status("1003", &old_timestamp(), true, false),
+                bookmarked,
+                make_status("1005", &old_timestamp(), false, false),
+                make_status_in("1006", &old_timestamp(), Some("en")),
+                make_status("1007", &old_timestamp(), false, false),
+            ])
+            .with_pinned_ids(&["1007"]);
+        let mut config = config_with_cutoff_days_ago(30);
+        config.keep_bookmarked = true;
+        config.keep_languages = vec!["en".into()];
+        let keep_list = KeepList::from_entries(["mastodon:1005"]);
+
+        let decide = |id: &'static str| explain(&fake, id, &config, &keep_list);
+        assert_eq!(decide("1001").await.unwrap(), Decision::Delete);
+        assert_eq!(decide("1002").await.unwrap(), Decision::NewerThanCutoff);
+        assert_eq!(decide("1003").await.unwrap(), Decision::Pinned);
+        assert_eq!(decide("1004").await.unwrap(), Decision::Bookmarked);
+        assert_eq!(
+            decide("1005").await.unwrap(),
+            Decision::Kept(KeptBy::Rkey, "mastodon:1005".into())
+        );
+        assert_eq!(decide("1006").await.unwrap(), Decision::LanguageFiltered);
+        assert_eq!(decide("1007").await.unwrap(), Decision::Pinned);
+        assert!(decide("9999").await.is_err());
+    }
+
+    #[test]
+    fn evaluate_skips_reblogs_when_reposts_are_disabled() {
+        let mut config = config_with_cutoff_days_ago(30);
+        config.delete_reposts = false;
+        let reblog = make_status("1001", &old_timestamp(), false, true);
+        let post_time = status_time(&reblog).unwrap();
+
+        assert_eq!(
+            evaluate(
+                &reblog,
+                post_time,
+                &config,
+                &KeepList::default(),
+                false,
+                false
+            ),
+            Decision::RepostsDisabled
+        );
+    }
+
+    #[test]
+    fn unparseable_created_at_is_reported() {
+        let status = make_status("1001", "yesterday", false, false);
+        assert_eq!(
+            status_time(&status).unwrap_err(),
+            Decision::UnparseableTimestamp
+        );
+    }
+
     // --- statuses tests ---
 
     #[tokio::test]
@@ -502,7 +2299,7 @@ mod tests {
         )]);
 
         let config = config_with_cutoff_days_ago(30);
-        let keep_list = HashSet::new();
+        let keep_list = KeepList::default();
         delete_old_posts(&fake, &config, &keep_list).await.unwrap();
 
         assert_eq!(fake.deleted_statuses(), vec!["1001"]);
@@ -519,7 +2316,7 @@ mod tests {
         )]);
 
         let config = config_with_cutoff_days_ago(30);
-        let keep_list = HashSet::new();
+        let keep_list = KeepList::default();
         delete_old_posts(&fake, &config, &keep_list).await.unwrap();
 
         assert!(fake.deleted_statuses().is_empty());
@@ -536,10 +2333,85 @@ mod tests {
         )]);
 
         let config = config_with_cutoff_days_ago(30);
-        let keep_list = HashSet::new();
-        delete_old_posts(&fake, &config, &keep_list).await.unwrap();
+        let keep_list = KeepList::default();
+        let summary = delete_old_posts(&fake, &config, &keep_list).await.unwrap();
 
         assert!(fake.deleted_statuses().is_empty());
+        assert_eq!(summary.skipped.pinned, 1);
+    }
+
+    #[tokio::test]
+    async fn force_delete_overrides_pinned_and_cutoff() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345").with_statuses(vec![
+            make_status("1003", &recent_timestamp(), false, false),
+            make_status("1002", &recent_timestamp(), false, false),
+            make_status("1001", &old_timestamp(), true, false),
+        ]);
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.force_delete = KeepList::parse("mastodon:1001\nmastodon:1003\n");
+        let summary = delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["1003", "1001"]);
+        assert_eq!(summary.skipped.pinned, 0);
+    }
+
+    #[tokio::test]
+    async fn protects_pinned_status_missing_its_flag_in_the_listing() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345")
+            .with_statuses(vec![
+                make_status("1002", &old_timestamp(), false, false),
+                make_status("1001", &old_timestamp(), false, false),
+            ])
+            .with_pinned_ids(&["1002"]);
+
+        let config = config_with_cutoff_days_ago(30);
+        let summary = delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["1001"]);
+        assert_eq!(summary.skipped.pinned, 1);
+    }
+
+    #[tokio::test]
+    async fn skips_self_reblog_of_pinned_status() {
+        tokio::time::pause();
+        let mut self_boost = make_status("1003", &old_timestamp(), false, true);
+        self_boost.reblog = Some(serde_json::json!({"id": "1001"}));
+        let fake = FakeMastodonClient::new("12345").with_statuses(vec![
+            self_boost,
+            make_status("1002", &old_timestamp(), false, true),
+            make_status("1001", &recent_timestamp(), true, false),
+        ]);
+
+        let config = config_with_cutoff_days_ago(30);
+        let summary = delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["1002"]);
+        assert_eq!(summary.skipped.pinned, 1);
+    }
+
+    #[tokio::test]
+    async fn pinned_set_is_ignored_when_deleting_pinned() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345")
+            .with_statuses(vec![make_status("1002", &old_timestamp(), false, false)])
+            .with_pinned_ids(&["1002"]);
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.delete_pinned = true;
+        delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["1002"]);
     }
 
     #[tokio::test]
@@ -554,12 +2426,228 @@ mod tests {
 
         let mut config = config_with_cutoff_days_ago(30);
         config.delete_reposts = false;
-        let keep_list = HashSet::new();
+        let keep_list = KeepList::default();
         delete_old_posts(&fake, &config, &keep_list).await.unwrap();
 
         assert!(fake.deleted_statuses().is_empty());
     }
 
+    #[tokio::test]
+    async fn consecutive_failures_abort_at_the_limit() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345")
+            .with_statuses(vec![
+                make_status("1004", &old_timestamp(), false, false),
+                make_status("1003", &old_timestamp(), false, false),
+                make_status("1002", &old_timestamp(), false, false),
+                make_status("1001", &old_timestamp(), false, false),
+            ])
+            .with_failing_ids(&["1004", "1003", "1002", "1001"]);
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.error_limits.max_consecutive = Some(2);
+        let err = delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Aborting: 2 deletions failed in a row (MAX_CONSECUTIVE_ERRORS=2)"
+        );
+        assert_eq!(config.counters.snapshot().errors, 2);
+    }
+
+    #[tokio::test]
+    async fn repeated_403s_abort_as_a_suspended_account() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345")
+            .with_statuses(
+                (1001..=1005)
+                    .rev()
+                    .map(|id| make_status(&id.to_string(), &old_timestamp(), false, false))
+                    .collect(),
+            )
+            .with_forbidden_deletes();
+
+        let config = config_with_cutoff_days_ago(30);
+        let err = delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("may be suspended or limited"));
+        assert_eq!(config.counters.snapshot().errors, FORBIDDEN_STREAK_LIMIT);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_delete_waits_for_retry_after_and_retries() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345")
+            .with_statuses(vec![
+                make_status("1002", &old_timestamp(), false, false),
+                make_status("1001", &old_timestamp(), false, false),
+            ])
+            .with_rate_limited_deletes(1, Some(Duration::from_secs(90)));
+
+        let config = config_with_cutoff_days_ago(30);
+        let start = tokio::time::Instant::now();
+        let summary = delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["1002", "1001"]);
+        assert_eq!(summary.posts, 2);
+        assert!(start.elapsed() >= Duration::from_secs(90));
+        assert_eq!(config.counters.snapshot().errors, 0);
+    }
+
+    #[tokio::test]
+    async fn endless_429s_stop_retrying_and_leave_the_rest() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345")
+            .with_statuses(vec![
+                make_status("1002", &old_timestamp(), false, false),
+                make_status("1001", &old_timestamp(), false, false),
+            ])
+            .with_rate_limited_deletes(usize::MAX, Some(Duration::from_secs(60)));
+
+        let config = config_with_cutoff_days_ago(30);
+        let start = tokio::time::Instant::now();
+        let summary = delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert!(fake.deleted_statuses().is_empty());
+        assert_eq!(summary.remaining.unwrap().count, 1);
+        let attempts = usize::MAX - *fake.rate_limited_deletes.lock().unwrap();
+        assert_eq!(attempts, MAX_RETRY_AFTER_ATTEMPTS as usize + 1);
+        assert!(start.elapsed() < Duration::from_secs(60 * 10));
+    }
+
+    #[tokio::test]
+    async fn retry_after_longer_than_the_cap_leaves_the_rest() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345")
+            .with_statuses(vec![make_status("1001", &old_timestamp(), false, false)])
+            .with_rate_limited_deletes(1, Some(Duration::from_secs(86_400)));
+
+        let config = config_with_cutoff_days_ago(30);
+        let start = tokio::time::Instant::now();
+        delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert!(fake.deleted_statuses().is_empty());
+        assert!(start.elapsed() < MAX_RETRY_AFTER);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_delete_without_retry_after_leaves_the_rest() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345")
+            .with_statuses(vec![
+                make_status("1002", &old_timestamp(), false, false),
+                make_status("1001", &old_timestamp(), false, false),
+            ])
+            .with_rate_limited_deletes(1, None);
+
+        let config = config_with_cutoff_days_ago(30);
+        let summary = delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert!(fake.deleted_statuses().is_empty());
+        assert_eq!(summary.remaining.unwrap().count, 1);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_delete_is_written_to_the_rate_limit_log() {
+        tokio::time::pause();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rate-limits.jsonl");
+        let fake = FakeMastodonClient::new("12345")
+            .with_statuses(vec![make_status("1001", &old_timestamp(), false, false)])
+            .with_rate_limited_deletes(1, None);
+        let mut config = config_with_cutoff_days_ago(30);
+        config.rate_limit_log = Some(crate::RateLimitLog::open(&path).unwrap());
+
+        delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let events: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["platform"], "mastodon");
+        assert_eq!(events[0]["endpoint"], "/api/v1/statuses/1001");
+        assert_eq!(events[0]["remaining"], 0);
+    }
+
+    #[tokio::test]
+    async fn report_lists_deleted_statuses_and_favourites() {
+        tokio::time::pause();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.json");
+        let fake = FakeMastodonClient::new("12345")
+            .with_statuses(vec![
+                make_status("1002", &recent_timestamp(), false, false),
+                make_status("1001", &old_timestamp(), false, true),
+            ])
+            .with_favourites(vec![make_status("2001", &old_timestamp(), false, false)]);
+        let mut config = config_with_cutoff_days_ago(30);
+        config.report = Some(crate::Report::new(&path));
+
+        delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+        config.report.unwrap().write(false, Utc::now()).unwrap();
+
+        let report: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let entries: Vec<(&str, &str, &str)> = report["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| {
+                (
+                    e["id"].as_str().unwrap(),
+                    e["kind"].as_str().unwrap(),
+                    e["action"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                ("1001", "reblog", "delete"),
+                ("2001", "favourite", "unfavourite")
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn a_success_resets_the_consecutive_failures() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345")
+            .with_statuses(vec![
+                make_status("1004", &old_timestamp(), false, false),
+                make_status("1003", &old_timestamp(), false, false),
+                make_status("1002", &old_timestamp(), false, false),
+                make_status("1001", &old_timestamp(), false, false),
+            ])
+            .with_failing_ids(&["1004", "1002"]);
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.error_limits.max_consecutive = Some(2);
+        delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["1003", "1001"]);
+    }
+
     #[tokio::test]
     async fn deletes_reblog_when_delete_reposts_true() {
         tokio::time::pause();
@@ -571,7 +2659,7 @@ mod tests {
         )]);
 
         let config = config_with_cutoff_days_ago(30); // delete_reposts defaults to true
-        let keep_list = HashSet::new();
+        let keep_list = KeepList::default();
         delete_old_posts(&fake, &config, &keep_list).await.unwrap();
 
         assert_eq!(fake.deleted_statuses(), vec!["1001"]);
@@ -588,12 +2676,30 @@ mod tests {
         )]);
 
         let config = config_with_cutoff_days_ago(30);
-        let keep_list = HashSet::from(["mastodon:1001".to_string()]);
+        let keep_list = KeepList::from_entries(["mastodon:1001".to_string()]);
         delete_old_posts(&fake, &config, &keep_list).await.unwrap();
 
         assert!(fake.deleted_statuses().is_empty());
     }
 
+    #[tokio::test]
+    async fn counts_statuses_with_unparseable_created_at() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345").with_statuses(vec![
+            make_status("1002", &old_timestamp(), false, false),
+            make_status("1001", "not a date", false, false),
+        ]);
+
+        let config = config_with_cutoff_days_ago(30);
+        let summary = delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["1002"]);
+        assert_eq!(summary.skipped.unparseable_timestamp, 1);
+        assert_eq!(summary.skipped.missing_timestamp, 0);
+    }
+
     #[tokio::test]
     async fn dry_run_does_not_call_delete() {
         tokio::time::pause();
@@ -606,7 +2712,7 @@ mod tests {
 
         let mut config = config_with_cutoff_days_ago(30);
         config.dry_run = true;
-        let keep_list = HashSet::new();
+        let keep_list = KeepList::default();
         delete_old_posts(&fake, &config, &keep_list).await.unwrap();
 
         assert!(fake.deleted_statuses().is_empty());
@@ -623,7 +2729,7 @@ mod tests {
             ]);
 
         let config = config_with_cutoff_days_ago(30);
-        let keep_list = HashSet::new();
+        let keep_list = KeepList::default();
         delete_old_posts(&fake, &config, &keep_list).await.unwrap();
 
         let deleted = fake.deleted_statuses();
@@ -632,6 +2738,78 @@ mod tests {
         assert!(deleted.contains(&"1000".to_string()));
     }
 
+    #[tokio::test]
+    async fn keep_one_per_month_keeps_the_newest_original_post_of_each_month() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345")
+            .with_page_size(2)
+            .with_statuses(vec![
+                make_status("1008", &recent_timestamp(), false, false),
+                make_status("1007", "2020-03-20T00:00:00Z", false, true),
+                make_status("1006", "2020-03-10T00:00:00Z", false, false),
+                make_status("1005", "2020-03-01T00:00:00Z", false, false),
+                make_status("1004", "2020-02-28T00:00:00Z", false, false),
+                make_status("1003", "2020-02-01T00:00:00Z", false, false),
+                make_status("1002", "2020-01-15T00:00:00Z", false, false),
+                make_status("1001", "2019-01-15T00:00:00Z", false, false),
+            ]);
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.keep_one_per_month = true;
+        let summary = delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        // The March boost never stands for its month
+        assert_eq!(fake.deleted_statuses(), vec!["1007", "1005", "1003"]);
+        assert_eq!(summary.skipped.one_per_month, 4);
+    }
+
+    #[tokio::test]
+    async fn purge_all_reposts_deletes_recent_reblogs_but_not_recent_posts() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345").with_statuses(vec![
+            make_status("1004", &recent_timestamp(), false, true),
+            make_status("1003", &recent_timestamp(), false, true),
+            make_status("1002", &recent_timestamp(), false, false),
+            make_status("1001", &old_timestamp(), false, false),
+        ]);
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.purge_all_reposts = true;
+        let keep_list = KeepList::parse("mastodon:1003\n");
+        let summary = delete_old_posts(&fake, &config, &keep_list).await.unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["1004", "1001"]);
+        assert_eq!((summary.posts, summary.reposts), (1, 1));
+        assert_eq!(summary.skipped.kept, 1);
+    }
+
+    #[tokio::test]
+    async fn oldest_first_deletes_across_pages_in_creation_order() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345")
+            .with_page_size(2)
+            .with_statuses(vec![
+                make_status("1005", "2020-05-01T00:00:00Z", false, false),
+                make_status("1004", "2020-04-01T00:00:00Z", false, false),
+                make_status("1003", &recent_timestamp(), false, false),
+                make_status("1002", "2020-02-01T00:00:00Z", false, false),
+                make_status("1001", "2020-01-01T00:00:00Z", false, false),
+            ]);
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.oldest_first = true;
+        delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            fake.deleted_statuses(),
+            vec!["1001", "1002", "1004", "1005"]
+        );
+    }
+
     // --- favourites tests ---
 
     #[tokio::test]
@@ -645,25 +2823,84 @@ mod tests {
         )]);
 
         let config = config_with_cutoff_days_ago(30);
-        let keep_list = HashSet::new();
+        let keep_list = KeepList::default();
         delete_old_posts(&fake, &config, &keep_list).await.unwrap();
 
         assert_eq!(fake.unfavourited(), vec!["2001"]);
     }
 
     #[tokio::test]
-    async fn skips_likes_when_delete_likes_false() {
+    async fn unfavourites_when_the_token_has_the_favourites_scopes() {
         tokio::time::pause();
-        let fake = FakeMastodonClient::new("12345").with_favourites(vec![make_status(
-            "2001",
-            &old_timestamp(),
-            false,
-            false,
-        )]);
+        let fake = FakeMastodonClient::new("12345")
+            .with_scopes(&["read", "write:statuses", "write:favourites"])
+            .with_favourites(vec![make_status("2001", &old_timestamp(), false, false)]);
+
+        let config = config_with_cutoff_days_ago(30);
+        let summary = delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.unfavourited(), vec!["2001"]);
+        assert_eq!(summary.likes, 1);
+    }
+
+    #[tokio::test]
+    async fn skips_favourites_when_the_token_lacks_the_scope() {
+        tokio::time::pause();
+        let dir = tempfile::tempdir().unwrap();
+        let phases = dir.path().join("mastodon-phases.json");
+        let fake = FakeMastodonClient::new("12345")
+            .with_scopes(&["read:statuses", "write:statuses"])
+            .with_statuses(vec![make_status("1001", &old_timestamp(), false, false)])
+            .with_favourites(vec![make_status("2001", &old_timestamp(), false, false)]);
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.phase_state_file = Some(phases.clone());
+        let summary = delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["1001"]);
+        assert!(fake.unfavourited().is_empty());
+        assert_eq!(summary.likes, 0);
+        // Nothing to resume at, so statuses aren't checkpointed
+        assert!(!phases.exists());
+    }
+
+    #[test]
+    fn missing_scopes_counts_top_level_scopes_as_covering_their_children() {
+        let granted =
+            |scopes: &[&str]| -> Vec<String> { scopes.iter().map(|s| s.to_string()).collect() };
+        assert!(missing_scopes(&granted(&["read", "write"]), &FAVOURITES_SCOPES).is_empty());
+        assert!(missing_scopes(
+            &granted(&["read:favourites", "write:favourites"]),
+            &FAVOURITES_SCOPES
+        )
+        .is_empty());
+        assert_eq!(
+            missing_scopes(&granted(&["read", "write:statuses"]), &FAVOURITES_SCOPES),
+            vec!["write:favourites"]
+        );
+        assert_eq!(
+            missing_scopes(&granted(&["readable"]), &FAVOURITES_SCOPES),
+            vec!["read:favourites", "write:favourites"]
+        );
+    }
+
+    #[tokio::test]
+    async fn skips_likes_when_delete_likes_false() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345").with_favourites(vec![make_status(
+            "2001",
+            &old_timestamp(),
+            false,
+            false,
+        )]);
 
         let mut config = config_with_cutoff_days_ago(30);
         config.delete_likes = false;
-        let keep_list = HashSet::new();
+        let keep_list = KeepList::default();
         delete_old_posts(&fake, &config, &keep_list).await.unwrap();
 
         assert!(fake.unfavourited().is_empty());
@@ -680,7 +2917,7 @@ mod tests {
             ]);
 
         let config = config_with_cutoff_days_ago(30);
-        let keep_list = HashSet::new();
+        let keep_list = KeepList::default();
         delete_old_posts(&fake, &config, &keep_list).await.unwrap();
 
         let unfavd = fake.unfavourited();
@@ -689,6 +2926,55 @@ mod tests {
         assert!(unfavd.contains(&"2000".to_string()));
     }
 
+    #[tokio::test]
+    async fn delete_account_data_sweeps_statuses_favourites_and_bookmarks() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345")
+            .with_statuses(vec![
+                make_status("1003", &recent_timestamp(), false, false),
+                make_status("1002", &old_timestamp(), true, false),
+                make_status("1001", &old_timestamp(), false, true),
+            ])
+            .with_favourites(vec![make_status("2001", &recent_timestamp(), false, false)])
+            .with_bookmarks(vec![
+                make_status("3002", &old_timestamp(), false, false),
+                make_status("3001", &old_timestamp(), false, false),
+            ])
+            .with_page_size(1);
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.keep_bookmarked = true;
+        config.delete_reposts = false;
+        config.delete_account_data = true;
+        let config = config.for_account_data();
+        let summary = delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["1003", "1002", "1001"]);
+        assert_eq!(fake.unfavourited(), vec!["2001"]);
+        assert_eq!(fake.unbookmarked(), vec!["3002", "3001"]);
+        assert_eq!(summary.other, 2);
+    }
+
+    #[tokio::test]
+    async fn bookmarks_are_left_alone_without_delete_account_data() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345").with_bookmarks(vec![make_status(
+            "3001",
+            &old_timestamp(),
+            false,
+            false,
+        )]);
+
+        let config = config_with_cutoff_days_ago(30);
+        delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert!(fake.unbookmarked().is_empty());
+    }
+
     #[tokio::test]
     async fn favourites_respects_keep_list() {
         tokio::time::pause();
@@ -700,9 +2986,1022 @@ mod tests {
         )]);
 
         let config = config_with_cutoff_days_ago(30);
-        let keep_list = HashSet::from(["mastodon:2001".to_string()]);
+        let keep_list = KeepList::from_entries(["mastodon:2001".to_string()]);
         delete_old_posts(&fake, &config, &keep_list).await.unwrap();
 
         assert!(fake.unfavourited().is_empty());
     }
+
+    // --- bookmarks tests ---
+
+    #[tokio::test]
+    async fn keeps_bookmarked_status_when_enabled() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345")
+            .with_page_size(1)
+            .with_statuses(vec![
+                make_status("1001", &old_timestamp(), false, false),
+                make_status("1000", &old_timestamp(), false, false),
+            ])
+            .with_bookmarks(vec![
+                make_status("1001", &old_timestamp(), false, false),
+                make_status("0999", &old_timestamp(), false, false),
+            ]);
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.keep_bookmarked = true;
+        let keep_list = KeepList::default();
+        delete_old_posts(&fake, &config, &keep_list).await.unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["1000"]);
+    }
+
+    #[tokio::test]
+    async fn ignores_bookmarks_by_default() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345")
+            .with_statuses(vec![make_status("1001", &old_timestamp(), false, false)])
+            .with_bookmarks(vec![make_status("1001", &old_timestamp(), false, false)]);
+
+        let config = config_with_cutoff_days_ago(30);
+        let keep_list = KeepList::default();
+        delete_old_posts(&fake, &config, &keep_list).await.unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["1001"]);
+    }
+
+    // --- content tests ---
+
+    #[test]
+    fn html_to_text_strips_tags_and_breaks_paragraphs() {
+        let html = r#"<p>Hello <a href="https://example.com" class="u-url mention">@<span>friend</span></a>!</p><p>Line one<br>Line two<br />Line three</p>"#;
+        assert_eq!(
+            html_to_text(html),
+            "Hello @friend!\nLine one\nLine two\nLine three"
+        );
+    }
+
+    #[test]
+    fn html_to_text_decodes_entities() {
+        assert_eq!(
+            html_to_text(
+                "<p>Fish &amp; chips &lt;3 &quot;tasty&quot; it&#39;s &#x1F41F;&nbsp;ok</p>"
+            ),
+            "Fish & chips <3 \"tasty\" it's \u{1F41F} ok"
+        );
+    }
+
+    #[test]
+    fn html_to_text_leaves_unknown_or_bare_ampersands() {
+        assert_eq!(
+            html_to_text("<p>AT&T &bogus; a & b</p>"),
+            "AT&T &bogus; a & b"
+        );
+    }
+
+    #[test]
+    fn html_to_text_keeps_hashtag_text() {
+        let html = r##"<p>Shipping it <a href="https://mastodon.social/tags/RustLang" class="mention hashtag" rel="tag">#<span>RustLang</span></a></p>"##;
+        assert_eq!(html_to_text(html), "Shipping it #RustLang");
+    }
+
+    #[tokio::test]
+    async fn keep_keywords_match_rendered_text() {
+        tokio::time::pause();
+        let with_content = |id: &str, content: &str| Status {
+            content: content.to_string(),
+            ..make_status(id, &old_timestamp(), false, false)
+        };
+        let fake = FakeMastodonClient::new("12345").with_statuses(vec![
+            // Entity-encoded keyword still matches once decoded
+            with_content("3", "<p>Q&amp;A notes</p>"),
+            // Keyword split by markup still matches once tags are stripped
+            with_content("2", "<p><a href=\"#\">#<span>Rust</span>Conf</a> talk</p>"),
+            // "amp" only appears in the markup, so it must not match
+            with_content("1", "<p>Tom &amp; Jerry</p>"),
+        ]);
+        let mut config = config_with_cutoff_days_ago(30);
+        config.keep_keywords = vec![
+            "q&a".to_string(),
+            "#rustconf".to_string(),
+            "amp".to_string(),
+        ];
+
+        delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["1"]);
+    }
+
+    #[tokio::test]
+    async fn plain_text_only_keeps_statuses_with_media_or_links() {
+        tokio::time::pause();
+        let with_content = |id: &str, content: &str| Status {
+            content: content.to_string(),
+            ..make_status(id, &old_timestamp(), false, false)
+        };
+        let with_media = Status {
+            media_attachments: vec![serde_json::json!({"type": "image"})],
+            ..with_content("1", "<p>lunch</p>")
+        };
+        let fake = FakeMastodonClient::new("12345").with_statuses(vec![
+            with_content(
+                "5",
+                r#"<p>hi <span class="h-card"><a href="https://example.social/@bob" class="u-url mention">@bob</a></span> <a href="https://example.social/tags/rust" class="mention hashtag" rel="tag">#rust</a></p>"#,
+            ),
+            with_content("4", "<p>just text</p>"),
+            with_content(
+                "3",
+                r#"<p>read <a href="https://example.com/post" rel="nofollow noopener" target="_blank">example.com/post</a></p>"#,
+            ),
+            Status {
+                card: Some(serde_json::json!({"url": "https://example.com"})),
+                ..with_content("2", "<p>card only</p>")
+            },
+            with_media,
+        ]);
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.delete_plain_text_only = true;
+        delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["5", "4"]);
+    }
+
+    // --- already gone tests ---
+
+    #[tokio::test]
+    async fn status_deleted_twice_counts_once_without_error() {
+        tokio::time::pause();
+        // Listed twice, as when it's deleted elsewhere mid-run
+        let fake = FakeMastodonClient::new("12345").with_statuses(vec![
+            make_status("1001", &old_timestamp(), false, false),
+            make_status("1001", &old_timestamp(), false, false),
+        ]);
+        let config = config_with_cutoff_days_ago(30);
+
+        let summary = delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["1001"]);
+        assert_eq!(summary.posts, 1);
+        assert_eq!(summary.remaining.unwrap().count, 0);
+    }
+
+    // --- verify each page tests ---
+
+    #[tokio::test]
+    async fn verify_each_page_flags_a_deleted_status_still_listed() {
+        tokio::time::pause();
+        let statuses = || {
+            vec![
+                make_status("1002", &old_timestamp(), false, false),
+                make_status("1001", &old_timestamp(), false, false),
+            ]
+        };
+        let mut config = config_with_cutoff_days_ago(30);
+
+        let fake = FakeMastodonClient::new("12345")
+            .with_statuses(statuses())
+            .with_lingering_ids(&["1002"]);
+        let summary = delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+        assert_eq!(summary.remaining.unwrap().count, 0);
+
+        config.verify_each_page = true;
+        let fake = FakeMastodonClient::new("12345")
+            .with_statuses(statuses())
+            .with_lingering_ids(&["1002"]);
+        let summary = delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+        assert_eq!(fake.deleted_statuses(), vec!["1002", "1001"]);
+        assert_eq!(summary.remaining.unwrap().count, 1);
+    }
+
+    // --- remaining tests ---
+
+    #[tokio::test]
+    async fn remaining_counts_surviving_statuses_and_oldest() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345").with_statuses(vec![
+            make_status("4", &recent_timestamp(), false, false),
+            make_status("3", "2022-02-01T00:00:00Z", true, false),
+            make_status("2", "2021-07-04T00:00:00Z", false, false),
+            make_status("1", &old_timestamp(), false, false),
+        ]);
+        let config = config_with_cutoff_days_ago(30);
+        let keep_list = KeepList::from_entries(["mastodon:2"]);
+
+        let summary = delete_old_posts(&fake, &config, &keep_list).await.unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["1"]);
+        assert_eq!(summary.remaining.unwrap().count, 3);
+        assert_eq!(
+            summary.remaining.unwrap().oldest,
+            Some(
+                crate::parse_timestamp("2021-07-04T00:00:00Z")
+                    .unwrap()
+                    .into()
+            )
+        );
+    }
+
+    // --- replies tests ---
+
+    #[tokio::test]
+    async fn keep_if_has_replies_preserves_replied_to_posts() {
+        tokio::time::pause();
+        let replied_to = Status {
+            replies_count: 3,
+            ..make_status("2", &old_timestamp(), false, false)
+        };
+        let fake = FakeMastodonClient::new("12345").with_statuses(vec![
+            replied_to,
+            make_status("1", &old_timestamp(), false, false),
+        ]);
+        let mut config = config_with_cutoff_days_ago(30);
+        config.keep_if_has_replies = true;
+
+        delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["1"]);
+    }
+
+    #[tokio::test]
+    async fn replies_ignored_without_keep_if_has_replies() {
+        tokio::time::pause();
+        let replied_to = Status {
+            replies_count: 3,
+            ..make_status("1", &old_timestamp(), false, false)
+        };
+        let fake = FakeMastodonClient::new("12345").with_statuses(vec![replied_to]);
+        let config = config_with_cutoff_days_ago(30);
+
+        delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["1"]);
+    }
+
+    // --- recently edited tests ---
+
+    #[tokio::test]
+    async fn keep_recently_edited_preserves_old_but_recently_edited_posts() {
+        tokio::time::pause();
+        let edited = Status {
+            edited_at: Some((chrono::Utc::now() - chrono::TimeDelta::days(2)).to_rfc3339()),
+            ..make_status("3", &old_timestamp(), false, false)
+        };
+        let edited_long_ago = Status {
+            edited_at: Some(old_timestamp()),
+            ..make_status("2", &old_timestamp(), false, false)
+        };
+        let unedited = make_status("1", &old_timestamp(), false, false);
+        let fake =
+            FakeMastodonClient::new("12345").with_statuses(vec![edited, edited_long_ago, unedited]);
+        let mut config = config_with_cutoff_days_ago(30);
+        config.keep_edited_since = Some(chrono::Utc::now() - chrono::TimeDelta::days(7));
+
+        delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["2", "1"]);
+    }
+
+    #[tokio::test]
+    async fn edited_at_ignored_without_keep_recently_edited() {
+        tokio::time::pause();
+        let edited = Status {
+            edited_at: Some(chrono::Utc::now().to_rfc3339()),
+            ..make_status("1", &old_timestamp(), false, false)
+        };
+        let fake = FakeMastodonClient::new("12345").with_statuses(vec![edited]);
+        let config = config_with_cutoff_days_ago(30);
+
+        delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["1"]);
+    }
+
+    // --- application filter tests ---
+
+    fn make_status_from(id: &str, app: Option<&str>) -> Status {
+        Status {
+            application: app.map(|name| Application {
+                name: name.to_string(),
+            }),
+            ..make_status(id, &old_timestamp(), false, false)
+        }
+    }
+
+    #[tokio::test]
+    async fn delete_from_apps_applies_to_each_accounts_statuses() {
+        tokio::time::pause();
+        let mut config = config_with_cutoff_days_ago(30);
+        config.delete_from_apps = vec!["Crossposter".to_string()];
+        let work = FakeMastodonClient::new("111").with_statuses(vec![
+            make_status_from("14", Some("crossposter")),
+            make_status_from("13", Some("Web")),
+            make_status_from("12", None),
+            make_status("11", &old_timestamp(), false, true),
+        ]);
+        let personal = FakeMastodonClient::new("222").with_statuses(vec![
+            make_status_from("24", Some("Ivory")),
+            make_status_from("23", Some("Crossposter")),
+        ]);
+
+        for fake in [&work, &personal] {
+            delete_old_posts(fake, &config, &KeepList::default())
+                .await
+                .unwrap();
+        }
+
+        // Boosts aren't posted from an app, so the filter leaves them to DELETE_REPOSTS
+        assert_eq!(work.deleted_statuses(), vec!["14", "11"]);
+        assert_eq!(personal.deleted_statuses(), vec!["23"]);
+    }
+
+    // --- language filter tests ---
+
+    fn multilingual_fake() -> FakeMastodonClient {
+        FakeMastodonClient::new("12345").with_statuses(vec![
+            make_status_in("4", &old_timestamp(), Some("de")),
+            make_status_in("3", &old_timestamp(), Some("en")),
+            make_status_in("2", &old_timestamp(), None),
+            make_status_in("1", &old_timestamp(), Some("DE")),
+        ])
+    }
+
+    #[tokio::test]
+    async fn delete_languages_only_deletes_matching_statuses() {
+        tokio::time::pause();
+        let fake = multilingual_fake();
+        let mut config = config_with_cutoff_days_ago(30);
+        config.delete_languages = vec!["de".to_string()];
+
+        delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["4", "1"]);
+    }
+
+    #[tokio::test]
+    async fn keep_languages_protects_matching_statuses() {
+        tokio::time::pause();
+        let fake = multilingual_fake();
+        let mut config = config_with_cutoff_days_ago(30);
+        config.keep_languages = vec!["en".to_string()];
+
+        delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["4", "2", "1"]);
+    }
+
+    #[tokio::test]
+    async fn null_language_falls_back_to_default_language() {
+        tokio::time::pause();
+        let fake = multilingual_fake();
+        let mut config = config_with_cutoff_days_ago(30);
+        config.keep_languages = vec!["en".to_string()];
+        config.default_language = Some("en".to_string());
+
+        delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["4", "1"]);
+    }
+
+    #[tokio::test]
+    async fn language_filter_ignores_reblogs() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345").with_statuses(vec![make_status(
+            "1",
+            &old_timestamp(),
+            false,
+            true,
+        )]);
+        let mut config = config_with_cutoff_days_ago(30);
+        config.delete_languages = vec!["de".to_string()];
+
+        delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses(), vec!["1"]);
+    }
+
+    // --- persisted rate limit budget tests ---
+
+    fn old_statuses(ids: std::ops::Range<u32>) -> Vec<Status> {
+        ids.rev()
+            .map(|i| make_status(&format!("{i:04}"), &old_timestamp(), false, false))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn consecutive_runs_share_persisted_deletion_budget() {
+        tokio::time::pause();
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = config_with_cutoff_days_ago(30);
+        config.rate_limit_state_file = Some(dir.path().join("mastodon-rate-limit.json"));
+        let keep_list = KeepList::default();
+
+        // First run spends 25 of the 30 deletions in the window
+        let first_run = FakeMastodonClient::new("12345").with_statuses(old_statuses(1000..1025));
+        delete_old_posts(&first_run, &config, &keep_list)
+            .await
+            .unwrap();
+        assert_eq!(first_run.deleted_statuses().len(), 25);
+
+        // A second run moments later only has 5 left, and leaves likes alone
+        let second_run = FakeMastodonClient::new("12345")
+            .with_statuses(old_statuses(2000..2010))
+            .with_favourites(vec![make_status("3000", &old_timestamp(), false, false)]);
+        delete_old_posts(&second_run, &config, &keep_list)
+            .await
+            .unwrap();
+        assert_eq!(second_run.deleted_statuses().len(), 5);
+        assert!(second_run.unfavourited().is_empty());
+    }
+
+    #[tokio::test]
+    async fn checkpointed_statuses_let_the_next_run_go_straight_to_favourites() {
+        tokio::time::pause();
+        let dir = tempfile::tempdir().unwrap();
+        let phases = dir.path().join("mastodon-phases.json");
+        let mut config = config_with_cutoff_days_ago(30);
+        config.rate_limit_state_file = Some(dir.path().join("mastodon-rate-limit.json"));
+        config.phase_state_file = Some(phases.clone());
+        let keep_list = KeepList::default();
+
+        // Statuses use up the whole budget, leaving nothing for favourites
+        let first_run = FakeMastodonClient::new("12345")
+            .with_statuses(old_statuses(1000..1030))
+            .with_favourites(vec![make_status("3000", &old_timestamp(), false, false)]);
+        delete_old_posts(&first_run, &config, &keep_list)
+            .await
+            .unwrap();
+        assert_eq!(first_run.deleted_statuses().len(), 30);
+        assert!(first_run.unfavourited().is_empty());
+        assert!(PhaseCheckpoints::load(&phases)
+            .unwrap()
+            .is_complete(phases::STATUSES));
+
+        // Once the window has passed, the next run skips straight to favourites
+        config.rate_limit_state_file = None;
+        let second_run = FakeMastodonClient::new("12345")
+            .with_statuses(old_statuses(2000..2010))
+            .with_favourites(vec![make_status("3000", &old_timestamp(), false, false)]);
+        delete_old_posts(&second_run, &config, &keep_list)
+            .await
+            .unwrap();
+        assert!(second_run.deleted_statuses().is_empty());
+        assert_eq!(second_run.unfavourited(), vec!["3000"]);
+
+        // Both phases are done, so the one after starts over
+        assert!(!PhaseCheckpoints::load(&phases)
+            .unwrap()
+            .is_complete(phases::STATUSES));
+    }
+
+    #[tokio::test]
+    async fn skipped_statuses_leave_the_remaining_count_unknown() {
+        tokio::time::pause();
+        let dir = tempfile::tempdir().unwrap();
+        let csv_path = dir.path().join("remaining.csv");
+        let mut config = config_with_cutoff_days_ago(30);
+        config.rate_limit_state_file = Some(dir.path().join("mastodon-rate-limit.json"));
+        config.phase_state_file = Some(dir.path().join("mastodon-phases.json"));
+        let keep_list = KeepList::default();
+
+        let mut statuses = old_statuses(1000..1030);
+        statuses.insert(0, make_status("1999", &recent_timestamp(), false, false));
+        let first_run = FakeMastodonClient::new("12345")
+            .with_statuses(statuses)
+            .with_favourites(vec![make_status("3000", &old_timestamp(), false, false)]);
+        config.remaining_csv = Some(crate::RemainingCsv::new(&csv_path));
+        let summary = delete_old_posts(&first_run, &config, &keep_list)
+            .await
+            .unwrap();
+        assert_eq!(summary.remaining.unwrap().count, 1);
+        config.remaining_csv.as_ref().unwrap().write().unwrap();
+
+        config.rate_limit_state_file = None;
+        let second_run = FakeMastodonClient::new("12345").with_favourites(vec![make_status(
+            "3000",
+            &old_timestamp(),
+            false,
+            false,
+        )]);
+        config.remaining_csv = Some(crate::RemainingCsv::new(&csv_path));
+        let summary = delete_old_posts(&second_run, &config, &keep_list)
+            .await
+            .unwrap();
+        assert_eq!(summary.remaining, None);
+        assert_eq!(
+            summary.remaining_text(),
+            "remaining posts were counted by an earlier run."
+        );
+        assert_eq!(config.remaining_csv.as_ref().unwrap().write().unwrap(), 1);
+        assert!(std::fs::read_to_string(&csv_path)
+            .unwrap()
+            .contains("mastodon,post,1999,"));
+    }
+
+    #[tokio::test]
+    async fn interrupted_statuses_are_not_checkpointed() {
+        tokio::time::pause();
+        let dir = tempfile::tempdir().unwrap();
+        let phases = dir.path().join("mastodon-phases.json");
+        let mut config = config_with_cutoff_days_ago(30);
+        config.rate_limit_state_file = Some(dir.path().join("mastodon-rate-limit.json"));
+        config.phase_state_file = Some(phases.clone());
+
+        let fake = FakeMastodonClient::new("12345").with_statuses(old_statuses(1000..1040));
+        delete_old_posts(&fake, &config, &KeepList::default())
+            .await
+            .unwrap();
+
+        assert_eq!(fake.deleted_statuses().len(), 30);
+        assert!(!phases.exists());
+    }
+
+    #[tokio::test]
+    async fn dry_run_does_not_consume_deletion_budget() {
+        tokio::time::pause();
+        let dir = tempfile::tempdir().unwrap();
+        let state = dir.path().join("mastodon-rate-limit.json");
+        let mut config = config_with_cutoff_days_ago(30);
+        config.rate_limit_state_file = Some(state.clone());
+        config.dry_run = true;
+        let keep_list = KeepList::default();
+
+        let fake = FakeMastodonClient::new("12345").with_statuses(old_statuses(1000..1040));
+        let summary = delete_old_posts(&fake, &config, &keep_list).await.unwrap();
+
+        assert_eq!(summary.posts, 40);
+        assert!(!state.exists());
+    }
+
+    // --- summary status tests ---
+
+    #[tokio::test]
+    async fn posts_summary_status_when_enabled() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345")
+            .with_statuses(vec![
+                make_status("1001", &old_timestamp(), false, false),
+                make_status("1000", &old_timestamp(), false, true),
+            ])
+            .with_favourites(vec![make_status("2001", &old_timestamp(), false, false)]);
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.post_summary = true;
+        let keep_list = KeepList::default();
+        let summary = delete_old_posts(&fake, &config, &keep_list).await.unwrap();
+
+        assert_eq!((summary.posts, summary.reposts, summary.likes), (1, 1, 1));
+        assert_eq!(fake.created(), vec![summary.status_text(config.cutoff)]);
+    }
+
+    #[tokio::test]
+    async fn does_not_post_summary_status_in_dry_run() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345").with_statuses(vec![make_status(
+            "1001",
+            &old_timestamp(),
+            false,
+            false,
+        )]);
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.post_summary = true;
+        config.dry_run = true;
+        let keep_list = KeepList::default();
+        delete_old_posts(&fake, &config, &keep_list).await.unwrap();
+
+        assert!(fake.created().is_empty());
+    }
+
+    #[tokio::test]
+    async fn does_not_post_summary_status_when_nothing_deleted() {
+        tokio::time::pause();
+        let fake = FakeMastodonClient::new("12345").with_statuses(vec![make_status(
+            "1001",
+            &recent_timestamp(),
+            false,
+            false,
+        )]);
+
+        let mut config = config_with_cutoff_days_ago(30);
+        config.post_summary = true;
+        let keep_list = KeepList::default();
+        delete_old_posts(&fake, &config, &keep_list).await.unwrap();
+
+        assert!(fake.created().is_empty());
+    }
+
+    // --- HttpMastodonClient (against a mock server) ---
+
+    mod http {
+        use super::super::*;
+        use wiremock::matchers::{body_json, header, method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        fn status_json(id: &str) -> serde_json::Value {
+            serde_json::json!({
+                "id": id,
+                "created_at": "2020-01-01T00:00:00.000Z",
+                "pinned": false,
+                "reblog": null,
+            })
+        }
+
+        #[tokio::test]
+        async fn verify_credentials_sends_bearer_token() {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/accounts/verify_credentials"))
+                .and(header("Authorization", "Bearer token"))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_json(serde_json::json!({ "id": "12345" })),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let client = HttpMastodonClient::new(&server.uri(), "token");
+            assert_eq!(client.verify_credentials().await.unwrap().id, "12345");
+        }
+
+        #[tokio::test]
+        async fn verify_retries_once_after_a_server_error() {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/accounts/verify_credentials"))
+                .respond_with(ResponseTemplate::new(503))
+                .up_to_n_times(1)
+                .expect(1)
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/accounts/verify_credentials"))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_json(serde_json::json!({ "id": "12345" })),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let client = HttpMastodonClient::new(&server.uri(), "token");
+            let config = crate::test_helpers::config_with_cutoff_days_ago(30);
+            assert_eq!(verify(&client, &config).await.unwrap().id, "12345");
+        }
+
+        #[tokio::test]
+        async fn verify_does_not_retry_a_rejected_token() {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/accounts/verify_credentials"))
+                .respond_with(ResponseTemplate::new(401))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let client = HttpMastodonClient::new(&server.uri(), "token");
+            let config = crate::test_helpers::config_with_cutoff_days_ago(30);
+            assert!(verify(&client, &config).await.is_err());
+        }
+
+        #[tokio::test]
+        async fn token_scopes_reads_an_array_or_a_string_or_nothing() {
+            for (app, expected) in [
+                (
+                    serde_json::json!({"name": "skyscraper", "scopes": ["read", "write"]}),
+                    Some(vec!["read", "write"]),
+                ),
+                (
+                    serde_json::json!({"name": "skyscraper", "scopes": "read write:favourites"}),
+                    Some(vec!["read", "write:favourites"]),
+                ),
+                (serde_json::json!({"name": "skyscraper"}), None),
+            ] {
+                let server = MockServer::start().await;
+                Mock::given(method("GET"))
+                    .and(path("/api/v1/apps/verify_credentials"))
+                    .and(header("Authorization", "Bearer token"))
+                    .respond_with(ResponseTemplate::new(200).set_body_json(app))
+                    .mount(&server)
+                    .await;
+
+                let client = HttpMastodonClient::new(&server.uri(), "token");
+                let scopes = client.token_scopes().await.unwrap();
+                assert_eq!(
+                    scopes,
+                    expected.map(|s| s.into_iter().map(String::from).collect())
+                );
+            }
+        }
+
+        #[tokio::test]
+        async fn requests_use_the_configured_user_agent() {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/accounts/verify_credentials"))
+                .and(header("User-Agent", "my-bot/2.0"))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_json(serde_json::json!({ "id": "12345" })),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let client =
+                HttpMastodonClient::new(&server.uri(), "token").with_user_agent("my-bot/2.0");
+            assert_eq!(client.verify_credentials().await.unwrap().id, "12345");
+        }
+
+        #[tokio::test]
+        async fn verify_credentials_failure_is_an_error() {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/accounts/verify_credentials"))
+                .respond_with(ResponseTemplate::new(401))
+                .mount(&server)
+                .await;
+
+            let client = HttpMastodonClient::new(&server.uri(), "bad");
+            let err = client.verify_credentials().await.err().unwrap();
+            assert!(format!("{err:#}").contains("Failed to verify Mastodon credentials"));
+        }
+
+        #[tokio::test]
+        async fn get_status_reads_bookmarked_flag() {
+            let server = MockServer::start().await;
+            let mut status = status_json("1001");
+            status["bookmarked"] = true.into();
+            Mock::given(method("GET"))
+                .and(path("/api/v1/statuses/1001"))
+                .and(header("Authorization", "Bearer token"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(status))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let client = HttpMastodonClient::new(&server.uri(), "token");
+            let status = client.get_status("1001").await.unwrap();
+            assert_eq!(status.id, "1001");
+            assert!(status.bookmarked);
+        }
+
+        #[tokio::test]
+        async fn list_pinned_statuses_queries_pinned_only() {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/accounts/12345/statuses"))
+                .and(query_param("pinned", "true"))
+                .and(header("Authorization", "Bearer token"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!([status_json("1001")])),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let client = HttpMastodonClient::new(&server.uri(), "token");
+            let pinned = client.list_pinned_statuses("12345").await.unwrap();
+            assert_eq!(pinned.len(), 1);
+            assert_eq!(pinned[0].id, "1001");
+        }
+
+        #[tokio::test]
+        async fn list_statuses_builds_url_with_limit_and_max_id() {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/accounts/12345/statuses"))
+                .and(query_param("limit", "40"))
+                .and(query_param("max_id", "1001"))
+                .and(header("Authorization", "Bearer token"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!([status_json("1000")])),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let client = HttpMastodonClient::new(&server.uri(), "token");
+            let statuses = client.list_statuses("12345", Some("1001")).await.unwrap();
+            assert_eq!(statuses.len(), 1);
+            assert_eq!(statuses[0].id, "1000");
+        }
+
+        #[tokio::test]
+        async fn delete_status_sends_delete_request() {
+            let server = MockServer::start().await;
+            Mock::given(method("DELETE"))
+                .and(path("/api/v1/statuses/1001"))
+                .and(header("Authorization", "Bearer token"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let client = HttpMastodonClient::new(&server.uri(), "token");
+            assert_eq!(
+                client.delete_status("1001").await.unwrap(),
+                DeleteOutcome::Deleted
+            );
+        }
+
+        #[tokio::test]
+        async fn delete_status_reports_403_as_forbidden() {
+            let server = MockServer::start().await;
+            Mock::given(method("DELETE"))
+                .and(path("/api/v1/statuses/1001"))
+                .respond_with(ResponseTemplate::new(403))
+                .mount(&server)
+                .await;
+
+            let client = HttpMastodonClient::new(&server.uri(), "token");
+            let err = client.delete_status("1001").await.unwrap_err();
+            assert!(err.is::<Forbidden>());
+        }
+
+        #[tokio::test]
+        async fn delete_status_surfaces_retry_after_on_429() {
+            let server = MockServer::start().await;
+            Mock::given(method("DELETE"))
+                .and(path("/api/v1/statuses/1001"))
+                .respond_with(
+                    ResponseTemplate::new(429)
+                        .insert_header("Retry-After", "120")
+                        .insert_header("X-RateLimit-Remaining", "0")
+                        .insert_header("X-RateLimit-Reset", "2024-06-15T12:00:00.000Z"),
+                )
+                .mount(&server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/api/v1/statuses/1001/unfavourite"))
+                .respond_with(ResponseTemplate::new(429))
+                .mount(&server)
+                .await;
+
+            let client = HttpMastodonClient::new(&server.uri(), "token");
+            let err = client.delete_status("1001").await.unwrap_err();
+            let limited = err.downcast_ref::<RateLimited>().unwrap();
+            assert_eq!(limited.retry_after, Some(Duration::from_secs(120)));
+            assert_eq!(limited.endpoint, "/api/v1/statuses/1001");
+            assert_eq!(limited.remaining, Some(0));
+            assert_eq!(
+                limited.reset,
+                Some(
+                    crate::parse_timestamp("2024-06-15T12:00:00Z")
+                        .unwrap()
+                        .into()
+                )
+            );
+
+            let err = client.unfavourite("1001").await.unwrap_err();
+            let limited = err.downcast_ref::<RateLimited>().unwrap();
+            assert_eq!(limited.retry_after, None);
+        }
+
+        #[tokio::test]
+        async fn idempotency_key_sent_on_deletes_only_when_enabled() {
+            let server = MockServer::start().await;
+            Mock::given(method("DELETE"))
+                .respond_with(ResponseTemplate::new(200))
+                .mount(&server)
+                .await;
+
+            let client = HttpMastodonClient::new(&server.uri(), "token");
+            client.delete_status("1001").await.unwrap();
+            let client = client.with_idempotency(true);
+            client.delete_status("1002").await.unwrap();
+            client.delete_status("1003").await.unwrap();
+
+            let requests = server.received_requests().await.unwrap();
+            let keys: Vec<_> = requests
+                .iter()
+                .map(|r| {
+                    r.headers
+                        .get("Idempotency-Key")
+                        .map(|v| v.to_str().unwrap().to_string())
+                })
+                .collect();
+            assert_eq!(keys[0], None);
+            let (a, b) = (keys[1].as_ref().unwrap(), keys[2].as_ref().unwrap());
+            assert_ne!(a, b);
+            for key in [a, b] {
+                assert_eq!(key.len(), 36);
+                assert_eq!(&key[14..15], "4");
+            }
+        }
+
+        #[tokio::test]
+        async fn deleting_twice_reports_already_gone_on_404() {
+            let server = MockServer::start().await;
+            Mock::given(method("DELETE"))
+                .and(path("/api/v1/statuses/1001"))
+                .respond_with(ResponseTemplate::new(200))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+            Mock::given(method("DELETE"))
+                .and(path("/api/v1/statuses/1001"))
+                .respond_with(ResponseTemplate::new(404))
+                .mount(&server)
+                .await;
+
+            let client = HttpMastodonClient::new(&server.uri(), "token");
+            assert_eq!(
+                client.delete_status("1001").await.unwrap(),
+                DeleteOutcome::Deleted
+            );
+            assert_eq!(
+                client.delete_status("1001").await.unwrap(),
+                DeleteOutcome::AlreadyGone
+            );
+        }
+
+        #[tokio::test]
+        async fn list_favourites_round_trips_link_header_max_id() {
+            let server = MockServer::start().await;
+            let next = format!(
+                r#"<{}/api/v1/favourites?max_id=2000>; rel="next", <{}/api/v1/favourites?min_id=2002>; rel="prev""#,
+                server.uri(),
+                server.uri()
+            );
+            Mock::given(method("GET"))
+                .and(path("/api/v1/favourites"))
+                .and(query_param("max_id", "2000"))
+                .and(header("Authorization", "Bearer token"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!([status_json("1999")])),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+            // Registered last so the more specific max_id mock above wins for page 2
+            Mock::given(method("GET"))
+                .and(path("/api/v1/favourites"))
+                .and(query_param("limit", "40"))
+                .and(header("Authorization", "Bearer token"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .insert_header("Link", next.as_str())
+                        .set_body_json(serde_json::json!([status_json("2001")])),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let client = HttpMastodonClient::new(&server.uri(), "token");
+            let (first, next_max_id) = client.list_favourites(None).await.unwrap();
+            assert_eq!(first[0].id, "2001");
+            assert_eq!(next_max_id.as_deref(), Some("2000"));
+
+            let (second, next_max_id) = client
+                .list_favourites(next_max_id.as_deref())
+                .await
+                .unwrap();
+            assert_eq!(second[0].id, "1999");
+            assert_eq!(next_max_id, None);
+        }
+
+        #[tokio::test]
+        async fn create_status_posts_text() {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/api/v1/statuses"))
+                .and(header("Authorization", "Bearer token"))
+                .and(body_json(serde_json::json!({ "status": "hello" })))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let client = HttpMastodonClient::new(&server.uri(), "token");
+            client.create_status("hello").await.unwrap();
+        }
+    }
 }
@@ -0,0 +1,75 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+/// Mastodon's statuses sweep, ahead of favourites.
+pub const STATUSES: &str = "statuses";
+
+/// Phases of a platform's cleanup that finished in an earlier run, persisted
+/// for `PHASE_STATE_FILE` so a run cut short by the rate limit can pick up at
+/// the next phase instead of starting over.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PhaseCheckpoints {
+    completed: BTreeSet<String>,
+}
+
+impl PhaseCheckpoints {
+    /// Load checkpoints from `path`. A missing file means no phase is done.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read phase state {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse phase state {}", path.display()))
+    }
+
+    pub fn is_complete(&self, phase: &str) -> bool {
+        self.completed.contains(phase)
+    }
+
+    pub fn complete(&mut self, phase: &str) {
+        self.completed.insert(phase.to_string());
+    }
+
+    /// Forget every phase, once the last one is done and the cycle starts over.
+    pub fn clear(&mut self) {
+        self.completed.clear();
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write phase state {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completed_phases_persist_until_cleared() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state/phases.json");
+        assert_eq!(
+            PhaseCheckpoints::load(&path).unwrap(),
+            PhaseCheckpoints::default()
+        );
+
+        let mut phases = PhaseCheckpoints::default();
+        phases.complete(STATUSES);
+        phases.save(&path).unwrap();
+        assert!(PhaseCheckpoints::load(&path).unwrap().is_complete(STATUSES));
+
+        phases.clear();
+        phases.save(&path).unwrap();
+        assert!(!PhaseCheckpoints::load(&path).unwrap().is_complete(STATUSES));
+    }
+}
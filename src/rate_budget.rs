@@ -0,0 +1,117 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A sliding-window deletion budget persisted to a state file, so that
+/// separate invocations (e.g. cron every 10 minutes) share a platform's
+/// rate limit instead of each assuming a fresh allowance.
+pub struct DeletionBudget {
+    path: PathBuf,
+    limit: usize,
+    window: TimeDelta,
+    state: BudgetState,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct BudgetState {
+    deletions: Vec<DateTime<Utc>>,
+}
+
+impl DeletionBudget {
+    /// Load the budget from `path`. A missing file means no recent deletions.
+    pub fn load(path: &Path, limit: usize, window: TimeDelta) -> Result<Self> {
+        let state = if path.exists() {
+            let contents = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read rate limit state {}", path.display()))?;
+            serde_json::from_str(&contents)
+                .with_context(|| format!("Failed to parse rate limit state {}", path.display()))?
+        } else {
+            BudgetState::default()
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            limit,
+            window,
+            state,
+        })
+    }
+
+    /// Deletions still allowed within the window ending at `now`.
+    pub fn remaining(&self, now: DateTime<Utc>) -> usize {
+        let window_start = now - self.window;
+        let used = self
+            .state
+            .deletions
+            .iter()
+            .filter(|t| **t > window_start)
+            .count();
+        self.limit.saturating_sub(used)
+    }
+
+    /// Record a deletion at `now` and persist the state, dropping entries
+    /// that have aged out of the window.
+    pub fn record(&mut self, now: DateTime<Utc>) -> Result<()> {
+        let window_start = now - self.window;
+        self.state.deletions.retain(|t| *t > window_start);
+        self.state.deletions.push(now);
+        fs::write(&self.path, serde_json::to_string(&self.state)?)
+            .with_context(|| format!("Failed to write rate limit state {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-06-15T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+            + TimeDelta::minutes(minutes)
+    }
+
+    #[test]
+    fn missing_file_has_full_budget() {
+        let dir = tempfile::tempdir().unwrap();
+        let budget =
+            DeletionBudget::load(&dir.path().join("state.json"), 3, TimeDelta::minutes(30))
+                .unwrap();
+        assert_eq!(budget.remaining(at(0)), 3);
+    }
+
+    #[test]
+    fn record_consumes_budget_and_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        let mut budget = DeletionBudget::load(&path, 3, TimeDelta::minutes(30)).unwrap();
+        budget.record(at(0)).unwrap();
+        budget.record(at(1)).unwrap();
+        assert_eq!(budget.remaining(at(2)), 1);
+
+        let reloaded = DeletionBudget::load(&path, 3, TimeDelta::minutes(30)).unwrap();
+        assert_eq!(reloaded.remaining(at(2)), 1);
+    }
+
+    #[test]
+    fn deletions_age_out_of_the_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut budget =
+            DeletionBudget::load(&dir.path().join("state.json"), 2, TimeDelta::minutes(30))
+                .unwrap();
+        budget.record(at(0)).unwrap();
+        budget.record(at(10)).unwrap();
+        assert_eq!(budget.remaining(at(20)), 0);
+        assert_eq!(budget.remaining(at(30)), 1);
+        assert_eq!(budget.remaining(at(40)), 2);
+    }
+
+    #[test]
+    fn corrupt_state_file_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        fs::write(&path, "not json").unwrap();
+        assert!(DeletionBudget::load(&path, 3, TimeDelta::minutes(30)).is_err());
+    }
+}
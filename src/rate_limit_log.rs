@@ -0,0 +1,101 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::warn;
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// One rate limited request, as a `RATE_LIMIT_LOG` line.
+#[derive(Debug, Serialize)]
+pub struct RateLimitEvent<'a> {
+    pub at: DateTime<Utc>,
+    /// `bluesky` or `mastodon`.
+    pub platform: &'a str,
+    /// XRPC method on Bluesky, API path on Mastodon.
+    pub endpoint: &'a str,
+    /// Requests left in the window, if the response said.
+    pub remaining: Option<u64>,
+    /// When the window resets, if the response said.
+    pub reset: Option<DateTime<Utc>>,
+}
+
+/// `RATE_LIMIT_LOG`: a JSON Lines file with one [`RateLimitEvent`] per rate
+/// limited request, appended to across runs. Clones share the file.
+#[derive(Clone)]
+pub struct RateLimitLog(Arc<Mutex<File>>);
+
+impl RateLimitLog {
+    /// Open the log at `path` for appending, creating it and its directory
+    /// if needed.
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create RATE_LIMIT_LOG dir {}", parent.display())
+            })?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open RATE_LIMIT_LOG {}", path.display()))?;
+        Ok(Self(Arc::new(Mutex::new(file))))
+    }
+
+    /// Append `event`. A failed write is logged rather than failing the run.
+    pub fn record(&self, event: &RateLimitEvent) {
+        let line = match serde_json::to_string(event) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Could not serialize rate limit event: {e}");
+                return;
+            }
+        };
+        let mut file = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = writeln!(file, "{line}") {
+            warn!("Could not write to RATE_LIMIT_LOG: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_are_appended_as_json_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs/rate-limits.jsonl");
+        let at = DateTime::parse_from_rfc3339("2024-06-15T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let event = RateLimitEvent {
+            at,
+            platform: "bluesky",
+            endpoint: "com.atproto.repo.deleteRecord",
+            remaining: Some(0),
+            reset: None,
+        };
+
+        RateLimitLog::open(&path).unwrap().record(&event);
+        RateLimitLog::open(&path).unwrap().record(&event);
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            serde_json::json!({
+                "at": "2024-06-15T12:00:00Z",
+                "platform": "bluesky",
+                "endpoint": "com.atproto.repo.deleteRecord",
+                "remaining": 0,
+                "reset": null,
+            })
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// One record a sweep left in place for being newer than the cutoff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Survivor {
+    platform: &'static str,
+    /// Collection NSID on Bluesky, `post` or `reblog` on Mastodon.
+    kind: String,
+    /// AT URI on Bluesky, status ID on Mastodon.
+    id: String,
+    created_at: DateTime<Utc>,
+}
+
+/// `REMAINING_CSV`: the records each sweep saw but left alone for being newer
+/// than the cutoff, written as CSV once the run is over. Clones share the
+/// rows, so both platforms add to the same file.
+#[derive(Clone)]
+pub struct RemainingCsv {
+    path: PathBuf,
+    rows: Arc<Mutex<Vec<Survivor>>>,
+    /// Platforms whose rows are carried over from the existing file.
+    carried: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl RemainingCsv {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            rows: Arc::default(),
+            carried: Arc::default(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn record(
+        &self,
+        platform: &'static str,
+        kind: impl Into<String>,
+        id: impl Into<String>,
+        created_at: DateTime<Utc>,
+    ) {
+        let survivor = Survivor {
+            platform,
+            kind: kind.into(),
+            id: id.into(),
+            created_at,
+        };
+        self.rows
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(survivor);
+    }
+
+    /// Keep `platform`'s rows from the file an earlier run wrote rather than
+    /// replacing them, for a sweep that didn't list its records this run.
+    pub fn carry_over(&self, platform: &'static str) {
+        self.carried
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(platform);
+    }
+
+    /// Write `platform,kind,id,created_at` rows, by platform and then oldest
+    /// first.
+    pub fn write(&self) -> Result<usize> {
+        let mut rows = self.rows.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        rows.extend(self.carried_rows());
+        rows.sort_by(|a, b| (a.platform, a.created_at).cmp(&(b.platform, b.created_at)));
+        let mut csv = String::from("platform,kind,id,created_at\n");
+        for row in &rows {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                row.platform,
+                row.kind,
+                row.id,
+                row.created_at.to_rfc3339_opts(SecondsFormat::Secs, true)
+            ));
+        }
+        std::fs::write(&self.path, csv)
+            .with_context(|| format!("Failed to write REMAINING_CSV {}", self.path.display()))?;
+        Ok(rows.len())
+    }
+
+    /// Rows of the carried-over platforms in the existing file, if any.
+    fn carried_rows(&self) -> Vec<Survivor> {
+        let carried = self.carried.lock().unwrap_or_else(|e| e.into_inner());
+        if carried.is_empty() {
+            return Vec::new();
+        }
+        let Ok(existing) = std::fs::read_to_string(&self.path) else {
+            return Vec::new();
+        };
+        existing
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let mut fields = line.splitn(4, ',');
+                let platform = fields.next()?;
+                let platform = *carried.iter().find(|p| **p == platform)?;
+                let kind = fields.next()?.to_string();
+                let id = fields.next()?.to_string();
+                let created_at = DateTime::parse_from_rfc3339(fields.next()?).ok()?;
+                Some(Survivor {
+                    platform,
+                    kind,
+                    id,
+                    created_at: created_at.with_timezone(&Utc),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_are_written_by_platform_oldest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let ts = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let csv = RemainingCsv::new(dir.path().join("remaining.csv"));
+        let shared = csv.clone();
+        csv.record("mastodon", "post", "112", ts("2024-06-02T00:00:00Z"));
+        shared.record(
+            "bluesky",
+            "app.bsky.feed.like",
+            "at://l",
+            ts("2024-06-03T00:00:00Z"),
+        );
+        shared.record("mastodon", "reblog", "111", ts("2024-06-01T00:00:00Z"));
+
+        assert_eq!(csv.write().unwrap(), 3);
+        assert_eq!(
+            std::fs::read_to_string(csv.path()).unwrap(),
+            "platform,kind,id,created_at\n\
+             bluesky,app.bsky.feed.like,at://l,2024-06-03T00:00:00Z\n\
+             mastodon,reblog,111,2024-06-01T00:00:00Z\n\
+             mastodon,post,112,2024-06-02T00:00:00Z\n"
+        );
+    }
+
+    #[test]
+    fn carried_over_rows_survive_a_rewrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let ts = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let path = dir.path().join("remaining.csv");
+        let earlier = RemainingCsv::new(&path);
+        earlier.record("mastodon", "post", "112", ts("2024-06-02T00:00:00Z"));
+        earlier.record(
+            "bluesky",
+            "app.bsky.feed.post",
+            "at://old",
+            ts("2024-06-01T00:00:00Z"),
+        );
+        earlier.write().unwrap();
+
+        let resumed = RemainingCsv::new(&path);
+        resumed.carry_over("mastodon");
+        resumed.record(
+            "bluesky",
+            "app.bsky.feed.post",
+            "at://new",
+            ts("2024-06-03T00:00:00Z"),
+        );
+
+        assert_eq!(resumed.write().unwrap(), 2);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "platform,kind,id,created_at\n\
+             bluesky,app.bsky.feed.post,at://new,2024-06-03T00:00:00Z\n\
+             mastodon,post,112,2024-06-02T00:00:00Z\n"
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// How a record was removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Delete,
+    /// Overwritten in place under `REDACT`.
+    Redact,
+    Unfavourite,
+    Unbookmark,
+}
+
+/// One `REPORT_FILE` entry: a record the run removed, or would have in a dry
+/// run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReportEntry {
+    pub platform: &'static str,
+    /// AT URI on Bluesky, status ID on Mastodon.
+    pub id: String,
+    /// Collection NSID on Bluesky; `post`, `reblog`, `favourite` or
+    /// `bookmark` on Mastodon.
+    pub kind: String,
+    /// `None` when only the ID was known, as with `DELETE_FROM_EXPORT`.
+    pub created_at: Option<DateTime<Utc>>,
+    pub action: Action,
+}
+
+/// `REPORT_FILE`: every record the run removed, collected by the sweeps and
+/// written as JSON once the run is over. Clones share the entries, so both
+/// platforms add to the same report.
+#[derive(Clone)]
+pub struct Report {
+    path: PathBuf,
+    entries: Arc<Mutex<Vec<ReportEntry>>>,
+}
+
+impl Report {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            entries: Arc::default(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn record(
+        &self,
+        platform: &'static str,
+        kind: impl Into<String>,
+        id: impl Into<String>,
+        created_at: Option<DateTime<Utc>>,
+        action: Action,
+    ) {
+        let entry = ReportEntry {
+            platform,
+            id: id.into(),
+            kind: kind.into(),
+            created_at,
+            action,
+        };
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(entry);
+    }
+
+    /// Write `{"generated_at": ..., "dry_run": ..., "entries": [...]}`, the
+    /// entries in the order they were removed. Returns how many there were.
+    pub fn write(&self, dry_run: bool, now: DateTime<Utc>) -> Result<usize> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let report = serde_json::json!({
+            "generated_at": now,
+            "dry_run": dry_run,
+            "entries": *entries,
+        });
+        let json = serde_json::to_string_pretty(&report)?;
+        std::fs::write(&self.path, json)
+            .with_context(|| format!("Failed to write REPORT_FILE {}", self.path.display()))?;
+        Ok(entries.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_from_every_clone_are_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let at = DateTime::parse_from_rfc3339("2024-06-15T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let report = Report::new(dir.path().join("report.json"));
+        report.record(
+            "bluesky",
+            "app.bsky.feed.post",
+            "at://p",
+            Some(at),
+            Action::Delete,
+        );
+        report
+            .clone()
+            .record("mastodon", "favourite", "111", None, Action::Unfavourite);
+
+        assert_eq!(report.write(true, at).unwrap(), 2);
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(report.path()).unwrap()).unwrap();
+        assert_eq!(written["dry_run"], serde_json::json!(true));
+        assert_eq!(
+            written["entries"],
+            serde_json::json!([
+                {
+                    "platform": "bluesky",
+                    "id": "at://p",
+                    "kind": "app.bsky.feed.post",
+                    "created_at": "2024-06-15T12:00:00Z",
+                    "action": "delete",
+                },
+                {
+                    "platform": "mastodon",
+                    "id": "111",
+                    "kind": "favourite",
+                    "created_at": null,
+                    "action": "unfavourite",
+                },
+            ])
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Ctrl-C handling shared by every platform. Once triggered, the pauses
+/// between requests end immediately and sweeps stop before the next request.
+#[derive(Clone)]
+pub struct Shutdown(Arc<watch::Sender<bool>>);
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self(Arc::new(watch::channel(false).0))
+    }
+}
+
+impl Shutdown {
+    pub fn trigger(&self) {
+        self.0.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Pause for `duration`, or until shutdown is triggered. Returns `false`
+    /// if the pause was cut short, so the caller should stop.
+    pub async fn sleep(&self, duration: Duration) -> bool {
+        let mut triggered = self.0.subscribe();
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => true,
+            _ = triggered.wait_for(|&t| t) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn sleep_runs_to_completion_without_shutdown() {
+        tokio::time::pause();
+        assert!(Shutdown::default().sleep(Duration::from_secs(5)).await);
+    }
+
+    #[tokio::test]
+    async fn trigger_during_sleep_returns_promptly() {
+        let shutdown = Shutdown::default();
+        let sleeper = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.sleep(Duration::from_secs(3600)).await }
+        });
+        tokio::task::yield_now().await;
+        shutdown.trigger();
+
+        let completed = tokio::time::timeout(Duration::from_secs(1), sleeper)
+            .await
+            .expect("sleep should end as soon as shutdown is triggered")
+            .unwrap();
+        assert!(!completed);
+        assert!(shutdown.is_triggered());
+    }
+
+    #[tokio::test]
+    async fn sleep_after_trigger_returns_immediately() {
+        let shutdown = Shutdown::default();
+        shutdown.trigger();
+        assert!(!shutdown.sleep(Duration::from_secs(3600)).await);
+    }
+}
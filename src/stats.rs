@@ -0,0 +1,131 @@
+use crate::Decision;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Width of each age bucket in the histogram.
+pub const BUCKET_DAYS: i64 = 30;
+
+/// `STATS_JSON` for one platform: what's there, what a run would delete, and
+/// how old it all is.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlatformStats {
+    pub cutoff: DateTime<Utc>,
+    /// Every dated record, whatever its age.
+    pub total: u64,
+    /// Older than the cutoff and not protected, so a run would delete it.
+    pub eligible: u64,
+    /// Older than the cutoff but kept by a rule (pinned, keep list, filters, ...).
+    pub protected: u64,
+    /// Records with no usable `createdAt`, left out of the other counts.
+    pub undated: u64,
+    /// Counts by age, `BUCKET_DAYS` wide, youngest first. Sums to `total`.
+    pub age_buckets: Vec<AgeBucket>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgeBucket {
+    /// Inclusive lower bound of the age in days.
+    pub from_days: i64,
+    /// Exclusive upper bound.
+    pub to_days: i64,
+    pub count: u64,
+}
+
+impl PlatformStats {
+    pub fn new(cutoff: DateTime<Utc>) -> Self {
+        Self {
+            cutoff,
+            total: 0,
+            eligible: 0,
+            protected: 0,
+            undated: 0,
+            age_buckets: Vec::new(),
+        }
+    }
+
+    /// Tally a record created at `created` that a sweep would decide as
+    /// `decision`. Future-dated records count as zero days old.
+    pub fn add(&mut self, created: DateTime<Utc>, decision: &Decision, now: DateTime<Utc>) {
+        self.total += 1;
+        if created < self.cutoff {
+            match decision {
+                Decision::Delete | Decision::ForceDelete | Decision::Blocklisted => {
+                    self.eligible += 1
+                }
+                _ => self.protected += 1,
+            }
+        }
+
+        let bucket = ((now - created).num_days().max(0) / BUCKET_DAYS) as usize;
+        while self.age_buckets.len() <= bucket {
+            let from_days = self.age_buckets.len() as i64 * BUCKET_DAYS;
+            self.age_buckets.push(AgeBucket {
+                from_days,
+                to_days: from_days + BUCKET_DAYS,
+                count: 0,
+            });
+        }
+        self.age_buckets[bucket].count += 1;
+    }
+}
+
+/// Write `platforms` to `path` as `{"generated_at": ..., "platforms": {...}}`.
+pub fn write(
+    path: &Path,
+    platforms: &BTreeMap<&str, PlatformStats>,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    let report = serde_json::json!({
+        "generated_at": now,
+        "platforms": platforms,
+    });
+    let json = serde_json::to_string_pretty(&report)?;
+    std::fs::write(path, json)
+        .with_context(|| format!("Failed to write STATS_JSON {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    #[test]
+    fn written_report_buckets_sum_to_the_total() {
+        let now = Utc::now();
+        let days_ago = |days: i64| now - TimeDelta::days(days);
+        let mut stats = PlatformStats::new(days_ago(90));
+        stats.add(days_ago(-1), &Decision::FutureDated, now);
+        stats.add(days_ago(10), &Decision::NewerThanCutoff, now);
+        stats.add(days_ago(29), &Decision::NewerThanCutoff, now);
+        stats.add(days_ago(100), &Decision::Delete, now);
+        stats.add(days_ago(200), &Decision::Pinned, now);
+        stats.add(days_ago(200), &Decision::Delete, now);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stats.json");
+        write(&path, &BTreeMap::from([("bluesky", stats)]), now).unwrap();
+
+        let report: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let read: PlatformStats =
+            serde_json::from_value(report["platforms"]["bluesky"].clone()).unwrap();
+        assert_eq!((read.total, read.eligible, read.protected), (6, 2, 1));
+        assert_eq!(
+            read.age_buckets.iter().map(|b| b.count).sum::<u64>(),
+            read.total
+        );
+        let counts: Vec<u64> = read.age_buckets.iter().map(|b| b.count).collect();
+        assert_eq!(counts, vec![3, 0, 0, 1, 0, 0, 2]);
+        assert_eq!(
+            read.age_buckets[6],
+            AgeBucket {
+                from_days: 180,
+                to_days: 210,
+                count: 2,
+            }
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// The newest `createdAt` deleted from each Bluesky collection by a completed
+/// sweep, persisted for `INCREMENTAL` runs. Everything older was already
+/// looked at, so later runs can stop paging once they reach it.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Watermarks {
+    collections: BTreeMap<String, DateTime<Utc>>,
+}
+
+impl Watermarks {
+    /// Load watermarks from `path`. A missing file means nothing was swept yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read incremental state {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse incremental state {}", path.display()))
+    }
+
+    pub fn get(&self, collection: &str) -> Option<DateTime<Utc>> {
+        self.collections.get(collection).copied()
+    }
+
+    /// Raise `collection`'s watermark to `t`. It never moves backwards.
+    pub fn advance(&mut self, collection: &str, t: DateTime<Utc>) {
+        let mark = self.collections.entry(collection.to_string()).or_insert(t);
+        *mark = (*mark).max(t);
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write incremental state {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn advance_persists_and_never_moves_backwards() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state/incremental.json");
+        assert_eq!(Watermarks::load(&path).unwrap(), Watermarks::default());
+
+        let mut marks = Watermarks::default();
+        marks.advance("app.bsky.feed.post", ts("2024-01-01T00:00:00Z"));
+        marks.advance("app.bsky.feed.post", ts("2023-01-01T00:00:00Z"));
+        marks.save(&path).unwrap();
+
+        let reloaded = Watermarks::load(&path).unwrap();
+        assert_eq!(
+            reloaded.get("app.bsky.feed.post"),
+            Some(ts("2024-01-01T00:00:00Z"))
+        );
+        assert_eq!(reloaded.get("app.bsky.feed.like"), None);
+    }
+
+    #[test]
+    fn corrupt_state_file_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("incremental.json");
+        fs::write(&path, "not json").unwrap();
+        assert!(Watermarks::load(&path).is_err());
+    }
+}